@@ -1,3 +1,4 @@
+use alloc::borrow::Cow;
 use alloc::string::String;
 
 use core::{error, fmt};
@@ -18,7 +19,10 @@ pub enum Error {
     Unsupported(&'static str),
 
     /// クエリ演算子のパラメータが不正であることを示す。
-    InvalidQueryParameter(&'static str),
+    ///
+    /// クエリ定義（[`Query::parse`](crate::Query::parse)）の誤りもこれで表し、メッセージに
+    /// 誤りのある字句とその位置（バイト単位）を含める。
+    InvalidQueryParameter(Cow<'static, str>),
 
     /// [`Source`](crate::Source) 実装側で発生した読み取りエラー。
     ///
@@ -30,6 +34,27 @@ pub enum Error {
     /// 直列化ライブラリの都合を呼び出し側へ漏らさないため、文字列へ畳んで返す。
     Persist(String),
 
+    /// GeoJSON として解釈できない入力。
+    ///
+    /// JSON の構文誤り、GeoJSON の構造に合わないメンバ、型の合わないプロパティを表す。
+    GeoJson(String),
+
+    /// JSON として読み書きできない入力・値。
+    ///
+    /// JSON の構文誤り、スキーマに合わない文書、JSON にできない値を表す。
+    Json(String),
+
+    /// 入出力の失敗。
+    ///
+    /// 呼び出し側が失敗の種類で振り分けられるよう、元の [`std::io::ErrorKind`] を保つ。
+    #[cfg(feature = "std")]
+    Io {
+        /// 元のエラーの種類。
+        kind: std::io::ErrorKind,
+        /// 元のエラーの表示。
+        message: String,
+    },
+
     /// 永続化バイト列の形式バージョンがこのビルドで扱えない。
     ///
     /// スキーマ（`MapArena` / `ArenaNode` の構造）を変更したら `FORMAT_VERSION` を上げる。
@@ -88,6 +113,27 @@ pub enum GeometryError {
 
     /// Y 方向インデックスが、指定されたズームレベルに対して有効範囲外であることを示す。
     FractionalYOutOfRange { z: u8, y: f64 },
+
+    /// 被覆の許容誤差が有効範囲（`0.0..1.0`）外であることを示す。
+    ToleranceOutOfRange { tolerance: f64 },
+
+    /// 角柱の底面（フットプリント）が 3 つ以上の異なる頂点を持たないことを示す。
+    FootprintEmpty,
+
+    /// 角柱の屋根が底面より高くない箇所があることを示す。
+    RoofNotAboveBottom { bottom: f64, roof: f64 },
+
+    /// 傾斜屋根を定める 3 点が水平方向に一直線上に並び、平面が定まらないことを示す。
+    RoofPlaneDegenerate,
+
+    /// 図形の寸法（長さ・幅・高さ・半軸など）が正でないことを示す。
+    SizeNotPositive { size: f64 },
+
+    /// 軌跡の経由点が 2 つ未満であることを示す。
+    WaypointsTooFew { count: usize },
+
+    /// 軌跡の `index` 番目の経由点の時刻が、1 つ前の経由点の時刻より後でないことを示す。
+    TimeNotIncreasing { index: usize },
 }
 
 /// SpatialId 関連で発生するエラー。
@@ -117,7 +163,14 @@ pub enum SpatialIdError {
     /// 時間間隔 `i` に 0 を指定した場合のエラー。
     TIntervalError { i: u64 },
     /// 文字列表現を空間 ID として解釈できないことを示す。
-    ParseSpatialIdFormat { kind: &'static str, input: String },
+    ///
+    /// `offset` は複数の ID を並べたテキストを読んだときの、`input` の先頭のバイト位置。
+    /// 単体の文字列を解釈したときは `None`。
+    ParseSpatialIdFormat {
+        kind: &'static str,
+        input: String,
+        offset: Option<usize>,
+    },
 
     /// シャードのマージに渡された2つが、指定親領域の正当な兄弟（下半分/上半分）でないことを示す。
     InvalidShardMerge,
@@ -145,6 +198,29 @@ impl From<core::convert::Infallible> for Error {
     }
 }
 
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        use alloc::string::ToString;
+        Self::Io {
+            kind: value.kind(),
+            message: value.to_string(),
+        }
+    }
+}
+
+/// `std::io::Result` を返す呼び出し側で `?` を使えるようにする。[`Error::Io`] は元の種類へ戻し、
+/// それ以外は [`std::io::ErrorKind::Other`] で包む。
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::Io { kind, message } => std::io::Error::new(kind, message),
+            other => std::io::Error::other(other),
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -154,6 +230,10 @@ impl fmt::Display for Error {
             Error::InvalidQueryParameter(what) => write!(f, "invalid query parameter: {what}"),
             Error::SourceRead(msg) => write!(f, "source read failed: {msg}"),
             Error::Persist(msg) => write!(f, "persistence failed: {msg}"),
+            Error::GeoJson(msg) => write!(f, "invalid geojson: {msg}"),
+            Error::Json(msg) => write!(f, "invalid json: {msg}"),
+            #[cfg(feature = "std")]
+            Error::Io { message, .. } => write!(f, "i/o failed: {message}"),
             Error::UnsupportedFormatVersion { expected, found } => write!(
                 f,
                 "unsupported persisted format version: expected {expected}, found {found}"
@@ -222,6 +302,39 @@ impl fmt::Display for GeometryError {
                     y, z
                 )
             }
+            GeometryError::ToleranceOutOfRange { tolerance } => {
+                write!(
+                    f,
+                    "Tolerance '{}' is out of range (valid: 0.0..1.0)",
+                    tolerance
+                )
+            }
+            GeometryError::FootprintEmpty => {
+                write!(f, "Prism footprint needs at least 3 distinct vertices.")
+            }
+            GeometryError::RoofNotAboveBottom { bottom, roof } => {
+                write!(
+                    f,
+                    "Roof altitude '{}' must be above bottom altitude '{}'.",
+                    roof, bottom
+                )
+            }
+            GeometryError::RoofPlaneDegenerate => {
+                write!(f, "Roof plane points must not be collinear.")
+            }
+            GeometryError::SizeNotPositive { size } => {
+                write!(f, "Size need to be positive (size = {}).", size)
+            }
+            GeometryError::WaypointsTooFew { count } => {
+                write!(f, "Trajectory needs at least 2 waypoints (got {}).", count)
+            }
+            GeometryError::TimeNotIncreasing { index } => {
+                write!(
+                    f,
+                    "Waypoint time at index {} must be later than the previous one.",
+                    index
+                )
+            }
         }
     }
 }
@@ -273,8 +386,16 @@ impl fmt::Display for SpatialIdError {
                     i
                 )
             }
-            SpatialIdError::ParseSpatialIdFormat { kind, input } => {
-                write!(f, "{} '{}' has invalid display format", kind, input)
+            SpatialIdError::ParseSpatialIdFormat {
+                kind,
+                input,
+                offset,
+            } => {
+                write!(f, "{} '{}' has invalid display format", kind, input)?;
+                if let Some(offset) = offset {
+                    write!(f, " at byte {}", offset)?;
+                }
+                Ok(())
             }
             SpatialIdError::InvalidShardMerge => {
                 write!(
@@ -0,0 +1,477 @@
+//! GeoJSON（RFC 7946）の地物を、このクレートの図形へ変換して空間IDで覆う。
+//!
+//! 対応するジオメトリは `Point`・`LineString`・`Polygon`（穴あり）・`MultiPoint`・
+//! `MultiLineString`・`MultiPolygon`・`GeometryCollection` で、入力は
+//! `FeatureCollection`・`Feature`・ジオメトリ単体のいずれでもよい。
+//!
+//! 位置 `[経度, 緯度, 高度]` の高度を省略した場合は、[`GeoJsonReader::altitude_property`]
+//! で指定した地物プロパティ、それも無ければ `0` を使う。
+//! [`GeoJsonReader::extrusion_property`] で指定したプロパティ（メートル）を持つ地物は
+//! 上方向へ押し出し、点は鉛直の [`Line`]、線は鉛直の壁（[`Polygon`]）、
+//! 面は角柱の [`Prism`] になる。
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use hashbrown::HashSet;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::spatial_id::collection::flex_tree::core::ptr::SafeValue;
+use crate::{
+    Coordinate, CoverSingleIds, Error, Line, MultiPolygon, Polygon, Prism, SingleId, SpatialIdSet,
+    SpatialIdTable,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// GeoJSON のジオメトリ 1 つ分を、このクレートの図形で表したもの。
+///
+/// `LineString` は線分ごとに [`Line`] へ分け、`MultiPoint`・`MultiLineString`・
+/// `GeometryCollection` と押し出した `MultiPolygon` は要素ごとに分けるので、
+/// 1 つのジオメトリから複数の `GeoJsonShape` ができる。
+#[derive(Debug, Clone)]
+pub enum GeoJsonShape {
+    /// `Point`。
+    Point(Coordinate),
+    /// `LineString` の 1 線分、または押し出した `Point`。
+    Line(Line),
+    /// 穴を持ちうる `Polygon`、または押し出した `LineString` の 1 線分（壁）。
+    Surface(Polygon),
+    /// `MultiPolygon`。
+    MultiSurface(MultiPolygon),
+    /// 押し出した `Polygon`、または押し出した `MultiPolygon` の 1 つの面。
+    ///
+    /// 底面の高度は外周の頂点のうち最も低いもの。
+    Solid(Prism),
+}
+
+/// GeoJSON の地物 1 つ分。
+#[derive(Debug, Clone)]
+pub struct GeoJsonFeature {
+    /// ジオメトリを変換した図形。ジオメトリが `null` の地物では空。
+    pub shapes: Vec<GeoJsonShape>,
+    /// 地物の `properties`。`null` や省略時は空。
+    pub properties: Map<String, Value>,
+}
+
+/// GeoJSON を読み、図形や空間IDの集合へ変換するリーダ。
+///
+/// # 動作例
+///
+/// タイトル: 押し出した建物の外形を集合にする
+/// ```
+/// # use kasane_logic::GeoJsonReader;
+/// let json = r#"{
+///   "type": "Feature",
+///   "properties": { "height": 12.0 },
+///   "geometry": {
+///     "type": "Polygon",
+///     "coordinates": [[[139.7670, 35.6810], [139.7673, 35.6810],
+///                       [139.7673, 35.6812], [139.7670, 35.6812], [139.7670, 35.6810]]]
+///   }
+/// }"#;
+///
+/// let set = GeoJsonReader::new()
+///     .extrusion_property("height")
+///     .to_set(json, 22)
+///     .unwrap();
+/// assert!(!set.is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct GeoJsonReader {
+    altitude_property: Option<String>,
+    extrusion_property: Option<String>,
+    epsilon: f64,
+}
+
+impl Default for GeoJsonReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GeoJsonReader {
+    /// 同一点とみなす許容誤差の既定値（メートル）。
+    pub const DEFAULT_EPSILON: f64 = 0.01;
+
+    /// 高度・押し出しのプロパティを持たないリーダを作る。
+    pub fn new() -> Self {
+        Self {
+            altitude_property: None,
+            extrusion_property: None,
+            epsilon: Self::DEFAULT_EPSILON,
+        }
+    }
+
+    /// 位置が高度を持たないときに使う、地物プロパティの名前を設定する。
+    pub fn altitude_property(mut self, name: impl Into<String>) -> Self {
+        self.altitude_property = Some(name.into());
+        self
+    }
+
+    /// 押し出す高さ（メートル）を表す地物プロパティの名前を設定する。
+    ///
+    /// 値が `0` 以下の地物は押し出さない。
+    pub fn extrusion_property(mut self, name: impl Into<String>) -> Self {
+        self.extrusion_property = Some(name.into());
+        self
+    }
+
+    /// 同一点とみなす許容誤差（メートル）を設定する。[`Polygon::new`] へそのまま渡す。
+    pub fn epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// GeoJSON 文字列を地物の列へ変換する。
+    ///
+    /// JSON として不正な場合や GeoJSON の構造に合わない場合は [`Error::GeoJson`]、
+    /// 座標が範囲外の場合は [`Coordinate::new`] のエラーを返す。
+    pub fn read_features(&self, json: &str) -> Result<Vec<GeoJsonFeature>, Error> {
+        let root: Value = serde_json::from_str(json).map_err(|e| Error::GeoJson(e.to_string()))?;
+        let mut features = Vec::new();
+        self.collect_features(&root, &mut features)?;
+        Ok(features)
+    }
+
+    /// GeoJSON のすべての地物を、ズームレベル `z` の空間IDで覆った集合を返す。
+    pub fn to_set(&self, json: &str, z: u8) -> Result<SpatialIdSet, Error> {
+        let mut set = SpatialIdSet::new();
+        for feature in self.read_features(json)? {
+            for id in feature.cover_single_ids(z)? {
+                set.insert(id);
+            }
+        }
+        Ok(set)
+    }
+
+    /// GeoJSON の各地物をズームレベル `z` の空間IDで覆い、プロパティ `value_property`
+    /// の値を持たせたテーブルを返す。
+    ///
+    /// 地物同士が重なる場合は、後に現れた地物の値で上書きする。
+    /// `value_property` を持たない地物や、値を `V` として解釈できない地物があれば
+    /// [`Error::GeoJson`] を返す。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 区域の用途をテーブルにする
+    /// ```
+    /// # use kasane_logic::{GeoJsonReader, SpatialIdTable};
+    /// let json = r#"{"type": "FeatureCollection", "features": [
+    ///   {"type": "Feature", "properties": {"use": "park"},
+    ///    "geometry": {"type": "Point", "coordinates": [139.7670, 35.6810, 3.0]}},
+    ///   {"type": "Feature", "properties": {"use": "road"},
+    ///    "geometry": {"type": "LineString",
+    ///                 "coordinates": [[139.7680, 35.6810], [139.7681, 35.6811]]}}
+    /// ]}"#;
+    ///
+    /// let table: SpatialIdTable<String> = GeoJsonReader::new().to_table(json, 22, "use").unwrap();
+    /// assert_eq!(table.values().count(), 2);
+    /// ```
+    pub fn to_table<V>(
+        &self,
+        json: &str,
+        z: u8,
+        value_property: &str,
+    ) -> Result<SpatialIdTable<V>, Error>
+    where
+        V: SafeValue + Ord + DeserializeOwned,
+    {
+        let mut table = SpatialIdTable::new();
+        for (index, feature) in self.read_features(json)?.into_iter().enumerate() {
+            let value = feature.properties.get(value_property).ok_or_else(|| {
+                Error::GeoJson(format!(
+                    "feature {index} has no property '{value_property}'"
+                ))
+            })?;
+            let value: V = serde_json::from_value(value.clone()).map_err(|e| {
+                Error::GeoJson(format!(
+                    "property '{value_property}' of feature {index}: {e}"
+                ))
+            })?;
+            for id in feature.cover_single_ids(z)? {
+                table.insert(id, value.clone());
+            }
+        }
+        Ok(table)
+    }
+
+    fn collect_features(&self, value: &Value, out: &mut Vec<GeoJsonFeature>) -> Result<(), Error> {
+        match member_str(value, "type")? {
+            "FeatureCollection" => {
+                for feature in member_array(value, "features")? {
+                    self.collect_features(feature, out)?;
+                }
+            }
+            "Feature" => {
+                let properties = match value.get("properties") {
+                    None | Some(Value::Null) => Map::new(),
+                    Some(Value::Object(properties)) => properties.clone(),
+                    Some(_) => return Err(invalid("'properties' must be an object or null")),
+                };
+                let mut shapes = Vec::new();
+                match value.get("geometry") {
+                    None | Some(Value::Null) => {}
+                    Some(geometry) => {
+                        let extrusion =
+                            self.number_property(&properties, &self.extrusion_property)?;
+                        let context = Context {
+                            altitude: self
+                                .number_property(&properties, &self.altitude_property)?
+                                .unwrap_or(0.0),
+                            extrusion: extrusion.filter(|h| *h > 0.0),
+                            epsilon: self.epsilon,
+                        };
+                        context.collect_shapes(geometry, &mut shapes)?;
+                    }
+                }
+                out.push(GeoJsonFeature { shapes, properties });
+            }
+            _ => {
+                let mut shapes = Vec::new();
+                let context = Context {
+                    altitude: 0.0,
+                    extrusion: None,
+                    epsilon: self.epsilon,
+                };
+                context.collect_shapes(value, &mut shapes)?;
+                out.push(GeoJsonFeature {
+                    shapes,
+                    properties: Map::new(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn number_property(
+        &self,
+        properties: &Map<String, Value>,
+        name: &Option<String>,
+    ) -> Result<Option<f64>, Error> {
+        let Some(name) = name else {
+            return Ok(None);
+        };
+        match properties.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => value
+                .as_f64()
+                .map(Some)
+                .ok_or_else(|| Error::GeoJson(format!("property '{name}' is not a number"))),
+        }
+    }
+}
+
+/// 1 つの地物のジオメトリを変換するときの設定。
+struct Context {
+    /// 位置が高度を持たないときの高度。
+    altitude: f64,
+    /// 押し出す高さ。押し出さないなら `None`。
+    extrusion: Option<f64>,
+    epsilon: f64,
+}
+
+impl Context {
+    fn collect_shapes(&self, geometry: &Value, out: &mut Vec<GeoJsonShape>) -> Result<(), Error> {
+        match member_str(geometry, "type")? {
+            "GeometryCollection" => {
+                for geometry in member_array(geometry, "geometries")? {
+                    self.collect_shapes(geometry, out)?;
+                }
+            }
+            "Point" => self.point(coordinates(geometry)?, out)?,
+            "MultiPoint" => {
+                for point in as_array(coordinates(geometry)?)? {
+                    self.point(point, out)?;
+                }
+            }
+            "LineString" => self.line_string(coordinates(geometry)?, out)?,
+            "MultiLineString" => {
+                for line_string in as_array(coordinates(geometry)?)? {
+                    self.line_string(line_string, out)?;
+                }
+            }
+            "Polygon" => {
+                let polygon = self.polygon(coordinates(geometry)?)?;
+                out.push(match self.extrusion {
+                    None => GeoJsonShape::Surface(polygon),
+                    Some(height) => GeoJsonShape::Solid(prism(polygon, height)?),
+                });
+            }
+            "MultiPolygon" => {
+                let polygons = as_array(coordinates(geometry)?)?
+                    .iter()
+                    .map(|polygon| self.polygon(polygon))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                match self.extrusion {
+                    None => out.push(GeoJsonShape::MultiSurface(MultiPolygon::new(polygons))),
+                    Some(height) => {
+                        for polygon in polygons {
+                            out.push(GeoJsonShape::Solid(prism(polygon, height)?));
+                        }
+                    }
+                }
+            }
+            other => return Err(Error::GeoJson(format!("unknown type '{other}'"))),
+        }
+        Ok(())
+    }
+
+    fn point(&self, position: &Value, out: &mut Vec<GeoJsonShape>) -> Result<(), Error> {
+        let point = self.position(position)?;
+        out.push(match self.extrusion {
+            None => GeoJsonShape::Point(point),
+            Some(height) => GeoJsonShape::Line(Line::new([point, raise(&point, height)?])),
+        });
+        Ok(())
+    }
+
+    fn line_string(&self, positions: &Value, out: &mut Vec<GeoJsonShape>) -> Result<(), Error> {
+        let points = self.positions(positions)?;
+        if points.len() < 2 {
+            return Err(invalid("a LineString needs at least 2 positions"));
+        }
+        for pair in points.windows(2) {
+            match self.extrusion {
+                None => out.push(GeoJsonShape::Line(Line::new([pair[0], pair[1]]))),
+                Some(height) => {
+                    let wall = Polygon::new(
+                        vec![
+                            pair[0],
+                            pair[1],
+                            raise(&pair[1], height)?,
+                            raise(&pair[0], height)?,
+                        ],
+                        self.epsilon,
+                    );
+                    // 同じ位置が続いた線分は壁にならない。
+                    if !wall.vertices().is_empty() {
+                        out.push(GeoJsonShape::Surface(wall));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 外周と穴のリングを [`Polygon::with_holes`] で 1 つの面にする。
+    fn polygon(&self, rings: &Value) -> Result<Polygon, Error> {
+        let mut rings = as_array(rings)?
+            .iter()
+            .map(|ring| self.positions(ring))
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter();
+        let exterior = rings
+            .next()
+            .ok_or_else(|| invalid("a Polygon needs an exterior ring"))?;
+        let holes: Vec<_> = rings.collect();
+        let hole_count = holes.len();
+
+        let polygon = Polygon::with_holes(exterior, holes, self.epsilon);
+        // with_holes は 3 点に満たないリングを黙って捨てるので、ここで不正な入力として止める。
+        if polygon.vertices().is_empty() || polygon.holes().len() != hole_count {
+            return Err(invalid(
+                "a Polygon ring needs at least 3 distinct positions",
+            ));
+        }
+        Ok(polygon)
+    }
+
+    fn positions(&self, positions: &Value) -> Result<Vec<Coordinate>, Error> {
+        as_array(positions)?
+            .iter()
+            .map(|position| self.position(position))
+            .collect()
+    }
+
+    /// `[経度, 緯度]` または `[経度, 緯度, 高度]` を [`Coordinate`] へ変換する。
+    fn position(&self, position: &Value) -> Result<Coordinate, Error> {
+        let numbers = as_array(position)?;
+        let number = |i: usize| {
+            numbers.get(i).map(|n| {
+                n.as_f64()
+                    .ok_or_else(|| invalid("a position must hold numbers"))
+            })
+        };
+        let longitude = number(0).ok_or_else(|| invalid("a position needs a longitude"))??;
+        let latitude = number(1).ok_or_else(|| invalid("a position needs a latitude"))??;
+        let altitude = number(2).transpose()?.unwrap_or(self.altitude);
+        Coordinate::new(latitude, longitude, altitude)
+    }
+}
+
+/// `polygon` を、外周の最も低い頂点の高度から `height` だけ上方向へ押し出した角柱を作る。
+fn prism(polygon: Polygon, height: f64) -> Result<Prism, Error> {
+    let bottom = polygon
+        .vertices()
+        .iter()
+        .map(Coordinate::altitude)
+        .fold(f64::INFINITY, f64::min);
+    Prism::new(polygon, bottom, bottom + height)
+}
+
+fn raise(point: &Coordinate, height: f64) -> Result<Coordinate, Error> {
+    Coordinate::new(
+        point.latitude(),
+        point.longitude(),
+        point.altitude() + height,
+    )
+}
+
+fn invalid(message: &str) -> Error {
+    Error::GeoJson(message.into())
+}
+
+fn member_str<'a>(value: &'a Value, key: &str) -> Result<&'a str, Error> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::GeoJson(format!("missing string member '{key}'")))
+}
+
+fn member_array<'a>(value: &'a Value, key: &str) -> Result<&'a Vec<Value>, Error> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .ok_or_else(|| Error::GeoJson(format!("missing array member '{key}'")))
+}
+
+fn coordinates(geometry: &Value) -> Result<&Value, Error> {
+    geometry
+        .get("coordinates")
+        .ok_or_else(|| invalid("missing member 'coordinates'"))
+}
+
+fn as_array(value: &Value) -> Result<&Vec<Value>, Error> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid("'coordinates' has an unexpected nesting"))
+}
+
+impl CoverSingleIds for GeoJsonShape {
+    /// 図形を覆う [`SingleId`] を返す。穴の扱いは [`Polygon`]・[`Prism`] の被覆に従う。
+    fn cover_single_ids(&self, z: u8) -> Result<impl Iterator<Item = SingleId>, Error> {
+        let mut ids: HashSet<SingleId> = HashSet::new();
+        match self {
+            GeoJsonShape::Point(point) => ids.extend(point.cover_single_ids(z)?),
+            GeoJsonShape::Line(line) => ids.extend(line.cover_single_ids(z)?),
+            GeoJsonShape::Surface(polygon) => ids.extend(polygon.cover_single_ids(z)?),
+            GeoJsonShape::MultiSurface(polygons) => ids.extend(polygons.cover_single_ids(z)?),
+            GeoJsonShape::Solid(prism) => ids.extend(prism.cover_single_ids(z)?),
+        }
+        Ok(ids.into_iter())
+    }
+}
+
+impl CoverSingleIds for GeoJsonFeature {
+    /// 地物のすべての図形を覆う [`SingleId`] の和集合を返す。
+    fn cover_single_ids(&self, z: u8) -> Result<impl Iterator<Item = SingleId>, Error> {
+        let mut ids: HashSet<SingleId> = HashSet::new();
+        for shape in &self.shapes {
+            ids.extend(shape.cover_single_ids(z)?);
+        }
+        Ok(ids.into_iter())
+    }
+}
@@ -0,0 +1,263 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::{GeoJsonReader, GeoJsonShape};
+use crate::{Coordinate, CoverSingleIds, Error, SingleId, SpatialIdSet, SpatialIdTable};
+
+/// 外周 約180m×200m、中央に 約54m×67m の穴を持つ区画。
+const COURTYARD: &str = r#"{
+  "type": "Feature",
+  "properties": { "name": "courtyard", "base": 5.0, "height": 30.0 },
+  "geometry": {
+    "type": "Polygon",
+    "coordinates": [
+      [[139.7660, 35.6800], [139.7680, 35.6800], [139.7680, 35.6818], [139.7660, 35.6818], [139.7660, 35.6800]],
+      [[139.7667, 35.6806], [139.7673, 35.6806], [139.7673, 35.6812], [139.7667, 35.6812], [139.7667, 35.6806]]
+    ]
+  }
+}"#;
+
+fn contains(set: &SpatialIdSet, id: &SingleId) -> bool {
+    set.get_overlapping(id).next().is_some()
+}
+
+fn id_at(latitude: f64, longitude: f64, altitude: f64, z: u8) -> SingleId {
+    Coordinate::new(latitude, longitude, altitude)
+        .unwrap()
+        .single_id(z)
+        .unwrap()
+}
+
+mod read_features {
+    use super::*;
+
+    #[test]
+    fn point_uses_altitude_from_position_or_property() {
+        let json = r#"{"type": "FeatureCollection", "features": [
+          {"type": "Feature", "properties": {"base": 40.0},
+           "geometry": {"type": "Point", "coordinates": [139.767, 35.681, 12.0]}},
+          {"type": "Feature", "properties": {"base": 40.0},
+           "geometry": {"type": "Point", "coordinates": [139.767, 35.681]}},
+          {"type": "Feature", "properties": null,
+           "geometry": {"type": "Point", "coordinates": [139.767, 35.681]}}
+        ]}"#;
+        let features = GeoJsonReader::new()
+            .altitude_property("base")
+            .read_features(json)
+            .unwrap();
+
+        let altitudes: Vec<f64> = features
+            .iter()
+            .map(|feature| match &feature.shapes[..] {
+                [GeoJsonShape::Point(point)] => point.altitude(),
+                other => panic!("unexpected shapes: {other:?}"),
+            })
+            .collect();
+        assert_eq!(altitudes, [12.0, 40.0, 0.0]);
+    }
+
+    #[test]
+    fn line_string_is_split_into_segments() {
+        let json = r#"{"type": "MultiLineString", "coordinates": [
+          [[139.7670, 35.6810], [139.7671, 35.6811], [139.7672, 35.6810]],
+          [[139.7680, 35.6810], [139.7681, 35.6811]]
+        ]}"#;
+        let features = GeoJsonReader::new().read_features(json).unwrap();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0].shapes.len(), 3);
+        assert!(
+            features[0]
+                .shapes
+                .iter()
+                .all(|shape| matches!(shape, GeoJsonShape::Line(_)))
+        );
+    }
+
+    #[test]
+    fn polygons_keep_holes_and_multi_polygons_stay_together() {
+        let features = GeoJsonReader::new().read_features(COURTYARD).unwrap();
+        assert!(matches!(
+            &features[0].shapes[..],
+            [GeoJsonShape::Surface(polygon)] if polygon.vertices().len() == 4 && polygon.holes().len() == 1
+        ));
+
+        let json = r#"{"type": "MultiPolygon", "coordinates": [
+          [[[139.7660, 35.6800], [139.7662, 35.6800], [139.7662, 35.6802], [139.7660, 35.6800]]],
+          [[[139.7670, 35.6810], [139.7672, 35.6810], [139.7672, 35.6812], [139.7670, 35.6810]]]
+        ]}"#;
+        let features = GeoJsonReader::new().read_features(json).unwrap();
+        assert!(matches!(
+            &features[0].shapes[..],
+            [GeoJsonShape::MultiSurface(polygons)] if polygons.polygons().len() == 2
+        ));
+    }
+
+    #[test]
+    fn extrusion_lifts_each_kind_by_one_dimension() {
+        let json = r#"{"type": "Feature", "properties": {"height": 10},
+          "geometry": {"type": "GeometryCollection", "geometries": [
+            {"type": "Point", "coordinates": [139.767, 35.681]},
+            {"type": "LineString", "coordinates": [[139.7670, 35.6810], [139.7671, 35.6811]]},
+            {"type": "MultiPolygon", "coordinates": [
+              [[[139.7660, 35.6800], [139.7662, 35.6800], [139.7662, 35.6802], [139.7660, 35.6800]]]
+            ]}
+          ]}}"#;
+        let features = GeoJsonReader::new()
+            .extrusion_property("height")
+            .read_features(json)
+            .unwrap();
+        let shapes = &features[0].shapes;
+        assert_eq!(shapes.len(), 3);
+        match &shapes[0] {
+            GeoJsonShape::Line(line) => {
+                assert_eq!(line.points[0].altitude(), 0.0);
+                assert_eq!(line.points[1].altitude(), 10.0);
+            }
+            other => panic!("unexpected shape: {other:?}"),
+        }
+        assert!(matches!(
+            &shapes[1],
+            GeoJsonShape::Surface(wall) if wall.vertices().len() == 4 && wall.holes().is_empty()
+        ));
+        assert!(matches!(
+            &shapes[2],
+            GeoJsonShape::Solid(prism) if prism.bottom() == 0.0 && prism.footprint().holes().is_empty()
+        ));
+    }
+
+    #[test]
+    fn null_geometry_keeps_properties() {
+        let json = r#"{"type": "Feature", "properties": {"id": 7}, "geometry": null}"#;
+        let features = GeoJsonReader::new().read_features(json).unwrap();
+        assert!(features[0].shapes.is_empty());
+        assert_eq!(features[0].properties["id"], 7);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let reader = GeoJsonReader::new();
+        for json in [
+            "{",
+            r#"{"type": "Circle", "coordinates": [0, 0]}"#,
+            r#"{"type": "Point", "coordinates": [139.0]}"#,
+            r#"{"type": "Point", "coordinates": ["139", 35]}"#,
+            r#"{"type": "LineString", "coordinates": [[139.0, 35.0]]}"#,
+            r#"{"type": "Polygon", "coordinates": [[[139.0, 35.0], [139.1, 35.0], [139.0, 35.0]]]}"#,
+            r#"{"type": "Polygon", "coordinates": []}"#,
+        ] {
+            assert!(
+                matches!(reader.read_features(json), Err(Error::GeoJson(_))),
+                "{json}"
+            );
+        }
+
+        // 範囲外の緯度は Coordinate::new のエラーになる。
+        let json = r#"{"type": "Point", "coordinates": [139.0, 95.0]}"#;
+        assert!(matches!(
+            reader.read_features(json),
+            Err(Error::Geometry(_))
+        ));
+
+        let json = r#"{"type": "Feature", "properties": {"height": "tall"},
+          "geometry": {"type": "Point", "coordinates": [139.0, 35.0]}}"#;
+        assert!(matches!(
+            reader.extrusion_property("height").read_features(json),
+            Err(Error::GeoJson(_))
+        ));
+    }
+}
+
+mod to_set {
+    use super::*;
+
+    #[test]
+    fn point_matches_coordinate_cover() {
+        let json = r#"{"type": "Point", "coordinates": [139.767, 35.681, 12.0]}"#;
+        let set = GeoJsonReader::new().to_set(json, 25).unwrap();
+        let mut expected = SpatialIdSet::new();
+        for id in Coordinate::new(35.681, 139.767, 12.0)
+            .unwrap()
+            .cover_single_ids(25)
+            .unwrap()
+        {
+            expected.insert(id);
+        }
+        assert_eq!(set, expected);
+    }
+
+    #[test]
+    fn polygon_hole_interior_is_not_covered() {
+        let z = 22;
+        let set = GeoJsonReader::new()
+            .altitude_property("base")
+            .to_set(COURTYARD, z)
+            .unwrap();
+
+        // 外周と穴の間は覆う。
+        assert!(contains(&set, &id_at(35.6803, 139.7663, 5.0, z)));
+        // 穴の中心は覆わない。
+        assert!(!contains(&set, &id_at(35.6809, 139.7670, 5.0, z)));
+        // 穴の縁は残す。
+        assert!(contains(&set, &id_at(35.6806, 139.7670, 5.0, z)));
+    }
+
+    #[test]
+    fn extruded_polygon_hole_keeps_walls() {
+        let z = 22;
+        let set = GeoJsonReader::new()
+            .altitude_property("base")
+            .extrusion_property("height")
+            .to_set(COURTYARD, z)
+            .unwrap();
+
+        // 外周と穴の間は、床から屋根までの途中の高さも覆う。
+        assert!(contains(&set, &id_at(35.6803, 139.7663, 20.0, z)));
+        // 中庭は、床・屋根の高さも含めて覆わない。
+        for altitude in [5.0, 20.0, 35.0] {
+            assert!(!contains(&set, &id_at(35.6809, 139.7670, altitude, z)));
+        }
+        // 中庭の側面は残す。
+        assert!(contains(&set, &id_at(35.6806, 139.7670, 20.0, z)));
+        // 屋根より上は覆わない。
+        assert!(!contains(&set, &id_at(35.6803, 139.7663, 80.0, z)));
+    }
+}
+
+mod to_table {
+    use super::*;
+
+    #[test]
+    fn carries_property_as_value() {
+        let json = r#"{"type": "FeatureCollection", "features": [
+          {"type": "Feature", "properties": {"class": 1},
+           "geometry": {"type": "Point", "coordinates": [139.7670, 35.6810, 3.0]}},
+          {"type": "Feature", "properties": {"class": 2},
+           "geometry": {"type": "LineString", "coordinates": [[139.7680, 35.6810, 3.0], [139.7681, 35.6811, 3.0]]}}
+        ]}"#;
+        let table: SpatialIdTable<u32> = GeoJsonReader::new().to_table(json, 23, "class").unwrap();
+
+        let point = id_at(35.6810, 139.7670, 3.0, 23);
+        let found: Vec<u32> = table
+            .get_overlapping(&point)
+            .map(|(_, value)| *value)
+            .collect();
+        assert_eq!(found, [1]);
+        assert_eq!(table.values().copied().collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn rejects_missing_or_mistyped_property() {
+        let json = r#"{"type": "Feature", "properties": {"class": "road"},
+          "geometry": {"type": "Point", "coordinates": [139.767, 35.681]}}"#;
+        let reader = GeoJsonReader::new();
+        assert!(matches!(
+            reader.to_table::<String>(json, 20, "name"),
+            Err(Error::GeoJson(_))
+        ));
+        assert!(matches!(
+            reader.to_table::<u32>(json, 20, "class"),
+            Err(Error::GeoJson(_))
+        ));
+        assert!(reader.to_table::<String>(json, 20, "class").is_ok());
+    }
+}
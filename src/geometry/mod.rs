@@ -6,6 +6,9 @@
 /// 地理空間座標の計算で使用される各種の代表的な定数。
 pub mod constants;
 
+#[cfg(feature = "json")]
+pub mod geojson;
+
 pub mod point;
 
 pub mod shape;
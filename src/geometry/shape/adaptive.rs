@@ -0,0 +1,170 @@
+//! 図形の被覆を、粗さの混じった [`FlexId`] へまとめ直すための補助。
+//!
+//! 最大ズームレベルで求めた被覆（互いに重ならない [`RangeId`] の列）を入力に、
+//! ズームレベル 0 の空間から始めて 1 軸ずつ 2 分割していく。
+//! 分割した空間が被覆で（許容誤差の範囲まで）埋まっていればその空間を 1 つの [`FlexId`] として出力し、
+//! 被覆と交わらなければ捨て、どちらでもなければさらに分割する。
+
+use alloc::vec::Vec;
+
+use crate::spatial_id::zoom_level::ZoomLevel;
+use crate::{Error, FlexId, GeometryError, RangeId};
+
+/// 最大ズームレベルでのインデックスの閉区間を `[F, X, Y]` の順に並べた直方体。
+type Cuboid = [[i64; 2]; 3];
+
+/// 分割途中の空間。軸ごとのズームレベルとインデックスを `[F, X, Y]` の順に持つ。
+#[derive(Clone, Copy)]
+struct Cell {
+    zoom: [u8; 3],
+    index: [i64; 3],
+}
+
+impl Cell {
+    /// 最大ズームレベル `max_z` でのインデックスの範囲。
+    fn bounds(&self, max_z: u8) -> Cuboid {
+        core::array::from_fn(|axis| {
+            let shift = max_z - self.zoom[axis];
+            let index = self.index[axis];
+            [index << shift, ((index + 1) << shift) - 1]
+        })
+    }
+
+    /// `axis` 方向に 2 分割する。
+    fn split(&self, axis: usize) -> [Cell; 2] {
+        let mut lower = *self;
+        lower.zoom[axis] += 1;
+        lower.index[axis] *= 2;
+        let mut upper = lower;
+        upper.index[axis] += 1;
+        [lower, upper]
+    }
+
+    fn flex_id(&self) -> Result<FlexId, Error> {
+        FlexId::new(
+            self.zoom[0],
+            self.index[0] as i32,
+            self.zoom[1],
+            self.index[1] as u32,
+            self.zoom[2],
+            self.index[2] as u32,
+        )
+    }
+}
+
+fn volume(cuboid: &Cuboid) -> u128 {
+    cuboid
+        .iter()
+        .map(|[lo, hi]| (hi - lo + 1) as u128)
+        .product()
+}
+
+/// `a` と `b` の共通部分。交わらなければ `None`。
+fn intersect(a: &Cuboid, b: &Cuboid) -> Option<Cuboid> {
+    let mut out = [[0; 2]; 3];
+    for axis in 0..3 {
+        let lo = a[axis][0].max(b[axis][0]);
+        let hi = a[axis][1].min(b[axis][1]);
+        if lo > hi {
+            return None;
+        }
+        out[axis] = [lo, hi];
+    }
+    Some(out)
+}
+
+/// ズームレベル `max_z` の被覆 `ranges` を、できるだけ粗い [`FlexId`] の列にまとめ直す。
+///
+/// `ranges` は互いに重ならないこと。`tolerance` は 1 つの [`FlexId`] のうち
+/// `ranges` の外にはみ出してよい体積の割合で、`0.0..1.0` でなければエラーを返す。
+pub(crate) fn flex_ids(
+    max_z: u8,
+    ranges: impl IntoIterator<Item = RangeId>,
+    tolerance: f64,
+) -> Result<Vec<FlexId>, Error> {
+    if !(0.0..1.0).contains(&tolerance) {
+        return Err(GeometryError::ToleranceOutOfRange { tolerance }.into());
+    }
+    let max_z = ZoomLevel::new(max_z)?.get();
+
+    let cuboids: Vec<Cuboid> = ranges
+        .into_iter()
+        .map(|range| {
+            let ([f0, f1], [x0, x1], [y0, y1]) = (range.f(), range.x(), range.y());
+            [
+                [f0 as i64, f1 as i64],
+                [x0 as i64, x1 as i64],
+                [y0 as i64, y1 as i64],
+            ]
+        })
+        .collect();
+
+    let mut out = Vec::new();
+    // ズームレベル 0 の F は -1（地下）と 0（地上）の 2 つに分かれる。
+    for f in [-1, 0] {
+        let root = Cell {
+            zoom: [0; 3],
+            index: [f, 0, 0],
+        };
+        refine(root, &cuboids, max_z, tolerance, &mut out)?;
+    }
+    Ok(out)
+}
+
+/// `cell` を `cuboids` で埋まった空間と比べ、出力・破棄・分割のいずれかを行う。
+fn refine(
+    cell: Cell,
+    cuboids: &[Cuboid],
+    max_z: u8,
+    tolerance: f64,
+    out: &mut Vec<FlexId>,
+) -> Result<(), Error> {
+    let bounds = cell.bounds(max_z);
+    let inside: Vec<Cuboid> = cuboids
+        .iter()
+        .filter_map(|cuboid| intersect(cuboid, &bounds))
+        .collect();
+    if inside.is_empty() {
+        return Ok(());
+    }
+
+    let full = volume(&bounds);
+    let covered: u128 = inside.iter().map(volume).sum();
+    let uncovered = full - covered;
+    if uncovered == 0 || (uncovered as f64) <= tolerance * full as f64 {
+        out.push(cell.flex_id()?);
+        return Ok(());
+    }
+
+    // 分割してすぐに埋まる・空になる子が多い軸を選ぶ。同点なら粗い軸、さらに同点なら F, X, Y の順。
+    let Some(axis) = (0..3)
+        .filter(|&axis| cell.zoom[axis] < max_z)
+        .max_by_key(|&axis| {
+            let decided = cell
+                .split(axis)
+                .iter()
+                .filter(|child| {
+                    let bounds = child.bounds(max_z);
+                    let covered: u128 = inside
+                        .iter()
+                        .filter_map(|cuboid| intersect(cuboid, &bounds))
+                        .map(|cuboid| volume(&cuboid))
+                        .sum();
+                    covered == 0 || covered == volume(&bounds)
+                })
+                .count();
+            (
+                decided,
+                core::cmp::Reverse(cell.zoom[axis]),
+                core::cmp::Reverse(axis),
+            )
+        })
+    else {
+        // 最大ズームレベルの空間は埋まっているか交わらないかのどちらかなので、ここには来ない。
+        return Ok(());
+    };
+    for child in cell.split(axis) {
+        refine(child, &inside, max_z, tolerance, out)?;
+    }
+    Ok(())
+}
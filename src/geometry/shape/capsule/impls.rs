@@ -0,0 +1,81 @@
+use crate::geometry::shape::convex::{self, Convex, IdBox};
+use crate::geometry::shape::distance::{self, DistanceField};
+use crate::{
+    Capsule, Coordinate, CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds, Error,
+    RangeId, Shape, SingleId, SpatialPredicates, Vec3, Vec3Ecef,
+};
+
+impl Shape for Capsule {
+    fn center(&self) -> Coordinate {
+        Coordinate::center_gravity([self.start, self.end])
+    }
+}
+
+impl DistanceField for Capsule {
+    fn distance(&self, p: Vec3Ecef) -> f64 {
+        distance::segment_distance(p, self.start.into(), self.end.into()) - self.radius_m
+    }
+
+    fn ecef_bounds(&self) -> [Vec3Ecef; 2] {
+        let (a, b): (Vec3Ecef, Vec3Ecef) = (self.start.into(), self.end.into());
+        let r = self.radius_m;
+        [
+            Vec3Ecef::new(
+                a.a().min(b.a()) - r,
+                a.b().min(b.b()) - r,
+                a.c().min(b.c()) - r,
+            ),
+            Vec3Ecef::new(
+                a.a().max(b.a()) + r,
+                a.b().max(b.b()) + r,
+                a.c().max(b.c()) + r,
+            ),
+        ]
+    }
+}
+
+impl CoverSingleIds for Capsule {
+    /// 中心がカプセルからボクセルの対角線の半分以内にあるボクセルを返す。
+    fn cover_single_ids(&self, z: u8) -> Result<impl Iterator<Item = SingleId>, Error> {
+        let ranges = distance::range_ids(self, z)?;
+        Ok(ranges.into_iter().flat_map(|range| range.single_ids()))
+    }
+}
+
+impl CoverRangeIds for Capsule {
+    /// [`cover_single_ids`](CoverSingleIds::cover_single_ids) と同じ空間を [`RangeId`] で返す。
+    ///
+    /// `(F, Y)` の行ごとに、連続して覆うボクセルを 1 つの区間として集めてからまとめる。
+    fn cover_range_ids(&self, z: u8) -> Result<impl Iterator<Item = RangeId>, Error> {
+        Ok(distance::range_ids(self, z)?.into_iter())
+    }
+}
+
+impl CoverFlexIds for Capsule {}
+
+impl CoverClassifiedIds for Capsule {}
+
+impl Convex for Capsule {
+    fn support(&self, d: Vec3Ecef) -> Vec3Ecef {
+        convex::RoundedSegment {
+            a: self.start.into(),
+            b: self.end.into(),
+            radius: self.radius_m,
+        }
+        .support(d)
+    }
+}
+
+impl SpatialPredicates for Capsule {
+    fn intersects_part(&self, id: &RangeId) -> bool {
+        convex::intersects(self, &IdBox::new(id))
+    }
+
+    fn contains_part(&self, id: &RangeId) -> bool {
+        convex::contains_box(self, &IdBox::new(id))
+    }
+
+    fn within_part(&self, id: &RangeId) -> bool {
+        IdBox::new(id).encloses(self)
+    }
+}
@@ -0,0 +1,48 @@
+pub mod impls;
+#[cfg(test)]
+mod tests;
+
+use crate::{Coordinate, Error, GeometryError};
+
+/// 3次元空間におけるカプセル（両端を半球で閉じた円柱）を表す型。
+///
+/// 中心線の両端と半径によって定義され、中心線からの距離が半径以下の点全体を表す。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capsule {
+    start: Coordinate,
+    end: Coordinate,
+    radius_m: f64,
+}
+
+impl Capsule {
+    /// [Capsule]を作成する。
+    ///
+    /// 半径が正でない場合は [GeometryError::RadiusNegative] を返す。
+    /// `start` と `end` が同じ点なら球と同じ形になる。
+    pub fn new(start: Coordinate, end: Coordinate, radius_m: f64) -> Result<Self, Error> {
+        if radius_m > 0.0 {
+            Ok(Capsule {
+                start,
+                end,
+                radius_m,
+            })
+        } else {
+            Err(GeometryError::RadiusNegative { radius: radius_m }.into())
+        }
+    }
+
+    /// 中心線の始点を返す。
+    pub fn start(&self) -> Coordinate {
+        self.start
+    }
+
+    /// 中心線の終点を返す。
+    pub fn end(&self) -> Coordinate {
+        self.end
+    }
+
+    /// 半径（メートル）を返す。
+    pub fn radius_m(&self) -> f64 {
+        self.radius_m
+    }
+}
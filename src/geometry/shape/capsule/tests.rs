@@ -0,0 +1,69 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{Capsule, Coordinate, CoverSingleIds, Error, GeometryError};
+
+fn sorted_ids(capsule: &Capsule, z: u8) -> Vec<String> {
+    let mut ids: Vec<String> = capsule
+        .cover_single_ids(z)
+        .unwrap()
+        .map(|id| id.to_string())
+        .collect();
+    ids.sort();
+    ids
+}
+
+/// 高度 50 m から 80 m へ、東へ約 180 m 進む飛行経路。
+fn corridor() -> Capsule {
+    Capsule::new(
+        Coordinate::new(35.681, 139.766, 50.0).unwrap(),
+        Coordinate::new(35.681, 139.768, 80.0).unwrap(),
+        15.0,
+    )
+    .unwrap()
+}
+
+mod cover_single_ids {
+    use super::*;
+
+    /// 傾いた飛行経路を変換する
+    #[test]
+    fn sloped_corridor_at_z20() {
+        insta::assert_debug_snapshot!(sorted_ids(&corridor(), 20));
+    }
+
+    /// 両端の半球まで覆い、半径の外は覆わない
+    #[test]
+    fn covers_rounded_ends() {
+        let ids = sorted_ids(&corridor(), 22);
+        let contains = |lat: f64, lon: f64, altitude: f64| {
+            let id = Coordinate::new(lat, lon, altitude)
+                .unwrap()
+                .single_id(22)
+                .unwrap()
+                .to_string();
+            ids.binary_search(&id).is_ok()
+        };
+        // 両端の中心と、端から中心線の延長方向へ 10 m 先
+        assert!(contains(35.681, 139.766, 50.0));
+        assert!(contains(35.681, 139.768, 80.0));
+        assert!(contains(35.681, 139.7659, 50.0));
+        assert!(contains(35.681, 139.7681, 80.0));
+        // 端から 40 m 以上離れた点
+        assert!(!contains(35.681, 139.7655, 50.0));
+        assert!(!contains(35.681, 139.767, 20.0));
+    }
+}
+
+mod new {
+    use super::*;
+
+    #[test]
+    fn rejects_non_positive_radius() {
+        let p = Coordinate::new(35.681, 139.766, 50.0).unwrap();
+        assert!(matches!(
+            Capsule::new(p, p, 0.0),
+            Err(Error::Geometry(GeometryError::RadiusNegative { .. }))
+        ));
+    }
+}
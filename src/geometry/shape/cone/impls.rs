@@ -0,0 +1,112 @@
+use crate::geometry::shape::convex::{self, Convex, IdBox};
+use crate::geometry::shape::distance::{self, DistanceField};
+use crate::{
+    Cone, Coordinate, CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds, Error,
+    RangeId, Shape, SingleId, SpatialPredicates, Vec3, Vec3Ecef,
+};
+
+impl Shape for Cone {
+    fn center(&self) -> Coordinate {
+        Coordinate::center_gravity([self.base, self.top])
+    }
+}
+
+impl DistanceField for Cone {
+    /// 円錐台までの符号付き距離。
+    ///
+    /// 中心線を含む断面（中心線方向と、中心線からの距離の 2 次元）で、台形までの距離を求める。
+    fn distance(&self, p: Vec3Ecef) -> f64 {
+        let (a, b): (Vec3Ecef, Vec3Ecef) = (self.base.into(), self.top.into());
+        let (ra, rb) = (self.base_radius_m, self.top_radius_m);
+        let ba = b - a;
+        let pa = p - a;
+        let baba = ba.norm_squared();
+        let paba = pa.dot(&ba) / baba;
+        // 中心線からの距離
+        let x = libm::sqrt((pa.norm_squared() - paba * paba * baba).max(0.0));
+
+        // 底面・上面の円板までの成分
+        let cax = (x - if paba < 0.5 { ra } else { rb }).max(0.0);
+        let cay = libm::fabs(paba - 0.5) - 0.5;
+        // 側面の母線までの成分
+        let rba = rb - ra;
+        let k = rba * rba + baba;
+        let f = ((rba * (x - ra) + paba * baba) / k).clamp(0.0, 1.0);
+        let cbx = x - ra - f * rba;
+        let cby = paba - f;
+
+        let sign = if cbx < 0.0 && cay < 0.0 { -1.0 } else { 1.0 };
+        sign * libm::sqrt((cax * cax + cay * cay * baba).min(cbx * cbx + cby * cby * baba))
+    }
+
+    fn ecef_bounds(&self) -> [Vec3Ecef; 2] {
+        let (a, b): (Vec3Ecef, Vec3Ecef) = (self.base.into(), self.top.into());
+        let r = self.base_radius_m.max(self.top_radius_m);
+        [
+            Vec3Ecef::new(
+                a.a().min(b.a()) - r,
+                a.b().min(b.b()) - r,
+                a.c().min(b.c()) - r,
+            ),
+            Vec3Ecef::new(
+                a.a().max(b.a()) + r,
+                a.b().max(b.b()) + r,
+                a.c().max(b.c()) + r,
+            ),
+        ]
+    }
+}
+
+impl CoverSingleIds for Cone {
+    /// 中心が円錐台からボクセルの対角線の半分以内にあるボクセルを返す。
+    fn cover_single_ids(&self, z: u8) -> Result<impl Iterator<Item = SingleId>, Error> {
+        let ranges = distance::range_ids(self, z)?;
+        Ok(ranges.into_iter().flat_map(|range| range.single_ids()))
+    }
+}
+
+impl CoverRangeIds for Cone {
+    /// [`cover_single_ids`](CoverSingleIds::cover_single_ids) と同じ空間を [`RangeId`] で返す。
+    ///
+    /// `(F, Y)` の行ごとに、連続して覆うボクセルを 1 つの区間として集めてからまとめる。
+    fn cover_range_ids(&self, z: u8) -> Result<impl Iterator<Item = RangeId>, Error> {
+        Ok(distance::range_ids(self, z)?.into_iter())
+    }
+}
+
+impl CoverFlexIds for Cone {}
+
+impl CoverClassifiedIds for Cone {}
+
+impl Convex for Cone {
+    /// 両端の円板の支持点のうち、遠い方を返す。
+    fn support(&self, d: Vec3Ecef) -> Vec3Ecef {
+        let (base, top): (Vec3Ecef, Vec3Ecef) = (self.base.into(), self.top.into());
+        let axis = (top - base)
+            .normalize()
+            .expect("the axis of a cone has positive length");
+        let ends = [
+            convex::disk_support(base, axis, self.base_radius_m, d),
+            convex::disk_support(top, axis, self.top_radius_m, d),
+        ];
+        if ends[0].dot(&d) >= ends[1].dot(&d) {
+            ends[0]
+        } else {
+            ends[1]
+        }
+    }
+}
+
+impl SpatialPredicates for Cone {
+    fn intersects_part(&self, id: &RangeId) -> bool {
+        convex::intersects(self, &IdBox::new(id))
+    }
+
+    fn contains_part(&self, id: &RangeId) -> bool {
+        convex::contains_box(self, &IdBox::new(id))
+    }
+
+    fn within_part(&self, id: &RangeId) -> bool {
+        IdBox::new(id).encloses(self)
+    }
+}
@@ -0,0 +1,93 @@
+pub mod impls;
+#[cfg(test)]
+mod tests;
+
+use crate::{Coordinate, Error, GeometryError};
+
+/// 3次元空間における円錐・円錐台を表す型。
+///
+/// 中心線の両端と、それぞれの端での半径によって定義される。先端の半径が 0 なら円錐、
+/// 正なら円錐台になる。アンテナの覆域や、先細りする飛行経路の端部などを表す。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cone {
+    base: Coordinate,
+    top: Coordinate,
+    base_radius_m: f64,
+    top_radius_m: f64,
+}
+
+impl Cone {
+    /// 底面の中心 `base`、頂点 `apex`、底面の半径から円錐を作成する。
+    ///
+    /// 検証は [`Cone::frustum`] と同じ。
+    pub fn new(base: Coordinate, apex: Coordinate, base_radius_m: f64) -> Result<Self, Error> {
+        Self::frustum(base, apex, base_radius_m, 0.0)
+    }
+
+    /// 両端の中心と半径から円錐台を作成する。
+    ///
+    /// # バリデーション
+    /// - `base_radius_m` が正でない、または `top_radius_m` が負の場合は [GeometryError::RadiusNegative] を返す。
+    /// - 両端が同じ点の場合は [GeometryError::SizeNotPositive] を返す。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 上空へ広がるアンテナの覆域
+    /// ```
+    /// # use kasane_logic::{Cone, Coordinate, CoverSingleIds};
+    /// let antenna = Coordinate::new(35.681, 139.766, 30.0).unwrap();
+    /// let sky = Coordinate::new(35.681, 139.766, 130.0).unwrap();
+    /// let coverage = Cone::frustum(antenna, sky, 1.0, 50.0).unwrap();
+    ///
+    /// assert!(coverage.cover_single_ids(20).unwrap().count() > 0);
+    /// ```
+    pub fn frustum(
+        base: Coordinate,
+        top: Coordinate,
+        base_radius_m: f64,
+        top_radius_m: f64,
+    ) -> Result<Self, Error> {
+        if base_radius_m <= 0.0 {
+            return Err(GeometryError::RadiusNegative {
+                radius: base_radius_m,
+            }
+            .into());
+        }
+        if top_radius_m < 0.0 {
+            return Err(GeometryError::RadiusNegative {
+                radius: top_radius_m,
+            }
+            .into());
+        }
+        let height = base.distance(&top);
+        if height <= 0.0 {
+            return Err(GeometryError::SizeNotPositive { size: height }.into());
+        }
+        Ok(Cone {
+            base,
+            top,
+            base_radius_m,
+            top_radius_m,
+        })
+    }
+
+    /// 底面の中心を返す。
+    pub fn base(&self) -> Coordinate {
+        self.base
+    }
+
+    /// 先端（円錐なら頂点）の中心を返す。
+    pub fn top(&self) -> Coordinate {
+        self.top
+    }
+
+    /// 底面の半径（メートル）を返す。
+    pub fn base_radius_m(&self) -> f64 {
+        self.base_radius_m
+    }
+
+    /// 先端の半径（メートル）を返す。円錐なら 0。
+    pub fn top_radius_m(&self) -> f64 {
+        self.top_radius_m
+    }
+}
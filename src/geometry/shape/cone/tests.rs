@@ -0,0 +1,94 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{Cone, Coordinate, CoverRangeIds, CoverSingleIds, Error, GeometryError};
+
+fn sorted_ids(cone: &Cone, z: u8) -> Vec<String> {
+    let mut ids: Vec<String> = cone
+        .cover_single_ids(z)
+        .unwrap()
+        .map(|id| id.to_string())
+        .collect();
+    ids.sort();
+    ids
+}
+
+fn p(altitude: f64) -> Coordinate {
+    Coordinate::new(35.681, 139.766, altitude).unwrap()
+}
+
+mod cover_single_ids {
+    use super::*;
+
+    /// 地上から上空へ細くなる円錐を変換する
+    #[test]
+    fn upright_cone_at_z20() {
+        let cone = Cone::new(p(0.0), p(60.0), 30.0).unwrap();
+        insta::assert_debug_snapshot!(sorted_ids(&cone, 20));
+    }
+
+    /// 上空へ広がる円錐台を変換する
+    #[test]
+    fn widening_frustum_at_z20() {
+        let cone = Cone::frustum(p(10.0), p(50.0), 2.0, 25.0).unwrap();
+        insta::assert_debug_snapshot!(sorted_ids(&cone, 20));
+    }
+
+    /// 高さごとの断面は頂点へ近づくほど小さくなる
+    #[test]
+    fn layers_narrow_toward_apex() {
+        let cone = Cone::new(p(0.0), p(100.0), 50.0).unwrap();
+        let mut per_layer: Vec<(i32, usize)> = Vec::new();
+        for id in cone.cover_single_ids(22).unwrap() {
+            match per_layer.iter_mut().find(|(f, _)| *f == id.f()) {
+                Some((_, count)) => *count += 1,
+                None => per_layer.push((id.f(), 1)),
+            }
+        }
+        per_layer.sort();
+        assert!(per_layer.len() > 5);
+        for pair in per_layer.windows(2) {
+            assert!(pair[0].1 >= pair[1].1, "{per_layer:?}");
+        }
+    }
+}
+
+mod cover_range_ids {
+    use super::*;
+
+    /// RangeId 同士は重ならない
+    #[test]
+    fn ranges_are_disjoint() {
+        let cone = Cone::frustum(p(10.0), p(50.0), 2.0, 25.0).unwrap();
+        let mut ids: Vec<String> = cone
+            .cover_range_ids(22)
+            .unwrap()
+            .flat_map(|range| range.single_ids())
+            .map(|id| id.to_string())
+            .collect();
+        ids.sort();
+        let len = ids.len();
+        ids.dedup();
+        assert_eq!(ids.len(), len, "RangeId が重なっている");
+    }
+}
+
+mod new {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_cones() {
+        assert!(matches!(
+            Cone::new(p(0.0), p(10.0), 0.0),
+            Err(Error::Geometry(GeometryError::RadiusNegative { .. }))
+        ));
+        assert!(matches!(
+            Cone::frustum(p(0.0), p(10.0), 5.0, -1.0),
+            Err(Error::Geometry(GeometryError::RadiusNegative { .. }))
+        ));
+        assert!(matches!(
+            Cone::new(p(10.0), p(10.0), 5.0),
+            Err(Error::Geometry(GeometryError::SizeNotPositive { .. }))
+        ));
+    }
+}
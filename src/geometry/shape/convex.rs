@@ -0,0 +1,389 @@
+//! 凸な図形と空間IDの直方体の位置関係を、支持関数から判定するための補助。
+//!
+//! 凸な図形は、方向 `d` に最も遠い点（支持点）を返す関数で表す。2 つの凸な図形が交わるかは
+//! GJK 法で、凸な図形が直方体に収まるかは直方体の各面の法線方向の支持点で判定する。
+//! 凸でない図形は凸な部品（三角形・カプセルなど）に分けて判定する。
+
+use alloc::vec::Vec;
+
+use crate::spatial_id::helpers;
+use crate::{Coordinate, RangeId, SpatialId, Triangle, Vec3, Vec3Ecef};
+
+/// GJK 法の反復回数の上限。曲面どうしが接する場合などに打ち切る。
+const GJK_ITERATIONS: usize = 64;
+
+/// 凸包による判定を信用する、X・Y の最小のズームレベル。
+///
+/// これより粗い範囲は頂点の凸包が実際の領域から大きく外れる（経度方向に半周を超えると頂点が重なる）。
+pub(crate) const MIN_PREDICATE_ZOOM: u8 = 6;
+
+/// `range` を、X・Y がズームレベル [`MIN_PREDICATE_ZOOM`] の 1 マスに収まる部分へ割る。時間は外す。
+///
+/// `range` のズームレベルがそれより粗ければ [`MIN_PREDICATE_ZOOM`] の範囲に直してから割る。
+/// X が東西の端で折り返す範囲は、折り返しの前後で分ける。
+pub(crate) fn predicate_parts(range: &RangeId) -> Vec<RangeId> {
+    let z = range.z().max(MIN_PREDICATE_ZOOM);
+    let block = 1u32 << (z - MIN_PREDICATE_ZOOM);
+    // [lo, hi] を block の境界で区切る。
+    let chunks = |lo: u32, hi: u32| {
+        (lo / block..=hi / block).map(move |b| [lo.max(b * block), hi.min(b * block + block - 1)])
+    };
+
+    let (f0, f1) = range.f_fine_range(z);
+    let (x0, x1) = range.x_fine_range(z);
+    let (y0, y1) = range.y_fine_range(z);
+    let xs: Vec<[u32; 2]> = if x0 <= x1 {
+        chunks(x0, x1).collect()
+    } else {
+        chunks(x0, (1u32 << z) - 1).chain(chunks(0, x1)).collect()
+    };
+    let mut parts = Vec::new();
+    for y in chunks(y0, y1) {
+        for x in &xs {
+            parts.push(RangeId::new(z, [f0, f1], *x, y).expect("元の範囲を割った範囲は有効"));
+        }
+    }
+    parts
+}
+
+/// 支持点を返せる凸な図形。
+pub(crate) trait Convex {
+    /// 方向 `d` に最も遠い図形上の点。`d` は正規化されていなくてよい。
+    fn support(&self, d: Vec3Ecef) -> Vec3Ecef;
+}
+
+/// 点の集まりの凸包。
+pub(crate) struct Hull<const N: usize>(pub(crate) [Vec3Ecef; N]);
+
+impl<const N: usize> Convex for Hull<N> {
+    fn support(&self, d: Vec3Ecef) -> Vec3Ecef {
+        farthest(&self.0, d)
+    }
+}
+
+/// 線分 `[a, b]` から `radius` 以内の点の集まり。`radius` が 0 なら線分になる。
+pub(crate) struct RoundedSegment {
+    pub(crate) a: Vec3Ecef,
+    pub(crate) b: Vec3Ecef,
+    pub(crate) radius: f64,
+}
+
+impl Convex for RoundedSegment {
+    fn support(&self, d: Vec3Ecef) -> Vec3Ecef {
+        let end = farthest(&[self.a, self.b], d);
+        match d.normalize() {
+            Some(unit) => end + unit.scale(self.radius),
+            None => end,
+        }
+    }
+}
+
+/// 中心 `center`・法線 `axis`（単位ベクトル）・半径 `radius` の円板。
+pub(crate) fn disk_support(center: Vec3Ecef, axis: Vec3Ecef, radius: f64, d: Vec3Ecef) -> Vec3Ecef {
+    let radial = d - axis.scale(d.dot(&axis));
+    match radial.normalize() {
+        Some(unit) => center + unit.scale(radius),
+        None => center,
+    }
+}
+
+/// `points` のうち方向 `d` に最も遠い点。
+fn farthest(points: &[Vec3Ecef], d: Vec3Ecef) -> Vec3Ecef {
+    let mut best = points[0];
+    let mut best_dot = best.dot(&d);
+    for p in &points[1..] {
+        let dot = p.dot(&d);
+        if dot > best_dot {
+            best = *p;
+            best_dot = dot;
+        }
+    }
+    best
+}
+
+/// 空間IDの直方体。8 頂点の凸包を、地球の曲率による面のふくらみの分だけ膨らませて扱う。
+pub(crate) struct IdBox {
+    vertices: [Vec3Ecef; 8],
+    center: Vec3Ecef,
+    /// 外向きの法線と、その方向の頂点の最大値の組。頂点は全て `n·v <= d` を満たす。
+    planes: [(Vec3Ecef, f64); 6],
+    /// 曲面が凸包からはみ出しうる距離（メートル）。
+    margin: f64,
+}
+
+impl IdBox {
+    pub(crate) fn new<S: SpatialId>(id: &S) -> Self {
+        let vertices = box_vertices(&id.clone().into()).map(Vec3Ecef::from);
+        let centroid = vertices
+            .iter()
+            .fold(Vec3Ecef::new(0.0, 0.0, 0.0), |sum, v| sum + *v)
+            .scale(1.0 / 8.0);
+        // spatial_vertices は [高度][緯度][経度] の順で、添字は 高度 * 4 + 緯度 * 2 + 経度
+        let faces = [
+            [0, 1, 3, 2],
+            [4, 5, 7, 6],
+            [0, 2, 6, 4],
+            [1, 3, 7, 5],
+            [0, 1, 5, 4],
+            [2, 3, 7, 6],
+        ];
+        let planes = faces.map(|[a, b, c, d]| {
+            let (va, vb, vc, vd) = (vertices[a], vertices[b], vertices[c], vertices[d]);
+            let mut normal = (vc - va).cross(&(vd - vb));
+            let face_center = (va + vb + vc + vd).scale(0.25);
+            if normal.dot(&(face_center - centroid)) < 0.0 {
+                normal = normal.scale(-1.0);
+            }
+            let offset = vertices
+                .iter()
+                .map(|v| normal.dot(v))
+                .fold(f64::NEG_INFINITY, f64::max);
+            (normal, offset)
+        });
+        let margin = curvature_margin(&vertices);
+        Self {
+            vertices,
+            center: centroid,
+            planes: planes.map(|(normal, offset)| (normal, offset + margin * normal.norm())),
+            margin,
+        }
+    }
+
+    pub(crate) fn vertices(&self) -> &[Vec3Ecef; 8] {
+        &self.vertices
+    }
+
+    pub(crate) fn center(&self) -> Vec3Ecef {
+        self.center
+    }
+
+    /// 凸な図形 `shape` が直方体に収まるか。
+    pub(crate) fn encloses(&self, shape: &impl Convex) -> bool {
+        self.planes
+            .iter()
+            .all(|(normal, offset)| normal.dot(&shape.support(*normal)) <= *offset)
+    }
+}
+
+impl Convex for IdBox {
+    fn support(&self, d: Vec3Ecef) -> Vec3Ecef {
+        let vertex = farthest(&self.vertices, d);
+        match d.normalize() {
+            Some(unit) => vertex + unit.scale(self.margin),
+            None => vertex,
+        }
+    }
+}
+
+/// [`RangeId`] の 8 頂点。並びは [`spatial_vertices`](SpatialId::spatial_vertices) と同じ。
+///
+/// Web Mercator の上下端の緯度は [`Coordinate`] が受け付ける範囲をわずかに超えるので、
+/// 緯度だけ [`vertex_latitude`](helpers::vertex_latitude) で丸める。
+fn box_vertices(range: &RangeId) -> [Coordinate; 8] {
+    let z = range.z();
+    let [x0, x1] = range.x();
+    let [y0, y1] = range.y();
+    let [f0, f1] = range.f();
+    let longitudes = [x0 as f64, x1 as f64 + 1.0].map(|x| helpers::longitude(x, z));
+    let latitudes = [y0 as f64, y1 as f64 + 1.0].map(|y| helpers::vertex_latitude(y, z));
+    let altitudes = [f0 as f64, f1 as f64 + 1.0].map(|f| helpers::altitude(f, z));
+    core::array::from_fn(|i| {
+        Coordinate::new(
+            latitudes[(i >> 1) & 1],
+            longitudes[i & 1],
+            altitudes[i >> 2],
+        )
+        .expect("vertex must be within valid range")
+    })
+}
+
+/// 地心から見て `vertices` が張る角 `θ` の弧が、弦からふくらむ距離 `r (1 - cos(θ / 2))`。
+///
+/// 空間IDの側面のうち緯線に沿う面と、上下の面は曲面なので、頂点の凸包からこの距離だけはみ出しうる。
+/// ズームレベル 20 前後のボクセルでは 0.1 mm にも満たない。
+fn curvature_margin(vertices: &[Vec3Ecef; 8]) -> f64 {
+    let mut radius: f64 = 0.0;
+    let mut min_cos: f64 = 1.0;
+    for (i, a) in vertices.iter().enumerate() {
+        radius = radius.max(a.norm());
+        for b in &vertices[i + 1..] {
+            if let (Some(ua), Some(ub)) = (a.normalize(), b.normalize()) {
+                min_cos = min_cos.min(ua.dot(&ub));
+            }
+        }
+    }
+    // cos(θ / 2) = sqrt((1 + cos θ) / 2)
+    radius * (1.0 - libm::sqrt((1.0 + min_cos.max(-1.0)) / 2.0))
+}
+
+/// 凸な図形 `shape` が、直方体 `id_box` の全ての頂点を含むか。
+///
+/// GJK 法が打ち切られた頂点は、含まれると確かめられないので含まないとみなす。
+pub(crate) fn contains_box(shape: &impl Convex, id_box: &IdBox) -> bool {
+    id_box
+        .vertices()
+        .iter()
+        .all(|v| gjk(shape, &Hull([*v])) == Some(true))
+}
+
+/// 三角形の頂点を ECEF にする。
+pub(crate) fn triangle_hull(triangle: &Triangle) -> Hull<3> {
+    Hull(triangle.points.map(Vec3Ecef::from))
+}
+
+/// 閉じた三角形の網 `triangles` の内側に点 `p` があるか。`p` から伸ばした半直線が網と交わる回数で判定する。
+pub(crate) fn inside_mesh(triangles: &[Hull<3>], p: Vec3Ecef) -> bool {
+    // 網の辺や頂点をかすめにくい、座標軸に揃わない向き
+    let direction = Vec3Ecef::new(0.5773, 0.5831, 0.5716);
+    let mut inside = false;
+    for Hull([a, b, c]) in triangles {
+        // Möller–Trumbore 法
+        let (e1, e2) = (*b - *a, *c - *a);
+        let h = direction.cross(&e2);
+        let det = e1.dot(&h);
+        if det == 0.0 {
+            continue;
+        }
+        let s = p - *a;
+        let u = s.dot(&h) / det;
+        if !(0.0..=1.0).contains(&u) {
+            continue;
+        }
+        let q = s.cross(&e1);
+        let v = direction.dot(&q) / det;
+        if v < 0.0 || u + v > 1.0 {
+            continue;
+        }
+        if e2.dot(&q) / det > 0.0 {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// 三角形の網で囲まれた立体と直方体の位置関係。
+pub(crate) struct Mesh {
+    triangles: Vec<Hull<3>>,
+}
+
+impl Mesh {
+    pub(crate) fn new(triangles: impl IntoIterator<Item = Triangle>) -> Self {
+        Self {
+            triangles: triangles.into_iter().map(|t| triangle_hull(&t)).collect(),
+        }
+    }
+
+    /// 網の表面または内側が直方体と交わるか。
+    pub(crate) fn intersects(&self, id_box: &IdBox) -> bool {
+        self.surface_intersects(id_box) || inside_mesh(&self.triangles, id_box.center())
+    }
+
+    /// 直方体が網の内側にあり、表面と交わらないか。
+    pub(crate) fn contains(&self, id_box: &IdBox) -> bool {
+        !self.surface_intersects(id_box) && inside_mesh(&self.triangles, id_box.center())
+    }
+
+    /// 網の全ての頂点が直方体に収まるか。
+    pub(crate) fn within(&self, id_box: &IdBox) -> bool {
+        self.triangles.iter().all(|t| id_box.encloses(t))
+    }
+
+    fn surface_intersects(&self, id_box: &IdBox) -> bool {
+        self.triangles.iter().any(|t| intersects(t, id_box))
+    }
+}
+
+/// 2 つの凸な図形が交わりうるか。境界が接する場合も交わるとみなす。
+///
+/// GJK 法が打ち切られた場合は、交わらないと確かめられないので交わるとみなす。
+pub(crate) fn intersects(a: &impl Convex, b: &impl Convex) -> bool {
+    gjk(a, b).unwrap_or(true)
+}
+
+/// 2 つの凸な図形が交わるかを GJK 法で判定する。反復回数の上限までに決まらなければ `None`。
+fn gjk(a: &impl Convex, b: &impl Convex) -> Option<bool> {
+    let support = |d: Vec3Ecef| a.support(d) - b.support(d.scale(-1.0));
+
+    let first = support(Vec3Ecef::new(1.0, 0.0, 0.0));
+    let mut simplex: Vec<Vec3Ecef> = vec![first];
+    let mut d = first.scale(-1.0);
+    for _ in 0..GJK_ITERATIONS {
+        if d.norm_squared() == 0.0 {
+            return Some(true);
+        }
+        let p = support(d);
+        if p.dot(&d) < 0.0 {
+            return Some(false);
+        }
+        simplex.push(p);
+        if let Some(next) = nearest_simplex(&mut simplex) {
+            d = next;
+        } else {
+            return Some(true);
+        }
+    }
+    None
+}
+
+/// 単体 `simplex`（最後が最新の点）を原点に最も近い部分へ縮め、次に探す方向を返す。
+/// 単体が原点を含めば `None`。
+fn nearest_simplex(simplex: &mut Vec<Vec3Ecef>) -> Option<Vec3Ecef> {
+    match simplex.len() {
+        2 => Some(line_case(simplex)),
+        3 => triangle_case(simplex),
+        _ => tetrahedron_case(simplex),
+    }
+}
+
+fn line_case(simplex: &mut Vec<Vec3Ecef>) -> Vec3Ecef {
+    let (b, a) = (simplex[0], simplex[1]);
+    let (ab, ao) = (b - a, a.scale(-1.0));
+    if ab.dot(&ao) > 0.0 {
+        ab.cross(&ao).cross(&ab)
+    } else {
+        *simplex = vec![a];
+        ao
+    }
+}
+
+fn triangle_case(simplex: &mut Vec<Vec3Ecef>) -> Option<Vec3Ecef> {
+    let (c, b, a) = (simplex[0], simplex[1], simplex[2]);
+    let (ab, ac, ao) = (b - a, c - a, a.scale(-1.0));
+    let abc = ab.cross(&ac);
+
+    if abc.cross(&ac).dot(&ao) > 0.0 {
+        if ac.dot(&ao) > 0.0 {
+            *simplex = vec![c, a];
+            return Some(ac.cross(&ao).cross(&ac));
+        }
+        *simplex = vec![b, a];
+        return Some(line_case(simplex));
+    }
+    if ab.cross(&abc).dot(&ao) > 0.0 {
+        *simplex = vec![b, a];
+        return Some(line_case(simplex));
+    }
+    let side = abc.dot(&ao);
+    if side == 0.0 {
+        // 原点が三角形の上にある
+        return None;
+    }
+    Some(if side > 0.0 { abc } else { abc.scale(-1.0) })
+}
+
+fn tetrahedron_case(simplex: &mut Vec<Vec3Ecef>) -> Option<Vec3Ecef> {
+    let (d, c, b, a) = (simplex[0], simplex[1], simplex[2], simplex[3]);
+    let ao = a.scale(-1.0);
+    // 最新の点 a を含む 3 面のうち、原点が外側にある面へ縮める
+    for (x, y, opposite) in [(b, c, d), (c, d, b), (d, b, c)] {
+        let mut normal = (x - a).cross(&(y - a));
+        if normal.dot(&(opposite - a)) > 0.0 {
+            normal = normal.scale(-1.0);
+        }
+        if normal.dot(&ao) > 0.0 {
+            *simplex = vec![y, x, a];
+            return triangle_case(simplex);
+        }
+    }
+    None
+}
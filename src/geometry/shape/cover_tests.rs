@@ -0,0 +1,37 @@
+//! 図形のテストで共有する、範囲の被覆を確かめる補助。
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{CoverRangeIds, CoverSingleIds, RangeId};
+
+/// [`CoverRangeIds`] の結果を [`SingleId`](crate::SingleId) に展開して並べる。
+///
+/// 戻り値は `RangeId` の個数と展開した ID。`RangeId` 同士が重なっていれば失敗する。
+fn expanded_range_ids<S: CoverRangeIds>(shape: &S, z: u8) -> (usize, Vec<String>) {
+    let ranges: Vec<RangeId> = shape.cover_range_ids(z).unwrap().collect();
+    let mut ids: Vec<String> = ranges
+        .iter()
+        .flat_map(|range| range.single_ids())
+        .map(|id| id.to_string())
+        .collect();
+    ids.sort();
+    let len = ids.len();
+    ids.dedup();
+    assert_eq!(ids.len(), len, "RangeId が重なっている");
+    (ranges.len(), ids)
+}
+
+/// 範囲で返した被覆が [`CoverSingleIds`] と同じ空間を覆うことを確かめ、`RangeId` の個数を返す。
+pub(crate) fn assert_same_cover<S: CoverRangeIds + CoverSingleIds>(shape: &S, z: u8) -> usize {
+    let mut expected: Vec<String> = shape
+        .cover_single_ids(z)
+        .unwrap()
+        .map(|id| id.to_string())
+        .collect();
+    expected.sort();
+    expected.dedup();
+    let (count, ids) = expanded_range_ids(shape, z);
+    assert_eq!(ids, expected);
+    count
+}
@@ -0,0 +1,91 @@
+//! 図形が各ボクセルをどれだけ占めるかを表す型と、その見積もりの補助。
+//!
+//! 占有率は、ボクセルを各軸 `2^SUBDIVISION` 分割した細かいボクセルのうち、
+//! 図形の被覆に含まれるものの割合として見積もる。線や面のように体積を持たない図形では、
+//! 図形が通過する細かいボクセルの割合になる。
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::spatial_id::zoom_level::ZoomLevel;
+use crate::{Error, RangeId, SingleId};
+
+/// 占有率を見積もるときに、ボクセルを何段細かくするか。
+///
+/// 1 つのボクセルを `8^SUBDIVISION` 個に分けるので、占有率の分解能は `1 / 512` になる。
+pub const SUBDIVISION: u8 = 3;
+
+/// ボクセルと図形の位置関係。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CoverClass {
+    /// ボクセル全体が図形の内側にある。
+    Inside,
+    /// ボクセルの一部だけが図形にかかっている。
+    Boundary,
+}
+
+/// 1 つのボクセルに対する図形の占有状況。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coverage {
+    /// ボクセルと図形の位置関係。
+    pub class: CoverClass,
+    /// ボクセルのうち図形が占める割合の見積もり（`0.0` より大きく `1.0` 以下）。
+    pub fraction: f64,
+}
+
+/// ズームレベル `z` の各ボクセルについて、図形の占有状況を見積もる。
+///
+/// `fine_cover` には細かいズームレベルを渡すと、そのズームレベルでの図形の被覆
+/// （互いに重ならない [`RangeId`] の列）を返す関数を渡す。
+/// `z` が大きく [`SUBDIVISION`] 段細かくできない場合は、最大ズームレベルまでで見積もる。
+pub(crate) fn classify<I>(
+    z: u8,
+    fine_cover: impl FnOnce(u8) -> Result<I, Error>,
+) -> Result<Vec<(SingleId, Coverage)>, Error>
+where
+    I: IntoIterator<Item = RangeId>,
+{
+    let z = ZoomLevel::new(z)?.get();
+    let depth = SUBDIVISION.min(ZoomLevel::MAX.get() - z);
+    let side = 1u64 << depth;
+    let full = side * side * side;
+
+    // 親ボクセルごとに、被覆に含まれる細かいボクセルの数を数える。
+    let mut counts: BTreeMap<(i32, u32, u32), u64> = BTreeMap::new();
+    for range in fine_cover(z + depth)? {
+        let ([f0, f1], [x0, x1], [y0, y1]) = (range.f(), range.x(), range.y());
+        for f in (f0 >> depth)..=(f1 >> depth) {
+            let df = overlap(f as i64, depth, f0 as i64, f1 as i64);
+            for x in (x0 >> depth)..=(x1 >> depth) {
+                let dx = overlap(x as i64, depth, x0 as i64, x1 as i64);
+                for y in (y0 >> depth)..=(y1 >> depth) {
+                    let dy = overlap(y as i64, depth, y0 as i64, y1 as i64);
+                    *counts.entry((f, x, y)).or_default() += df * dx * dy;
+                }
+            }
+        }
+    }
+
+    counts
+        .into_iter()
+        .map(|((f, x, y), count)| {
+            let class = if count >= full {
+                CoverClass::Inside
+            } else {
+                CoverClass::Boundary
+            };
+            let coverage = Coverage {
+                class,
+                fraction: count.min(full) as f64 / full as f64,
+            };
+            Ok((SingleId::new(z, f, x, y)?, coverage))
+        })
+        .collect()
+}
+
+/// 親のインデックス `parent` の細かい区間と、閉区間 `[start, end]` が重なる長さ。
+fn overlap(parent: i64, depth: u8, start: i64, end: i64) -> u64 {
+    let lo = (parent << depth).max(start);
+    let hi = (((parent + 1) << depth) - 1).min(end);
+    (hi - lo + 1) as u64
+}
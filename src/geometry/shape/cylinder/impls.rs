@@ -1,6 +1,10 @@
 use alloc::vec::Vec;
 
-use crate::{Coordinate, CoverRangeIds, CoverSingleIds, Cylinder, Error, RangeId, Shape, SingleId};
+use crate::geometry::shape::convex::{self, Convex, IdBox};
+use crate::{
+    Coordinate, CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds, Cylinder, Error,
+    RangeId, Shape, SingleId, SpatialPredicates, Vec3, Vec3Ecef,
+};
 
 impl Shape for Cylinder {
     fn center(&self) -> Coordinate {
@@ -25,3 +29,40 @@ impl CoverRangeIds for Cylinder {
         Ok(ids.into_iter())
     }
 }
+
+impl CoverFlexIds for Cylinder {}
+
+impl CoverClassifiedIds for Cylinder {}
+
+impl Convex for Cylinder {
+    /// 両端の円板の支持点のうち、遠い方を返す。
+    fn support(&self, d: Vec3Ecef) -> Vec3Ecef {
+        let (start, end): (Vec3Ecef, Vec3Ecef) = (self.start.into(), self.end.into());
+        let Some(axis) = (end - start).normalize() else {
+            return start;
+        };
+        let ends = [
+            convex::disk_support(start, axis, self.radius_m, d),
+            convex::disk_support(end, axis, self.radius_m, d),
+        ];
+        if ends[0].dot(&d) >= ends[1].dot(&d) {
+            ends[0]
+        } else {
+            ends[1]
+        }
+    }
+}
+
+impl SpatialPredicates for Cylinder {
+    fn intersects_part(&self, id: &RangeId) -> bool {
+        convex::intersects(self, &IdBox::new(id))
+    }
+
+    fn contains_part(&self, id: &RangeId) -> bool {
+        convex::contains_box(self, &IdBox::new(id))
+    }
+
+    fn within_part(&self, id: &RangeId) -> bool {
+        IdBox::new(id).encloses(self)
+    }
+}
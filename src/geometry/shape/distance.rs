@@ -0,0 +1,170 @@
+//! 点から図形までの距離で表した図形を、空間IDで覆うための補助。
+//!
+//! ボクセルの中心から図形までの距離が、ボクセルの対角線の半分以下なら、そのボクセルは
+//! 図形にかかりうるとみなして覆う。探索は図形を囲む範囲の `(F, Y)` の行ごとに X 方向へ走査し、
+//! 連続して覆うボクセルを 1 つの区間として [`RowRuns`] に加える。距離は点の移動量以上には
+//! 変わらないので、中心での距離と対角線の半分との差が X 方向のボクセルいくつ分かを見て、その間の判定は省く。
+
+use alloc::vec::Vec;
+
+use crate::geometry::shape::runs::RowRuns;
+use crate::geometry::shape::sphere::impls::{voxel_length_f, voxel_length_xy};
+use crate::spatial_id::zoom_level::ZoomLevel;
+use crate::{Coordinate, Ecef, Error, RangeId, SingleId, SpatialId, Vec3, Vec3Ecef};
+
+/// 点からの距離で表せる図形。
+pub(crate) trait DistanceField {
+    /// ECEF の点 `p` から図形までの距離（メートル）。図形の内側では 0 以下を返す。
+    fn distance(&self, p: Vec3Ecef) -> f64;
+
+    /// 図形を囲む、ECEF の座標軸に平行な直方体の `[最小の角, 最大の角]`。
+    fn ecef_bounds(&self) -> [Vec3Ecef; 2];
+}
+
+/// `shape` を覆う行ごとの X 方向の区間を `runs` に加える。
+pub(crate) fn push_runs(
+    shape: &impl DistanceField,
+    z: u8,
+    runs: &mut RowRuns,
+) -> Result<(), Error> {
+    let z = ZoomLevel::new(z)?.get();
+    let limit = half_diagonal(z);
+
+    let Some(([f_min, f_max], [x_min, x_max], [y_min, y_max])) = search_bounds(shape, z) else {
+        return Ok(());
+    };
+    for f in f_min..=f_max {
+        for y in y_min..=y_max {
+            // 同じ行のボクセルの中心は地軸から等距離にあるので、X が 1 つ隣の中心までの距離は一定
+            let first = Vec3Ecef::from(SingleId::new(z, f, x_min, y)?.spatial_center());
+            let step = 2.0
+                * libm::hypot(first.a(), first.b())
+                * libm::sin(core::f64::consts::PI / (1u64 << z) as f64);
+            let mut start = None;
+            let mut x = x_min;
+            while x <= x_max {
+                let center = SingleId::new(z, f, x, y)?.spatial_center();
+                let d = shape.distance(Vec3Ecef::from(center));
+                let inside = d <= limit;
+                match (inside, start) {
+                    (true, None) => start = Some(x),
+                    (false, Some(s)) => {
+                        runs.push_run(f, y, s, x - 1);
+                        start = None;
+                    }
+                    _ => {}
+                }
+                // 距離は X が 1 つ進むごとに高々 step しか変わらないので、境界までの余裕の分は判定を省く
+                let skip = libm::floor(libm::fabs(d - limit) / step);
+                let advance = if skip.is_finite() && skip >= 1.0 {
+                    skip.min(u32::MAX as f64) as u32
+                } else {
+                    1
+                };
+                x = match x.checked_add(advance) {
+                    Some(next) => next,
+                    None => break,
+                };
+            }
+            if let Some(s) = start {
+                runs.push_run(f, y, s, x_max);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// ズームレベル `z` のボクセルの対角線の半分（メートル）。
+pub(crate) fn half_diagonal(z: u8) -> f64 {
+    let lxy = voxel_length_xy(z);
+    let lf = voxel_length_f(z);
+    libm::sqrt(2.0 * lxy * lxy + lf * lf) / 2.0
+}
+
+/// `shape` を覆う [`RangeId`] の列。
+pub(crate) fn range_ids(shape: &impl DistanceField, z: u8) -> Result<Vec<RangeId>, Error> {
+    let mut runs = RowRuns::new(z);
+    push_runs(shape, z, &mut runs)?;
+    runs.into_range_ids()
+}
+
+/// 図形を囲む直方体の 8 頂点から求めた `(F, X, Y)` の探索範囲。どの頂点も空間IDの範囲外なら `None`。
+pub(crate) fn search_bounds(
+    shape: &impl DistanceField,
+    z: u8,
+) -> Option<([i32; 2], [u32; 2], [u32; 2])> {
+    let [lo, hi] = shape.ecef_bounds();
+    let mut corners = Vec::with_capacity(8);
+    for a in [lo.a(), hi.a()] {
+        for b in [lo.b(), hi.b()] {
+            for c in [lo.c(), hi.c()] {
+                if let Ok(id) = Ecef::new(a, b, c).single_id(z) {
+                    corners.push(id);
+                }
+            }
+        }
+    }
+    let first = corners.first()?;
+    let mut bounds = ([first.f(); 2], [first.x(); 2], [first.y(); 2]);
+    for id in &corners {
+        bounds.0 = [bounds.0[0].min(id.f()), bounds.0[1].max(id.f())];
+        bounds.1 = [bounds.1[0].min(id.x()), bounds.1[1].max(id.x())];
+        bounds.2 = [bounds.2[0].min(id.y()), bounds.2[1].max(id.y())];
+    }
+    Some(bounds)
+}
+
+/// `center` における局所座標系の単位ベクトル `[前, 右, 上]`。
+///
+/// 「上」は楕円体の法線方向、「前」は北から時計回りに `heading_deg` 度回した水平方向。
+pub(crate) fn local_frame(center: &Coordinate, heading_deg: f64) -> [Vec3Ecef; 3] {
+    let (lat, lon) = (
+        center.latitude().to_radians(),
+        center.longitude().to_radians(),
+    );
+    let (sin_lat, cos_lat) = (libm::sin(lat), libm::cos(lat));
+    let (sin_lon, cos_lon) = (libm::sin(lon), libm::cos(lon));
+    let up = Vec3Ecef::new(cos_lat * cos_lon, cos_lat * sin_lon, sin_lat);
+    let east = Vec3Ecef::new(-sin_lon, cos_lon, 0.0);
+    let north = Vec3Ecef::new(-sin_lat * cos_lon, -sin_lat * sin_lon, cos_lat);
+
+    let heading = heading_deg.to_radians();
+    let (sin_h, cos_h) = (libm::sin(heading), libm::cos(heading));
+    let forward = north.scale(cos_h) + east.scale(sin_h);
+    let right = east.scale(cos_h) - north.scale(sin_h);
+    [forward, right, up]
+}
+
+/// 局所座標系 `frame` の原点 `origin` から見た点 `p` の座標。
+pub(crate) fn to_local(p: Vec3Ecef, origin: Vec3Ecef, frame: &[Vec3Ecef; 3]) -> [f64; 3] {
+    let d = p - origin;
+    [d.dot(&frame[0]), d.dot(&frame[1]), d.dot(&frame[2])]
+}
+
+/// `origin` を中心に、局所座標系 `frame` の各軸へ `half_extents` だけ広がる直方体を囲む ECEF の範囲。
+pub(crate) fn frame_bounds(
+    origin: Vec3Ecef,
+    frame: &[Vec3Ecef; 3],
+    half_extents: [f64; 3],
+) -> [Vec3Ecef; 2] {
+    // 各 ECEF 軸への広がりは、局所軸の成分の絶対値の重み付き和
+    let reach = |component: fn(&Vec3Ecef) -> f64| {
+        (0..3)
+            .map(|i| libm::fabs(component(&frame[i])) * half_extents[i])
+            .sum::<f64>()
+    };
+    let r = Vec3Ecef::new(reach(Vec3Ecef::a), reach(Vec3Ecef::b), reach(Vec3Ecef::c));
+    [origin - r, origin + r]
+}
+
+/// 点 `p` から線分 `ab` までの距離。
+pub(crate) fn segment_distance(p: Vec3Ecef, a: Vec3Ecef, b: Vec3Ecef) -> f64 {
+    let ab = b - a;
+    let len2 = ab.norm_squared();
+    let t = if len2 == 0.0 {
+        0.0
+    } else {
+        ((p - a).dot(&ab) / len2).clamp(0.0, 1.0)
+    };
+    (p - (a + ab.scale(t))).norm()
+}
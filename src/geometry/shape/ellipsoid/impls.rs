@@ -0,0 +1,112 @@
+use crate::geometry::shape::convex::{self, Convex, IdBox};
+use crate::geometry::shape::distance::{self, DistanceField};
+use crate::{
+    Coordinate, CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds, Ellipsoid, Error,
+    RangeId, Shape, SingleId, SpatialPredicates, Vec3, Vec3Ecef,
+};
+
+/// 最近点を求める二分法の反復回数。
+const BISECTION_STEPS: usize = 64;
+
+impl Shape for Ellipsoid {
+    fn center(&self) -> Coordinate {
+        self.center
+    }
+}
+
+impl DistanceField for Ellipsoid {
+    /// 楕円体の表面までの距離。内側では 0 を返す。
+    ///
+    /// 外側の点 `y` に最も近い表面上の点 `x` は、ある `t >= 0` について
+    /// `x_i = a_i^2 y_i / (t + a_i^2)` と書ける。`x` が表面上にある条件
+    /// `Σ (a_i y_i / (t + a_i^2))^2 = 1` の左辺は `t` について単調減少なので、二分法で `t` を求める。
+    fn distance(&self, p: Vec3Ecef) -> f64 {
+        let y = distance::to_local(p, self.origin, &self.frame);
+        let a = self.semi_axes_m;
+        let level = |t: f64| -> f64 {
+            (0..3)
+                .map(|i| {
+                    let r = a[i] * y[i] / (t + a[i] * a[i]);
+                    r * r
+                })
+                .sum::<f64>()
+        };
+        if level(0.0) <= 1.0 {
+            return 0.0;
+        }
+
+        // t = max(a) * |y| で左辺は 1 以下になる
+        let norm = libm::sqrt(y.iter().map(|v| v * v).sum::<f64>());
+        let (mut lo, mut hi) = (0.0, a[0].max(a[1]).max(a[2]) * norm);
+        for _ in 0..BISECTION_STEPS {
+            let mid = (lo + hi) / 2.0;
+            if level(mid) > 1.0 {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let t = (lo + hi) / 2.0;
+        let squared: f64 = (0..3)
+            .map(|i| {
+                let d = y[i] - a[i] * a[i] * y[i] / (t + a[i] * a[i]);
+                d * d
+            })
+            .sum();
+        libm::sqrt(squared)
+    }
+
+    fn ecef_bounds(&self) -> [Vec3Ecef; 2] {
+        distance::frame_bounds(self.origin, &self.frame, self.semi_axes_m)
+    }
+}
+
+impl CoverSingleIds for Ellipsoid {
+    /// 中心が楕円体からボクセルの対角線の半分以内にあるボクセルを返す。
+    fn cover_single_ids(&self, z: u8) -> Result<impl Iterator<Item = SingleId>, Error> {
+        let ranges = distance::range_ids(self, z)?;
+        Ok(ranges.into_iter().flat_map(|range| range.single_ids()))
+    }
+}
+
+impl CoverRangeIds for Ellipsoid {
+    /// [`cover_single_ids`](CoverSingleIds::cover_single_ids) と同じ空間を [`RangeId`] で返す。
+    ///
+    /// `(F, Y)` の行ごとに、連続して覆うボクセルを 1 つの区間として集めてからまとめる。
+    fn cover_range_ids(&self, z: u8) -> Result<impl Iterator<Item = RangeId>, Error> {
+        Ok(distance::range_ids(self, z)?.into_iter())
+    }
+}
+
+impl CoverFlexIds for Ellipsoid {}
+
+impl CoverClassifiedIds for Ellipsoid {}
+
+impl Convex for Ellipsoid {
+    /// 局所座標系で `x_i = a_i^2 d_i / |(a_i d_i)|` となる表面上の点を返す。
+    fn support(&self, d: Vec3Ecef) -> Vec3Ecef {
+        let a = self.semi_axes_m;
+        let local = self.frame.map(|axis| d.dot(&axis));
+        let norm = libm::sqrt((0..3).map(|i| (a[i] * local[i]) * (a[i] * local[i])).sum());
+        if norm == 0.0 {
+            return self.origin;
+        }
+        (0..3).fold(self.origin, |p, i| {
+            p + self.frame[i].scale(a[i] * a[i] * local[i] / norm)
+        })
+    }
+}
+
+impl SpatialPredicates for Ellipsoid {
+    fn intersects_part(&self, id: &RangeId) -> bool {
+        convex::intersects(self, &IdBox::new(id))
+    }
+
+    fn contains_part(&self, id: &RangeId) -> bool {
+        convex::contains_box(self, &IdBox::new(id))
+    }
+
+    fn within_part(&self, id: &RangeId) -> bool {
+        IdBox::new(id).encloses(self)
+    }
+}
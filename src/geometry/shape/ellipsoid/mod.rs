@@ -0,0 +1,50 @@
+pub mod impls;
+#[cfg(test)]
+mod tests;
+
+use crate::geometry::shape::distance;
+use crate::{Coordinate, Error, GeometryError, Vec3Ecef};
+
+/// 3次元空間における楕円体を表す型。
+///
+/// 中心点・3 つの半軸の長さ・向きによって定義される。半軸は `[前, 右, 上]` の順で、
+/// 「上」は中心点での鉛直方向、「前」は北から時計回りに `heading_deg` 度回した水平方向にとる。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ellipsoid {
+    center: Coordinate,
+    semi_axes_m: [f64; 3],
+    heading_deg: f64,
+    origin: Vec3Ecef,
+    frame: [Vec3Ecef; 3],
+}
+
+impl Ellipsoid {
+    /// [Ellipsoid]を作成する。
+    ///
+    /// 半軸の長さのいずれかが正でない場合は [GeometryError::SizeNotPositive] を返す。
+    pub fn new(center: Coordinate, semi_axes_m: [f64; 3], heading_deg: f64) -> Result<Self, Error> {
+        if let Some(&size) = semi_axes_m
+            .iter()
+            .find(|size| size.is_nan() || **size <= 0.0)
+        {
+            return Err(GeometryError::SizeNotPositive { size }.into());
+        }
+        Ok(Ellipsoid {
+            center,
+            semi_axes_m,
+            heading_deg,
+            origin: center.into(),
+            frame: distance::local_frame(&center, heading_deg),
+        })
+    }
+
+    /// 半軸の長さ `[前, 右, 上]`（メートル）を返す。
+    pub fn semi_axes_m(&self) -> [f64; 3] {
+        self.semi_axes_m
+    }
+
+    /// 「前」の半軸の向き（北から時計回りの度数）を返す。
+    pub fn heading_deg(&self) -> f64 {
+        self.heading_deg
+    }
+}
@@ -0,0 +1,70 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::geometry::shape::distance::DistanceField;
+use crate::{Coordinate, CoverSingleIds, Ellipsoid, Error, GeometryError, Vec3, Vec3Ecef};
+
+fn sorted_ids(ellipsoid: &Ellipsoid, z: u8) -> Vec<String> {
+    let mut ids: Vec<String> = ellipsoid
+        .cover_single_ids(z)
+        .unwrap()
+        .map(|id| id.to_string())
+        .collect();
+    ids.sort();
+    ids
+}
+
+fn center() -> Coordinate {
+    Coordinate::new(35.681, 139.766, 100.0).unwrap()
+}
+
+mod cover_single_ids {
+    use super::*;
+
+    /// 北東を向いた扁平な楕円体を変換する
+    #[test]
+    fn flattened_ellipsoid_at_z20() {
+        let ellipsoid = Ellipsoid::new(center(), [60.0, 25.0, 15.0], 45.0).unwrap();
+        insta::assert_debug_snapshot!(sorted_ids(&ellipsoid, 20));
+    }
+}
+
+mod distance {
+    use super::*;
+
+    /// 半軸の方向に離れた点までの距離は、半軸の外へはみ出した長さになる
+    #[test]
+    fn distance_along_axes() {
+        let ellipsoid = Ellipsoid::new(center(), [60.0, 25.0, 15.0], 0.0).unwrap();
+        let origin: Vec3Ecef = center().into();
+        let [forward, right, up] = crate::geometry::shape::distance::local_frame(&center(), 0.0);
+        for (axis, semi) in [(forward, 60.0), (right, 25.0), (up, 15.0)] {
+            let outside = origin + axis.scale(semi + 10.0);
+            let d = ellipsoid.distance(outside);
+            assert!(libm::fabs(d - 10.0) < 1e-6, "{d}");
+            assert_eq!(ellipsoid.distance(origin + axis.scale(semi - 1.0)), 0.0);
+        }
+    }
+
+    /// 半軸が等しければ球までの距離と一致する
+    #[test]
+    fn matches_sphere_when_axes_are_equal() {
+        let ellipsoid = Ellipsoid::new(center(), [20.0; 3], 30.0).unwrap();
+        let origin: Vec3Ecef = center().into();
+        let p = origin + Vec3Ecef::new(30.0, -20.0, 15.0);
+        let expected = Vec3Ecef::new(30.0, -20.0, 15.0).norm() - 20.0;
+        assert!(libm::fabs(ellipsoid.distance(p) - expected) < 1e-6);
+    }
+}
+
+mod new {
+    use super::*;
+
+    #[test]
+    fn rejects_non_positive_axes() {
+        assert!(matches!(
+            Ellipsoid::new(center(), [10.0, 0.0, 5.0], 0.0),
+            Err(Error::Geometry(GeometryError::SizeNotPositive { .. }))
+        ));
+    }
+}
@@ -1,6 +1,12 @@
 use alloc::vec::Vec;
 
-use crate::{Coordinate, Ecef, Error, Line, Shape, SingleId, geometry::traits::CoverSingleIds};
+use crate::geometry::shape::convex::{self, Convex, IdBox};
+use crate::geometry::shape::runs::RowRuns;
+use crate::{
+    Coordinate, Ecef, Error, Line, RangeId, Shape, SingleId,
+    geometry::traits::{CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds},
+};
+use crate::{SpatialPredicates, Vec3Ecef};
 
 impl Shape for Line {
     fn center(&self) -> Coordinate {
@@ -12,31 +18,8 @@ impl CoverSingleIds for Line {
     fn cover_single_ids(&self, z: u8) -> Result<impl Iterator<Item = SingleId>, Error> {
         let zoom = crate::spatial_id::zoom_level::ZoomLevel::new(z)?;
         let z = zoom.get();
-        let a = self.points[0];
-        let b = self.points[1];
-
-        let ecef_a: Ecef = a.into();
-        let ecef_b: Ecef = b.into();
-        let dx = ecef_a.x() - ecef_b.x();
-        let dy = ecef_a.y() - ecef_b.y();
-        let dz = ecef_a.z() - ecef_b.z();
-        let distance = libm::sqrt(dx * dx + dy * dy + dz * dz);
-        let (v1, v2) = (a.single_id(z)?, b.single_id(z)?);
-        let diff = ((v1.f() - v2.f()).abs()
-            + (v1.x() as i32 - v2.x() as i32).abs()
-            + (v1.y() as i32 - v2.y() as i32).abs()) as f64;
-        let devide_num = 5 + libm::floor(diff / 120.0 + distance / 2000.0) as u16;
-        let mut coordinates = Vec::with_capacity(devide_num as usize + 1);
-        for i in 0..=devide_num {
-            let t = i as f64 / devide_num as f64;
-            let x = ecef_a.x() * (1.0 - t) + ecef_b.x() * t;
-            let y = ecef_a.y() * (1.0 - t) + ecef_b.y() * t;
-            let z_pos = ecef_a.z() * (1.0 - t) + ecef_b.z() * t;
-            let coo: Coordinate = Ecef::new(x, y, z_pos).try_into()?;
-            coordinates.push(coo);
-        }
-        let estimated_capacity = (diff as usize) + 10;
-        let mut voxels: Vec<SingleId> = Vec::with_capacity(estimated_capacity);
+        let coordinates = waypoints(z, self.points[0], self.points[1])?;
+        let mut voxels: Vec<SingleId> = Vec::new();
         for pair in coordinates.windows(2) {
             let start = pair[0];
             let end = pair[1];
@@ -48,6 +31,33 @@ impl CoverSingleIds for Line {
     }
 }
 
+/// `a` から `b` への ECEF 上の線分を分けた点の列。両端を含む。
+///
+/// 空間 ID のインデックス空間では直線が曲がるため、区間ごとに DDA で辿れる程度まで細かく分ける。
+pub(crate) fn waypoints(z: u8, a: Coordinate, b: Coordinate) -> Result<Vec<Coordinate>, Error> {
+    let ecef_a: Ecef = a.into();
+    let ecef_b: Ecef = b.into();
+    let dx = ecef_a.x() - ecef_b.x();
+    let dy = ecef_a.y() - ecef_b.y();
+    let dz = ecef_a.z() - ecef_b.z();
+    let distance = libm::sqrt(dx * dx + dy * dy + dz * dz);
+    let (v1, v2) = (a.single_id(z)?, b.single_id(z)?);
+    let diff = ((v1.f() - v2.f()).abs()
+        + (v1.x() as i32 - v2.x() as i32).abs()
+        + (v1.y() as i32 - v2.y() as i32).abs()) as f64;
+    let devide_num = 5 + libm::floor(diff / 120.0 + distance / 2000.0) as u16;
+    let mut coordinates = Vec::with_capacity(devide_num as usize + 1);
+    for i in 0..=devide_num {
+        let t = i as f64 / devide_num as f64;
+        let x = ecef_a.x() * (1.0 - t) + ecef_b.x() * t;
+        let y = ecef_a.y() * (1.0 - t) + ecef_b.y() * t;
+        let z_pos = ecef_a.z() * (1.0 - t) + ecef_b.z() * t;
+        let coo: Coordinate = Ecef::new(x, y, z_pos).try_into()?;
+        coordinates.push(coo);
+    }
+    Ok(coordinates)
+}
+
 ///DDAを用いたLine関数
 fn line_dda(z: u8, a: Coordinate, b: Coordinate) -> Result<impl Iterator<Item = SingleId>, Error> {
     let zoom = crate::spatial_id::zoom_level::ZoomLevel::new(z)?;
@@ -178,3 +188,42 @@ fn coordinate_to_matrix(p: Coordinate, z: u8) -> [f64; 3] {
         * n;
     [f, x, y]
 }
+
+impl CoverRangeIds for Line {
+    /// [`cover_single_ids`](CoverSingleIds::cover_single_ids) と同じ空間を [`RangeId`] で返す。
+    ///
+    /// DDA で辿ったボクセルを行ごとの X 方向の区間に集め、同じ区間を持つ隣接した行・面を
+    /// 1 つの [`RangeId`] にまとめる。
+    fn cover_range_ids(&self, z: u8) -> Result<impl Iterator<Item = RangeId>, Error> {
+        let mut runs = RowRuns::new(z);
+        for id in self.cover_single_ids(z)? {
+            runs.push(&id);
+        }
+        Ok(runs.into_range_ids()?.into_iter())
+    }
+}
+
+impl CoverFlexIds for Line {}
+
+impl CoverClassifiedIds for Line {}
+
+impl Convex for Line {
+    fn support(&self, d: Vec3Ecef) -> Vec3Ecef {
+        convex::Hull(self.points.map(Vec3Ecef::from)).support(d)
+    }
+}
+
+impl SpatialPredicates for Line {
+    fn intersects_part(&self, id: &RangeId) -> bool {
+        convex::intersects(self, &IdBox::new(id))
+    }
+
+    /// 体積を持たないので常に `false`。
+    fn contains_part(&self, _id: &RangeId) -> bool {
+        false
+    }
+
+    fn within_part(&self, id: &RangeId) -> bool {
+        IdBox::new(id).encloses(self)
+    }
+}
@@ -1,6 +1,7 @@
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
+use crate::geometry::shape::cover_tests::assert_same_cover;
 use crate::{Coordinate, CoverSingleIds, Line};
 
 fn sorted_ids(line: &Line, z: u8) -> Vec<String> {
@@ -59,3 +60,26 @@ mod cover_single_ids {
         insta::assert_debug_snapshot!(sorted_ids(&line, 25));
     }
 }
+
+mod cover_range_ids {
+    use super::*;
+
+    /// 垂直な線分は 1 本の柱にまとまる
+    #[test]
+    fn vertical_segment_is_one_range() {
+        let p0 = Coordinate::new(35.681, 139.766, 0.0).unwrap();
+        let p1 = Coordinate::new(35.681, 139.766, 100.0).unwrap();
+        let line = Line::new([p0, p1]);
+        assert_eq!(assert_same_cover(&line, 25), 1);
+    }
+
+    /// 斜めの線分も同じ空間を覆う
+    #[test]
+    fn diagonal_segment_matches_single_ids() {
+        let p0 = Coordinate::new(35.680, 139.765, 0.0).unwrap();
+        let p1 = Coordinate::new(35.683, 139.768, 60.0).unwrap();
+        let line = Line::new([p0, p1]);
+        let count = assert_same_cover(&line, 25);
+        assert!(count < sorted_ids(&line, 25).len());
+    }
+}
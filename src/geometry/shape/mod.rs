@@ -2,18 +2,37 @@
 //!
 //! - `Line` は 2 つの `Coordinate` で表される。
 //! - `Triangle` は 3 本の `Line`（または 3 つの `Coordinate`）で表される。
-//! - `Polygon` は複数の `Triangle` に分解できる。
+//! - `Polygon` は複数の `Triangle` に分解できる。穴（内周）を持てる。
+//! - `MultiPolygon` は複数の `Polygon` で構成される。
 //! - `Solid` は複数の `Polygon` で構成される。
+//! - `Prism` は底面の `Polygon` を鉛直に押し出した立体で、底面・屋根・壁の `Polygon` に分解できる。
 //! - `Sphere` は中心点と半径を持つ独立した Shape として扱う。
+//! - `OrientedBox`・`Cone`・`Ellipsoid`・`Capsule` は、点から図形までの距離で覆う独立した Shape として扱う。
+//! - `Trajectory` は時刻付きの経由点と半径で表され、空間は `Tube` と同じ形を、時刻付きの `RangeId` で覆う。
 //!
 //! 詳細な図と説明は次のドキュメントを参照:
 //! [docs/geometry-relation.md](https://github.com/AirBee-Project/Kasane-Logic/blob/main/docs/geometry-relation.md)
 
+pub(crate) mod adaptive;
+pub mod capsule;
+pub mod cone;
+pub(crate) mod convex;
+#[cfg(test)]
+pub(crate) mod cover_tests;
+pub mod coverage;
 pub mod cylinder;
+pub(crate) mod distance;
+pub mod ellipsoid;
 pub mod line;
+pub mod multi_polygon;
+pub mod oriented_box;
 pub mod polygon;
+pub mod prism;
+pub(crate) mod runs;
 pub mod solid;
 pub mod sphere;
 pub mod traits;
+#[cfg(feature = "temporal_id")]
+pub mod trajectory;
 pub mod triangle;
 pub mod tube;
@@ -0,0 +1,34 @@
+use crate::{
+    Coordinate, ExpandCoordinates, ExpandLines, ExpandPolygons, ExpandTriangles, Line,
+    MultiPolygon, Polygon, Triangle,
+};
+
+impl ExpandCoordinates for MultiPolygon {
+    fn expand_coordinates(&self) -> impl Iterator<Item = Coordinate> {
+        self.polygons
+            .iter()
+            .flat_map(|polygon| polygon.expand_coordinates())
+    }
+}
+
+impl ExpandLines for MultiPolygon {
+    fn expand_lines(&self) -> impl Iterator<Item = Line> {
+        self.polygons
+            .iter()
+            .flat_map(|polygon| polygon.expand_lines())
+    }
+}
+
+impl ExpandTriangles for MultiPolygon {
+    fn expand_triangles(&self) -> impl Iterator<Item = Triangle> {
+        self.polygons
+            .iter()
+            .flat_map(|polygon| polygon.expand_triangles())
+    }
+}
+
+impl ExpandPolygons for MultiPolygon {
+    fn expand_polygons(&self) -> impl Iterator<Item = Polygon> {
+        self.polygons.clone().into_iter()
+    }
+}
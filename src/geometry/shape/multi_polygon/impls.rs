@@ -0,0 +1,65 @@
+use hashbrown::HashSet;
+
+use crate::SpatialPredicates;
+use crate::geometry::shape::convex::{self, IdBox};
+use crate::geometry::shape::runs::RowRuns;
+use crate::{
+    Coordinate, Error, ExpandCoordinates, ExpandTriangles, MultiPolygon, RangeId, Shape, SingleId,
+    geometry::traits::{CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds},
+};
+
+impl Shape for MultiPolygon {
+    /// 全ての [`Polygon`](crate::Polygon) の頂点の平均を返す。
+    fn center(&self) -> Coordinate {
+        Coordinate::center_gravity(self.expand_coordinates())
+    }
+}
+
+impl CoverSingleIds for MultiPolygon {
+    /// 各 [`Polygon`](crate::Polygon) が覆う [`SingleId`] の和集合を返す。穴の内側は含まない。
+    fn cover_single_ids(&self, z: u8) -> Result<impl Iterator<Item = SingleId>, Error> {
+        let mut unique_ids = HashSet::new();
+        for polygon in &self.polygons {
+            unique_ids.extend(polygon.cover_single_ids(z)?);
+        }
+        Ok(unique_ids.into_iter())
+    }
+}
+
+impl CoverRangeIds for MultiPolygon {
+    /// [`cover_single_ids`](CoverSingleIds::cover_single_ids) と同じ空間を [`RangeId`] で返す。
+    ///
+    /// 全ての [`Polygon`](crate::Polygon) の三角形が覆う行ごとの区間を 1 つの行の集まりに集めるので、
+    /// 隣り合う面にまたがる区間も 1 つの [`RangeId`] にまとまる。
+    fn cover_range_ids(&self, z: u8) -> Result<impl Iterator<Item = RangeId>, Error> {
+        let mut runs = RowRuns::new(z);
+        for triangle in self.expand_triangles() {
+            triangle.push_runs(z, &mut runs)?;
+        }
+        Ok(runs.into_range_ids()?.into_iter())
+    }
+}
+
+impl CoverFlexIds for MultiPolygon {}
+
+impl CoverClassifiedIds for MultiPolygon {}
+
+impl SpatialPredicates for MultiPolygon {
+    /// いずれかの三角形が直方体と交わるか。
+    fn intersects_part(&self, id: &RangeId) -> bool {
+        let id_box = IdBox::new(id);
+        self.expand_triangles()
+            .any(|triangle| convex::intersects(&convex::triangle_hull(&triangle), &id_box))
+    }
+
+    /// 体積を持たないので常に `false`。
+    fn contains_part(&self, _id: &RangeId) -> bool {
+        false
+    }
+
+    fn within_part(&self, id: &RangeId) -> bool {
+        let id_box = IdBox::new(id);
+        self.expand_triangles()
+            .all(|triangle| id_box.encloses(&convex::triangle_hull(&triangle)))
+    }
+}
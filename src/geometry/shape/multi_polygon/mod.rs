@@ -0,0 +1,50 @@
+use alloc::vec::Vec;
+
+use crate::Polygon;
+
+pub mod geometry_relation;
+pub mod impls;
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone)]
+/// 複数の [Polygon] をまとめて 1 つの領域として扱う型。
+///
+/// 飛び地を持つ行政区域のように、離れた複数の面からなる領域を表現する。
+/// 各 [Polygon] は穴を持ってよい。
+pub struct MultiPolygon {
+    polygons: Vec<Polygon>,
+}
+
+impl MultiPolygon {
+    /// [Polygon] のリストから新しい [MultiPolygon] を作成。
+    ///
+    /// 頂点が3未満に正規化された（空の）[Polygon] は取り除く。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 飛び地を持つ区域を作る
+    /// ```
+    /// # use kasane_logic::{Coordinate, MultiPolygon, Polygon};
+    /// let c = |lat, lon| Coordinate::new(lat, lon, 10.0).unwrap();
+    /// let main = Polygon::new(vec![c(35.0, 139.0), c(35.002, 139.0), c(35.0, 139.002)], 0.01);
+    /// let enclave = Polygon::new(vec![c(35.01, 139.01), c(35.011, 139.01), c(35.01, 139.011)], 0.01);
+    /// let empty = Polygon::new(vec![c(35.0, 139.0)], 0.01);
+    /// let zone = MultiPolygon::new(vec![main, enclave, empty]);
+    ///
+    /// assert_eq!(zone.polygons().len(), 2);
+    /// ```
+    pub fn new(polygons: Vec<Polygon>) -> Self {
+        Self {
+            polygons: polygons
+                .into_iter()
+                .filter(|polygon| !polygon.vertices().is_empty())
+                .collect(),
+        }
+    }
+
+    /// [MultiPolygon]を構成する [Polygon] を返す。
+    pub fn polygons(&self) -> &Vec<Polygon> {
+        &self.polygons
+    }
+}
@@ -0,0 +1,84 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{Coordinate, CoverRangeIds, CoverSingleIds, MultiPolygon, Polygon, RangeId};
+
+fn sorted_ids<S: CoverSingleIds>(shape: &S, z: u8) -> Vec<String> {
+    let mut ids: Vec<String> = shape
+        .cover_single_ids(z)
+        .unwrap()
+        .map(|id| id.to_string())
+        .collect();
+    ids.sort();
+    ids
+}
+
+/// 本体と、中庭を持つ飛び地からなる区域。
+fn zone() -> MultiPolygon {
+    let c = |lat, lon| Coordinate::new(lat, lon, 10.0).unwrap();
+    let main = Polygon::new(
+        vec![c(35.681, 139.766), c(35.683, 139.766), c(35.681, 139.768)],
+        0.01,
+    );
+    let enclave = Polygon::with_holes(
+        vec![
+            c(35.685, 139.770),
+            c(35.688, 139.770),
+            c(35.688, 139.773),
+            c(35.685, 139.773),
+        ],
+        vec![vec![
+            c(35.686, 139.771),
+            c(35.687, 139.771),
+            c(35.687, 139.772),
+            c(35.686, 139.772),
+        ]],
+        0.01,
+    );
+    MultiPolygon::new(vec![main, enclave])
+}
+
+mod cover_single_ids {
+    use super::*;
+
+    /// 本体と飛び地からなる区域を変換する
+    #[test]
+    fn zone_with_enclave_at_z18() {
+        insta::assert_debug_snapshot!(sorted_ids(&zone(), 18));
+    }
+
+    /// 各ポリゴンの被覆の和集合になる
+    #[test]
+    fn is_union_of_polygons() {
+        let zone = zone();
+        let mut expected: Vec<String> = zone
+            .polygons()
+            .iter()
+            .flat_map(|polygon| sorted_ids(polygon, 20))
+            .collect();
+        expected.sort();
+        expected.dedup();
+        assert_eq!(sorted_ids(&zone, 20), expected);
+    }
+}
+
+mod cover_range_ids {
+    use super::*;
+
+    #[test]
+    fn zone_matches_single_ids() {
+        let zone = zone();
+        let ranges: Vec<RangeId> = zone.cover_range_ids(20).unwrap().collect();
+        let mut ids: Vec<String> = ranges
+            .iter()
+            .flat_map(|range| range.single_ids())
+            .map(|id| id.to_string())
+            .collect();
+        ids.sort();
+        let len = ids.len();
+        ids.dedup();
+        assert_eq!(ids.len(), len, "RangeId が重なっている");
+        assert_eq!(ids, sorted_ids(&zone, 20));
+        assert!(ranges.len() * 4 < ids.len());
+    }
+}
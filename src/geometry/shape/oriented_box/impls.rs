@@ -0,0 +1,77 @@
+use crate::geometry::shape::convex::{self, Convex, IdBox};
+use crate::geometry::shape::distance::{self, DistanceField};
+use crate::{
+    Coordinate, CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds, Error,
+    OrientedBox, RangeId, Shape, SingleId, SpatialPredicates, Vec3, Vec3Ecef,
+};
+
+impl Shape for OrientedBox {
+    fn center(&self) -> Coordinate {
+        self.center
+    }
+}
+
+impl DistanceField for OrientedBox {
+    fn distance(&self, p: Vec3Ecef) -> f64 {
+        let local = distance::to_local(p, self.origin, &self.frame);
+        let half = self.half_extents();
+        // 各軸で面からはみ出した量
+        let q: [f64; 3] = core::array::from_fn(|i| libm::fabs(local[i]) - half[i]);
+        let outside = libm::sqrt(q.iter().map(|d| d.max(0.0) * d.max(0.0)).sum::<f64>());
+        let inside = q[0].max(q[1]).max(q[2]).min(0.0);
+        outside + inside
+    }
+
+    fn ecef_bounds(&self) -> [Vec3Ecef; 2] {
+        distance::frame_bounds(self.origin, &self.frame, self.half_extents())
+    }
+}
+
+impl CoverSingleIds for OrientedBox {
+    /// 中心が直方体からボクセルの対角線の半分以内にあるボクセルを返す。
+    fn cover_single_ids(&self, z: u8) -> Result<impl Iterator<Item = SingleId>, Error> {
+        let ranges = distance::range_ids(self, z)?;
+        Ok(ranges.into_iter().flat_map(|range| range.single_ids()))
+    }
+}
+
+impl CoverRangeIds for OrientedBox {
+    /// [`cover_single_ids`](CoverSingleIds::cover_single_ids) と同じ空間を [`RangeId`] で返す。
+    ///
+    /// `(F, Y)` の行ごとに、連続して覆うボクセルを 1 つの区間として集めてからまとめる。
+    fn cover_range_ids(&self, z: u8) -> Result<impl Iterator<Item = RangeId>, Error> {
+        Ok(distance::range_ids(self, z)?.into_iter())
+    }
+}
+
+impl CoverFlexIds for OrientedBox {}
+
+impl CoverClassifiedIds for OrientedBox {}
+
+impl Convex for OrientedBox {
+    fn support(&self, d: Vec3Ecef) -> Vec3Ecef {
+        let half = self.half_extents();
+        (0..3).fold(self.origin, |p, i| {
+            let sign = if d.dot(&self.frame[i]) >= 0.0 {
+                1.0
+            } else {
+                -1.0
+            };
+            p + self.frame[i].scale(sign * half[i])
+        })
+    }
+}
+
+impl SpatialPredicates for OrientedBox {
+    fn intersects_part(&self, id: &RangeId) -> bool {
+        convex::intersects(self, &IdBox::new(id))
+    }
+
+    fn contains_part(&self, id: &RangeId) -> bool {
+        convex::contains_box(self, &IdBox::new(id))
+    }
+
+    fn within_part(&self, id: &RangeId) -> bool {
+        IdBox::new(id).encloses(self)
+    }
+}
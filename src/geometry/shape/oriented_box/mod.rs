@@ -0,0 +1,66 @@
+pub mod impls;
+#[cfg(test)]
+mod tests;
+
+use crate::geometry::shape::distance;
+use crate::{Coordinate, Error, GeometryError, Vec3Ecef};
+
+/// 3次元空間において、向きを持つ直方体を表す型。
+///
+/// 中心点・大きさ・向きによって定義される。高さ方向は中心点での鉛直方向にとり、
+/// 長さ方向は北から時計回りに `heading_deg` 度回した水平方向にとる。
+/// 滑走路周辺の飛行禁止空域のように、経線に沿わない直方体の領域を表す。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrientedBox {
+    center: Coordinate,
+    size_m: [f64; 3],
+    heading_deg: f64,
+    origin: Vec3Ecef,
+    frame: [Vec3Ecef; 3],
+}
+
+impl OrientedBox {
+    /// [OrientedBox]を作成する。
+    ///
+    /// `size_m` は `[長さ, 幅, 高さ]` をメートルで表す。いずれかが正でない場合は
+    /// [GeometryError::SizeNotPositive] を返す。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 北東を向いた飛行禁止空域
+    /// ```
+    /// # use kasane_logic::{Coordinate, CoverSingleIds, OrientedBox};
+    /// let center = Coordinate::new(35.681, 139.766, 50.0).unwrap();
+    /// let no_fly = OrientedBox::new(center, [200.0, 40.0, 100.0], 45.0).unwrap();
+    ///
+    /// let inside = Coordinate::new(35.681, 139.766, 50.0).unwrap();
+    /// let id = inside.single_id(20).unwrap();
+    /// assert!(no_fly.cover_single_ids(20).unwrap().any(|covered| covered == id));
+    /// ```
+    pub fn new(center: Coordinate, size_m: [f64; 3], heading_deg: f64) -> Result<Self, Error> {
+        if let Some(&size) = size_m.iter().find(|size| size.is_nan() || **size <= 0.0) {
+            return Err(GeometryError::SizeNotPositive { size }.into());
+        }
+        Ok(OrientedBox {
+            center,
+            size_m,
+            heading_deg,
+            origin: center.into(),
+            frame: distance::local_frame(&center, heading_deg),
+        })
+    }
+
+    /// `[長さ, 幅, 高さ]`（メートル）を返す。
+    pub fn size_m(&self) -> [f64; 3] {
+        self.size_m
+    }
+
+    /// 長さ方向の向き（北から時計回りの度数）を返す。
+    pub fn heading_deg(&self) -> f64 {
+        self.heading_deg
+    }
+
+    fn half_extents(&self) -> [f64; 3] {
+        self.size_m.map(|size| size / 2.0)
+    }
+}
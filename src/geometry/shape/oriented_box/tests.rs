@@ -0,0 +1,71 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{Coordinate, CoverSingleIds, Error, GeometryError, OrientedBox};
+
+fn sorted_ids(oriented_box: &OrientedBox, z: u8) -> Vec<String> {
+    let mut ids: Vec<String> = oriented_box
+        .cover_single_ids(z)
+        .unwrap()
+        .map(|id| id.to_string())
+        .collect();
+    ids.sort();
+    ids
+}
+
+fn center() -> Coordinate {
+    Coordinate::new(35.681, 139.766, 50.0).unwrap()
+}
+
+mod cover_single_ids {
+    use super::*;
+
+    /// 北東を向いた細長い直方体を変換する
+    #[test]
+    fn diagonal_box_at_z20() {
+        let oriented_box = OrientedBox::new(center(), [150.0, 30.0, 40.0], 45.0).unwrap();
+        insta::assert_debug_snapshot!(sorted_ids(&oriented_box, 20));
+    }
+
+    /// 90 度回すと長さと幅を入れ替えた直方体になる
+    #[test]
+    fn quarter_turn_swaps_length_and_width() {
+        let turned = OrientedBox::new(center(), [120.0, 40.0, 30.0], 90.0).unwrap();
+        let swapped = OrientedBox::new(center(), [40.0, 120.0, 30.0], 0.0).unwrap();
+        assert_eq!(sorted_ids(&turned, 21), sorted_ids(&swapped, 21));
+    }
+
+    /// 長さの方向に沿って伸びる
+    #[test]
+    fn extends_along_heading() {
+        let oriented_box = OrientedBox::new(center(), [200.0, 20.0, 20.0], 0.0).unwrap();
+        let ids = sorted_ids(&oriented_box, 22);
+        let contains = |lat: f64, lon: f64| {
+            let id = Coordinate::new(lat, lon, 50.0)
+                .unwrap()
+                .single_id(22)
+                .unwrap()
+                .to_string();
+            ids.binary_search(&id).is_ok()
+        };
+        // 北へ約 80 m は内側、東へ約 80 m は外側
+        assert!(contains(35.68172, 139.766));
+        assert!(!contains(35.681, 139.76688));
+    }
+}
+
+mod new {
+    use super::*;
+
+    #[test]
+    fn rejects_non_positive_size() {
+        assert!(matches!(
+            OrientedBox::new(center(), [10.0, 10.0, -1.0], 0.0),
+            Err(Error::Geometry(GeometryError::SizeNotPositive { .. }))
+        ));
+        assert!(matches!(
+            OrientedBox::new(center(), [f64::NAN, 10.0, 10.0], 0.0),
+            Err(Error::Geometry(GeometryError::SizeNotPositive { .. }))
+        ));
+    }
+}
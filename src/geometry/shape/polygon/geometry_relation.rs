@@ -5,8 +5,12 @@ use crate::{
 };
 
 impl ExpandCoordinates for Polygon {
+    /// 外周、続いて各穴の頂点を返す。
     fn expand_coordinates(&self) -> impl Iterator<Item = Coordinate> {
-        self.vertices.clone().into_iter()
+        self.vertices
+            .clone()
+            .into_iter()
+            .chain(self.holes.clone().into_iter().flatten())
     }
 }
 
@@ -18,13 +22,17 @@ impl ExpandLines for Polygon {
 }
 
 impl ExpandTriangles for Polygon {
+    /// 耳切り法で三角形に分割する。
+    ///
+    /// 穴がある場合は、各穴を外周の見える頂点へ橋渡しして 1 つの周にまとめてから分割するので、
+    /// 穴の内側に三角形はできない。
     fn expand_triangles(&self) -> impl Iterator<Item = Triangle> {
         let n = self.vertices.len();
         if n < 3 {
             return Vec::<Triangle>::new().into_iter();
         }
 
-        if n == 3 {
+        if n == 3 && self.holes.is_empty() {
             return vec![Triangle::new([
                 self.vertices[0],
                 self.vertices[1],
@@ -33,11 +41,19 @@ impl ExpandTriangles for Polygon {
             .into_iter();
         }
 
+        // 外周・穴の順に全ての頂点を並べる
+        let points: Vec<Coordinate> = self
+            .vertices
+            .iter()
+            .chain(self.holes.iter().flatten())
+            .copied()
+            .collect();
+
         // 計算用に全て ECEF に変換
-        let ecef_points: Vec<Ecef> = self.vertices.iter().map(|&c| c.into()).collect();
+        let ecef_points: Vec<Ecef> = points.iter().map(|&c| c.into()).collect();
 
         // 投影軸の決定
-        let (u_axis, v_axis) = get_projection_axes(&ecef_points);
+        let (u_axis, v_axis) = get_projection_axes(&ecef_points[..n]);
 
         // 2D投影
         let points_2d: Vec<(f64, f64)> = ecef_points
@@ -46,14 +62,34 @@ impl ExpandTriangles for Polygon {
             .collect();
 
         // 回転方向の検知
-        let area = signed_area(&points_2d);
+        let area = signed_area(&points_2d[..n]);
         let area_sign = if area > 0.0 { 1.0 } else { -1.0 };
 
+        // 穴を外周と逆回りにそろえてから、外周へ橋渡しする
+        let mut holes: Vec<Vec<usize>> = Vec::with_capacity(self.holes.len());
+        let mut offset = n;
+        for hole in &self.holes {
+            let mut indices: Vec<usize> = (offset..offset + hole.len()).collect();
+            offset += hole.len();
+            let hole_2d: Vec<(f64, f64)> = indices.iter().map(|&i| points_2d[i]).collect();
+            if signed_area(&hole_2d) * area_sign > 0.0 {
+                indices.reverse();
+            }
+            holes.push(indices);
+        }
+        let mut indices = eliminate_holes((0..n).collect(), holes, &points_2d, area_sign);
+
         // 耳切りループ
-        let mut indices: Vec<usize> = (0..n).collect();
-        let mut result = Vec::with_capacity(n - 2);
+        let len = indices.len();
+        let mut result = Vec::with_capacity(len - 2);
         let mut count = 0;
-        let max_iters = n * n;
+        let max_iters = len * len;
+        let mut push = |a: usize, b: usize, c: usize| {
+            // 橋の両端は同じ頂点が 2 度現れるので、面積を持たない三角形ができる
+            if a != b && b != c && c != a {
+                result.push(Triangle::new([points[a], points[b], points[c]]));
+            }
+        };
 
         while indices.len() > 3 && count < max_iters {
             let mut ear_found = false;
@@ -67,11 +103,7 @@ impl ExpandTriangles for Polygon {
                 if is_ear(
                     prev_idx, curr_idx, next_idx, &indices, &points_2d, area_sign,
                 ) {
-                    result.push(Triangle::new([
-                        self.vertices[prev_idx],
-                        self.vertices[curr_idx],
-                        self.vertices[next_idx],
-                    ]));
+                    push(prev_idx, curr_idx, next_idx);
                     indices.remove(i);
                     ear_found = true;
                     break;
@@ -84,17 +116,114 @@ impl ExpandTriangles for Polygon {
         }
 
         if indices.len() == 3 {
-            result.push(Triangle::new([
-                self.vertices[indices[0]],
-                self.vertices[indices[1]],
-                self.vertices[indices[2]],
-            ]));
+            push(indices[0], indices[1], indices[2]);
         }
 
         result.into_iter()
     }
 }
 
+/// 各穴を外周の周へ橋渡しし、穴の周も含めた 1 つの周にする。
+///
+/// 右端（U が最大）の頂点が右にある穴から順に、その右端の頂点と、そこから辺を横切らずに
+/// 見える最も近い周の頂点を往復の辺で結ぶ。
+fn eliminate_holes(
+    mut ring: Vec<usize>,
+    mut holes: Vec<Vec<usize>>,
+    pts: &[(f64, f64)],
+    area_sign: f64,
+) -> Vec<usize> {
+    let rightmost = |hole: &[usize]| {
+        (0..hole.len())
+            .max_by(|&a, &b| pts[hole[a]].0.total_cmp(&pts[hole[b]].0))
+            .unwrap_or(0)
+    };
+    holes.sort_by(|a, b| {
+        let a = pts[a[rightmost(a)]].0;
+        let b = pts[b[rightmost(b)]].0;
+        b.total_cmp(&a)
+    });
+
+    for h in 0..holes.len() {
+        let hole = &holes[h];
+        let m_pos = rightmost(hole);
+        let m = pts[hole[m_pos]];
+
+        // 残りの穴（自身を含む）の辺も、橋が横切ってはならない
+        let blocked = |a: (f64, f64), b: (f64, f64)| {
+            let ring_edges = (0..ring.len()).map(|i| (ring[i], ring[(i + 1) % ring.len()]));
+            let hole_edges = holes[h..]
+                .iter()
+                .flat_map(|hole| (0..hole.len()).map(|i| (hole[i], hole[(i + 1) % hole.len()])));
+            ring_edges
+                .chain(hole_edges)
+                .any(|(c, d)| segments_cross(a, b, pts[c], pts[d]))
+        };
+
+        let mut candidates: Vec<usize> = (0..ring.len()).collect();
+        candidates.sort_by(|&a, &b| {
+            distance_squared(pts[ring[a]], m).total_cmp(&distance_squared(pts[ring[b]], m))
+        });
+        let bridge = candidates
+            .iter()
+            .copied()
+            .find(|&i| {
+                let len = ring.len();
+                let prev = pts[ring[(i + len - 1) % len]];
+                let next = pts[ring[(i + 1) % len]];
+                let v = pts[ring[i]];
+                in_wedge(prev, v, next, m, area_sign) && !blocked(v, m)
+            })
+            .unwrap_or(candidates[0]);
+
+        // ..., V, M, (穴を一周), M, V, ... の順に差し込む
+        let mut spliced = Vec::with_capacity(ring.len() + hole.len() + 2);
+        spliced.extend_from_slice(&ring[..=bridge]);
+        spliced.extend(hole[m_pos..].iter().chain(&hole[..=m_pos]));
+        spliced.extend_from_slice(&ring[bridge..]);
+        ring = spliced;
+    }
+    ring
+}
+
+fn distance_squared(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0) * (a.0 - b.0) + (a.1 - b.1) * (a.1 - b.1)
+}
+
+/// `a`→`b` に対して `p` がどちら側にあるか（2D外積）。
+fn orient(a: (f64, f64), b: (f64, f64), p: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+/// 線分 `ab` と `cd` が端点以外で交差するかどうか。端点を共有する場合は交差とみなさない。
+fn segments_cross(a: (f64, f64), b: (f64, f64), c: (f64, f64), d: (f64, f64)) -> bool {
+    if a == c || a == d || b == c || b == d {
+        return false;
+    }
+    let o1 = orient(a, b, c);
+    let o2 = orient(a, b, d);
+    let o3 = orient(c, d, a);
+    let o4 = orient(c, d, b);
+    o1 * o2 < 0.0 && o3 * o4 < 0.0
+}
+
+/// 頂点 `v` から見た `p` の方向が、`prev`→`v`→`next` の内側の角に入っているかどうか。
+fn in_wedge(
+    prev: (f64, f64),
+    v: (f64, f64),
+    next: (f64, f64),
+    p: (f64, f64),
+    area_sign: f64,
+) -> bool {
+    let left_of_prev = orient(prev, v, p) * area_sign > 0.0;
+    let left_of_next = orient(v, next, p) * area_sign > 0.0;
+    if orient(prev, v, next) * area_sign >= 0.0 {
+        left_of_prev && left_of_next
+    } else {
+        left_of_prev || left_of_next
+    }
+}
+
 /// Newell's Method による法線概算と投影軸の選択
 fn get_projection_axes(pts: &[Ecef]) -> (usize, usize) {
     let mut nx = 0.0;
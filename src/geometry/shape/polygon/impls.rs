@@ -1,7 +1,11 @@
 use hashbrown::HashSet;
 
+use crate::SpatialPredicates;
+use crate::geometry::shape::convex::{self, IdBox};
+use crate::geometry::shape::runs::RowRuns;
 use crate::{
-    Coordinate, ExpandTriangles, Polygon, Shape, SingleId, geometry::traits::CoverSingleIds,
+    Coordinate, Error, ExpandTriangles, Polygon, RangeId, Shape, SingleId,
+    geometry::traits::{CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds},
 };
 
 impl Shape for Polygon {
@@ -72,3 +76,42 @@ impl CoverSingleIds for Polygon {
         Ok(unique_ids.into_iter())
     }
 }
+
+impl CoverRangeIds for Polygon {
+    /// [`cover_single_ids`](CoverSingleIds::cover_single_ids) と同じ空間を [`RangeId`] で返す。
+    ///
+    /// 三角形分割した各三角形について、行ごとの X 方向の区間を式から求めて集め、同じ区間を
+    /// 持つ隣接した行・面を 1 つの [`RangeId`] にまとめる。三角形同士の重なりは区間の併合で
+    /// 取り除くので、[`SingleId`] を 1 つずつ列挙しない。
+    fn cover_range_ids(&self, z: u8) -> Result<impl Iterator<Item = RangeId>, Error> {
+        let mut runs = RowRuns::new(z);
+        for triangle in self.expand_triangles() {
+            triangle.push_runs(z, &mut runs)?;
+        }
+        Ok(runs.into_range_ids()?.into_iter())
+    }
+}
+
+impl CoverFlexIds for Polygon {}
+
+impl CoverClassifiedIds for Polygon {}
+
+impl SpatialPredicates for Polygon {
+    /// いずれかの三角形が直方体と交わるか。
+    fn intersects_part(&self, id: &RangeId) -> bool {
+        let id_box = IdBox::new(id);
+        self.expand_triangles()
+            .any(|triangle| convex::intersects(&convex::triangle_hull(&triangle), &id_box))
+    }
+
+    /// 体積を持たないので常に `false`。
+    fn contains_part(&self, _id: &RangeId) -> bool {
+        false
+    }
+
+    fn within_part(&self, id: &RangeId) -> bool {
+        let id_box = IdBox::new(id);
+        self.expand_triangles()
+            .all(|triangle| id_box.encloses(&convex::triangle_hull(&triangle)))
+    }
+}
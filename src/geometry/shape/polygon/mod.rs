@@ -11,9 +11,11 @@ mod tests;
 /// 3次元空間における多角形（ポリゴン）を表す型。
 ///
 /// 頂点リスト（[Coordinate] のVec）によって定義される平面的な領域を表現する。
+/// 外周のほかに、領域から除く穴（内周）を持てる。
 /// 生成時に頂点の重複排除などが行われ、幾何計算に適した状態に保たれる。
 pub struct Polygon {
     vertices: Vec<Coordinate>,
+    holes: Vec<Vec<Coordinate>>,
 }
 
 impl Polygon {
@@ -28,35 +30,89 @@ impl Polygon {
     /// - `raw_points` - ポリゴンを構成する頂点のリスト。
     /// - `epsilon` - 同一点とみなす許容誤差（メートル単位）。
     pub fn new(raw_points: Vec<Coordinate>, epsilon: f64) -> Self {
-        if raw_points.is_empty() {
-            return Self { vertices: vec![] };
+        Self {
+            vertices: normalize_ring(raw_points, epsilon),
+            holes: vec![],
         }
+    }
 
-        let mut vertices: Vec<Coordinate> = Vec::new();
-
-        for p in raw_points {
-            if let Some(last) = vertices.last() {
-                if !last.eq_epsilon(&p, epsilon) {
-                    vertices.push(p);
-                }
-            } else {
-                vertices.push(p);
-            }
+    /// 外周と穴（内周）の頂点座標のリストから、穴のある [Polygon] を作成。
+    ///
+    /// # 処理内容
+    /// - 外周・各穴のそれぞれに [`Polygon::new`] と同じ正規化を行う。
+    /// - 点の数が3未満になった穴は取り除く。外周が3未満になった場合は穴も含めて空にする。
+    /// - 穴は外周と同じ平面上にあり、外周の内側に収まり、互いに交わらないこと。
+    ///
+    /// # 引数
+    /// - `exterior` - 外周の頂点のリスト。
+    /// - `holes` - 穴ごとの頂点のリスト。
+    /// - `epsilon` - 同一点とみなす許容誤差（メートル単位）。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 中庭のある敷地を作る
+    /// ```
+    /// # use kasane_logic::{Coordinate, Polygon};
+    /// let c = |lat, lon| Coordinate::new(lat, lon, 10.0).unwrap();
+    /// let exterior = vec![c(35.0, 139.0), c(35.003, 139.0), c(35.003, 139.003), c(35.0, 139.003)];
+    /// let courtyard = vec![c(35.001, 139.001), c(35.002, 139.001), c(35.002, 139.002), c(35.001, 139.002)];
+    /// let polygon = Polygon::with_holes(exterior, vec![courtyard], 0.01);
+    ///
+    /// assert_eq!(polygon.vertices().len(), 4);
+    /// assert_eq!(polygon.holes().len(), 1);
+    /// ```
+    pub fn with_holes(
+        exterior: Vec<Coordinate>,
+        holes: Vec<Vec<Coordinate>>,
+        epsilon: f64,
+    ) -> Self {
+        let vertices = normalize_ring(exterior, epsilon);
+        if vertices.is_empty() {
+            return Self {
+                vertices,
+                holes: vec![],
+            };
         }
+        let holes = holes
+            .into_iter()
+            .map(|hole| normalize_ring(hole, epsilon))
+            .filter(|hole| !hole.is_empty())
+            .collect();
+        Self { vertices, holes }
+    }
 
-        if vertices.len() > 2 && vertices[0].eq_epsilon(vertices.last().unwrap(), epsilon) {
-            vertices.pop();
-        }
+    /// [Polygon]の外周を構成する点を返す。
+    pub fn vertices(&self) -> &Vec<Coordinate> {
+        &self.vertices
+    }
+
+    /// [Polygon]の穴ごとに、その周を構成する点を返す。
+    pub fn holes(&self) -> &Vec<Vec<Coordinate>> {
+        &self.holes
+    }
+}
+
+/// 連続する重複点と閉じた終点を取り除く。点が3未満になれば空にする。
+fn normalize_ring(raw_points: Vec<Coordinate>, epsilon: f64) -> Vec<Coordinate> {
+    let mut vertices: Vec<Coordinate> = Vec::new();
 
-        if vertices.len() < 3 {
-            return Self { vertices: vec![] };
+    for p in raw_points {
+        if let Some(last) = vertices.last() {
+            if !last.eq_epsilon(&p, epsilon) {
+                vertices.push(p);
+            }
+        } else {
+            vertices.push(p);
         }
+    }
 
-        Self { vertices }
+    if vertices.len() > 2 && vertices[0].eq_epsilon(vertices.last().unwrap(), epsilon) {
+        vertices.pop();
     }
 
-    /// [Polygon]を構成する点を返す。
-    pub fn vertices(&self) -> &Vec<Coordinate> {
-        &self.vertices
+    if vertices.len() < 3 {
+        return vec![];
     }
+
+    vertices
 }
@@ -1,7 +1,32 @@
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
-use crate::{Coordinate, CoverSingleIds, Polygon};
+use crate::geometry::shape::cover_tests::assert_same_cover;
+use crate::{Coordinate, CoverSingleIds, ExpandTriangles, Polygon};
+
+/// 1 辺 300 m ほどの正方形の中央に、1 辺 100 m ほどの正方形の中庭を持つポリゴン。
+fn courtyard() -> Polygon {
+    let c = |lat, lon| Coordinate::new(lat, lon, 10.0).unwrap();
+    Polygon::with_holes(
+        vec![
+            c(35.681, 139.766),
+            c(35.684, 139.766),
+            c(35.684, 139.769),
+            c(35.681, 139.769),
+        ],
+        vec![vec![
+            c(35.682, 139.767),
+            c(35.683, 139.767),
+            c(35.683, 139.768),
+            c(35.682, 139.768),
+        ]],
+        0.01,
+    )
+}
+
+fn triangle_area(polygon: &Polygon) -> f64 {
+    polygon.expand_triangles().map(|t| t.area()).sum()
+}
 
 fn sorted_ids(polygon: &Polygon, z: u8) -> Vec<String> {
     let mut ids: Vec<String> = polygon
@@ -48,4 +73,168 @@ mod cover_single_ids {
         let polygon = Polygon::new(vec![p0, p1, p2, p3, p4], 0.01);
         insta::assert_debug_snapshot!(sorted_ids(&polygon, 18));
     }
+
+    /// 中庭のある四角形ポリゴンを変換する
+    #[test]
+    fn quadrilateral_with_courtyard_at_z18() {
+        insta::assert_debug_snapshot!(sorted_ids(&courtyard(), 18));
+    }
+
+    /// 穴の内側のボクセルは覆わず、穴の縁のボクセルは残す
+    #[test]
+    fn courtyard_interior_is_excluded() {
+        let polygon = courtyard();
+        let ids = sorted_ids(&polygon, 20);
+        let outer = Polygon::new(polygon.vertices().clone(), 0.01);
+        let hole = Polygon::new(polygon.holes()[0].clone(), 0.01);
+
+        let center = Coordinate::new(35.6825, 139.7675, 10.0).unwrap();
+        let center = center.single_id(20).unwrap().to_string();
+        assert!(ids.binary_search(&center).is_err());
+
+        let hole_ids = sorted_ids(&hole, 20);
+        let outer_ids = sorted_ids(&outer, 20);
+        for id in &outer_ids {
+            if hole_ids.binary_search(id).is_err() {
+                assert!(ids.binary_search(id).is_ok(), "{id}");
+            }
+        }
+        assert!(ids.len() < outer_ids.len());
+        assert!(hole_ids.iter().any(|id| ids.binary_search(id).is_ok()));
+    }
+}
+
+mod expand_triangles {
+    use super::*;
+
+    /// 穴の面積だけ三角形の面積が減る（地球の曲率で分割ごとにわずかにずれる）
+    #[test]
+    fn hole_area_is_excluded() {
+        let polygon = courtyard();
+        let outer = Polygon::new(polygon.vertices().clone(), 0.01);
+        let hole = Polygon::new(polygon.holes()[0].clone(), 0.01);
+
+        let expected = triangle_area(&outer) - triangle_area(&hole);
+        let area = triangle_area(&polygon);
+        assert!(
+            libm::fabs(area - expected) < expected * 1e-4,
+            "{area} != {expected}"
+        );
+        // 外周 4 点・穴 4 点を 2 本の橋でつなぐので 8 枚になる
+        assert_eq!(polygon.expand_triangles().count(), 8);
+    }
+
+    /// 向きの揃っていない複数の穴も取り除く
+    #[test]
+    fn multiple_holes_in_either_winding() {
+        let c = |lat, lon| Coordinate::new(lat, lon, 10.0).unwrap();
+        let exterior = vec![
+            c(35.681, 139.766),
+            c(35.681, 139.770),
+            c(35.684, 139.770),
+            c(35.684, 139.766),
+        ];
+        let west = vec![
+            c(35.682, 139.767),
+            c(35.683, 139.767),
+            c(35.683, 139.768),
+            c(35.682, 139.768),
+        ];
+        let east = vec![
+            c(35.682, 139.7685),
+            c(35.682, 139.7695),
+            c(35.683, 139.7695),
+            c(35.683, 139.7685),
+        ];
+        let polygon = Polygon::with_holes(exterior.clone(), vec![west.clone(), east.clone()], 0.01);
+
+        let expected = triangle_area(&Polygon::new(exterior, 0.01))
+            - triangle_area(&Polygon::new(west, 0.01))
+            - triangle_area(&Polygon::new(east, 0.01));
+        let area = triangle_area(&polygon);
+        assert!(
+            libm::fabs(area - expected) < expected * 1e-4,
+            "{area} != {expected}"
+        );
+    }
+
+    /// 頂点が 3 未満になった穴は取り除かれる
+    #[test]
+    fn degenerate_hole_is_dropped() {
+        let polygon = courtyard();
+        let c = Coordinate::new(35.6825, 139.7675, 10.0).unwrap();
+        let with_point_hole =
+            Polygon::with_holes(polygon.vertices().clone(), vec![vec![c, c, c]], 0.01);
+        assert!(with_point_hole.holes().is_empty());
+    }
+}
+
+mod cover_range_ids {
+    use super::*;
+
+    /// 水平な四角形は少数の矩形にまとまる
+    #[test]
+    fn horizontal_quadrilateral_matches_single_ids() {
+        let p0 = Coordinate::new(35.681, 139.766, 10.0).unwrap();
+        let p1 = Coordinate::new(35.684, 139.766, 10.0).unwrap();
+        let p2 = Coordinate::new(35.684, 139.769, 10.0).unwrap();
+        let p3 = Coordinate::new(35.681, 139.769, 10.0).unwrap();
+        let polygon = Polygon::new(vec![p0, p1, p2, p3], 0.01);
+        let count = assert_same_cover(&polygon, 20);
+        assert!(count * 10 < sorted_ids(&polygon, 20).len());
+    }
+
+    /// 頂点数が多い多角形も同じ空間を覆う
+    #[test]
+    fn pentagon_matches_single_ids() {
+        let p0 = Coordinate::new(35.682, 139.767, 10.0).unwrap();
+        let p1 = Coordinate::new(35.683, 139.766, 10.0).unwrap();
+        let p2 = Coordinate::new(35.684, 139.767, 10.0).unwrap();
+        let p3 = Coordinate::new(35.684, 139.769, 10.0).unwrap();
+        let p4 = Coordinate::new(35.681, 139.769, 10.0).unwrap();
+        let polygon = Polygon::new(vec![p0, p1, p2, p3, p4], 0.01);
+        assert_same_cover(&polygon, 20);
+    }
+
+    /// 穴のあるポリゴンも同じ空間を覆う
+    #[test]
+    fn courtyard_matches_single_ids() {
+        assert_same_cover(&courtyard(), 20);
+    }
+}
+
+mod spatial_predicates {
+    use super::*;
+    use crate::{SingleId, SpatialPredicates};
+
+    fn cell_at(lat: f64, lon: f64, z: u8) -> SingleId {
+        Coordinate::new(lat, lon, 10.0)
+            .unwrap()
+            .single_id(z)
+            .unwrap()
+    }
+
+    /// 中庭のボクセルとは交わらず、外周部のボクセルとは交わる
+    #[test]
+    fn courtyard_cell_is_not_intersected() {
+        let polygon = courtyard();
+        assert!(polygon.intersects(&cell_at(35.6815, 139.7665, 22)));
+        assert!(!polygon.intersects(&cell_at(35.6825, 139.7675, 22)));
+    }
+
+    /// 面は体積を持たないので、どの空間IDも含まない
+    #[test]
+    fn never_contains() {
+        let polygon = courtyard();
+        assert!(!polygon.contains(&cell_at(35.6815, 139.7665, 22)));
+        assert!(!polygon.contains(&cell_at(35.6815, 139.7665, 12)));
+    }
+
+    /// ポリゴン全体を囲む粗いボクセルには収まり、細かいボクセルには収まらない
+    #[test]
+    fn within_coarse_cell_only() {
+        let polygon = courtyard();
+        assert!(polygon.within(&cell_at(35.6825, 139.7675, 12)));
+        assert!(!polygon.within(&cell_at(35.6825, 139.7675, 22)));
+    }
 }
@@ -0,0 +1,62 @@
+use alloc::vec::Vec;
+
+use crate::{
+    Coordinate, ExpandCoordinates, ExpandLines, ExpandPolygons, ExpandTriangles, Line, Polygon,
+    Prism, Triangle,
+};
+
+impl ExpandCoordinates for Prism {
+    fn expand_coordinates(&self) -> impl Iterator<Item = Coordinate> {
+        self.expand_polygons()
+            .flat_map(|polygon| polygon.expand_coordinates().collect::<Vec<_>>())
+    }
+}
+
+impl ExpandLines for Prism {
+    fn expand_lines(&self) -> impl Iterator<Item = Line> {
+        self.expand_polygons()
+            .flat_map(|polygon| polygon.expand_lines().collect::<Vec<_>>())
+    }
+}
+
+impl ExpandTriangles for Prism {
+    fn expand_triangles(&self) -> impl Iterator<Item = Triangle> {
+        self.expand_polygons()
+            .flat_map(|polygon| polygon.expand_triangles().collect::<Vec<_>>())
+    }
+}
+
+impl ExpandPolygons for Prism {
+    /// 底面・屋根・外周と穴の各辺の壁を返す。これらで [`Solid`](crate::Solid) を作ると閉じている。
+    fn expand_polygons(&self) -> impl Iterator<Item = Polygon> {
+        let bottom = |_: f64, _: f64| self.bottom;
+        let roof = |lat: f64, lon: f64| self.roof_altitude(lat, lon);
+
+        // 頂点は底面の Polygon で正規化済みなので、これ以上まとめない
+        let exterior = self.footprint.vertices();
+        let holes = self.footprint.holes();
+        let mut polygons = Vec::with_capacity(2 + exterior.len());
+        polygons.push(Polygon::with_holes(
+            self.lift(exterior, bottom),
+            holes.iter().map(|hole| self.lift(hole, bottom)).collect(),
+            0.0,
+        ));
+        polygons.push(Polygon::with_holes(
+            self.lift(exterior, roof),
+            holes.iter().map(|hole| self.lift(hole, roof)).collect(),
+            0.0,
+        ));
+        for ring in core::iter::once(exterior).chain(holes) {
+            let lower = self.lift(ring, bottom);
+            let upper = self.lift(ring, roof);
+            for i in 0..ring.len() {
+                let j = (i + 1) % ring.len();
+                polygons.push(Polygon::new(
+                    vec![lower[i], lower[j], upper[j], upper[i]],
+                    0.0,
+                ));
+            }
+        }
+        polygons.into_iter()
+    }
+}
@@ -0,0 +1,128 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+
+use crate::geometry::shape::convex::{IdBox, Mesh};
+use crate::geometry::shape::{prism::Roof, runs::RowRuns};
+use crate::spatial_id::helpers::{latitude, longitude};
+use crate::spatial_id::zoom_level::ZoomLevel;
+use crate::{
+    Coordinate, CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds, Error,
+    ExpandCoordinates, ExpandTriangles, Polygon, Prism, RangeId, Shape, SingleId,
+    SpatialPredicates,
+};
+
+impl Shape for Prism {
+    fn center(&self) -> Coordinate {
+        Coordinate::center_gravity(self.expand_coordinates())
+    }
+}
+
+impl CoverSingleIds for Prism {
+    /// [`cover_range_ids`](CoverRangeIds::cover_range_ids) の柱を [`SingleId`] に展開して返す。
+    fn cover_single_ids(&self, z: u8) -> Result<impl Iterator<Item = SingleId>, Error> {
+        let ranges = self.columns(z)?;
+        Ok(ranges.into_iter().flat_map(|range| range.single_ids()))
+    }
+}
+
+impl CoverRangeIds for Prism {
+    /// 底面を水平方向に塗りつぶした `(x, y)` の柱を、底面から屋根までの F 方向へ伸ばして返す。
+    ///
+    /// 屋根の高さが同じ柱は X・Y 方向にまとめるので、平らな屋根なら底面の形だけで
+    /// [`RangeId`] の個数が決まり、高さには依存しない。
+    fn cover_range_ids(&self, z: u8) -> Result<impl Iterator<Item = RangeId>, Error> {
+        Ok(self.columns(z)?.into_iter())
+    }
+}
+
+impl CoverFlexIds for Prism {}
+
+impl CoverClassifiedIds for Prism {}
+
+impl Prism {
+    /// ズームレベル `z` で底面にかかる柱ごとに F の範囲を求め、互いに重ならない [`RangeId`] にまとめる。
+    fn columns(&self, z: u8) -> Result<Vec<RangeId>, Error> {
+        let zoom = ZoomLevel::new(z)?;
+        let z = zoom.get();
+        let (f_min, f_max) = (zoom.f_min() as i64, zoom.f_max() as i64);
+
+        // 底面を底の高さに置いた水平な面が通る (x, y) が柱になる。
+        let bottom = |_: f64, _: f64| self.bottom;
+        let footprint = Polygon::with_holes(
+            self.lift(self.footprint.vertices(), bottom),
+            self.footprint
+                .holes()
+                .iter()
+                .map(|hole| self.lift(hole, bottom))
+                .collect(),
+            0.0,
+        );
+        let mut cells: BTreeSet<(u32, u32)> = BTreeSet::new();
+        for triangle in footprint.expand_triangles() {
+            for id in triangle.cover_single_ids(z)? {
+                cells.insert((id.y(), id.x()));
+            }
+        }
+
+        //Z=25のとき高さはちょうど1m
+        let factor = libm::pow(2_f64, (z as i32 - 25) as f64);
+        let f_bottom = (libm::floor(factor * self.bottom) as i64).clamp(f_min, f_max) as i32;
+        let (roof_min, roof_max) = self
+            .footprint_vertices()
+            .map(|p| self.roof_altitude(p.latitude(), p.longitude()))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), h| {
+                (lo.min(h), hi.max(h))
+            });
+
+        // 屋根の F が同じ柱ごとに、X・Y 方向の区間を集める。
+        let mut layers: BTreeMap<i32, RowRuns> = BTreeMap::new();
+        for (y, x) in cells {
+            let top = match self.roof {
+                Roof::Flat(top) => top,
+                // 屋根は平面なので、柱の中での最高点は柱の四隅のどれかにある。
+                Roof::Plane(_) => {
+                    let corners = [x, x + 1].into_iter().flat_map(|cx| {
+                        [y, y + 1].into_iter().map(move |cy| {
+                            self.roof_altitude(latitude(cy as f64, z), longitude(cx as f64, z))
+                        })
+                    });
+                    corners
+                        .fold(f64::NEG_INFINITY, f64::max)
+                        .clamp(roof_min, roof_max)
+                }
+            };
+            // 屋根がちょうどボクセルの境界にあるときは、その上のボクセルを含めない。
+            let f_top =
+                ((libm::ceil(factor * top) as i64 - 1).clamp(f_min, f_max) as i32).max(f_bottom);
+            layers
+                .entry(f_top)
+                .or_insert_with(|| RowRuns::new(z))
+                .push_run(f_bottom, y, x, x);
+        }
+
+        let mut out = Vec::new();
+        for (f_top, runs) in layers {
+            for range in runs.into_range_ids()? {
+                out.push(RangeId::new(z, [f_bottom, f_top], range.x(), range.y())?);
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl SpatialPredicates for Prism {
+    /// 表面の三角形が直方体と交わるか、直方体の中心が内側にあるか。
+    fn intersects_part(&self, id: &RangeId) -> bool {
+        Mesh::new(self.expand_triangles()).intersects(&IdBox::new(id))
+    }
+
+    /// 直方体の中心が内側にあり、表面の三角形がどれも直方体と交わらないか。
+    /// 直方体が表面に接する場合は `false`。
+    fn contains_part(&self, id: &RangeId) -> bool {
+        Mesh::new(self.expand_triangles()).contains(&IdBox::new(id))
+    }
+
+    fn within_part(&self, id: &RangeId) -> bool {
+        Mesh::new(self.expand_triangles()).within(&IdBox::new(id))
+    }
+}
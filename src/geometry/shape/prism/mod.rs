@@ -0,0 +1,160 @@
+use alloc::vec::Vec;
+
+use crate::{Coordinate, Error, GeometryError, Polygon};
+
+pub mod geometry_relation;
+pub mod impls;
+#[cfg(test)]
+mod tests;
+
+/// [Prism] の屋根の形。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Roof {
+    /// 高度（メートル）が一定の平らな屋根。
+    Flat(f64),
+    /// 3 点を通る平面の傾斜屋根。屋根の高度は緯度・経度に対して線形に変化する。
+    Plane([Coordinate; 3]),
+}
+
+#[derive(Debug, Clone)]
+/// 水平な底面（フットプリント）を、底面の高度から屋根まで鉛直に押し出した角柱を表す型。
+///
+/// 建物のように「平面形と、基部・屋根の高度」で与えられる立体を、壁や上下の面を
+/// 自分で組み立てずに表現する。底面の [Polygon] は穴を持ってよく、頂点の高度は無視される。
+pub struct Prism {
+    footprint: Polygon,
+    bottom: f64,
+    roof: Roof,
+    /// 屋根の高度を `a * 緯度 + b * 経度 + c` で表す係数 `[a, b, c]`。
+    roof_plane: [f64; 3],
+}
+
+impl Prism {
+    /// 平らな屋根の [Prism] を作成する。
+    ///
+    /// # 引数
+    /// - `footprint` - 底面の形。頂点の高度は無視される。
+    /// - `bottom` - 底面の高度（メートル）。
+    /// - `top` - 屋根の高度（メートル）。
+    ///
+    /// # バリデーション
+    /// - `footprint` が空の場合は [GeometryError::FootprintEmpty] を返す。
+    /// - `top` が `bottom` 以下の場合は [GeometryError::RoofNotAboveBottom] を返す。
+    /// - 高度が有効範囲外の場合は [GeometryError::AltitudeOutOfRange] を返す。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 高さ 30 m の建物を作る
+    /// ```
+    /// # use kasane_logic::{Coordinate, Polygon, Prism};
+    /// let c = |lat, lon| Coordinate::new(lat, lon, 0.0).unwrap();
+    /// let footprint = Polygon::new(
+    ///     vec![c(35.681, 139.766), c(35.6812, 139.766), c(35.6812, 139.7663), c(35.681, 139.7663)],
+    ///     0.01,
+    /// );
+    /// let building = Prism::new(footprint, 5.0, 35.0).unwrap();
+    ///
+    /// assert_eq!(building.roof_altitude(35.6811, 139.7661), 35.0);
+    /// ```
+    pub fn new(footprint: Polygon, bottom: f64, top: f64) -> Result<Self, Error> {
+        Self::with_roof(footprint, bottom, Roof::Flat(top))
+    }
+
+    /// 屋根の形を指定して [Prism] を作成する。
+    ///
+    /// 検証は [`Prism::new`] と同じで、傾斜屋根の場合は底面の全ての頂点の真上で屋根が
+    /// 底面より高いこと、屋根を定める 3 点が一直線上にないこと（[GeometryError::RoofPlaneDegenerate]）も検証する。
+    pub fn with_roof(footprint: Polygon, bottom: f64, roof: Roof) -> Result<Self, Error> {
+        if footprint.vertices().is_empty() {
+            return Err(GeometryError::FootprintEmpty.into());
+        }
+        let roof_plane = match roof {
+            Roof::Flat(top) => [0.0, 0.0, top],
+            Roof::Plane(points) => plane_through(&points)?,
+        };
+        let prism = Self {
+            footprint,
+            bottom,
+            roof,
+            roof_plane,
+        };
+
+        for vertex in prism.footprint_vertices() {
+            let top = prism.roof_altitude(vertex.latitude(), vertex.longitude());
+            if top <= bottom {
+                return Err(GeometryError::RoofNotAboveBottom { bottom, roof: top }.into());
+            }
+            Coordinate::new(vertex.latitude(), vertex.longitude(), bottom)?;
+            Coordinate::new(vertex.latitude(), vertex.longitude(), top)?;
+        }
+        Ok(prism)
+    }
+
+    /// 底面の形を返す。
+    pub fn footprint(&self) -> &Polygon {
+        &self.footprint
+    }
+
+    /// 底面の高度（メートル）を返す。
+    pub fn bottom(&self) -> f64 {
+        self.bottom
+    }
+
+    /// 屋根の形を返す。
+    pub fn roof(&self) -> Roof {
+        self.roof
+    }
+
+    /// 緯度・経度の真上での屋根の高度（メートル）を返す。
+    pub fn roof_altitude(&self, latitude: f64, longitude: f64) -> f64 {
+        let [a, b, c] = self.roof_plane;
+        a * latitude + b * longitude + c
+    }
+
+    /// 底面の外周と穴の全ての頂点。
+    fn footprint_vertices(&self) -> impl Iterator<Item = &Coordinate> {
+        self.footprint
+            .vertices()
+            .iter()
+            .chain(self.footprint.holes().iter().flatten())
+    }
+
+    /// `ring` の各頂点を、高度 `altitude(緯度, 経度)` へ移した点の列。
+    fn lift(&self, ring: &[Coordinate], altitude: impl Fn(f64, f64) -> f64) -> Vec<Coordinate> {
+        ring.iter()
+            .map(|p| {
+                // 高度は生成時に検証済み
+                Coordinate::new(
+                    p.latitude(),
+                    p.longitude(),
+                    altitude(p.latitude(), p.longitude()),
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+}
+
+/// 3 点を通る平面を `高度 = a * 緯度 + b * 経度 + c` の係数で返す。
+fn plane_through(points: &[Coordinate; 3]) -> Result<[f64; 3], Error> {
+    let [p0, p1, p2] = points;
+    let (u1, v1, h1) = (
+        p1.latitude() - p0.latitude(),
+        p1.longitude() - p0.longitude(),
+        p1.altitude() - p0.altitude(),
+    );
+    let (u2, v2, h2) = (
+        p2.latitude() - p0.latitude(),
+        p2.longitude() - p0.longitude(),
+        p2.altitude() - p0.altitude(),
+    );
+    let det = u1 * v2 - u2 * v1;
+    // 緯度・経度の差の丸め誤差に埋もれるほど小さければ、一直線上とみなす
+    if libm::fabs(det) <= 1e-9 * (libm::fabs(u1 * v2) + libm::fabs(u2 * v1)) {
+        return Err(GeometryError::RoofPlaneDegenerate.into());
+    }
+    let a = (h1 * v2 - h2 * v1) / det;
+    let b = (u1 * h2 - u2 * h1) / det;
+    let c = p0.altitude() - a * p0.latitude() - b * p0.longitude();
+    Ok([a, b, c])
+}
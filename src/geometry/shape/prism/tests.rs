@@ -0,0 +1,255 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{
+    Coordinate, CoverRangeIds, CoverSingleIds, Error, ExpandPolygons, GeometryError, Polygon,
+    Prism, RangeId, Roof, Solid,
+};
+
+fn c(lat: f64, lon: f64) -> Coordinate {
+    Coordinate::new(lat, lon, 0.0).unwrap()
+}
+
+/// 1 辺 100 m ほどの正方形の底面。
+fn square() -> Vec<Coordinate> {
+    vec![
+        c(35.681, 139.766),
+        c(35.682, 139.766),
+        c(35.682, 139.767),
+        c(35.681, 139.767),
+    ]
+}
+
+fn sorted_ids(prism: &Prism, z: u8) -> Vec<String> {
+    let mut ids: Vec<String> = prism
+        .cover_single_ids(z)
+        .unwrap()
+        .map(|id| id.to_string())
+        .collect();
+    ids.sort();
+    ids
+}
+
+mod cover_single_ids {
+    use super::*;
+
+    /// 平らな屋根の建物を変換する
+    #[test]
+    fn flat_building_at_z20() {
+        let prism = Prism::new(Polygon::new(square(), 0.01), 5.0, 40.0).unwrap();
+        insta::assert_debug_snapshot!(sorted_ids(&prism, 20));
+    }
+
+    /// 中庭の柱は覆わない
+    #[test]
+    fn courtyard_column_is_excluded() {
+        let courtyard = vec![
+            c(35.6813, 139.7663),
+            c(35.6817, 139.7663),
+            c(35.6817, 139.7667),
+            c(35.6813, 139.7667),
+        ];
+        let footprint = Polygon::with_holes(square(), vec![courtyard], 0.01);
+        let prism = Prism::new(footprint, 0.0, 20.0).unwrap();
+        let ids = sorted_ids(&prism, 22);
+
+        for altitude in [1.0, 10.0, 19.0] {
+            let inside = Coordinate::new(35.6815, 139.7665, altitude).unwrap();
+            let inside = inside.single_id(22).unwrap().to_string();
+            assert!(ids.binary_search(&inside).is_err());
+            let wall = Coordinate::new(35.6811, 139.7661, altitude).unwrap();
+            let wall = wall.single_id(22).unwrap().to_string();
+            assert!(ids.binary_search(&wall).is_ok());
+        }
+    }
+
+    /// 傾斜屋根は高い側の柱ほど高くまで覆う
+    #[test]
+    fn sloped_roof_follows_plane() {
+        let roof = Roof::Plane([
+            Coordinate::new(35.681, 139.766, 10.0).unwrap(),
+            Coordinate::new(35.682, 139.766, 10.0).unwrap(),
+            Coordinate::new(35.681, 139.767, 30.0).unwrap(),
+        ]);
+        let prism = Prism::with_roof(Polygon::new(square(), 0.01), 0.0, roof).unwrap();
+        let top_at = |lon: f64| {
+            prism
+                .cover_single_ids(25)
+                .unwrap()
+                .filter(|id| {
+                    let point = Coordinate::new(35.6815, lon, 0.0).unwrap();
+                    let column = point.single_id(25).unwrap();
+                    id.x() == column.x() && id.y() == column.y()
+                })
+                .map(|id| id.f())
+                .max()
+                .unwrap()
+        };
+        // 高度 1 m ごとのボクセルで、屋根の高さ（約 11 m と約 29 m）まで伸びる
+        assert!(
+            (10..=12).contains(&top_at(139.7661)),
+            "{}",
+            top_at(139.7661)
+        );
+        assert!(
+            (28..=30).contains(&top_at(139.7669)),
+            "{}",
+            top_at(139.7669)
+        );
+    }
+}
+
+mod cover_range_ids {
+    use super::*;
+
+    /// 平らな屋根なら、RangeId の個数は高さによらない
+    #[test]
+    fn flat_roof_range_count_is_independent_of_height() {
+        let low = Prism::new(Polygon::new(square(), 0.01), 0.0, 10.0).unwrap();
+        let high = Prism::new(Polygon::new(square(), 0.01), 0.0, 300.0).unwrap();
+        let low: Vec<RangeId> = low.cover_range_ids(22).unwrap().collect();
+        let high: Vec<RangeId> = high.cover_range_ids(22).unwrap().collect();
+        assert_eq!(low.len(), high.len());
+        // z=22 のボクセルの高さは 8 m
+        assert!(low.iter().all(|range| range.f() == [0, 1]), "{low:?}");
+        assert!(high.iter().all(|range| range.f() == [0, 37]), "{high:?}");
+    }
+
+    /// 柱同士は重ならず、展開すると cover_single_ids と一致する
+    #[test]
+    fn matches_single_ids() {
+        let roof = Roof::Plane([
+            Coordinate::new(35.681, 139.766, 10.0).unwrap(),
+            Coordinate::new(35.682, 139.766, 20.0).unwrap(),
+            Coordinate::new(35.681, 139.767, 30.0).unwrap(),
+        ]);
+        let prism = Prism::with_roof(Polygon::new(square(), 0.01), 0.0, roof).unwrap();
+        let mut ids: Vec<String> = prism
+            .cover_range_ids(23)
+            .unwrap()
+            .flat_map(|range| range.single_ids())
+            .map(|id| id.to_string())
+            .collect();
+        ids.sort();
+        let len = ids.len();
+        ids.dedup();
+        assert_eq!(ids.len(), len, "RangeId が重なっている");
+        assert_eq!(ids, sorted_ids(&prism, 23));
+    }
+}
+
+mod expand_polygons {
+    use super::*;
+
+    /// 底面・屋根・壁から作った Solid は閉じている
+    #[test]
+    fn faces_form_watertight_solid() {
+        let courtyard = vec![
+            c(35.6813, 139.7663),
+            c(35.6817, 139.7663),
+            c(35.6817, 139.7667),
+            c(35.6813, 139.7667),
+        ];
+        let footprint = Polygon::with_holes(square(), vec![courtyard], 0.01);
+        let prism = Prism::new(footprint, 0.0, 20.0).unwrap();
+        let faces: Vec<Polygon> = prism.expand_polygons().collect();
+        // 底面・屋根と、外周 4 辺・中庭 4 辺の壁
+        assert_eq!(faces.len(), 10);
+        assert!(Solid::new(faces, 1e-6).is_ok());
+    }
+}
+
+mod new {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_prisms() {
+        let footprint = Polygon::new(square(), 0.01);
+        assert!(matches!(
+            Prism::new(footprint.clone(), 10.0, 10.0),
+            Err(Error::Geometry(GeometryError::RoofNotAboveBottom { .. }))
+        ));
+        assert!(matches!(
+            Prism::new(Polygon::new(vec![c(35.0, 139.0)], 0.01), 0.0, 10.0),
+            Err(Error::Geometry(GeometryError::FootprintEmpty))
+        ));
+        let collinear = Roof::Plane([
+            Coordinate::new(35.681, 139.766, 10.0).unwrap(),
+            Coordinate::new(35.682, 139.767, 20.0).unwrap(),
+            Coordinate::new(35.683, 139.768, 30.0).unwrap(),
+        ]);
+        assert!(matches!(
+            Prism::with_roof(footprint.clone(), 0.0, collinear),
+            Err(Error::Geometry(GeometryError::RoofPlaneDegenerate))
+        ));
+        // 屋根が一部で底面を下回る
+        let dipping = Roof::Plane([
+            Coordinate::new(35.681, 139.766, -5.0).unwrap(),
+            Coordinate::new(35.682, 139.766, -5.0).unwrap(),
+            Coordinate::new(35.681, 139.767, 30.0).unwrap(),
+        ]);
+        assert!(matches!(
+            Prism::with_roof(footprint, 0.0, dipping),
+            Err(Error::Geometry(GeometryError::RoofNotAboveBottom { .. }))
+        ));
+    }
+}
+
+mod spatial_predicates {
+    use super::*;
+    use crate::{SingleId, SpatialPredicates};
+
+    fn cell_at(lat: f64, lon: f64, altitude: f64, z: u8) -> SingleId {
+        Coordinate::new(lat, lon, altitude)
+            .unwrap()
+            .single_id(z)
+            .unwrap()
+    }
+
+    /// 壁の内側・壁をまたぐ・中庭・屋根の上のボクセル
+    #[test]
+    fn cells_around_courtyard_building() {
+        let courtyard = vec![
+            c(35.6813, 139.7663),
+            c(35.6817, 139.7663),
+            c(35.6817, 139.7667),
+            c(35.6813, 139.7667),
+        ];
+        let footprint = Polygon::with_holes(square(), vec![courtyard], 0.01);
+        let prism = Prism::new(footprint, 0.0, 40.0).unwrap();
+
+        let inside = cell_at(35.6811, 139.7661, 20.0, 24);
+        assert!(prism.intersects(&inside));
+        assert!(prism.contains(&inside));
+        assert!(!prism.within(&inside));
+
+        // 屋根の高さ 40 m をまたぐ
+        let roof = cell_at(35.6811, 139.7661, 40.0, 21);
+        assert!(prism.intersects(&roof));
+        assert!(!prism.contains(&roof));
+
+        let court = cell_at(35.6815, 139.7665, 20.0, 24);
+        assert!(!prism.intersects(&court));
+        assert!(!prism.contains(&court));
+
+        let above = cell_at(35.6811, 139.7661, 80.0, 24);
+        assert!(!prism.intersects(&above));
+    }
+
+    /// 建物全体を囲む RangeId に収まる
+    #[test]
+    fn within_enclosing_range() {
+        let prism = Prism::new(Polygon::new(square(), 0.01), 5.0, 40.0).unwrap();
+        let corner = cell_at(35.681, 139.766, 0.0, 20);
+        let around = RangeId::new(
+            20,
+            [0, 3],
+            [corner.x() - 1, corner.x() + 4],
+            [corner.y() - 4, corner.y() + 1],
+        )
+        .unwrap();
+        assert!(prism.within(&around));
+        assert!(prism.intersects(&around));
+        assert!(!prism.contains(&around));
+    }
+}
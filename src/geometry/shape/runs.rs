@@ -0,0 +1,105 @@
+//! 図形の被覆を [`RangeId`] としてまとめるための補助。
+//!
+//! 被覆を F・Y を固定した行ごとの X 方向の区間（run）として集め、
+//! 同じ区間を持つ隣接した Y 行、同じ矩形を持つ隣接した F 面を 1 つの [`RangeId`] にまとめる。
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::{Error, RangeId, SingleId};
+
+/// `(f, y)` の行ごとの X 方向の区間の集まり。
+pub(crate) struct RowRuns {
+    z: u8,
+    rows: BTreeMap<(i32, u32), Vec<[u32; 2]>>,
+}
+
+impl RowRuns {
+    pub(crate) fn new(z: u8) -> Self {
+        Self {
+            z,
+            rows: BTreeMap::new(),
+        }
+    }
+
+    /// 行 `(f, y)` に X 方向の区間 `[x_start, x_end]` を加える。重なっていてもよい。
+    pub(crate) fn push_run(&mut self, f: i32, y: u32, x_start: u32, x_end: u32) {
+        self.rows.entry((f, y)).or_default().push([x_start, x_end]);
+    }
+
+    /// 1 つの [`SingleId`] を加える。`id` のズームレベルは `self` と同じであること。
+    pub(crate) fn push(&mut self, id: &SingleId) {
+        self.push_run(id.f(), id.y(), id.x(), id.x());
+    }
+
+    /// [`RangeId`] が覆う全ての行を加える。`range` のズームレベルは `self` と同じであること。
+    pub(crate) fn push_range(&mut self, range: &RangeId) {
+        let ([f_start, f_end], [x_start, x_end], [y_start, y_end]) =
+            (range.f(), range.x(), range.y());
+        for f in f_start..=f_end {
+            for y in y_start..=y_end {
+                self.push_run(f, y, x_start, x_end);
+            }
+        }
+    }
+
+    /// まとめた [`RangeId`] の列にする。出力同士は重ならない。
+    pub(crate) fn into_range_ids(self) -> Result<Vec<RangeId>, Error> {
+        // 各行の区間を昇順に並べ、重なり・隣接を併合する。
+        let rows = self.rows.into_iter().map(|(key, mut runs)| {
+            runs.sort_unstable();
+            let mut merged: Vec<[u32; 2]> = Vec::with_capacity(runs.len());
+            for [start, end] in runs {
+                match merged.last_mut() {
+                    Some(last) if start <= last[1].saturating_add(1) => last[1] = last[1].max(end),
+                    _ => merged.push([start, end]),
+                }
+            }
+            (key, merged)
+        });
+
+        // F 面ごとに、同じ区間の並びを持つ連続した Y 行を矩形にまとめる。
+        // 矩形は `(x, y, f)` を持ち、後で F 方向にもまとめる。
+        let mut rects: Vec<([u32; 2], [u32; 2], i32)> = Vec::new();
+        let mut open: Option<(i32, [u32; 2], Vec<[u32; 2]>)> = None;
+        let close = |open: (i32, [u32; 2], Vec<[u32; 2]>), rects: &mut Vec<_>| {
+            let (f, y, runs) = open;
+            rects.extend(runs.into_iter().map(|x| (x, y, f)));
+        };
+        for ((f, y), runs) in rows {
+            match &mut open {
+                Some((open_f, open_y, open_runs))
+                    if *open_f == f && open_y[1] + 1 == y && *open_runs == runs =>
+                {
+                    open_y[1] = y;
+                }
+                _ => {
+                    if let Some(done) = open.replace((f, [y, y], runs)) {
+                        close(done, &mut rects);
+                    }
+                }
+            }
+        }
+        if let Some(done) = open {
+            close(done, &mut rects);
+        }
+
+        // 同じ矩形が連続した F 面に並ぶものを 1 つにまとめる。
+        rects.sort_unstable();
+        let mut out = Vec::new();
+        let mut iter = rects.into_iter().peekable();
+        while let Some((x, y, f_start)) = iter.next() {
+            let mut f_end = f_start;
+            while let Some(&(next_x, next_y, next_f)) = iter.peek() {
+                if next_x == x && next_y == y && next_f == f_end + 1 {
+                    f_end = next_f;
+                    iter.next();
+                } else {
+                    break;
+                }
+            }
+            out.push(RangeId::new(self.z, [f_start, f_end], x, y)?);
+        }
+        Ok(out)
+    }
+}
@@ -3,10 +3,12 @@ use alloc::vec::Vec;
 use alloc::collections::VecDeque;
 use hashbrown::HashSet;
 
+use crate::geometry::shape::convex::{IdBox, Mesh};
 use crate::{
     Coordinate, Error, ExpandCoordinates, RangeId, Shape, SingleId, Solid, SpatialId,
-    geometry::traits::{CoverRangeIds, CoverSingleIds},
+    geometry::traits::{CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds},
 };
+use crate::{ExpandTriangles, SpatialPredicates};
 
 impl Shape for Solid {
     fn center(&self) -> Coordinate {
@@ -221,3 +223,24 @@ impl CoverRangeIds for Solid {
         Ok(results.into_iter())
     }
 }
+
+impl CoverFlexIds for Solid {}
+
+impl CoverClassifiedIds for Solid {}
+
+impl SpatialPredicates for Solid {
+    /// 表面の三角形が直方体と交わるか、直方体の中心が内側にあるか。
+    fn intersects_part(&self, id: &RangeId) -> bool {
+        Mesh::new(self.expand_triangles()).intersects(&IdBox::new(id))
+    }
+
+    /// 直方体の中心が内側にあり、表面の三角形がどれも直方体と交わらないか。
+    /// 直方体が表面に接する場合は `false`。
+    fn contains_part(&self, id: &RangeId) -> bool {
+        Mesh::new(self.expand_triangles()).contains(&IdBox::new(id))
+    }
+
+    fn within_part(&self, id: &RangeId) -> bool {
+        Mesh::new(self.expand_triangles()).within(&IdBox::new(id))
+    }
+}
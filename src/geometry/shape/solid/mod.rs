@@ -64,8 +64,11 @@ impl Solid {
         // エッジの出現回数を記録するマップ
         let mut edge_counts: HashMap<(QuantizedCoord, QuantizedCoord), usize> = HashMap::new();
 
-        for polygon in polygons {
-            let vertices = &polygon.vertices();
+        // 穴のある面は、穴の周も辺として数える
+        let rings = polygons
+            .iter()
+            .flat_map(|polygon| core::iter::once(polygon.vertices()).chain(polygon.holes()));
+        for vertices in rings {
             let len = vertices.len();
             if len < 3 {
                 continue;
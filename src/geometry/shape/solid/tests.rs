@@ -1,7 +1,9 @@
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
-use crate::{Coordinate, CoverSingleIds, Polygon, Solid};
+use crate::{
+    Coordinate, CoverFlexIds, CoverRangeIds, CoverSingleIds, Polygon, Solid, SpatialIdSet,
+};
 
 fn sorted_ids(solid: &Solid, z: u8) -> Vec<String> {
     let mut ids: Vec<String> = solid
@@ -49,3 +51,24 @@ mod cover_single_ids {
         insta::assert_debug_snapshot!(sorted_ids(&solid, 16));
     }
 }
+
+mod cover_flex_ids {
+    use super::*;
+
+    /// 許容誤差 0 なら、最大ズームレベルの被覆と同じ空間を覆う
+    #[test]
+    fn tetrahedron_matches_range_ids() {
+        let solid = tetrahedron(
+            Coordinate::new(35.680, 139.765, 0.0).unwrap(),
+            Coordinate::new(35.680, 139.767, 0.0).unwrap(),
+            Coordinate::new(35.682, 139.766, 0.0).unwrap(),
+            Coordinate::new(35.681, 139.766, 200.0).unwrap(),
+        );
+        let mut expected = SpatialIdSet::new();
+        for range in solid.cover_range_ids(20).unwrap() {
+            expected.insert(range);
+        }
+        let adaptive: SpatialIdSet = solid.cover_flex_ids(20, 0.0).unwrap().collect();
+        assert_eq!(adaptive, expected);
+    }
+}
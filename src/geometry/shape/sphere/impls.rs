@@ -1,8 +1,12 @@
 use alloc::vec::Vec;
 
+use crate::geometry::shape::convex::{self, Convex, IdBox};
+use crate::geometry::shape::runs::RowRuns;
 use crate::{
-    Coordinate, Ecef, Shape, SingleId, SpatialId, Sphere, WGS84_A, geometry::traits::CoverSingleIds,
+    Coordinate, Ecef, Error, RangeId, Shape, SingleId, SpatialId, Sphere, WGS84_A,
+    geometry::traits::{CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds},
 };
+use crate::{SpatialPredicates, Vec3Ecef};
 
 impl Shape for Sphere {
     fn center(&self) -> Coordinate {
@@ -17,11 +21,41 @@ impl CoverSingleIds for Sphere {
 
         let center = self.center;
         let radius = self.radius_m;
-
         let voxel_diag_half = voxel_length_xy(z) * libm::sqrt(3.0) / 2.0;
-        let center_ecef: Ecef = (center).into();
+        let ([f_min, f_max], [x_min, x_max], [y_min, y_max]) = self.search_bounds(z);
+
+        Ok((x_min..=x_max)
+            .flat_map(move |x| {
+                (y_min..=y_max).flat_map(move |y| {
+                    (f_min..=f_max).map(move |f| SingleId::new(z, f, x, y).unwrap())
+                })
+            })
+            .filter(move |id| {
+                let p: Coordinate = id.spatial_center();
+                center.distance(&p) <= radius + voxel_diag_half
+            }))
+    }
+}
+
+impl CoverRangeIds for Sphere {
+    /// [`cover_single_ids`](CoverSingleIds::cover_single_ids) と同じ空間を [`RangeId`] で返す。
+    ///
+    /// F・Y を固定した行の中では、球の中心に最も近いのは中心と同じ経度のボクセルで、
+    /// そこから離れるほど距離は単調に増える。そのため行ごとに両端を二分探索で求めれば、
+    /// 行の中のボクセルを 1 つずつ調べずに X 方向の区間が得られる。
+    fn cover_range_ids(&self, z: u8) -> Result<impl Iterator<Item = RangeId>, Error> {
+        let mut runs = RowRuns::new(z);
+        self.push_runs(z, &mut runs)?;
+        Ok(runs.into_range_ids()?.into_iter())
+    }
+}
+
+impl Sphere {
+    /// 球を覆う `(F, X, Y)` の探索範囲。球に外接する ECEF の立方体の 8 頂点から求める。
+    fn search_bounds(&self, z: u8) -> ([i32; 2], [u32; 2], [u32; 2]) {
+        let center_ecef: Ecef = self.center.into();
+        let radius = self.radius_m;
 
-        // 球の8頂点 -> 探索範囲推定
         let mut corners = Vec::with_capacity(8);
         for &sx in &[1.0, -1.0] {
             for &sy in &[1.0, -1.0] {
@@ -44,17 +78,50 @@ impl CoverSingleIds for Sphere {
         let y_max = corners.iter().map(|v| v.y()).max().unwrap();
         let f_min = corners.iter().map(|v| v.f()).min().unwrap();
         let f_max = corners.iter().map(|v| v.f()).max().unwrap();
+        ([f_min, f_max], [x_min, x_max], [y_min, y_max])
+    }
 
-        Ok((x_min..=x_max)
-            .flat_map(move |x| {
-                (y_min..=y_max).flat_map(move |y| {
-                    (f_min..=f_max).map(move |f| SingleId::new(z, f, x, y).unwrap())
-                })
-            })
-            .filter(move |id| {
-                let p: Coordinate = id.spatial_center();
-                center.distance(&p) <= radius + voxel_diag_half
-            }))
+    /// 球を覆う行ごとの X 方向の区間を `runs` に加える。
+    pub(crate) fn push_runs(&self, z: u8, runs: &mut RowRuns) -> Result<(), Error> {
+        let z = crate::spatial_id::zoom_level::ZoomLevel::new(z)?.get();
+        let center = self.center;
+        let limit = self.radius_m + voxel_length_xy(z) * libm::sqrt(3.0) / 2.0;
+        let ([f_min, f_max], [x_min, x_max], [y_min, y_max]) = self.search_bounds(z);
+        let x_center = center.single_id(z)?.x().clamp(x_min, x_max);
+
+        for f in f_min..=f_max {
+            for y in y_min..=y_max {
+                let inside = |x: u32| {
+                    let p = SingleId::new(z, f, x, y).unwrap().spatial_center();
+                    center.distance(&p) <= limit
+                };
+                if !inside(x_center) {
+                    continue;
+                }
+                // inside(lo) かつ !inside(hi) を保って狭める。
+                let (mut lo, mut hi) = (x_center as u64, x_max as u64 + 1);
+                while hi - lo > 1 {
+                    let mid = (lo + hi) / 2;
+                    if inside(mid as u32) {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                let x_end = lo as u32;
+                let (mut lo, mut hi) = (x_min as i64 - 1, x_center as i64);
+                while hi - lo > 1 {
+                    let mid = (lo + hi) / 2;
+                    if inside(mid as u32) {
+                        hi = mid;
+                    } else {
+                        lo = mid;
+                    }
+                }
+                runs.push_run(f, y, hi as u32, x_end);
+            }
+        }
+        Ok(())
     }
 }
 
@@ -70,3 +137,32 @@ pub fn voxel_length_xy(z: u8) -> f64 {
 pub fn voxel_length_f(z: u8) -> f64 {
     libm::pow(2_f64, (25 - z as i32) as f64)
 }
+
+impl CoverFlexIds for Sphere {}
+
+impl CoverClassifiedIds for Sphere {}
+
+impl Convex for Sphere {
+    fn support(&self, d: Vec3Ecef) -> Vec3Ecef {
+        convex::RoundedSegment {
+            a: self.center.into(),
+            b: self.center.into(),
+            radius: self.radius_m,
+        }
+        .support(d)
+    }
+}
+
+impl SpatialPredicates for Sphere {
+    fn intersects_part(&self, id: &RangeId) -> bool {
+        convex::intersects(self, &IdBox::new(id))
+    }
+
+    fn contains_part(&self, id: &RangeId) -> bool {
+        convex::contains_box(self, &IdBox::new(id))
+    }
+
+    fn within_part(&self, id: &RangeId) -> bool {
+        IdBox::new(id).encloses(self)
+    }
+}
@@ -1,7 +1,13 @@
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
-use crate::{Coordinate, CoverSingleIds, Sphere};
+use crate::geometry::shape::cover_tests::assert_same_cover;
+use crate::geometry::shape::coverage::CoverClass;
+use crate::merge_policy::Sum;
+use crate::{
+    Coordinate, CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds, Error, FlexId,
+    GeometryError, RangeId, Source, SpatialIdMap, SpatialIdSet, Sphere,
+};
 
 fn sorted_ids(sphere: &Sphere, z: u8) -> Vec<String> {
     let mut ids: Vec<String> = sphere
@@ -40,3 +46,280 @@ mod cover_single_ids {
         insta::assert_debug_snapshot!(sorted_ids(&sphere, 20));
     }
 }
+
+mod cover_range_ids {
+    use super::*;
+
+    #[test]
+    fn small_sphere_matches_single_ids() {
+        let center = Coordinate::new(35.681, 139.766, 10.0).unwrap();
+        let sphere = Sphere::new(center, 30.0).unwrap();
+        assert_same_cover(&sphere, 20);
+    }
+
+    #[test]
+    fn medium_sphere_matches_single_ids() {
+        let center = Coordinate::new(35.681, 139.766, 50.0).unwrap();
+        let sphere = Sphere::new(center, 100.0).unwrap();
+        let count = assert_same_cover(&sphere, 21);
+        assert!(count * 5 < sorted_ids(&sphere, 21).len());
+    }
+
+    /// 半径 500m の球でも、ボクセル数に比べてごく少ない RangeId で返す
+    #[test]
+    fn large_sphere_is_compact() {
+        let center = Coordinate::new(35.681, 139.766, 300.0).unwrap();
+        let sphere = Sphere::new(center, 500.0).unwrap();
+        let ranges: Vec<RangeId> = sphere.cover_range_ids(23).unwrap().collect();
+        let voxels: u64 = ranges
+            .iter()
+            .map(|r| {
+                let ([f0, f1], [x0, x1], [y0, y1]) = (r.f(), r.x(), r.y());
+                (f1 - f0 + 1) as u64 * (x1 - x0 + 1) as u64 * (y1 - y0 + 1) as u64
+            })
+            .sum();
+        assert!(voxels > 1_000_000, "{voxels}");
+        assert!((ranges.len() as u64) * 50 < voxels, "{}", ranges.len());
+    }
+}
+
+mod cover_flex_ids {
+    use super::*;
+
+    fn range_set(sphere: &Sphere, z: u8) -> SpatialIdSet {
+        let mut set = SpatialIdSet::new();
+        for range in sphere.cover_range_ids(z).unwrap() {
+            set.insert(range);
+        }
+        set
+    }
+
+    /// 許容誤差 0 なら、最大ズームレベルの被覆と同じ空間をボクセルよりずっと少ない ID で覆う
+    #[test]
+    fn exact_cover_matches_range_ids_with_coarse_interior() {
+        let center = Coordinate::new(35.681, 139.766, 50.0).unwrap();
+        let sphere = Sphere::new(center, 100.0).unwrap();
+        let ids: Vec<FlexId> = sphere.cover_flex_ids(22, 0.0).unwrap().collect();
+        let expected = range_set(&sphere, 22);
+
+        assert_eq!(ids.iter().copied().collect::<SpatialIdSet>(), expected);
+        assert!(
+            ids.len() <= expected.count(),
+            "{} > {}",
+            ids.len(),
+            expected.count()
+        );
+        assert!(ids.len() * 10 < sorted_ids(&sphere, 22).len());
+        // 内部は最大ズームレベルより粗い ID で覆われている。
+        assert!(
+            ids.iter()
+                .any(|id| id.f_zoomlevel() < 22 && id.x_zoomlevel() < 22 && id.y_zoomlevel() < 22)
+        );
+    }
+
+    /// 許容誤差を上げると ID は減り、覆う空間は広がる
+    #[test]
+    fn tolerance_trades_accuracy_for_fewer_ids() {
+        let center = Coordinate::new(35.681, 139.766, 50.0).unwrap();
+        let sphere = Sphere::new(center, 100.0).unwrap();
+        let exact: SpatialIdSet = sphere.cover_flex_ids(21, 0.0).unwrap().collect();
+        let loose: Vec<FlexId> = sphere.cover_flex_ids(21, 0.5).unwrap().collect();
+
+        assert!(loose.len() < exact.count());
+        let loose: SpatialIdSet = loose.into_iter().collect();
+        for id in exact.iter() {
+            assert!(loose.get_overlapping(&id).next().is_some());
+        }
+    }
+
+    #[test]
+    fn rejects_tolerance_out_of_range() {
+        let center = Coordinate::new(35.681, 139.766, 50.0).unwrap();
+        let sphere = Sphere::new(center, 10.0).unwrap();
+        for tolerance in [-0.1, 1.0, f64::NAN] {
+            assert!(matches!(
+                sphere.cover_flex_ids(20, tolerance).map(|ids| ids.count()),
+                Err(Error::Geometry(GeometryError::ToleranceOutOfRange { .. }))
+            ));
+        }
+    }
+}
+
+mod cover_classified_ids {
+    use super::*;
+
+    /// 中心のボクセルは内側、縁のボクセルは一部だけ占められた境界になる
+    #[test]
+    fn interior_is_inside_and_edge_is_boundary() {
+        let center = Coordinate::new(35.681, 139.766, 50.0).unwrap();
+        let sphere = Sphere::new(center, 100.0).unwrap();
+        let classified: Vec<_> = sphere.cover_classified_ids(20).unwrap().collect();
+        let cover = sorted_ids(&sphere, 20);
+
+        for (id, coverage) in &classified {
+            assert!(cover.binary_search(&id.to_string()).is_ok(), "{id}");
+            assert!(0.0 < coverage.fraction && coverage.fraction <= 1.0);
+            assert_eq!(
+                coverage.class == CoverClass::Inside,
+                coverage.fraction == 1.0
+            );
+        }
+        let center_id = center.single_id(20).unwrap();
+        let (_, at_center) = classified.iter().find(|(id, _)| *id == center_id).unwrap();
+        assert_eq!(at_center.class, CoverClass::Inside);
+        assert!(
+            classified
+                .iter()
+                .any(|(_, coverage)| coverage.class == CoverClass::Boundary
+                    && coverage.fraction < 0.5)
+        );
+    }
+
+    /// 占有率のマップを `Sum` で重ねると、重なった分だけ密度が上がる
+    #[test]
+    fn fraction_maps_merge_into_density() {
+        let center = Coordinate::new(35.681, 139.766, 50.0).unwrap();
+        let sphere = Sphere::new(center, 100.0).unwrap();
+        let map = sphere.cover_fraction_map(20).unwrap();
+
+        let merged: SpatialIdMap<f64> = map
+            .clone()
+            .query()
+            .merge(map.clone().query(), 0.0, Sum)
+            .run_working_tree()
+            .unwrap()
+            .into();
+
+        let center_id = center.single_id(20).unwrap();
+        let values: Vec<f64> = merged.get(&center_id).map(|(_, v)| *v).collect();
+        assert_eq!(values, [2.0]);
+        for (id, fraction) in map.flat_single_ids() {
+            let doubled: Vec<f64> = merged.get(&id).map(|(_, v)| *v).collect();
+            assert_eq!(doubled, [fraction * 2.0]);
+        }
+    }
+}
+
+mod spatial_predicates {
+    use super::*;
+    use crate::{SingleId, SpatialPredicates};
+
+    /// 皇居付近の高度 100 m を中心とする半径 100 m の球。
+    fn sphere() -> Sphere {
+        Sphere::new(Coordinate::new(35.681, 139.766, 100.0).unwrap(), 100.0).unwrap()
+    }
+
+    fn cell_at(altitude: f64, z: u8) -> SingleId {
+        Coordinate::new(35.681, 139.766, altitude)
+            .unwrap()
+            .single_id(z)
+            .unwrap()
+    }
+
+    /// 中心・表面・外側のボクセルとの位置関係
+    #[test]
+    fn single_ids_inside_straddling_and_outside() {
+        let sphere = sphere();
+
+        let inside = cell_at(100.0, 20);
+        assert!(sphere.intersects(&inside));
+        assert!(sphere.contains(&inside));
+        assert!(!sphere.within(&inside));
+
+        let straddling = cell_at(195.0, 20);
+        assert!(sphere.intersects(&straddling));
+        assert!(!sphere.contains(&straddling));
+
+        let outside = cell_at(400.0, 20);
+        assert!(!sphere.intersects(&outside));
+        assert!(!sphere.contains(&outside));
+    }
+
+    /// 球を囲む RangeId と、球を貫く細長い FlexId
+    #[test]
+    fn range_and_flex_ids() {
+        let sphere = sphere();
+        let center = cell_at(100.0, 20);
+
+        let around = RangeId::new(
+            20,
+            [0, 15],
+            [center.x() - 10, center.x() + 10],
+            [center.y() - 10, center.y() + 10],
+        )
+        .unwrap();
+        assert!(sphere.within(&around));
+        assert!(sphere.intersects(&around));
+        assert!(!sphere.contains(&around));
+
+        // 高度 0〜32768 m の柱
+        let column = FlexId::new(10, 0, 20, center.x(), 20, center.y()).unwrap();
+        assert!(sphere.intersects(&column));
+        assert!(!sphere.contains(&column));
+        assert!(!sphere.within(&column));
+        assert_eq!(
+            sphere.intersects(&column),
+            sphere.intersects(&RangeId::from(column))
+        );
+    }
+
+    /// Web Mercator の上下端の行も、緯度を丸めた直方体として判定できる
+    #[test]
+    fn ids_on_the_mercator_edge_rows() {
+        let north = Coordinate::new(85.0511, 0.0, 100.0).unwrap();
+        let sphere = Sphere::new(north, 100.0).unwrap();
+        let edge = north.single_id(20).unwrap();
+        assert_eq!(edge.y(), 0);
+        assert!(sphere.intersects(&edge));
+
+        let far = SingleId::new(20, 0, edge.x(), edge.y() + 100).unwrap();
+        assert!(!sphere.intersects(&far));
+    }
+
+    /// 水平方向に広い空間IDも、細かく割ってから判定する
+    #[test]
+    fn coarse_ids_are_split_before_testing() {
+        let sphere = sphere();
+        let center = cell_at(100.0, 20);
+
+        // 全球を覆う ID と、経度方向に全周を覆う行
+        let world = SingleId::new(0, 0, 0, 0).unwrap();
+        assert!(sphere.intersects(&world));
+        assert!(sphere.within(&world));
+        assert!(!sphere.contains(&world));
+        let row = RangeId::new(20, [0, 15], [0, (1 << 20) - 1], [center.y(), center.y()]).unwrap();
+        assert!(sphere.intersects(&row));
+
+        // 東西の端で折り返す範囲
+        let last = (1u32 << 20) - 1;
+        let wrapped = RangeId::new(20, [0, 15], [last, 5], [center.y(), center.y()]).unwrap();
+        assert!(!sphere.intersects(&wrapped));
+    }
+
+    /// 交わると判定したボクセルは、必ず被覆に含まれる
+    #[test]
+    fn intersecting_cells_are_covered() {
+        let sphere = sphere();
+        let covered = sorted_ids(&sphere, 20);
+        let center = cell_at(100.0, 20);
+        let around = RangeId::new(
+            20,
+            [-2, 10],
+            [center.x() - 6, center.x() + 6],
+            [center.y() - 6, center.y() + 6],
+        )
+        .unwrap();
+
+        let mut intersecting = 0;
+        for id in around.single_ids() {
+            if sphere.intersects(&id) {
+                intersecting += 1;
+                assert!(covered.binary_search(&id.to_string()).is_ok(), "{id}");
+            }
+            if sphere.contains(&id) {
+                assert!(sphere.intersects(&id), "{id}");
+            }
+        }
+        assert!(intersecting > 0);
+    }
+}
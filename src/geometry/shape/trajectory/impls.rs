@@ -0,0 +1,109 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::geometry::shape::distance;
+use crate::geometry::shape::runs::RowRuns;
+use crate::spatial_id::zoom_level::ZoomLevel;
+use crate::{
+    Coordinate, CoverRangeIds, Error, RangeId, Shape, SingleId, SpatialId, Trajectory, Vec3,
+    Vec3Ecef,
+};
+
+impl Shape for Trajectory {
+    fn center(&self) -> Coordinate {
+        Coordinate::center_gravity(self.waypoints().iter().map(|(point, _)| point))
+    }
+}
+
+/// 線分 `ab` 上を `s ∈ [0, 1]` で進む点が、`v` から `reach` 以内にいる `s` の範囲。
+///
+/// `|a + s (b - a) - v|² ≤ reach²` を `s` について解き、`[0, 1]` に切り詰める。
+fn reach_parameter(a: Vec3Ecef, b: Vec3Ecef, v: Vec3Ecef, reach: f64) -> Option<[f64; 2]> {
+    let d = b - a;
+    let w = v - a;
+    let dd = d.norm_squared();
+    let c = w.norm_squared() - reach * reach;
+    if dd == 0.0 {
+        // 静止している区間は、届くなら区間全体
+        return (c <= 0.0).then_some([0.0, 1.0]);
+    }
+    let wd = w.dot(&d);
+    let discriminant = wd * wd - dd * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = libm::sqrt(discriminant);
+    let lower = ((wd - root) / dd).max(0.0);
+    let upper = ((wd + root) / dd).min(1.0);
+    (lower <= upper).then_some([lower, upper])
+}
+
+/// 重なり・隣接する秒区間 `[開始, 終了)` を併合する。
+fn merge_spans(spans: &mut Vec<[u64; 2]>) {
+    spans.sort_unstable();
+    let mut merged: Vec<[u64; 2]> = Vec::with_capacity(spans.len());
+    for &[start, end] in spans.iter() {
+        match merged.last_mut() {
+            Some(last) if start <= last[1] => last[1] = last[1].max(end),
+            _ => merged.push([start, end]),
+        }
+    }
+    *spans = merged;
+}
+
+impl CoverRangeIds for Trajectory {
+    /// 経由点を結ぶ [`Tube`](crate::Tube) と同じ空間を、時刻付きの [`RangeId`] で返す。
+    ///
+    /// 各ボクセルには、物体の占める範囲がボクセルの中心から対角線の半分以内に入っている間の
+    /// 時刻を、秒単位に外側へ丸めて [`RangeId::with_time_span`] で付ける。
+    /// 1 つのボクセルに何度も入る軌跡では、占有していない間を空けた複数の区間になる。
+    /// 同じ秒区間を持つボクセル同士を 1 つの [`RangeId`] にまとめる。
+    fn cover_range_ids(&self, z: u8) -> Result<impl Iterator<Item = RangeId>, Error> {
+        let z = ZoomLevel::new(z)?.get();
+        let reach = self.radius_m() + distance::half_diagonal(z);
+
+        let mut occupancy: BTreeMap<(i32, u32, u32), Vec<[u64; 2]>> = BTreeMap::new();
+        for (capsule, t0, t1) in self.legs() {
+            let Some(([f_min, f_max], [x_min, x_max], [y_min, y_max])) =
+                distance::search_bounds(&capsule, z)
+            else {
+                continue;
+            };
+            let (a, b): (Vec3Ecef, Vec3Ecef) = (capsule.start().into(), capsule.end().into());
+            let duration = (t1 - t0) as f64;
+            for f in f_min..=f_max {
+                for y in y_min..=y_max {
+                    for x in x_min..=x_max {
+                        let center = Vec3Ecef::from(SingleId::new(z, f, x, y)?.spatial_center());
+                        let Some([lower, upper]) = reach_parameter(a, b, center, reach) else {
+                            continue;
+                        };
+                        let start = t0 + libm::floor(lower * duration) as u64;
+                        let end = (t0 + libm::ceil(upper * duration) as u64).max(start + 1);
+                        occupancy.entry((f, y, x)).or_default().push([start, end]);
+                    }
+                }
+            }
+        }
+
+        // 同じ秒区間を占有するボクセルを集めてから、空間方向にまとめる
+        let mut by_span: BTreeMap<[u64; 2], RowRuns> = BTreeMap::new();
+        for ((f, y, x), mut spans) in occupancy {
+            merge_spans(&mut spans);
+            for span in spans {
+                by_span
+                    .entry(span)
+                    .or_insert_with(|| RowRuns::new(z))
+                    .push_run(f, y, x, x);
+            }
+        }
+
+        let mut ids = Vec::new();
+        for ([start, end], runs) in by_span {
+            for id in runs.into_range_ids()? {
+                ids.push(id.with_time_span(start, end)?);
+            }
+        }
+        Ok(ids.into_iter())
+    }
+}
@@ -0,0 +1,93 @@
+use alloc::vec::Vec;
+
+pub mod impls;
+#[cfg(test)]
+mod tests;
+
+use crate::{Capsule, Coordinate, Error, GeometryError};
+
+/// 時刻付きの経由点をたどって移動する物体が占める、時空間の領域を表す型。
+///
+/// 物体は隣り合う経由点の間を等速で直進し、中心から半径以内の空間を占めるとみなす。
+/// 空間の形は経由点を結ぶ [`Tube`](crate::Tube) と同じで、各ボクセルは物体がその中に
+/// いる間だけ占有される。時刻は UNIX 秒で表す。
+///
+/// ```
+/// # #[cfg(feature = "temporal_id")]
+/// # {
+/// # use kasane_logic::{Coordinate, CoverRangeIds, SpatialIdSet, Trajectory};
+/// // 10 秒かけて東へ約 180 m 飛ぶドローンの予約
+/// let trajectory = Trajectory::new(
+///     vec![
+///         (Coordinate::new(35.681, 139.766, 50.0).unwrap(), 1_700_000_000),
+///         (Coordinate::new(35.681, 139.768, 50.0).unwrap(), 1_700_000_010),
+///     ],
+///     5.0,
+/// )
+/// .unwrap();
+///
+/// let mut reserved = SpatialIdSet::new();
+/// for id in trajectory.cover_range_ids(21).unwrap() {
+///     reserved.insert(id);
+/// }
+/// assert!(!reserved.is_empty());
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trajectory {
+    waypoints: Vec<(Coordinate, u64)>,
+    radius_m: f64,
+}
+
+impl Trajectory {
+    /// 経由点 `(座標, UNIX 秒)` の列と半径から [Trajectory] を作成する。
+    ///
+    /// 半径が正でない場合は [GeometryError::RadiusNegative]、経由点が 2 つ未満の場合は
+    /// [GeometryError::WaypointsTooFew]、時刻が狭義単調増加でない場合は
+    /// [GeometryError::TimeNotIncreasing] を返す。
+    pub fn new(waypoints: Vec<(Coordinate, u64)>, radius_m: f64) -> Result<Self, Error> {
+        if radius_m <= 0.0 || radius_m.is_nan() {
+            return Err(GeometryError::RadiusNegative { radius: radius_m }.into());
+        }
+        if waypoints.len() < 2 {
+            return Err(GeometryError::WaypointsTooFew {
+                count: waypoints.len(),
+            }
+            .into());
+        }
+        if let Some(index) = (1..waypoints.len()).find(|&i| waypoints[i].1 <= waypoints[i - 1].1) {
+            return Err(GeometryError::TimeNotIncreasing { index }.into());
+        }
+        Ok(Trajectory {
+            waypoints,
+            radius_m,
+        })
+    }
+
+    /// 経由点 `(座標, UNIX 秒)` の列を返す。
+    pub fn waypoints(&self) -> &[(Coordinate, u64)] {
+        &self.waypoints
+    }
+
+    /// 半径（メートル）を返す。
+    pub fn radius_m(&self) -> f64 {
+        self.radius_m
+    }
+
+    /// 最初の経由点から最後の経由点までの時刻 `[開始, 終了)`（UNIX 秒）を返す。
+    pub fn time_span(&self) -> (u64, u64) {
+        (
+            self.waypoints[0].1,
+            self.waypoints[self.waypoints.len() - 1].1,
+        )
+    }
+
+    /// 隣り合う経由点を結ぶ区間ごとの `(カプセル, 始点の時刻, 終点の時刻)`。
+    pub(crate) fn legs(&self) -> impl Iterator<Item = (Capsule, u64, u64)> + '_ {
+        self.waypoints.windows(2).map(|pair| {
+            let ((start, t0), (end, t1)) = (pair[0], pair[1]);
+            let capsule = Capsule::new(start, end, self.radius_m).expect("半径は作成時に検証済み");
+            (capsule, t0, t1)
+        })
+    }
+}
@@ -0,0 +1,136 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{
+    Coordinate, CoverRangeIds, Error, GeometryError, RangeId, SpatialId, SpatialIdSet,
+    SpatialIdTable, Trajectory,
+};
+
+const T0: u64 = 1_700_000_000;
+
+/// 高度 50 m を東へ約 180 m、20 秒かけて進む飛行経路。
+fn flight() -> Trajectory {
+    Trajectory::new(
+        vec![
+            (Coordinate::new(35.681, 139.766, 50.0).unwrap(), T0),
+            (Coordinate::new(35.681, 139.768, 50.0).unwrap(), T0 + 20),
+        ],
+        5.0,
+    )
+    .unwrap()
+}
+
+/// `point` を含むズームレベル `z` のボクセルを占有する秒区間の列。
+fn occupied_spans(ids: &[RangeId], point: Coordinate, z: u8) -> Vec<(u64, u64)> {
+    let cell = point.single_id(z).unwrap();
+    let mut spans: Vec<(u64, u64)> = ids
+        .iter()
+        .filter(|id| {
+            let ([f0, f1], [x0, x1], [y0, y1]) = (id.f(), id.x(), id.y());
+            (f0..=f1).contains(&cell.f())
+                && (x0..=x1).contains(&cell.x())
+                && (y0..=y1).contains(&cell.y())
+        })
+        .map(|id| id.seconds_range())
+        .collect();
+    spans.sort_unstable();
+    spans
+}
+
+mod cover_range_ids {
+    use super::*;
+
+    /// 始点付近のボクセルは早い時刻に、終点付近のボクセルは遅い時刻に占有される
+    #[test]
+    fn cells_are_occupied_only_while_passing() {
+        let ids: Vec<RangeId> = flight().cover_range_ids(21).unwrap().collect();
+
+        let near_start = occupied_spans(&ids, Coordinate::new(35.681, 139.766, 50.0).unwrap(), 21);
+        let near_end = occupied_spans(&ids, Coordinate::new(35.681, 139.768, 50.0).unwrap(), 21);
+        assert_eq!(near_start.len(), 1);
+        assert_eq!(near_end.len(), 1);
+        let (start_begin, start_end) = near_start[0];
+        let (end_begin, end_end) = near_end[0];
+        assert_eq!(start_begin, T0);
+        assert!(start_end < T0 + 5);
+        assert!(end_begin > T0 + 15);
+        assert_eq!(end_end, T0 + 20);
+
+        // 経路から外れたボクセルは占有されない
+        let aside = occupied_spans(&ids, Coordinate::new(35.6812, 139.767, 50.0).unwrap(), 21);
+        assert!(aside.is_empty());
+    }
+
+    /// 同じ場所に戻ってくる軌跡は、離れている間を空けて 2 回占有する
+    #[test]
+    fn revisited_cell_has_separate_spans() {
+        let home = Coordinate::new(35.681, 139.766, 50.0).unwrap();
+        let away = Coordinate::new(35.681, 139.768, 50.0).unwrap();
+        let trajectory =
+            Trajectory::new(vec![(home, T0), (away, T0 + 20), (home, T0 + 40)], 5.0).unwrap();
+        let ids: Vec<RangeId> = trajectory.cover_range_ids(21).unwrap().collect();
+
+        let spans = occupied_spans(&ids, home, 21);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].0, T0);
+        assert_eq!(spans[1].1, T0 + 40);
+        assert!(spans[0].1 < spans[1].0);
+    }
+
+    /// 時刻付きのまま集合・テーブルに挿入でき、通過時刻の外では重ならない
+    #[test]
+    fn inserts_into_collections() {
+        let mut set = SpatialIdSet::new();
+        let mut table = SpatialIdTable::new();
+        for id in flight().cover_range_ids(21).unwrap() {
+            set.insert(id.clone());
+            table.insert(id, 7_u32);
+        }
+        assert!(!set.is_empty());
+        assert!(!table.is_empty());
+
+        let start_cell = RangeId::from(
+            Coordinate::new(35.681, 139.766, 50.0)
+                .unwrap()
+                .single_id(21)
+                .unwrap(),
+        );
+        let before = start_cell.clone().with_time_span(T0 - 10, T0).unwrap();
+        let departing = start_cell.with_time_span(T0, T0 + 1).unwrap();
+        assert!(set.get(&departing).next().is_some());
+        assert!(set.get(&before).next().is_none());
+    }
+}
+
+mod new {
+    use super::*;
+
+    #[test]
+    fn rejects_non_positive_radius() {
+        let p = Coordinate::new(35.681, 139.766, 50.0).unwrap();
+        assert!(matches!(
+            Trajectory::new(vec![(p, T0), (p, T0 + 1)], 0.0),
+            Err(Error::Geometry(GeometryError::RadiusNegative { .. }))
+        ));
+    }
+
+    #[test]
+    fn rejects_single_waypoint() {
+        let p = Coordinate::new(35.681, 139.766, 50.0).unwrap();
+        assert!(matches!(
+            Trajectory::new(vec![(p, T0)], 5.0),
+            Err(Error::Geometry(GeometryError::WaypointsTooFew { count: 1 }))
+        ));
+    }
+
+    #[test]
+    fn rejects_non_increasing_time() {
+        let p = Coordinate::new(35.681, 139.766, 50.0).unwrap();
+        assert!(matches!(
+            Trajectory::new(vec![(p, T0), (p, T0 + 5), (p, T0 + 5)], 5.0),
+            Err(Error::Geometry(GeometryError::TimeNotIncreasing {
+                index: 2
+            }))
+        ));
+    }
+}
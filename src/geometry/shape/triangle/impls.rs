@@ -1,9 +1,13 @@
 use hashbrown::HashSet;
 
+use crate::geometry::shape::convex::{self, Convex, IdBox};
+use crate::geometry::shape::runs::RowRuns;
 use crate::{
-    Coordinate, Error, ExpandCoordinates, Shape, SingleId, Triangle, Vec3, Vec3FractionalId,
-    geometry::traits::CoverSingleIds,
+    Coordinate, Error, ExpandCoordinates, RangeId, Shape, SingleId, Triangle, Vec3,
+    Vec3FractionalId,
+    geometry::traits::{CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds},
 };
+use crate::{SpatialPredicates, Vec3Ecef};
 
 impl Shape for Triangle {
     fn center(&self) -> Coordinate {
@@ -13,6 +17,30 @@ impl Shape for Triangle {
 
 impl CoverSingleIds for Triangle {
     fn cover_single_ids(&self, z: u8) -> Result<impl Iterator<Item = SingleId>, Error> {
+        let mut seen = HashSet::new();
+        let voxels = self
+            .divide(self.divide_steps(z)?)?
+            .flat_map(move |tri| tri.single_ids_limited(z).ok().into_iter().flatten())
+            .filter(move |voxel| seen.insert(voxel.clone()));
+        Ok(voxels)
+    }
+}
+
+impl CoverRangeIds for Triangle {
+    /// [`cover_single_ids`](CoverSingleIds::cover_single_ids) と同じ空間を [`RangeId`] で返す。
+    ///
+    /// 分割した小三角形ごとに、範囲内の `(F, Y)` の行でかかる X 方向の区間を式から求め、
+    /// 同じ区間を持つ隣接した行・面を 1 つの [`RangeId`] にまとめる。
+    fn cover_range_ids(&self, z: u8) -> Result<impl Iterator<Item = RangeId>, Error> {
+        let mut runs = RowRuns::new(z);
+        self.push_runs(z, &mut runs)?;
+        Ok(runs.into_range_ids()?.into_iter())
+    }
+}
+
+impl Triangle {
+    /// 小三角形の辺がおよそ 8 ボクセル以下になる分割数。
+    fn divide_steps(&self, z: u8) -> Result<u32, Error> {
         let points: [Vec3FractionalId; 3] = [
             Vec3FractionalId::from(self.points[0].fractional_id(z)?),
             Vec3FractionalId::from(self.points[1].fractional_id(z)?),
@@ -24,12 +52,40 @@ impl CoverSingleIds for Triangle {
             - libm::floor(points[0].b().min(points[1].b()).min(points[2].b()));
         let diff_y = libm::floor(points[0].c().max(points[1].c()).max(points[2].c()))
             - libm::floor(points[0].c().min(points[1].c()).min(points[2].c()));
-        let steps = libm::ceil(diff_f.max(diff_x).max(diff_y) / 8.0) as u32;
-        let mut seen = HashSet::new();
-        let voxels = self
-            .divide(steps)?
-            .flat_map(move |tri| tri.single_ids_limited(z).ok().into_iter().flatten())
-            .filter(move |voxel| seen.insert(voxel.clone()));
-        Ok(voxels)
+        Ok(libm::ceil(diff_f.max(diff_x).max(diff_y) / 8.0) as u32)
+    }
+
+    /// 三角形を覆う行ごとの X 方向の区間を `runs` に加える。
+    pub(crate) fn push_runs(&self, z: u8, runs: &mut RowRuns) -> Result<(), Error> {
+        for tri in self.divide(self.divide_steps(z)?)? {
+            // cover_single_ids と同じく、空間IDへ変換できない小三角形は飛ばす。
+            let _ = tri.push_runs_limited(z, runs);
+        }
+        Ok(())
+    }
+}
+
+impl CoverFlexIds for Triangle {}
+
+impl CoverClassifiedIds for Triangle {}
+
+impl Convex for Triangle {
+    fn support(&self, d: Vec3Ecef) -> Vec3Ecef {
+        convex::triangle_hull(self).support(d)
+    }
+}
+
+impl SpatialPredicates for Triangle {
+    fn intersects_part(&self, id: &RangeId) -> bool {
+        convex::intersects(self, &IdBox::new(id))
+    }
+
+    /// 体積を持たないので常に `false`。
+    fn contains_part(&self, _id: &RangeId) -> bool {
+        false
+    }
+
+    fn within_part(&self, id: &RangeId) -> bool {
+        IdBox::new(id).encloses(self)
     }
 }
@@ -2,6 +2,7 @@ use alloc::vec::Vec;
 
 use hashbrown::HashSet;
 
+use crate::geometry::shape::runs::RowRuns;
 use crate::{Coordinate, Ecef, Error, SingleId, Vec3, Vec3Ecef, Vec3FractionalId};
 pub mod geometry_relation;
 pub mod impls;
@@ -119,73 +120,213 @@ impl Triangle {
 
     ///[SingleId]の集合へ変換を行います。
     pub fn single_ids_limited(self, z: u8) -> Result<impl Iterator<Item = SingleId>, Error> {
+        let raster = Raster::new(&self, z)?;
+        let ([min_f, max_f], [min_x, max_x], [min_y, max_y]) = raster.bounds;
+        let mut voxels: HashSet<SingleId> = HashSet::new();
+        for f in min_f..=max_f {
+            for x in min_x..=max_x {
+                for y in min_y..=max_y {
+                    if raster.touches(f, x, y) {
+                        voxels.insert(SingleId::new(z, f, x, y).unwrap());
+                    }
+                }
+            }
+        }
+        Ok(voxels.into_iter())
+    }
+
+    /// [`single_ids_limited`](Self::single_ids_limited) と同じボクセルを、行ごとの X 方向の区間として `runs` に加える。
+    pub(crate) fn push_runs_limited(self, z: u8, runs: &mut RowRuns) -> Result<(), Error> {
+        Raster::new(&self, z)?.push_runs(runs);
+        Ok(())
+    }
+}
+
+/// 1 つの三角形がどのボクセルにかかるかの判定。
+///
+/// ボクセルの 8 頂点が三角形の平面の両側にあり、かついずれかの頂点が三角形を法線方向に
+/// 伸ばした柱の中にあれば、そのボクセルにかかるとみなす。
+struct Raster {
+    points: [Vec3FractionalId; 3],
+    n: Vec3FractionalId,
+    ma: Vec3FractionalId,
+    mb: Vec3FractionalId,
+    mc: Vec3FractionalId,
+    bounds: ([i32; 2], [u32; 2], [u32; 2]),
+}
+
+/// ボクセルの 8 頂点の、ボクセルの原点からのずれ。
+const EIGHT_PATTERNS: [[f64; 3]; 8] = [
+    [0.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0],
+    [0.0, 1.0, 0.0],
+    [0.0, 1.0, 1.0],
+    [1.0, 0.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [1.0, 1.0, 0.0],
+    [1.0, 1.0, 1.0],
+];
+
+impl Raster {
+    fn new(triangle: &Triangle, z: u8) -> Result<Self, Error> {
         let points: [Vec3FractionalId; 3] = [
-            Vec3FractionalId::from(self.points[0].fractional_id(z)?),
-            Vec3FractionalId::from(self.points[1].fractional_id(z)?),
-            Vec3FractionalId::from(self.points[2].fractional_id(z)?),
+            Vec3FractionalId::from(triangle.points[0].fractional_id(z)?),
+            Vec3FractionalId::from(triangle.points[1].fractional_id(z)?),
+            Vec3FractionalId::from(triangle.points[2].fractional_id(z)?),
         ];
-        let mut voxels: HashSet<SingleId> = HashSet::new();
         let vec_a = points[1] - points[0]; // 1-0
         let vec_b = points[0] - points[2]; // 0-2
         let vec_c = points[2] - points[1]; // 2-1
         let n = vec_b.cross(&vec_a);
-        let ma = n.cross(&vec_a);
-        let mb = n.cross(&vec_b);
-        let mc = n.cross(&vec_c);
         let min_f = libm::floor(points[0].a().min(points[1].a()).min(points[2].a())) as i32;
         let max_f = libm::floor(points[0].a().max(points[1].a()).max(points[2].a())) as i32;
         let min_x = libm::floor(points[0].b().min(points[1].b()).min(points[2].b())) as u32;
         let max_x = libm::floor(points[0].b().max(points[1].b()).max(points[2].b())) as u32;
         let min_y = libm::floor(points[0].c().min(points[1].c()).min(points[2].c())) as u32;
         let max_y = libm::floor(points[0].c().max(points[1].c()).max(points[2].c())) as u32;
-        let eight_patterns = [
-            Vec3FractionalId::new(0.0, 0.0, 0.0),
-            Vec3FractionalId::new(0.0, 0.0, 1.0),
-            Vec3FractionalId::new(0.0, 1.0, 0.0),
-            Vec3FractionalId::new(0.0, 1.0, 1.0),
-            Vec3FractionalId::new(1.0, 0.0, 0.0),
-            Vec3FractionalId::new(1.0, 0.0, 1.0),
-            Vec3FractionalId::new(1.0, 1.0, 0.0),
-            Vec3FractionalId::new(1.0, 1.0, 1.0),
-        ];
+        Ok(Self {
+            points,
+            n,
+            ma: n.cross(&vec_a),
+            mb: n.cross(&vec_b),
+            mc: n.cross(&vec_c),
+            bounds: ([min_f, max_f], [min_x, max_x], [min_y, max_y]),
+        })
+    }
+
+    fn corner(f: i32, x: u32, y: u32, pattern: [f64; 3]) -> Vec3FractionalId {
+        Vec3FractionalId::new(
+            f as f64 + pattern[0],
+            x as f64 + pattern[1],
+            y as f64 + pattern[2],
+        )
+    }
+
+    /// ボクセル `(f, x, y)` にかかるか。
+    fn touches(&self, f: i32, x: u32, y: u32) -> bool {
+        let points = &self.points;
+        let mut sign_before = true;
+        for (i, pattern) in EIGHT_PATTERNS.iter().enumerate() {
+            let vec_p = Self::corner(f, x, y, *pattern) - points[0];
+            let sign = self.n.dot(&vec_p).is_sign_positive();
+            if i == 0 || sign_before == sign {
+                sign_before = sign;
+            } else {
+                return EIGHT_PATTERNS.iter().any(|pattern| {
+                    let cp = Self::corner(f, x, y, *pattern);
+                    self.ma.dot(&(cp - points[0])) >= 0.0
+                        && self.mc.dot(&(cp - points[1])) >= 0.0
+                        && self.mb.dot(&(cp - points[2])) >= 0.0
+                });
+            }
+        }
+        false
+    }
+
+    /// 範囲内の各行について、かかるボクセルの X 方向の区間を `runs` に加える。
+    ///
+    /// 判定の各条件は行の中で X の一次式なので、調べる X の範囲を式から絞り込み、
+    /// 範囲の外のボクセルは調べない。
+    fn push_runs(&self, runs: &mut RowRuns) {
+        let ([min_f, max_f], [min_x, max_x], [min_y, max_y]) = self.bounds;
+        let (n, p0) = (self.n, self.points[0]);
+        let low = |v: f64| v.min(0.0);
+        let high = |v: f64| v.max(0.0);
         for f in min_f..=max_f {
-            for x in min_x..=max_x {
-                for y in min_y..=max_y {
-                    let mut sign_before = true;
-                    for (i, pattern) in eight_patterns.iter().enumerate() {
-                        let vec_p = Vec3FractionalId::new(
-                            f as f64 + pattern.a() - points[0].a(),
-                            x as f64 + pattern.b() - points[0].b(),
-                            y as f64 + pattern.c() - points[0].c(),
-                        );
-                        let sign = n.dot(&vec_p).is_sign_positive();
-                        if i == 0 || sign_before == sign {
-                            sign_before = sign;
-                        } else {
-                            for pattern in eight_patterns {
-                                let cp = Vec3FractionalId::new(
-                                    f as f64 + pattern.a(),
-                                    x as f64 + pattern.b(),
-                                    y as f64 + pattern.c(),
-                                );
-                                let rel_p0 = cp - points[0];
-                                let rel_p1 = cp - points[1];
-                                let rel_p2 = cp - points[2];
-
-                                if ma.dot(&rel_p0) >= 0.0
-                                    && mc.dot(&rel_p1) >= 0.0
-                                    && mb.dot(&rel_p2) >= 0.0
-                                {
-                                    voxels.insert(SingleId::new(z, f, x, y).unwrap());
-                                    break;
-                                }
-                            }
-                            break;
+            for y in min_y..=max_y {
+                // 8 頂点のうち平面との符号付き距離の最小・最大を X の一次式で表し、
+                // 最小が負かつ最大が非負（平面をまたぐ）になる X の範囲を求める。
+                let k = n.a() * (f as f64 - p0.a()) + n.c() * (y as f64 - p0.c()) - n.b() * p0.b();
+                let straddle = linear_range(&[
+                    (-n.b(), -(k + low(n.a()) + low(n.b()) + low(n.c()))),
+                    (n.b(), k + high(n.a()) + high(n.b()) + high(n.c())),
+                ]);
+                let Some(straddle) = straddle else {
+                    continue;
+                };
+
+                // F・Y のずれが同じ 2 頂点（X のずれ 0 と 1）ごとに、柱の中に入る X の範囲を求める。
+                for [da, dc] in [[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0]] {
+                    let (cf, cy) = (f as f64 + da, y as f64 + dc);
+                    let prism = [
+                        (self.ma, self.points[0]),
+                        (self.mc, self.points[1]),
+                        (self.mb, self.points[2]),
+                    ]
+                    .map(|(m, p)| {
+                        (
+                            m.b(),
+                            m.a() * (cf - p.a()) + m.c() * (cy - p.c()) - m.b() * p.b(),
+                        )
+                    });
+                    let Some([lo, hi]) = linear_range(&prism) else {
+                        continue;
+                    };
+                    // 頂点の X は x か x + 1 なので、x は [ceil(lo) - 1, floor(hi)]。
+                    let (lo, hi) = (libm::ceil(lo), libm::floor(hi));
+                    if lo > hi {
+                        continue;
+                    }
+                    let lo = (lo - 1.0).max(straddle[0]).max(min_x as f64);
+                    let hi = hi.min(straddle[1]).min(max_x as f64);
+                    if lo > hi {
+                        continue;
+                    }
+                    self.push_refined(runs, f, y, lo as u32, hi as u32);
+                }
+            }
+        }
+    }
+
+    /// 式から求めた区間 `[lo, hi]` を [`touches`](Self::touches) で確かめ、かかるボクセルの続く区間ごとに `runs` に加える。
+    ///
+    /// 細い小三角形では丸め誤差で区間の途中が抜けることがあるので、区間の中は 1 つずつ確かめ、
+    /// 両端はかかる限り外へ広げる。
+    fn push_refined(&self, runs: &mut RowRuns, f: i32, y: u32, lo: u32, hi: u32) {
+        let [min_x, max_x] = self.bounds.1;
+        let mut start: Option<u32> = None;
+        for x in lo..=hi {
+            match (self.touches(f, x, y), start) {
+                (true, None) => {
+                    let mut first = x;
+                    if x == lo {
+                        while first > min_x && self.touches(f, first - 1, y) {
+                            first -= 1;
                         }
                     }
+                    start = Some(first);
+                }
+                (false, Some(first)) => {
+                    runs.push_run(f, y, first, x - 1);
+                    start = None;
                 }
+                _ => {}
             }
         }
-        Ok(voxels.into_iter())
+        if let Some(first) = start {
+            let mut last = hi;
+            while last < max_x && self.touches(f, last + 1, y) {
+                last += 1;
+            }
+            runs.push_run(f, y, first, last);
+        }
+    }
+}
+
+/// `a * x + b >= 0` をすべて満たす実数 `x` の範囲（整数へ丸め済み）。満たす `x` が無ければ `None`。
+///
+/// 端は `ceil` / `floor` で整数に丸めるが、どちら向きにも 1 つ広げてある。
+fn linear_range(constraints: &[(f64, f64)]) -> Option<[f64; 2]> {
+    let (mut lo, mut hi) = (f64::NEG_INFINITY, f64::INFINITY);
+    for &(a, b) in constraints {
+        if a > 0.0 {
+            lo = lo.max(-b / a);
+        } else if a < 0.0 {
+            hi = hi.min(-b / a);
+        } else if b < 0.0 {
+            return None;
+        }
     }
+    let (lo, hi) = (libm::floor(lo) - 1.0, libm::ceil(hi) + 1.0);
+    (lo <= hi).then_some([lo, hi])
 }
@@ -1,7 +1,9 @@
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
-use crate::{Coordinate, CoverSingleIds, Triangle};
+use crate::geometry::shape::cover_tests::assert_same_cover;
+use crate::geometry::shape::coverage::CoverClass;
+use crate::{Coordinate, CoverClassifiedIds, CoverSingleIds, Triangle};
 
 fn sorted_ids(tri: &Triangle, z: u8) -> Vec<String> {
     let mut ids: Vec<String> = tri
@@ -57,3 +59,69 @@ mod cover_single_ids {
         insta::assert_debug_snapshot!(sorted_ids(&tri, 25));
     }
 }
+
+mod cover_range_ids {
+    use super::*;
+
+    /// 水平な三角形は行ごとの区間にまとまる
+    #[test]
+    fn horizontal_triangle_matches_single_ids() {
+        let p0 = Coordinate::new(35.681, 139.766, 10.0).unwrap();
+        let p1 = Coordinate::new(35.682, 139.766, 10.0).unwrap();
+        let p2 = Coordinate::new(35.681, 139.767, 10.0).unwrap();
+        let tri = Triangle::new([p0, p1, p2]);
+        let count = assert_same_cover(&tri, 22);
+        assert!(count * 4 < sorted_ids(&tri, 22).len());
+    }
+
+    /// 立体的な三角形も同じ空間を覆う
+    #[test]
+    fn three_dimensional_triangle_matches_single_ids() {
+        let p0 = Coordinate::new(35.681, 139.766, 0.0).unwrap();
+        let p1 = Coordinate::new(35.683, 139.767, 0.0).unwrap();
+        let p2 = Coordinate::new(35.682, 139.766, 64.0).unwrap();
+        let tri = Triangle::new([p0, p1, p2]);
+        assert_same_cover(&tri, 22);
+    }
+    /// 細い三角形を含む乱数の三角形でも、行の途中の抜けまで同じ空間を覆う
+    #[test]
+    fn random_triangles_match_single_ids() {
+        use rand::{RngExt, SeedableRng};
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(7);
+        for i in 0..100 {
+            let base = (35.0 + rng.random::<f64>(), 139.0 + rng.random::<f64>());
+            let s = 0.004 * rng.random::<f64>() + 0.00001;
+            let h = 200.0 * rng.random::<f64>();
+            let mut pt = || {
+                Coordinate::new(
+                    base.0 + s * rng.random::<f64>(),
+                    base.1 + s * rng.random::<f64>(),
+                    h * rng.random::<f64>(),
+                )
+                .unwrap()
+            };
+            let tri = Triangle::new([pt(), pt(), pt()]);
+            assert_same_cover(&tri, 20 + (i % 6) as u8);
+        }
+    }
+}
+
+mod cover_classified_ids {
+    use super::*;
+
+    /// 面は体積を持たないので、どのボクセルも境界として一部だけ占める
+    #[test]
+    fn horizontal_triangle_is_all_boundary() {
+        let p0 = Coordinate::new(35.681, 139.766, 10.0).unwrap();
+        let p1 = Coordinate::new(35.682, 139.766, 10.0).unwrap();
+        let p2 = Coordinate::new(35.681, 139.767, 10.0).unwrap();
+        let tri = Triangle::new([p0, p1, p2]);
+        let classified: Vec<_> = tri.cover_classified_ids(20).unwrap().collect();
+
+        assert!(!classified.is_empty());
+        for (_, coverage) in classified {
+            assert_eq!(coverage.class, CoverClass::Boundary);
+            assert!(coverage.fraction <= 0.5, "{}", coverage.fraction);
+        }
+    }
+}
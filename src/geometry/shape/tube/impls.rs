@@ -1,4 +1,10 @@
-use crate::{CoverSingleIds, Cylinder, Error, SingleId, Sphere, Tube};
+use crate::geometry::shape::convex::{self, IdBox, RoundedSegment};
+use crate::geometry::shape::runs::RowRuns;
+use crate::{
+    CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds, Cylinder, Error, RangeId,
+    SingleId, SpatialPredicates, Sphere, Tube, Vec3Ecef,
+};
+use alloc::vec::Vec;
 use hashbrown::HashSet;
 
 impl CoverSingleIds for Tube {
@@ -13,3 +19,68 @@ impl CoverSingleIds for Tube {
         Ok(ids.into_iter())
     }
 }
+
+impl CoverRangeIds for Tube {
+    /// [`cover_single_ids`](CoverSingleIds::cover_single_ids) と同じ空間を [`RangeId`] で返す。
+    ///
+    /// 節点の球と区間の円柱をそれぞれ行ごとの区間として重ね、重なりを併合してからまとめる。
+    fn cover_range_ids(&self, z: u8) -> Result<impl Iterator<Item = RangeId>, Error> {
+        let mut runs = RowRuns::new(z);
+        Sphere::new(self.points[0], self.radius_m)?.push_runs(z, &mut runs)?;
+        for coos in self.points.windows(2) {
+            for range in Cylinder::new(coos[0], coos[1], self.radius_m)?.cover_range_ids(z)? {
+                runs.push_range(&range);
+            }
+            Sphere::new(coos[1], self.radius_m)?.push_runs(z, &mut runs)?;
+        }
+        Ok(runs.into_range_ids()?.into_iter())
+    }
+}
+
+impl CoverFlexIds for Tube {}
+
+impl CoverClassifiedIds for Tube {}
+
+impl Tube {
+    /// 区間ごとのカプセル。節点が 1 つなら球になる。
+    fn segments(&self) -> Vec<RoundedSegment> {
+        let points: Vec<Vec3Ecef> = self.points.iter().map(|&p| p.into()).collect();
+        let capsule = |a: Vec3Ecef, b: Vec3Ecef| RoundedSegment {
+            a,
+            b,
+            radius: self.radius_m,
+        };
+        match points.as_slice() {
+            [point] => vec![capsule(*point, *point)],
+            _ => points
+                .windows(2)
+                .map(|pair| capsule(pair[0], pair[1]))
+                .collect(),
+        }
+    }
+}
+
+impl SpatialPredicates for Tube {
+    fn intersects_part(&self, id: &RangeId) -> bool {
+        let id_box = IdBox::new(id);
+        self.segments()
+            .iter()
+            .any(|segment| convex::intersects(segment, &id_box))
+    }
+
+    /// 直方体がいずれか 1 つの区間のカプセルに収まるか。
+    ///
+    /// 節点をまたいで複数のカプセルにかかる直方体は、全体がパイプの内側にあっても `false` になる。
+    fn contains_part(&self, id: &RangeId) -> bool {
+        let id_box = IdBox::new(id);
+        self.segments()
+            .iter()
+            .any(|segment| convex::contains_box(segment, &id_box))
+    }
+
+    fn within_part(&self, id: &RangeId) -> bool {
+        let id_box = IdBox::new(id);
+        let segments = self.segments();
+        !segments.is_empty() && segments.iter().all(|segment| id_box.encloses(segment))
+    }
+}
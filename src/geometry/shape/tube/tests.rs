@@ -1,6 +1,7 @@
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
+use crate::geometry::shape::cover_tests::assert_same_cover;
 use crate::{Coordinate, CoverSingleIds, Tube};
 
 fn sorted_ids(tube: &Tube, z: u8) -> Vec<String> {
@@ -47,3 +48,26 @@ mod cover_single_ids {
         insta::assert_debug_snapshot!(sorted_ids(&tube, 18));
     }
 }
+
+mod cover_range_ids {
+    use super::*;
+
+    #[test]
+    fn bent_two_segment_tube_matches_single_ids() {
+        let p0 = Coordinate::new(35.681, 139.766, 10.0).unwrap();
+        let p1 = Coordinate::new(35.682, 139.767, 10.0).unwrap();
+        let p2 = Coordinate::new(35.683, 139.766, 10.0).unwrap();
+        let tube = Tube::new(vec![p0, p1, p2], 5.0).unwrap();
+        assert_same_cover(&tube, 20);
+    }
+
+    #[test]
+    fn vertically_bent_tube_matches_single_ids() {
+        let p0 = Coordinate::new(35.681, 139.766, 0.0).unwrap();
+        let p1 = Coordinate::new(35.682, 139.766, 0.0).unwrap();
+        let p2 = Coordinate::new(35.682, 139.766, 32.0).unwrap();
+        let tube = Tube::new(vec![p0, p1, p2], 5.0).unwrap();
+        let count = assert_same_cover(&tube, 21);
+        assert!(count < sorted_ids(&tube, 21).len());
+    }
+}
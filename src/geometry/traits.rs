@@ -1,4 +1,7 @@
-use crate::{Error, RangeId, SingleId};
+use crate::geometry::shape::adaptive;
+use crate::geometry::shape::convex::predicate_parts;
+use crate::geometry::shape::coverage::{self, Coverage};
+use crate::{Error, FlexId, RangeId, SingleId, SpatialId, SpatialIdMap};
 
 pub trait CoverSingleIds {
     /// 指定されたズームレベルの[SingleId]を出力する。
@@ -12,3 +15,99 @@ pub trait CoverRangeIds {
     /// 実装内部で [RangeId] の出力を活かす処理を持つこと。
     fn cover_range_ids(&self, z: u8) -> Result<impl Iterator<Item = RangeId>, Error>;
 }
+
+/// [CoverRangeIds] の被覆を粗さの混じった[FlexId]へまとめ直す。
+///
+/// 既定の実装は `max_z` の [CoverRangeIds] から組み立てるので、互いに重ならない被覆を返す図形なら
+/// 空の `impl` で使える。
+pub trait CoverFlexIds: CoverRangeIds {
+    /// 最大ズームレベル `max_z` までの[FlexId]で、できるだけ粗く覆う。
+    ///
+    /// 図形の内側に収まる空間は粗い[FlexId]のまま残し、境界にかかる空間だけを `max_z` まで
+    /// 細かくする。`tolerance` は 1 つの[FlexId]のうち図形の外にはみ出してよい体積の割合
+    /// （`0.0..1.0`）で、`0.0` なら `max_z` の [CoverRangeIds] とちょうど同じ空間を覆う。
+    fn cover_flex_ids(
+        &self,
+        max_z: u8,
+        tolerance: f64,
+    ) -> Result<impl Iterator<Item = FlexId>, Error> {
+        let ids = adaptive::flex_ids(max_z, self.cover_range_ids(max_z)?, tolerance)?;
+        Ok(ids.into_iter())
+    }
+}
+
+/// 図形にかかる[SingleId]を、その占有状況とともに求める。
+///
+/// 既定の実装は細かいズームレベルの [CoverRangeIds] から見積もるので、互いに重ならない被覆を返す
+/// 図形なら空の `impl` で使える。
+pub trait CoverClassifiedIds: CoverRangeIds {
+    /// 指定されたズームレベルで図形にかかる[SingleId]を、その占有状況とともに出力する。
+    ///
+    /// 占有率は [`SUBDIVISION`](crate::geometry::shape::coverage::SUBDIVISION) 段細かい
+    /// ズームレベルの [CoverRangeIds] から見積もる。出力する[SingleId]もその細かい被覆から求めるので、
+    /// 同じズームレベルの [CoverSingleIds] より少なくなることがある。
+    fn cover_classified_ids(
+        &self,
+        z: u8,
+    ) -> Result<impl Iterator<Item = (SingleId, Coverage)>, Error> {
+        let ids = coverage::classify(z, |fine_z| self.cover_range_ids(fine_z))?;
+        Ok(ids.into_iter())
+    }
+
+    /// [`cover_classified_ids`](Self::cover_classified_ids) の占有率を値に持つ[SpatialIdMap]を作る。
+    ///
+    /// 複数の図形のマップを `Sum` や `Average` で重ね合わせると、占有密度が得られる。
+    fn cover_fraction_map(&self, z: u8) -> Result<SpatialIdMap<f64>, Error> {
+        let mut map = SpatialIdMap::new();
+        for (id, coverage) in self.cover_classified_ids(z)? {
+            map.insert(id, coverage.fraction);
+        }
+        Ok(map)
+    }
+}
+
+/// 空間IDが表す直方体との位置関係を、被覆を作らずに判定する。
+///
+/// 直方体は [`spatial_vertices`](SpatialId::spatial_vertices) の 8 頂点（緯度は Web Mercator の上下端で
+/// [`Coordinate`](crate::Coordinate) の範囲に丸める）を ECEF に変換した凸包として扱い、時間の範囲は考えない。境界が接する場合は交わるとみなす。凸包は、地球の曲率で面がふくらむ分だけ
+/// 膨らませてあるので、細かい空間IDと交わる図形は、それを含む粗い空間IDとも交わる。
+///
+/// 水平方向に広い空間IDは凸包が実際の領域から大きく外れるので、X・Y をズームレベル 6 の 1 マス
+/// 以下の部分に割り、部分ごとに `*_part` で判定する。実装する側は `*_part` だけを書けばよい。
+pub trait SpatialPredicates {
+    /// 図形と `id` の直方体が共通部分を持つか。
+    ///
+    /// 境界がごく近い場合は、交わらなくても `true` を返すことがある。交わるのに `false` を返すことはない。
+    fn intersects<S: SpatialId>(&self, id: &S) -> bool {
+        predicate_parts(&id.clone().into())
+            .iter()
+            .any(|part| self.intersects_part(part))
+    }
+
+    /// `id` の直方体全体が図形に含まれるか。体積を持たない図形では常に `false`。
+    ///
+    /// 含まれると確かめられた場合だけ `true` を返す。
+    fn contains<S: SpatialId>(&self, id: &S) -> bool {
+        predicate_parts(&id.clone().into())
+            .iter()
+            .all(|part| self.contains_part(part))
+    }
+
+    /// 図形全体が `id` の直方体に含まれるか。
+    ///
+    /// 水平方向に広い `id` では、割った部分のいずれか 1 つに収まる場合だけ `true` になる。
+    fn within<S: SpatialId>(&self, id: &S) -> bool {
+        predicate_parts(&id.clone().into())
+            .iter()
+            .any(|part| self.within_part(part))
+    }
+
+    /// [`intersects`](Self::intersects) の、X・Y がズームレベル 6 の 1 マスに収まる `part` についての判定。
+    fn intersects_part(&self, part: &RangeId) -> bool;
+
+    /// [`contains`](Self::contains) の、X・Y がズームレベル 6 の 1 マスに収まる `part` についての判定。
+    fn contains_part(&self, part: &RangeId) -> bool;
+
+    /// [`within`](Self::within) の、X・Y がズームレベル 6 の 1 マスに収まる `part` についての判定。
+    fn within_part(&self, part: &RangeId) -> bool;
+}
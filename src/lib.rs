@@ -28,17 +28,35 @@ pub use geometry::point::{
 
 #[doc(inline)]
 pub use error::{GeometryError, SpatialIdError};
+#[cfg(feature = "json")]
+#[doc(inline)]
+pub use geometry::geojson::{GeoJsonFeature, GeoJsonReader, GeoJsonShape};
+#[doc(inline)]
+pub use geometry::shape::capsule::Capsule;
+#[doc(inline)]
+pub use geometry::shape::cone::Cone;
 #[doc(inline)]
 pub use geometry::shape::cylinder::Cylinder;
 #[doc(inline)]
+pub use geometry::shape::ellipsoid::Ellipsoid;
+#[cfg(feature = "temporal_id")]
+#[doc(inline)]
+pub use geometry::shape::trajectory::Trajectory;
+#[doc(inline)]
 pub use geometry::shape::tube::Tube;
 
 // geometry: types
 #[doc(inline)]
 pub use geometry::shape::line::Line;
 #[doc(inline)]
+pub use geometry::shape::multi_polygon::MultiPolygon;
+#[doc(inline)]
+pub use geometry::shape::oriented_box::OrientedBox;
+#[doc(inline)]
 pub use geometry::shape::polygon::Polygon;
 #[doc(inline)]
+pub use geometry::shape::prism::{Prism, Roof};
+#[doc(inline)]
 pub use geometry::shape::solid::Solid;
 #[doc(inline)]
 pub use geometry::shape::sphere::Sphere;
@@ -61,7 +79,9 @@ pub use geometry::shape::traits::{
 #[doc(inline)]
 pub use geometry::shape::triangle::Triangle;
 #[doc(inline)]
-pub use geometry::traits::{CoverRangeIds, CoverSingleIds};
+pub use geometry::traits::{
+    CoverClassifiedIds, CoverFlexIds, CoverRangeIds, CoverSingleIds, SpatialPredicates,
+};
 
 // geometry: constants
 #[doc(inline)]
@@ -79,9 +99,17 @@ pub use spatial_id::single_id::SingleId;
 
 #[doc(inline)]
 pub use spatial_id::collection::flex_tree::set::SpatialIdSet;
+#[cfg(feature = "persist")]
+#[doc(inline)]
+pub use spatial_id::collection::flex_tree::set::archived::ArchivedSpatialIdSet;
+#[cfg(feature = "std")]
+#[doc(inline)]
+pub use spatial_id::collection::flex_tree::set::text::RangeIdTextReader;
 #[doc(inline)]
 pub use spatial_id::collection::flex_tree::traits::FlexIdValue;
 
+#[doc(inline)]
+pub use spatial_id::collection::flex_tree::components::ComponentStats;
 #[doc(inline)]
 pub use spatial_id::collection::flex_tree::map::SpatialIdMap;
 #[cfg(feature = "persist")]
@@ -91,10 +119,29 @@ pub use spatial_id::collection::flex_tree::map::archived::ArchivedSpatialIdMap;
 #[doc(inline)]
 pub use spatial_id::collection::flex_tree::map::arena::FORMAT_VERSION;
 #[doc(inline)]
+pub use spatial_id::collection::flex_tree::outline::LayerOutline;
+#[doc(inline)]
+pub use spatial_id::collection::flex_tree::path::PathPlan;
+#[doc(inline)]
+pub use spatial_id::collection::flex_tree::ray::RayHit;
+#[doc(inline)]
 pub use spatial_id::collection::flex_tree::table::SpatialIdTable;
+#[cfg(feature = "persist")]
+#[doc(inline)]
+pub use spatial_id::collection::flex_tree::table::archived::ArchivedSpatialIdTable;
+#[cfg(all(feature = "json", feature = "std"))]
+#[doc(inline)]
+pub use spatial_id::collection::flex_tree::table::json_stream::JsonStreamReader;
+#[cfg(feature = "persist")]
+#[doc(inline)]
+pub use spatial_id::collection::flex_tree::table::store::SpatialIdTableStore;
+#[doc(inline)]
+pub use spatial_id::collection::flex_tree::wire::WIRE_VERSION;
 
 // spatial_id: traits
 #[doc(inline)]
+pub use spatial_id::helpers::Connectivity;
+#[doc(inline)]
 pub use spatial_id::helpers::Side;
 #[doc(inline)]
 pub use spatial_id::traits::SpatialId;
@@ -117,6 +164,8 @@ pub use spatial_id::collection::query::merge_policy;
 #[doc(inline)]
 pub use spatial_id::collection::query::merge_policy::MergePolicy;
 #[doc(inline)]
+pub use spatial_id::collection::query::merge_policy::Policy;
+#[doc(inline)]
 pub use spatial_id::collection::query::source::Source;
 #[doc(inline)]
 pub use spatial_id::collection::query::working::WorkingTree;
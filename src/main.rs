@@ -1,11 +1,753 @@
-use kasane_logic::{Source, SpatialIdTable};
+//! クエリエンジンをコマンドラインから使うためのツール。
+//!
+//! 入力を読み、引数の順に演算子を積んだクエリ定義を [`Query::parse`] で組み、最適化後の木を
+//! 標準エラーへ表示してから実行し、結果を書き出す。使い方は `kasane-logic --help` を参照。
+//!
+//! 扱える値は `u32` だけで、入力・出力のテーブルはすべて `SpatialIdTable<u32>` になる。
+
 use std::fs;
+use std::io::{self, Write};
+use std::ops::Bound;
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use kasane_logic::spatial_id::collection::query::ops::unary::falloff::FalloffPattern;
+use kasane_logic::spatial_id::collection::query::ops::unary::filter_values::ValuePredicate;
+use kasane_logic::{
+    CancellationToken, Error, Policy, Query, Side, Source, SpatialIdSet, SpatialIdTable,
+    WorkingTree,
+};
+
+const USAGE: &str = "\
+Usage: kasane-logic [OPTIONS] <INPUT>...
+
+Runs a query pipeline over spatial ID tables with u32 values.
+Only u32 values are supported; every input is read as, and every output is
+written as, a table of u32 values.
+
+Inputs:
+  *.json               schema JSON (v1.0) of a table with u32 values
+  *.bin, *.arena       arena bytes written by SpatialIdTable<Vec<u8>>::to_bytes,
+                       each value a 4-byte little-endian u32 (needs `persist`)
+  The first input is the source of the pipeline. Binary operators refer to
+  the others by their position N (0-based).
+
+Pipeline (applied in the order given):
+  --shift AXIS:Z:D                       AXIS = f | x | y
+  --extrude AXIS:Z:START:END:POLICY
+  --falloff AXIS:Z:RADIUS:DIR:PATTERN:POLICY
+                                         DIR = both | lower | upper
+                                         PATTERN = linear | quadratic-in | quadratic-out
+  --zoom-out Z:POLICY
+  --filter eq:V | in:RANGE | not-in:RANGE
+                                         RANGE = LO..HI | LO..=HI (either side may be empty)
+  --merge N:POLICY:DEFAULT
+  --intersection N
+  --difference N
+  POLICY = Max | Min | Sum | Average | Overwrite | KeepExisting | Difference
+                                         (the same names as in the query language)
+
+Output:
+  -f, --format FORMAT    json (default) | text | stats
+  -o, --output PATH      write to PATH instead of stdout
+  -t, --timeout SECS     cancel the query after SECS seconds
+      --explain          print the optimized query and exit without running
+  -h, --help           print this help
+";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cli = match Cli::parse(&args) {
+        Ok(Some(cli)) => cli,
+        Ok(None) => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match cli.execute() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// 出力の形式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// スキーマ JSON（[`SpatialIdTable`] の `Serialize`）。
+    Json,
+    /// `RangeId` をカンマで並べたテキスト（[`SpatialIdSet::write_text`]）。値は含まない。
+    Text,
+    /// 件数・値の範囲・実行時間などの要約。
+    Stats,
+}
+
+/// 軸。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    F,
+    X,
+    Y,
+}
+
+impl std::fmt::Display for Axis {
+    /// クエリ言語の演算子名（`shift_f` など）の末尾に付く軸名を書く。
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Axis::F => "f",
+            Axis::X => "x",
+            Axis::Y => "y",
+        })
+    }
+}
+
+/// パイプラインの 1 段。
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Shift {
+        axis: Axis,
+        z: u8,
+        d: i32,
+    },
+    Extrude {
+        axis: Axis,
+        z: u8,
+        start: i64,
+        end: i64,
+        policy: Policy,
+    },
+    Falloff {
+        axis: Axis,
+        z: u8,
+        radius: u32,
+        direction: Option<Side>,
+        pattern: FalloffPattern,
+        policy: Policy,
+    },
+    ZoomOut {
+        z: u8,
+        policy: Policy,
+    },
+    Filter(ValuePredicate<u32>),
+    Merge {
+        input: usize,
+        policy: Policy,
+        default: u32,
+    },
+    Intersection {
+        input: usize,
+    },
+    Difference {
+        input: usize,
+    },
+}
+
+/// 解釈済みの引数。
+#[derive(Debug)]
+struct Cli {
+    inputs: Vec<String>,
+    steps: Vec<Step>,
+    format: Format,
+    output: Option<String>,
+    timeout: Option<Duration>,
+    explain: bool,
+}
+
+impl Cli {
+    /// 引数を解釈する。`--help` が指定されたら `None`。
+    fn parse(args: &[String]) -> Result<Option<Self>, String> {
+        let mut cli = Cli {
+            inputs: Vec::new(),
+            steps: Vec::new(),
+            format: Format::Json,
+            output: None,
+            timeout: None,
+            explain: false,
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .map(String::as_str)
+                    .ok_or_else(|| format!("{arg} needs a value"))
+            };
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--explain" => cli.explain = true,
+                "-f" | "--format" => {
+                    cli.format = match value()? {
+                        "json" => Format::Json,
+                        "text" => Format::Text,
+                        "stats" => Format::Stats,
+                        other => return Err(format!("unknown format '{other}'")),
+                    }
+                }
+                "-o" | "--output" => cli.output = Some(value()?.to_string()),
+                "-t" | "--timeout" => {
+                    let secs: f64 = parse_number(value()?, "timeout")?;
+                    cli.timeout = Some(
+                        Duration::try_from_secs_f64(secs)
+                            .map_err(|_| format!("invalid timeout '{secs}'"))?,
+                    );
+                }
+                "--shift" => {
+                    let [axis, z, d] = fields(value()?)?;
+                    cli.steps.push(Step::Shift {
+                        axis: parse_axis(axis)?,
+                        z: parse_number(z, "zoom level")?,
+                        d: parse_number(d, "shift")?,
+                    });
+                }
+                "--extrude" => {
+                    let [axis, z, start, end, policy] = fields(value()?)?;
+                    cli.steps.push(Step::Extrude {
+                        axis: parse_axis(axis)?,
+                        z: parse_number(z, "zoom level")?,
+                        start: parse_number(start, "start")?,
+                        end: parse_number(end, "end")?,
+                        policy: parse_policy(policy)?,
+                    });
+                }
+                "--falloff" => {
+                    let [axis, z, radius, direction, pattern, policy] = fields(value()?)?;
+                    cli.steps.push(Step::Falloff {
+                        axis: parse_axis(axis)?,
+                        z: parse_number(z, "zoom level")?,
+                        radius: parse_number(radius, "radius")?,
+                        direction: match direction {
+                            "both" => None,
+                            "lower" => Some(Side::Lower),
+                            "upper" => Some(Side::Upper),
+                            other => return Err(format!("unknown direction '{other}'")),
+                        },
+                        pattern: match pattern {
+                            "linear" => FalloffPattern::Linear,
+                            "quadratic-in" => FalloffPattern::QuadraticIn,
+                            "quadratic-out" => FalloffPattern::QuadraticOut,
+                            other => return Err(format!("unknown falloff pattern '{other}'")),
+                        },
+                        policy: parse_policy(policy)?,
+                    });
+                }
+                "--zoom-out" => {
+                    let [z, policy] = fields(value()?)?;
+                    cli.steps.push(Step::ZoomOut {
+                        z: parse_number(z, "zoom level")?,
+                        policy: parse_policy(policy)?,
+                    });
+                }
+                "--filter" => {
+                    let text = value()?;
+                    let (kind, operand) = text
+                        .split_once(':')
+                        .ok_or_else(|| format!("invalid filter '{text}'"))?;
+                    cli.steps.push(Step::Filter(match kind {
+                        "eq" => ValuePredicate::Equals(parse_number(operand, "value")?),
+                        "in" => {
+                            let (start, end) = parse_range(operand)?;
+                            ValuePredicate::InRange(start, end)
+                        }
+                        "not-in" => {
+                            let (start, end) = parse_range(operand)?;
+                            ValuePredicate::NotInRange(start, end)
+                        }
+                        other => return Err(format!("unknown filter '{other}'")),
+                    }));
+                }
+                "--merge" => {
+                    let [input, policy, default] = fields(value()?)?;
+                    cli.steps.push(Step::Merge {
+                        input: parse_number(input, "input")?,
+                        policy: parse_policy(policy)?,
+                        default: parse_number(default, "default")?,
+                    });
+                }
+                "--intersection" => cli.steps.push(Step::Intersection {
+                    input: parse_number(value()?, "input")?,
+                }),
+                "--difference" => cli.steps.push(Step::Difference {
+                    input: parse_number(value()?, "input")?,
+                }),
+                flag if flag.starts_with('-') && flag.len() > 1 => {
+                    return Err(format!("unknown option '{flag}'"));
+                }
+                input => cli.inputs.push(input.to_string()),
+            }
+        }
+
+        if cli.inputs.is_empty() {
+            return Err("no input given".into());
+        }
+        for step in &cli.steps {
+            if let Step::Merge { input, .. }
+            | Step::Intersection { input }
+            | Step::Difference { input } = step
+                && *input >= cli.inputs.len()
+            {
+                return Err(format!(
+                    "input {input} does not exist ({} given)",
+                    cli.inputs.len()
+                ));
+            }
+        }
+        Ok(Some(cli))
+    }
+
+    /// 入力を読み、クエリを組んで実行し、結果を書き出す。
+    fn execute(self) -> Result<(), String> {
+        let tables = self
+            .inputs
+            .iter()
+            .map(|path| load(path).map_err(|e| format!("{path}: {e}")))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let query = build(&tables, &self.steps);
+        query.validate().map_err(|e| e.to_string())?;
+        let query = query.optimize();
+        eprintln!("{query}");
+        if self.explain {
+            return Ok(());
+        }
+
+        let token = CancellationToken::new();
+        if let Some(timeout) = self.timeout {
+            let token = token.clone();
+            thread::spawn(move || {
+                thread::sleep(timeout);
+                token.cancel();
+            });
+        }
+
+        let started = Instant::now();
+        let result: WorkingTree<u32> = match query.raw_run_working_tree_cancellable(&token) {
+            Ok(result) => result,
+            Err(Error::Cancelled) => {
+                return Err(format!(
+                    "query cancelled after {:?} timeout",
+                    self.timeout.unwrap_or_default()
+                ));
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+        let elapsed = started.elapsed();
+
+        let target = self.output.as_deref().unwrap_or("stdout");
+        let mut out: Box<dyn Write> = match &self.output {
+            Some(path) => Box::new(io::BufWriter::new(
+                fs::File::create(path).map_err(|e| format!("{path}: {e}"))?,
+            )),
+            None => Box::new(io::BufWriter::new(io::stdout().lock())),
+        };
+        write_result(result, self.format, elapsed, &mut out)
+            .and_then(|()| out.flush())
+            .map_err(|e| format!("write {target}: {e}"))
+    }
+}
+
+/// `a:b:c` を `N` 個の欄に分ける。
+fn fields<const N: usize>(text: &str) -> Result<[&str; N], String> {
+    let parts: Vec<&str> = text.split(':').collect();
+    parts
+        .try_into()
+        .map_err(|_| format!("'{text}' must have {N} fields separated by ':'"))
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str, what: &str) -> Result<T, String> {
+    text.parse().map_err(|_| format!("invalid {what} '{text}'"))
+}
+
+fn parse_axis(text: &str) -> Result<Axis, String> {
+    match text {
+        "f" => Ok(Axis::F),
+        "x" => Ok(Axis::X),
+        "y" => Ok(Axis::Y),
+        other => Err(format!("unknown axis '{other}'")),
+    }
+}
+
+fn parse_policy(text: &str) -> Result<Policy, String> {
+    text.parse()
+        .map_err(|_| format!("unknown merge policy '{text}'"))
+}
+
+/// `LO..HI` / `LO..=HI`（両端とも省略可）を境界の組にする。
+fn parse_range(text: &str) -> Result<(Bound<u32>, Bound<u32>), String> {
+    let (start, end, inclusive) = if let Some((start, end)) = text.split_once("..=") {
+        (start, end, true)
+    } else if let Some((start, end)) = text.split_once("..") {
+        (start, end, false)
+    } else {
+        return Err(format!("invalid range '{text}'"));
+    };
+    let start = match start {
+        "" => Bound::Unbounded,
+        s => Bound::Included(parse_number(s, "range start")?),
+    };
+    let end = match (end, inclusive) {
+        ("", false) => Bound::Unbounded,
+        ("", true) => return Err(format!("invalid range '{text}'")),
+        (e, true) => Bound::Included(parse_number(e, "range end")?),
+        (e, false) => Bound::Excluded(parse_number(e, "range end")?),
+    };
+    Ok((start, end))
+}
+
+/// 入力ファイルを読む。拡張子で形式を選ぶ。
+fn load(path: &str) -> Result<SpatialIdTable<u32>, String> {
+    if path.ends_with(".bin") || path.ends_with(".arena") {
+        return load_arena(path);
+    }
+    let file = fs::File::open(path).map_err(|e| e.to_string())?;
+    serde_json::from_reader(io::BufReader::new(file)).map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "persist")]
+fn load_arena(path: &str) -> Result<SpatialIdTable<u32>, String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    // SAFETY: 入力は `SpatialIdTable::to_bytes` が書いたものであることを利用者に求めている。
+    let table =
+        unsafe { SpatialIdTable::<Vec<u8>>::from_bytes(&bytes) }.map_err(|e| e.to_string())?;
+    table
+        .into_iter()
+        .map(|(id, value)| {
+            let value: [u8; 4] = value
+                .as_slice()
+                .try_into()
+                .map_err(|_| format!("value of {id} is not a 4-byte u32"))?;
+            Ok((id, u32::from_le_bytes(value)))
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "persist"))]
+fn load_arena(_path: &str) -> Result<SpatialIdTable<u32>, String> {
+    Err("reading arena inputs needs the `persist` feature".into())
+}
+
+impl Step {
+    /// 直前までのパイプラインの定義 `query` にこの段を加えた、クエリ言語の定義を返す。
+    ///
+    /// 二項演算の相手は `inputN` という名前で参照する。
+    fn apply(&self, query: &str) -> String {
+        match self {
+            Step::Shift { axis, z, d } => format!("{query} | shift_{axis}({z}, {d})"),
+            Step::Extrude {
+                axis,
+                z,
+                start,
+                end,
+                policy,
+            } => format!("{query} | extrude_{axis}({z}, {start}, {end}, {policy})"),
+            Step::Falloff {
+                axis,
+                z,
+                radius,
+                direction,
+                pattern,
+                policy,
+            } => {
+                let direction = match direction {
+                    None => "Both",
+                    Some(Side::Upper) => "Upper",
+                    Some(Side::Lower) => "Lower",
+                };
+                let pattern = match pattern {
+                    FalloffPattern::Linear => "Linear",
+                    FalloffPattern::QuadraticIn => "QuadraticIn",
+                    FalloffPattern::QuadraticOut => "QuadraticOut",
+                };
+                format!("{query} | falloff_{axis}({z}, {radius}, {direction}, {pattern}, {policy})")
+            }
+            Step::ZoomOut { z, policy } => format!("{query} | zoom_out({z}, {policy})"),
+            Step::Filter(ValuePredicate::Equals(value)) => format!("{query} | filter_eq({value})"),
+            Step::Filter(ValuePredicate::InRange(start, end)) => {
+                format!("{query} | filter_in({})", range_text(start, end))
+            }
+            Step::Filter(ValuePredicate::NotInRange(start, end)) => {
+                format!("{query} | filter_not_in({})", range_text(start, end))
+            }
+            Step::Merge {
+                input,
+                policy,
+                default,
+            } => format!("merge({policy}, {query}, input{input}, {default})"),
+            Step::Intersection { input } => format!("intersection({query}, input{input})"),
+            Step::Difference { input } => format!("difference({query}, input{input})"),
+        }
+    }
+}
+
+/// [`parse_range`] の結果をクエリ言語の範囲（`LO..HI` / `LO..=HI`）に戻す。
+///
+/// [`parse_range`] は始端を `Excluded` にしないので、始端は `Included` か `Unbounded` だけ。
+fn range_text(start: &Bound<u32>, end: &Bound<u32>) -> String {
+    let start = match start {
+        Bound::Included(v) | Bound::Excluded(v) => v.to_string(),
+        Bound::Unbounded => String::new(),
+    };
+    match end {
+        Bound::Included(v) => format!("{start}..={v}"),
+        Bound::Excluded(v) => format!("{start}..{v}"),
+        Bound::Unbounded => format!("{start}.."),
+    }
+}
+
+/// `tables[0]` を起点に `steps` を積んだクエリを組む。
+///
+/// 各段をクエリ言語の定義へ書き直し、[`Query::parse`] で組み立てる。`tables[n]` は `inputN`
+/// という名前で参照する。組み立てに失敗した場合は [`Query::Error`] を返す。
+fn build(tables: &[SpatialIdTable<u32>], steps: &[Step]) -> Query<u32> {
+    let text = steps
+        .iter()
+        .fold(String::from("input0"), |query, step| step.apply(&query));
+    Query::parse(&text, |name| {
+        let n: usize = name.strip_prefix("input")?.parse().ok()?;
+        Some(tables.get(n)?.clone().query())
+    })
+    .unwrap_or_else(Query::Error)
+}
+
+/// 結果を `format` で書き出す。
+fn write_result(
+    result: WorkingTree<u32>,
+    format: Format,
+    elapsed: Duration,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    match format {
+        Format::Json => {
+            let table: SpatialIdTable<u32> = result.into();
+            serde_json::to_writer(&mut *out, &table)?;
+            writeln!(out)
+        }
+        Format::Text => {
+            let set: SpatialIdSet = result.into_iter().map(|(id, _)| id).collect();
+            set.write_text(&mut *out)?;
+            writeln!(out)
+        }
+        Format::Stats => {
+            let table: SpatialIdTable<u32> = result.into();
+            let values: Vec<u32> = table.values().copied().collect();
+            let stats = format!(
+                "flex_ids: {}\nrange_ids: {}\ndistinct_values: {}\nvalue_min: {}\nvalue_max: {}\nmax_zoomlevel: {}\nbounding_box: {}\nelapsed_ms: {:.3}\n",
+                table.iter().count(),
+                table.range_ids().count(),
+                values.len(),
+                display_option(values.first()),
+                display_option(values.last()),
+                display_option(table.iter().map(|(id, _)| max_zoom(&id)).max().as_ref()),
+                display_option(table.bounding_box().as_ref()),
+                elapsed.as_secs_f64() * 1000.0,
+            );
+            out.write_all(stats.as_bytes())
+        }
+    }
+}
+
+fn max_zoom(id: &kasane_logic::FlexId) -> u8 {
+    id.f_zoomlevel().max(id.x_zoomlevel()).max(id.y_zoomlevel())
+}
+
+fn display_option<T: std::fmt::Display>(value: Option<&T>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| v.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Cli>, String> {
+        Cli::parse(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn parses_pipeline_in_order() {
+        let cli = parse(&[
+            "a.json",
+            "--shift",
+            "x:25:10",
+            "b.json",
+            "--falloff",
+            "f:25:3:upper:quadratic-in:Max",
+            "--filter",
+            "in:1..=5",
+            "--merge",
+            "1:Sum:0",
+            "-f",
+            "stats",
+            "-t",
+            "1.5",
+        ])
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(cli.inputs, ["a.json", "b.json"]);
+        assert_eq!(cli.format, Format::Stats);
+        assert_eq!(cli.timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(
+            cli.steps,
+            [
+                Step::Shift {
+                    axis: Axis::X,
+                    z: 25,
+                    d: 10
+                },
+                Step::Falloff {
+                    axis: Axis::F,
+                    z: 25,
+                    radius: 3,
+                    direction: Some(Side::Upper),
+                    pattern: FalloffPattern::QuadraticIn,
+                    policy: Policy::Max,
+                },
+                Step::Filter(ValuePredicate::InRange(
+                    Bound::Included(1),
+                    Bound::Included(5)
+                )),
+                Step::Merge {
+                    input: 1,
+                    policy: Policy::Sum,
+                    default: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        for args in [
+            &[][..],
+            &["a.json", "--shift", "x:25"],
+            &["a.json", "--shift", "w:25:1"],
+            &["a.json", "--zoom-out", "20:median"],
+            &["a.json", "--intersection", "1"],
+            &["a.json", "--filter", "in:5"],
+            &["a.json", "--format"],
+            &["a.json", "--bogus"],
+        ] {
+            assert!(parse(args).is_err(), "{args:?}");
+        }
+        assert!(parse(&["--help"]).unwrap().is_none());
+    }
+
+    #[test]
+    fn parses_open_ranges() {
+        assert_eq!(
+            parse_range("..7").unwrap(),
+            (Bound::Unbounded, Bound::Excluded(7))
+        );
+        assert_eq!(
+            parse_range("3..").unwrap(),
+            (Bound::Included(3), Bound::Unbounded)
+        );
+        assert!(parse_range("3..=").is_err());
+    }
+
+    #[test]
+    fn builds_and_runs_binary_pipeline() {
+        let mut a = SpatialIdTable::new();
+        a.insert(kasane_logic::SingleId::new(20, 0, 10, 10).unwrap(), 3u32);
+        let mut b = SpatialIdTable::new();
+        b.insert(kasane_logic::SingleId::new(20, 0, 11, 10).unwrap(), 4u32);
+
+        let steps = [
+            Step::Shift {
+                axis: Axis::X,
+                z: 20,
+                d: 1,
+            },
+            Step::Merge {
+                input: 1,
+                policy: Policy::Sum,
+                default: 0,
+            },
+        ];
+        let result: SpatialIdTable<u32> = build(&[a, b], &steps).run().unwrap();
+        let values: Vec<(kasane_logic::FlexId, u32)> =
+            result.iter().map(|(id, v)| (id, *v)).collect();
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].1, 7);
+
+        let mut stats = Vec::new();
+        write_result(
+            build(&[result], &[]).run_working_tree().unwrap(),
+            Format::Stats,
+            Duration::ZERO,
+            &mut stats,
+        )
+        .unwrap();
+        let stats = String::from_utf8(stats).unwrap();
+        assert!(stats.contains("flex_ids: 1\n"), "{stats}");
+        assert!(stats.contains("value_max: 7\n"), "{stats}");
+    }
+
+    #[test]
+    fn steps_are_written_in_the_query_language() {
+        let steps = [
+            Step::Extrude {
+                axis: Axis::F,
+                z: 25,
+                start: -1,
+                end: 5,
+                policy: Policy::KeepExisting,
+            },
+            Step::Filter(ValuePredicate::NotInRange(
+                Bound::Unbounded,
+                Bound::Excluded(7),
+            )),
+            Step::Merge {
+                input: 1,
+                policy: Policy::Sum,
+                default: 0,
+            },
+        ];
+        let text = steps
+            .iter()
+            .fold(String::from("input0"), |query, step| step.apply(&query));
+        assert_eq!(
+            text,
+            "merge(Sum, input0 | extrude_f(25, -1, 5, KeepExisting) | filter_not_in(..7), input1, 0)"
+        );
 
-fn main() {
-    let json_str = fs::read_to_string("sample/bldg_risk.json").unwrap();
-    let table: SpatialIdTable<u32> = serde_json::from_str(&json_str).unwrap();
+        // 範囲外の引数はクエリ言語の解釈で弾かれ、実行前の検証で報告される。
+        let steps = [Step::Extrude {
+            axis: Axis::X,
+            z: 20,
+            start: -1,
+            end: 5,
+            policy: Policy::Max,
+        }];
+        assert!(build(&[SpatialIdTable::new()], &steps).validate().is_err());
+    }
 
-    let result = table.query().shift_x(25, 10).run().unwrap();
+    /// 書き込みの失敗は I/O エラーのまま返る
+    #[test]
+    fn write_failures_are_io_errors() {
+        struct Full;
+        impl Write for Full {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::StorageFull.into())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
 
-    fs::write("output.json", serde_json::to_string(&result).unwrap()).unwrap();
+        let mut table = SpatialIdTable::new();
+        table.insert(kasane_logic::SingleId::new(20, 0, 10, 10).unwrap(), 1u32);
+        for format in [Format::Json, Format::Text, Format::Stats] {
+            let result = build(&[table.clone()], &[]).run_working_tree().unwrap();
+            let e = write_result(result, format, Duration::ZERO, &mut Full).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::StorageFull, "{format:?}");
+        }
+    }
 }
@@ -0,0 +1,113 @@
+//! コレクションが覆う空間を、つながったまとまり（連結成分）ごとに分ける。
+//!
+//! 木の葉の [`FlexId`] をそのまま節点とし、指定したつながり方で接する葉どうしを同じ成分にまとめる。
+//! 葉の大きさが揃っていなくても、細かいズームレベルに割らずに判定する。時間は見ず、
+//! 時間を外した空間の形で判定する。
+
+use alloc::vec::Vec;
+use hashbrown::HashMap;
+
+use super::core::{FlexTreeCore, SafeValue};
+use crate::{Connectivity, FlexId, RangeId, SpatialId};
+
+/// 連結成分 1 つの統計。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentStats {
+    /// 成分の番号。0 から順に振る。
+    pub label: u32,
+    /// 成分を [`bounds`](Self::bounds) のズームレベルのボクセルに揃えたときの個数。
+    pub cell_count: u64,
+    /// 成分の体積（立方メートル）。
+    pub volume_m3: f64,
+    /// 成分を囲む最小の [`RangeId`]。ズームレベルは成分の中で最も細かいもの。
+    ///
+    /// X は巡回を考えず、最小のインデックスから最大のインデックスまでを取る。
+    pub bounds: RangeId,
+}
+
+/// 素集合の代表を、経路を縮めながら辿る。
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// `core` の空間を連結成分に分け、成分の番号を値に持つ木と成分ごとの統計を返す。
+///
+/// 番号は、木を走査して最初に現れた葉の順に 0 から振る。
+pub(crate) fn connected_components<V: SafeValue>(
+    core: &FlexTreeCore<V>,
+    connectivity: Connectivity,
+) -> (FlexTreeCore<u32>, Vec<ComponentStats>) {
+    // 時間違いで同じ場所にある葉を 1 つにまとめる。
+    let space: FlexTreeCore<()> = core
+        .iter_ref()
+        .map(|(id, _)| (id.without_time(), ()))
+        .collect();
+    let leaves: Vec<FlexId> = space.iter_ref().map(|(id, _)| id).collect();
+    let index: HashMap<FlexId, usize> = leaves.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+
+    let mut parent: Vec<usize> = (0..leaves.len()).collect();
+    for (i, leaf) in leaves.iter().enumerate() {
+        for (other, _) in space.get_overlapping_ref(leaf.touch_shell()) {
+            if other == *leaf || !leaf.touches(&other, connectivity) {
+                continue;
+            }
+            let (a, b) = (find(&mut parent, i), find(&mut parent, index[&other]));
+            if a != b {
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    // 根は成分の中で最も若い葉なので、根の現れた順に番号を振れば走査順になる。
+    let mut labels: HashMap<usize, u32> = HashMap::new();
+    let mut members: Vec<Vec<FlexId>> = Vec::new();
+    let mut labelled = Vec::with_capacity(leaves.len());
+    for (i, leaf) in leaves.iter().enumerate() {
+        let root = find(&mut parent, i);
+        let label = *labels.entry(root).or_insert_with(|| {
+            members.push(Vec::new());
+            (members.len() - 1) as u32
+        });
+        members[label as usize].push(*leaf);
+        labelled.push((*leaf, label));
+    }
+
+    let stats = members
+        .iter()
+        .enumerate()
+        .map(|(label, ids)| stats_of(label as u32, ids))
+        .collect();
+    (labelled.into_iter().collect(), stats)
+}
+
+/// 1 つの成分に属する葉 `ids` の統計。
+fn stats_of(label: u32, ids: &[FlexId]) -> ComponentStats {
+    let ranges: Vec<RangeId> = ids.iter().map(RangeId::from).collect();
+    let z = ranges.iter().map(|r| r.z()).max().unwrap_or(0);
+
+    let mut cell_count = 0u64;
+    let mut volume_m3 = 0.0;
+    let (mut f, mut x, mut y) = ([i32::MAX, i32::MIN], [u32::MAX, 0], [u32::MAX, 0]);
+    for (id, range) in ids.iter().zip(&ranges) {
+        let (f0, f1) = range.f_fine_range(z);
+        let (x0, x1) = range.x_fine_range(z);
+        let (y0, y1) = range.y_fine_range(z);
+        cell_count += (f1 - f0 + 1) as u64 * (x1 - x0 + 1) as u64 * (y1 - y0 + 1) as u64;
+        volume_m3 += id.length_f_meters() * id.length_x_meters() * id.length_y_meters();
+
+        f = [f[0].min(f0), f[1].max(f1)];
+        x = [x[0].min(x0), x[1].max(x1)];
+        y = [y[0].min(y0), y[1].max(y1)];
+    }
+
+    ComponentStats {
+        label,
+        cell_count,
+        volume_m3,
+        bounds: RangeId::new(z, f, x, y).expect("葉の範囲から求めた範囲は有効"),
+    }
+}
@@ -16,6 +16,7 @@ mod overlap;
 #[cfg(feature = "rayon")]
 pub(crate) mod parallel;
 pub(crate) mod ptr;
+pub(crate) mod shape;
 pub mod shard;
 pub(crate) mod walk;
 use ptr::{MaybeSend, MaybeSendSync, MaybeSync, SharedNode};
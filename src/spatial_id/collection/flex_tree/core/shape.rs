@@ -0,0 +1,195 @@
+//! 図形（[`SpatialPredicates`] を実装する型）による木の枝刈り走査。
+//!
+//! 枝ノードが表す [`FlexId`] と図形が交わらなければ、その部分木は丸ごと捨てる。
+//! ただし水平方向のズームレベルが [`MIN_PREDICATE_ZOOM`] より粗い枝は、判定のために割る手間の方が
+//! 大きいので判定せずに降りる。粗い葉の判定は [`SpatialPredicates`] が細かく割って行う。
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::convert::LeavesIterRef;
+use super::node::Node;
+use super::ptr::{SafeValue, SharedNode};
+use super::{FlexTreeCore, split_child_id};
+use crate::geometry::shape::convex::MIN_PREDICATE_ZOOM;
+use crate::spatial_id::collection::query::cancellation::CancellationToken;
+use crate::{Error, FlexId, Side, SpatialPredicates};
+
+/// 空間 3 軸のズームレベルが全て `max_z` 以上か。
+pub(crate) fn is_resolved(id: &FlexId, max_z: u8) -> bool {
+    id.f_zoomlevel() >= max_z && id.x_zoomlevel() >= max_z && id.y_zoomlevel() >= max_z
+}
+
+/// `id` のうち `shape` と交わる部分を `out` に加える。
+///
+/// `shape` に丸ごと含まれる部分はそのまま加え、境界にかかる部分だけを空間 3 軸がズームレベル
+/// `max_z` 以上になるまで最も粗い軸から順に二分する。交わらない側は捨てる。時間の範囲は `id` のまま保つ。
+pub(crate) fn clip_to_shape<P: SpatialPredicates>(
+    shape: &P,
+    id: FlexId,
+    max_z: u8,
+    out: &mut Vec<FlexId>,
+    token: &CancellationToken,
+) -> Result<(), Error> {
+    let mut stack = vec![id];
+    while let Some(id) = stack.pop() {
+        if token.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        if !shape.intersects(&id) {
+            continue;
+        }
+        if is_resolved(&id, max_z) || shape.contains(&id) {
+            out.push(id);
+            continue;
+        }
+        let zooms = [id.f_zoomlevel(), id.x_zoomlevel(), id.y_zoomlevel()];
+        let coarsest = (0..3).min_by_key(|&axis| zooms[axis]).unwrap();
+        for side in [Side::Upper, Side::Lower] {
+            let half = match coarsest {
+                0 => id.split_f(side),
+                1 => id.split_x(side),
+                _ => id.split_y(side),
+            };
+            stack.extend(half);
+        }
+    }
+    Ok(())
+}
+
+impl<V> FlexTreeCore<V>
+where
+    V: SafeValue,
+{
+    /// `shape` と交わる葉を、切り取らずにそのままの広さで返す。
+    ///
+    /// 空間 3 軸のズームレベルが `max_z` 以上の部分木は、その領域が `shape` と交われば
+    /// 中の葉を判定せずに全て返す。
+    pub fn shape_overlap_ref<P: SpatialPredicates>(
+        &self,
+        shape: &P,
+        max_z: u8,
+    ) -> Vec<(FlexId, &V)> {
+        let mut results = Vec::new();
+        let mut stack = self.root_node_stack();
+        while let Some((node, current_id)) = stack.pop() {
+            if let Node::Leaf { value: None } = node {
+                continue;
+            }
+            // 水平方向に粗い枝は、判定に割る手間の方が大きいので無条件に降りる
+            let coarse_branch = matches!(node, Node::Branch { .. })
+                && (current_id.x_zoomlevel() < MIN_PREDICATE_ZOOM
+                    || current_id.y_zoomlevel() < MIN_PREDICATE_ZOOM);
+            if !coarse_branch && !shape.intersects(&current_id) {
+                continue;
+            }
+            match node {
+                Node::Leaf { value: Some(v) } => results.push((current_id, v)),
+                Node::Branch { .. } if is_resolved(&current_id, max_z) => {
+                    results.extend(LeavesIterRef {
+                        stack: vec![(node, current_id)],
+                    });
+                }
+                Node::Branch {
+                    level,
+                    lower_child,
+                    upper_child,
+                    ..
+                } => {
+                    let axis = Node::<V>::axis(*level);
+                    stack.push((
+                        upper_child.as_ref(),
+                        split_child_id(&current_id, axis, Side::Upper),
+                    ));
+                    stack.push((
+                        lower_child.as_ref(),
+                        split_child_id(&current_id, axis, Side::Lower),
+                    ));
+                }
+                Node::Leaf { value: None } => unreachable!("空葉は上で除外済み"),
+            }
+        }
+        results
+    }
+
+    /// `shape` と交わる葉を丸ごとツリーから取り除き、そのままの広さで返す。
+    ///
+    /// `max_z` の扱いは [`shape_overlap_ref`](Self::shape_overlap_ref) と同じ。
+    pub fn shape_overlap_remove<P: SpatialPredicates>(
+        &mut self,
+        shape: &P,
+        max_z: u8,
+    ) -> Vec<(FlexId, V)> {
+        let mut removed = Vec::new();
+        for (root, root_id) in [
+            (&mut self.lower_root, FlexId::LOWER_MAX),
+            (&mut self.upper_root, FlexId::UPPER_MAX),
+        ] {
+            Self::prune_shape_mut(root, shape, max_z, root_id, &mut removed, &self.empty_leaf);
+        }
+        removed
+    }
+
+    /// `current_id` が指す部分木から `shape` と交わる葉を取り除く。
+    fn prune_shape_mut<P: SpatialPredicates>(
+        node: &mut SharedNode<Node<V>>,
+        shape: &P,
+        max_z: u8,
+        current_id: FlexId,
+        removed: &mut Vec<(FlexId, V)>,
+        empty_leaf: &SharedNode<Node<V>>,
+    ) {
+        if let Node::Leaf { value: None } = **node {
+            return;
+        }
+
+        let coarse_branch = matches!(**node, Node::Branch { .. })
+            && (current_id.x_zoomlevel() < MIN_PREDICATE_ZOOM
+                || current_id.y_zoomlevel() < MIN_PREDICATE_ZOOM);
+        if !coarse_branch && !shape.intersects(&current_id) {
+            return;
+        }
+
+        if matches!(**node, Node::Leaf { .. }) || is_resolved(&current_id, max_z) {
+            let leaves = LeavesIterRef {
+                stack: vec![(node.as_ref(), current_id)],
+            };
+            removed.extend(leaves.map(|(id, v)| (id, v.clone())));
+            *node = empty_leaf.clone();
+            return;
+        }
+
+        let replacement = {
+            let mut_node = SharedNode::make_mut(node);
+            let Node::Branch {
+                level,
+                lower_child,
+                upper_child,
+                leaf_count,
+                max_zoom,
+                split_mask,
+            } = mut_node
+            else {
+                unreachable!("葉は上で処理済み")
+            };
+
+            let axis = Node::<V>::axis(*level);
+            let upper_id = split_child_id(&current_id, axis, Side::Upper);
+            Self::prune_shape_mut(upper_child, shape, max_z, upper_id, removed, empty_leaf);
+            let lower_id = split_child_id(&current_id, axis, Side::Lower);
+            Self::prune_shape_mut(lower_child, shape, max_z, lower_id, removed, empty_leaf);
+
+            *leaf_count = (lower_child.leaf_count() + upper_child.leaf_count()) as u32;
+            *max_zoom = Node::<V>::fold_max_zoom(*level, lower_child, upper_child);
+            *split_mask = Node::<V>::fold_split_mask(*level, lower_child, upper_child);
+
+            Node::<V>::collapse_equal_children(lower_child, upper_child, *level, empty_leaf)
+        };
+
+        if let Some(rep) = replacement {
+            *node = rep;
+        } else if node.leaf_count() == 0 {
+            *node = empty_leaf.clone();
+        }
+    }
+}
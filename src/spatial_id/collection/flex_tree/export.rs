@@ -0,0 +1,287 @@
+//! コレクションの [`RangeId`] を、外部のビューアで眺めるための形式へ書き出す。
+//!
+//! - GeoJSON: 1 つの `RangeId` を、水平方向の外形（`Polygon`）と高度の範囲
+//!   （`altitude_min` / `altitude_max` プロパティ）を持つ `Feature` にする。
+//! - Wavefront OBJ: 1 つの `RangeId` を 1 つの直方体（8 頂点・6 面）にする。
+//!
+//! どちらも頂点は [`SpatialId::spatial_vertices`] から取る。
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::{Coordinate, Ecef, RangeId, SpatialId};
+
+/// `(RangeId, value)` の列を GeoJSON の `FeatureCollection` へ書き出す。
+///
+/// `value` が `Some` の `Feature` には `value` プロパティを付ける。
+#[cfg(feature = "json")]
+pub(crate) fn geojson(items: impl Iterator<Item = (RangeId, Option<serde_json::Value>)>) -> String {
+    use alloc::string::ToString;
+    use serde_json::{Map, Value, json};
+
+    let features: Vec<Value> = items
+        .map(|(range_id, value)| {
+            let vertices = range_id.spatial_vertices();
+            // spatial_vertices は [高度][緯度][経度] の順。緯度の添字 0 が北側なので、
+            // 北西 → 南西 → 南東 → 北東 と辿ると反時計回りになる。
+            let ring: Vec<Value> = [0, 2, 3, 1, 0]
+                .iter()
+                .map(|&i| json!([vertices[i].longitude(), vertices[i].latitude()]))
+                .collect();
+
+            let mut properties = Map::new();
+            properties.insert("id".into(), Value::String(format!("{range_id}")));
+            properties.insert("z".into(), json!(range_id.z()));
+            properties.insert("altitude_min".into(), json!(vertices[0].altitude()));
+            properties.insert("altitude_max".into(), json!(vertices[4].altitude()));
+            if let Some(value) = value {
+                properties.insert("value".into(), value);
+            }
+
+            json!({
+                "type": "Feature",
+                "geometry": { "type": "Polygon", "coordinates": [ring] },
+                "properties": properties,
+            })
+        })
+        .collect();
+
+    json!({ "type": "FeatureCollection", "features": features }).to_string()
+}
+
+/// `(RangeId, value_rank)` の列を Wavefront OBJ へ書き出す。
+///
+/// 座標は全 `RangeId` の中心の平均を原点とする局所座標（メートル）で、
+/// X が東、Y が上、Z が南の右手系。`value_rank` が `Some(n)` の直方体はグループ
+/// `value_{n}` に入れ、`values[n]` をそのグループの説明としてコメント行に書く。
+pub(crate) fn obj(
+    items: impl Iterator<Item = (RangeId, Option<usize>)>,
+    values: &[String],
+) -> String {
+    let items: Vec<(RangeId, Option<usize>)> = items.collect();
+    let mut out = String::new();
+    let _ = writeln!(out, "# kasane-logic: {} boxes", items.len());
+    if items.is_empty() {
+        return out;
+    }
+
+    let origin =
+        Coordinate::center_gravity(items.iter().map(|(range_id, _)| range_id.spatial_center()));
+    let frame = LocalFrame::new(origin);
+    let _ = writeln!(
+        out,
+        "# origin: latitude {} longitude {} altitude {}",
+        origin.latitude(),
+        origin.longitude(),
+        origin.altitude()
+    );
+    for (n, value) in values.iter().enumerate() {
+        let _ = writeln!(out, "# value_{n} = {value}");
+    }
+
+    for (i, (range_id, value_rank)) in items.iter().enumerate() {
+        let _ = writeln!(out, "o {range_id}");
+        if let Some(n) = value_rank {
+            let _ = writeln!(out, "g value_{n}");
+        }
+        // spatial_vertices の添字は bit0 が東、bit1 が南、bit2 が上。
+        for vertex in range_id.spatial_vertices() {
+            let [x, y, z] = frame.to_local(vertex);
+            let _ = writeln!(out, "v {x:.3} {y:.3} {z:.3}");
+        }
+        let base = i * 8 + 1;
+        for face in BOX_FACES {
+            let _ = writeln!(
+                out,
+                "f {} {} {} {}",
+                base + face[0],
+                base + face[1],
+                base + face[2],
+                base + face[3]
+            );
+        }
+    }
+    out
+}
+
+/// 直方体の 6 面。外から見て反時計回り。添字は bit0 = X、bit1 = Z、bit2 = Y。
+const BOX_FACES: [[usize; 4]; 6] = [
+    [1, 5, 7, 3], // +X
+    [0, 2, 6, 4], // -X
+    [4, 6, 7, 5], // +Y
+    [0, 1, 3, 2], // -Y
+    [2, 3, 7, 6], // +Z
+    [0, 4, 5, 1], // -Z
+];
+
+/// 原点での東・上・南の軸へ ECEF の差分を射影する。
+struct LocalFrame {
+    origin: Ecef,
+    east: [f64; 3],
+    north: [f64; 3],
+    up: [f64; 3],
+}
+
+impl LocalFrame {
+    fn new(origin: Coordinate) -> Self {
+        let (sin_lat, cos_lat) = libm::sincos(origin.latitude().to_radians());
+        let (sin_lon, cos_lon) = libm::sincos(origin.longitude().to_radians());
+        Self {
+            origin: origin.into(),
+            east: [-sin_lon, cos_lon, 0.0],
+            north: [-sin_lat * cos_lon, -sin_lat * sin_lon, cos_lat],
+            up: [cos_lat * cos_lon, cos_lat * sin_lon, sin_lat],
+        }
+    }
+
+    fn to_local(&self, point: Coordinate) -> [f64; 3] {
+        let point: Ecef = point.into();
+        let d = [
+            point.x() - self.origin.x(),
+            point.y() - self.origin.y(),
+            point.z() - self.origin.z(),
+        ];
+        let dot = |axis: &[f64; 3]| axis[0] * d[0] + axis[1] * d[1] + axis[2] * d[2];
+        [dot(&self.east), dot(&self.up), -dot(&self.north)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{SingleId, SpatialIdSet, SpatialIdTable};
+
+    /// OBJ の頂点と面を読み、面ごとの法線（外積）と頂点を返す。
+    fn parse_obj(obj: &str) -> (Vec<[f64; 3]>, Vec<[usize; 4]>) {
+        let mut vertices = Vec::new();
+        let mut faces = Vec::new();
+        for line in obj.lines() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("v") => {
+                    let v: Vec<f64> = words.map(|w| w.parse().unwrap()).collect();
+                    vertices.push([v[0], v[1], v[2]]);
+                }
+                Some("f") => {
+                    let f: Vec<usize> = words.map(|w| w.parse::<usize>().unwrap() - 1).collect();
+                    faces.push([f[0], f[1], f[2], f[3]]);
+                }
+                _ => {}
+            }
+        }
+        (vertices, faces)
+    }
+
+    #[test]
+    fn obj_boxes_face_outwards_in_a_y_up_frame() {
+        let mut set = SpatialIdSet::new();
+        set.insert(SingleId::new(22, 3, 3723000, 1651000).unwrap());
+
+        let (vertices, faces) = parse_obj(&set.to_obj());
+        assert_eq!(vertices.len(), 8);
+        assert_eq!(faces.len(), 6);
+
+        let centroid = vertices.iter().fold([0.0; 3], |acc, v| {
+            [
+                acc[0] + v[0] / 8.0,
+                acc[1] + v[1] / 8.0,
+                acc[2] + v[2] / 8.0,
+            ]
+        });
+        // 原点は中心なので、重心はほぼ 0。
+        assert!(
+            centroid.iter().all(|c| libm::fabs(*c) < 0.01),
+            "{centroid:?}"
+        );
+
+        let sub = |a: [f64; 3], b: [f64; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+        for face in &faces {
+            let [a, b, c] = [vertices[face[0]], vertices[face[1]], vertices[face[2]]];
+            let (u, v) = (sub(b, a), sub(c, a));
+            let normal = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            let outward = sub(a, centroid);
+            let dot: f64 = (0..3).map(|i| normal[i] * outward[i]).sum();
+            assert!(dot > 0.0, "face {face:?} points inwards");
+        }
+
+        // z22 の 1 Segment の高さは 8m。水平方向は東西・南北とも数 m の広がりを持つ。
+        let extent = |axis: usize| {
+            vertices.iter().map(|v| v[axis]).fold(f64::MIN, f64::max)
+                - vertices.iter().map(|v| v[axis]).fold(f64::MAX, f64::min)
+        };
+        assert!(libm::fabs(extent(1) - 8.0) < 0.01, "{}", extent(1));
+        assert!(extent(0) > 1.0 && extent(2) > 1.0);
+    }
+
+    #[test]
+    fn obj_of_empty_collection_has_no_geometry() {
+        let (vertices, faces) = parse_obj(&SpatialIdSet::new().to_obj());
+        assert!(vertices.is_empty() && faces.is_empty());
+    }
+
+    #[test]
+    fn obj_assigns_one_group_per_value() {
+        let mut table = SpatialIdTable::new();
+        table.insert(SingleId::new(20, 0, 10, 10).unwrap(), 'b');
+        table.insert(SingleId::new(20, 0, 12, 10).unwrap(), 'a');
+        table.insert(SingleId::new(20, 0, 14, 10).unwrap(), 'b');
+
+        let obj = table.to_obj();
+        assert!(obj.contains("# value_0 = 'a'\n# value_1 = 'b'\n"));
+        let groups: Vec<&str> = obj
+            .lines()
+            .filter_map(|line| line.strip_prefix("g "))
+            .collect();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups.iter().filter(|g| **g == "value_1").count(), 2);
+        // マテリアルファイルは書き出さないので、それを参照する行も書かない。
+        assert!(!obj.contains("usemtl") && !obj.contains("mtllib"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn geojson_footprints_are_counter_clockwise_with_altitude_range() {
+        use crate::{RangeId, SpatialId};
+
+        let mut table = SpatialIdTable::new();
+        let id = RangeId::new(21, [2, 3], [1862000, 1862001], [825000, 825000]).unwrap();
+        table.insert(id.clone(), 7u8);
+
+        let geojson: serde_json::Value =
+            serde_json::from_str(&table.to_geojson().unwrap()).unwrap();
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 1);
+
+        let properties = &features[0]["properties"];
+        assert_eq!(properties["id"], "21/2:3/1862000:1862001/825000");
+        assert_eq!(properties["value"], 7);
+        let vertices = id.spatial_vertices();
+        assert_eq!(properties["altitude_min"], vertices[0].altitude());
+        assert_eq!(properties["altitude_max"], vertices[7].altitude());
+        assert!(properties["altitude_min"].as_f64() < properties["altitude_max"].as_f64());
+
+        let ring = features[0]["geometry"]["coordinates"][0]
+            .as_array()
+            .unwrap();
+        assert_eq!(ring.len(), 5);
+        assert_eq!(ring[0], ring[4]);
+        // 靴紐公式の符号が正なら反時計回り。
+        let area2: f64 = (0..4)
+            .map(|i| {
+                let (x0, y0) = (ring[i][0].as_f64().unwrap(), ring[i][1].as_f64().unwrap());
+                let (x1, y1) = (
+                    ring[i + 1][0].as_f64().unwrap(),
+                    ring[i + 1][1].as_f64().unwrap(),
+                );
+                x0 * y1 - x1 * y0
+            })
+            .sum();
+        assert!(area2 > 0.0);
+    }
+}
@@ -20,14 +20,14 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{AllowedIntervals, FlexId, RangeId, SpatialId};
 
-const SCHEMA_URL: &str = "https://airbee-project.github.io/schemas/json/v1.0.json";
+pub(crate) const SCHEMA_URL: &str = "https://airbee-project.github.io/schemas/json/v1.0.json";
 
 /// 1つの空間IDを、スキーマの `spatialTemporalId` として書き出す／読み込む。
 ///
 /// `ref` は値ありコレクション（Table/Map）だけが使う、`data[].value` への添字。
-struct IdEntry {
-    range_id: RangeId,
-    r#ref: Option<usize>,
+pub(crate) struct IdEntry {
+    pub(crate) range_id: RangeId,
+    pub(crate) r#ref: Option<usize>,
 }
 
 fn serialize_pair<M, T>(map: &mut M, key: &'static str, pair: [T; 2]) -> Result<(), M::Error>
@@ -179,13 +179,13 @@ impl<'de> Deserialize<'de> for IdEntry {
 }
 
 #[derive(Serialize, Deserialize)]
-struct Meta {
+pub(crate) struct Meta {
     version: String,
     description: String,
 }
 
 impl Meta {
-    fn v1() -> Self {
+    pub(crate) fn v1() -> Self {
         Meta {
             version: "v1.0".to_string(),
             description: String::new(),
@@ -6,13 +6,20 @@
 //! 復元することもできるが、読むだけならその再構築コストは不要。[`ArchivedSpatialIdMap`] は
 //! archived バイト列を**直接走査**し、`&[u8]` を ZeroCopy で返す読み取り専用の窓口を提供する。
 
+use alloc::boxed::Box;
 use alloc::vec::Vec;
 
-use super::arena::{ArchivedArenaNode, ArchivedMapArena, EMPTY_LEAF, check_format};
+use super::arena::{
+    ArchivedArenaNode, ArchivedDictionary, ArchivedMapArena, EMPTY_LEAF, check_format, rebuild_core,
+};
+use crate::spatial_id::collection::flex_tree::core::SafeValue;
 use crate::spatial_id::collection::flex_tree::core::walk::{
     OverlapWalk, RangeOverlapWalk, TreeCursor,
 };
-use crate::{Error, FlexId};
+use crate::spatial_id::collection::query::cancellation::CancellationToken;
+use crate::spatial_id::collection::query::source::Source;
+use crate::spatial_id::collection::query::working::WorkingTree;
+use crate::{Error, FlexId, RangeId};
 
 /// バイト列を直接走査する ZeroCopy リーダ。`Arc` 木を再構築しない。
 ///
@@ -106,8 +113,49 @@ impl<'a> ArchivedSpatialIdMap<'a> {
     ///
     /// 葉は `target` で**切り取って**返す（検索が要求するのは要求範囲内のSegmentのため）。
     pub fn get_indexed(&self, target: &FlexId, mut visit: impl FnMut(FlexId, u32)) {
-        // F はズーム0で2Segmentしかないので、符号が属する側のルートだけを降りればよい。
-        let root = if target.f_index().is_negative() {
+        for (current_id, leaf) in OverlapWalk::new(alloc::vec![self.point_root(target)], *target) {
+            if let Some(packed) = leaf.leaf_value()
+                && let Some(clipped) = current_id.intersection(target)
+            {
+                visit(clipped, packed);
+            }
+        }
+    }
+
+    /// [`get_indexed`](Self::get_indexed) と異なり切り取りを行わず、`target` と重なった葉の
+    /// [`FlexId`] をそのままの広さで `visit(id, packed_value)` へ渡す。
+    pub fn get_overlapping_indexed(&self, target: &FlexId, mut visit: impl FnMut(FlexId, u32)) {
+        for (current_id, leaf) in OverlapWalk::new(alloc::vec![self.point_root(target)], *target) {
+            if let Some(packed) = leaf.leaf_value() {
+                visit(current_id, packed);
+            }
+        }
+    }
+
+    /// 値を持つ全ての葉について `visit(id, packed_value)` を呼ぶ。
+    ///
+    /// 木を丸ごと走査するので、コストは葉の数に比例する。
+    pub fn for_each_indexed(&self, mut visit: impl FnMut(FlexId, u32)) {
+        let roots = [
+            (self.inner.lower_root.to_native(), FlexId::LOWER_MAX),
+            (self.inner.upper_root.to_native(), FlexId::UPPER_MAX),
+        ];
+        for (idx, root_id) in roots {
+            for (current_id, leaf) in
+                OverlapWalk::new(alloc::vec![(self.cursor(idx), root_id)], root_id)
+            {
+                if let Some(packed) = leaf.leaf_value() {
+                    visit(current_id, packed);
+                }
+            }
+        }
+    }
+
+    /// 単一Segment `target` が属する側のルートを、走査開始点として返す。
+    ///
+    /// F はズーム0で2Segmentしかないので、符号が属する側のルートだけを降りればよい。
+    fn point_root(&self, target: &FlexId) -> (ArchivedCursor<'a>, FlexId) {
+        if target.f_index().is_negative() {
             (
                 self.cursor(self.inner.lower_root.to_native()),
                 FlexId::LOWER_MAX,
@@ -117,17 +165,14 @@ impl<'a> ArchivedSpatialIdMap<'a> {
                 self.cursor(self.inner.upper_root.to_native()),
                 FlexId::UPPER_MAX,
             )
-        };
-
-        for (current_id, leaf) in OverlapWalk::new(alloc::vec![root], *target) {
-            if let Some(packed) = leaf.leaf_value()
-                && let Some(clipped) = current_id.intersection(target)
-            {
-                visit(clipped, packed);
-            }
         }
     }
 
+    /// 値の辞書。`packed_value - 1` で引く。
+    pub(crate) fn dictionary(&self) -> &'a ArchivedDictionary {
+        &self.inner.dictionary
+    }
+
     /// [`get_indexed`](Self::get_indexed) が渡す辞書インデックス（1始まり）から実バイト列を引く。
     pub fn value_bytes(&self, packed: u32) -> &'a [u8] {
         self.inner.dictionary[(packed - 1) as usize].as_slice()
@@ -137,7 +182,19 @@ impl<'a> ArchivedSpatialIdMap<'a> {
     ///
     /// インメモリ側の `FlexTreeCore::range_overlap_ref` と同じ意味論で、葉は
     /// **切り取らずに**そのまま返す（クエリの入力源はSegment全体の値を必要とするため）。
-    pub fn get_range(&self, target: &crate::RangeId) -> Vec<(FlexId, &'a [u8])> {
+    pub fn get_range(&self, target: &RangeId) -> Vec<(FlexId, &'a [u8])> {
+        let mut out = Vec::new();
+        self.get_range_indexed(target, |id, packed| {
+            out.push((id, self.value_bytes(packed)))
+        });
+        out
+    }
+
+    /// [`get_range`](Self::get_range) の低レベル版。値をバイト列へ復元せず、
+    /// 辞書インデックス（1始まり）のまま `visit(id, packed_value)` へ渡す。
+    ///
+    /// 降りるのは `target` と重なる部分木だけで、それ以外のノードには触れない。
+    pub fn get_range_indexed(&self, target: &RangeId, mut visit: impl FnMut(FlexId, u32)) {
         // F はズーム0で 0（上半球）/ -1（下半球）の2Segmentしか無いので、
         // 範囲を半球ごとに割ってから、該当するルートだけを降りる。
         let mut roots = Vec::new();
@@ -168,12 +225,66 @@ impl<'a> ArchivedSpatialIdMap<'a> {
             }
         }
 
-        let mut out = Vec::new();
         for (id, leaf) in RangeOverlapWalk::new(roots) {
             if let Some(packed) = leaf.leaf_value() {
-                out.push((id, self.value_bytes(packed)));
+                visit(id, packed);
             }
         }
-        out
+    }
+
+    /// `bounds` と重なる葉だけを集めて作業木を組む。値は `unpack(packed_value)` で得る。
+    ///
+    /// [`Source::read_range_ids`] の共通実装。集合・テーブルのリーダもこれを使う。
+    pub(crate) fn read_range_ids_with<V: SafeValue>(
+        &self,
+        bounds: &[RangeId],
+        token: &CancellationToken,
+        unpack: impl Fn(u32) -> V,
+    ) -> Result<WorkingTree<V>, Error> {
+        let mut time_segments: Vec<(FlexId, V)> = Vec::new();
+        for b in bounds {
+            if token.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            self.get_range_indexed(b, |id, packed| time_segments.push((id, unpack(packed))));
+        }
+        Ok(time_segments.into_iter().collect())
+    }
+
+    /// アリーナ全体から作業木を組む。値は `unpack(辞書インデックス（0始まり）, 辞書)` で得る。
+    ///
+    /// 葉を平坦化して挿入し直すのではなく、アリーナの木の形をそのまま写す。
+    pub(crate) fn read_all_with<V: SafeValue>(
+        &self,
+        token: &CancellationToken,
+        unpack: impl Fn(usize, &ArchivedDictionary) -> V,
+    ) -> Result<WorkingTree<V>, Error> {
+        if token.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        Ok(WorkingTree::from_core(rebuild_core(self.inner, unpack)?))
+    }
+}
+
+/// バイト列を読みながらクエリの入力源になる。
+///
+/// [`read_range_ids`](Source::read_range_ids) は `bounds` と重なる部分木だけを降りるので、
+/// 巨大なバイト列でも [`SpatialIdMap::from_bytes`](crate::SpatialIdMap::from_bytes) で
+/// 全体を復元せずにクエリを実行できる。[`query`](Source::query) で [`Query`](crate::Query)
+/// へ載せるには、バイト列が `'static` で借用できる必要がある（メモリマップを
+/// リークさせる等）。
+impl<'a> Source for ArchivedSpatialIdMap<'a> {
+    type Value = Vec<u8>;
+
+    fn read_range_ids(
+        &self,
+        bounds: &[RangeId],
+        token: &CancellationToken,
+    ) -> Result<WorkingTree<Vec<u8>>, Error> {
+        self.read_range_ids_with(bounds, token, |packed| self.value_bytes(packed).to_vec())
+    }
+
+    fn read_all(self: Box<Self>, token: &CancellationToken) -> Result<WorkingTree<Vec<u8>>, Error> {
+        self.read_all_with(token, |idx, dictionary| dictionary[idx].to_vec())
     }
 }
@@ -15,10 +15,13 @@ use alloc::vec::Vec;
 use rkyv::{Archive, Deserialize, Serialize};
 
 use super::SpatialIdMap;
-use crate::spatial_id::collection::flex_tree::core::node::Node;
-use crate::spatial_id::collection::flex_tree::core::ptr::SharedNode;
+use crate::spatial_id::collection::flex_tree::core::node::{LEAF_LEVEL, Node};
+use crate::spatial_id::collection::flex_tree::core::ptr::{SafeValue, SharedNode};
 use crate::{Error, FlexId};
 
+/// archived 表現の値辞書（`MapArena::dictionary`）。
+pub(crate) type ArchivedDictionary = rkyv::vec::ArchivedVec<rkyv::vec::ArchivedVec<u8>>;
+
 /// 形式（バージョン・レイアウトフラグ）を検証する。
 ///
 /// バージョンとレイアウトフラグは独立に検証する。前者はスキーマ（`MapArena` /
@@ -87,7 +90,7 @@ pub const FORMAT_VERSION: u16 = 4;
 /// 枝分かれさせる必要が無い。
 ///
 /// - bit 0: `temporal_id` feature が有効（T 軸を持つ4軸レイアウト）。
-const LAYOUT_FLAGS: u8 = {
+pub(crate) const LAYOUT_FLAGS: u8 = {
     #[cfg(feature = "temporal_id")]
     {
         0b0000_0001
@@ -146,38 +149,19 @@ impl SpatialIdMap<Vec<u8>> {
     /// [`from_bytes`](Self::from_bytes) /
     /// [`ArchivedSpatialIdMap::access`](super::archived::ArchivedSpatialIdMap::access) が形式違いを検出できる。
     pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
-        let mut nodes: Vec<ArenaNode> = Vec::new();
-        let mut dictionary: Vec<Vec<u8>> = Vec::new();
         let mut value_to_idx: BTreeMap<Vec<u8>, u32> = BTreeMap::new();
-        let mut empty_idx: Option<u32> = None;
-
-        let lower_root = build_node(
-            &self.inner.lower_root,
-            &mut nodes,
-            &mut dictionary,
-            &mut value_to_idx,
-            &mut empty_idx,
-        );
-        let upper_root = build_node(
-            &self.inner.upper_root,
-            &mut nodes,
-            &mut dictionary,
-            &mut value_to_idx,
-            &mut empty_idx,
-        );
-
-        let arena = MapArena {
-            version: FORMAT_VERSION,
-            flags: LAYOUT_FLAGS,
-            lower_root,
-            upper_root,
-            shard: self.inner.shard,
-            nodes,
-            dictionary,
-        };
-        Ok(rkyv::to_bytes::<rkyv::rancor::Error>(&arena)
-            .map_err(|e| Error::Persist(alloc::format!("serialize: {e}")))?
-            .to_vec())
+        write_arena(&self.inner, Vec::new(), |v, dictionary| {
+            let dict_idx = match value_to_idx.get(v) {
+                Some(idx) => *idx,
+                None => {
+                    let idx = dictionary.len() as u32;
+                    dictionary.push(v.clone());
+                    value_to_idx.insert(v.clone(), idx);
+                    idx
+                }
+            };
+            dict_idx + 1
+        })
     }
 
     /// [`to_bytes`](Self::to_bytes) で直列化したバイト列から作業木（`Arc` ベース）を復元する。
@@ -189,41 +173,174 @@ impl SpatialIdMap<Vec<u8>> {
     /// # Safety
     /// `bytes` は [`SpatialIdMap::to_bytes`] が生成した正当なバイト列でなければならない。
     pub unsafe fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
-        let archived = unsafe { rkyv::access_unchecked::<ArchivedMapArena>(bytes) };
-        check_format(archived.version.to_native(), archived.flags)?;
-        // 所有型 `MapArena` へ復元してから木を組むと、ノード配列と値辞書を
-        // まるごと複製することになる。archived 表現を直接読んで組めばその一段が省ける。
-        let mut core = FlexTreeCore::<Vec<u8>>::new();
-        let empty = core.empty_leaf.clone();
-        core.lower_root = rebuild_node(
-            archived.lower_root.to_native(),
-            &archived.nodes,
-            &archived.dictionary,
-            &empty,
-        );
-        core.upper_root = rebuild_node(
-            archived.upper_root.to_native(),
-            &archived.nodes,
-            &archived.dictionary,
-            &empty,
-        );
-        core.shard = archived
-            .shard
-            .as_ref()
-            .map(rkyv::deserialize::<FlexId, rkyv::rancor::Error>)
-            .transpose()
-            .map_err(|e| Error::Persist(alloc::format!("deserialize shard: {e}")))?;
-
-        Ok(Self { inner: core })
+        let inner = unsafe { read_arena(bytes, |packed, dictionary| dictionary[packed].to_vec()) }?;
+        Ok(Self { inner })
+    }
+}
+
+/// 作業木 `core` をアリーナへ平坦化し、バイト列へ直列化する。
+///
+/// 値付きの葉ごとに `pack(値, 辞書)` を呼び、その葉に書く辞書インデックス（1始まり）を得る。
+/// `pack` は必要なら `dictionary` へ値のバイト列を積む。`dictionary` には事前に組んだ辞書を
+/// 渡してもよい（値の並び順を呼び出し側で決めたい [`SpatialIdTable`](crate::SpatialIdTable)
+/// のため）。
+pub(crate) fn write_arena<V: SafeValue>(
+    core: &FlexTreeCore<V>,
+    mut dictionary: Vec<Vec<u8>>,
+    mut pack: impl FnMut(&V, &mut Vec<Vec<u8>>) -> u32,
+) -> Result<Vec<u8>, Error> {
+    let mut nodes: Vec<ArenaNode> = Vec::new();
+    let mut empty_idx: Option<u32> = None;
+
+    let lower_root = build_node(
+        &core.lower_root,
+        &mut nodes,
+        &mut dictionary,
+        &mut pack,
+        &mut empty_idx,
+    );
+    let upper_root = build_node(
+        &core.upper_root,
+        &mut nodes,
+        &mut dictionary,
+        &mut pack,
+        &mut empty_idx,
+    );
+
+    let arena = MapArena {
+        version: FORMAT_VERSION,
+        flags: LAYOUT_FLAGS,
+        lower_root,
+        upper_root,
+        shard: core.shard,
+        nodes,
+        dictionary,
+    };
+    Ok(rkyv::to_bytes::<rkyv::rancor::Error>(&arena)
+        .map_err(|e| Error::Persist(alloc::format!("serialize: {e}")))?
+        .to_vec())
+}
+
+/// [`write_arena`] が書いたバイト列から作業木を復元する。
+///
+/// 値付きの葉ごとに `unpack(辞書インデックス（0始まり）, 辞書)` を呼び、葉の値を得る。
+/// 形式の検証は [`check_format`] に従う。
+///
+/// # Safety
+/// `bytes` は [`write_arena`] が生成した正当なバイト列でなければならない。
+pub(crate) unsafe fn read_arena<V: SafeValue>(
+    bytes: &[u8],
+    unpack: impl Fn(usize, &ArchivedDictionary) -> V,
+) -> Result<FlexTreeCore<V>, Error> {
+    let archived = unsafe { rkyv::access_unchecked::<ArchivedMapArena>(bytes) };
+    check_format(archived.version.to_native(), archived.flags)?;
+    rebuild_core(archived, unpack)
+}
+
+/// 信頼できないバイト列（ディスク上のファイルなど）を検査してから archived アリーナとして開く。
+///
+/// [`read_arena`] と異なり、rkyv の検査付きアクセス（bytecheck）でレイアウトを検証し、続けて
+/// [`check_format`] と [`check_nodes`] を通す。壊れた・細工されたバイト列でも未定義動作には
+/// ならず、[`Error::Persist`] などを返す。
+pub(crate) fn access_checked(bytes: &[u8]) -> Result<&ArchivedMapArena, Error> {
+    let archived = rkyv::access::<ArchivedMapArena, rkyv::rancor::Error>(bytes)
+        .map_err(|e| Error::Persist(alloc::format!("invalid arena: {e}")))?;
+    check_format(archived.version.to_native(), archived.flags)?;
+    check_nodes(archived)?;
+    Ok(archived)
+}
+
+/// ノード配列の参照関係が [`write_arena`] の書く形に収まっているかを確かめる。
+///
+/// 子は親より前にあり（後行順）、子の `level` は親より深く、`level` は葉の深さ未満で、
+/// 葉の値は辞書の範囲内にある。これが成り立てば [`rebuild_core`] は範囲外を読まず、必ず止まる。
+fn check_nodes(archived: &ArchivedMapArena) -> Result<(), Error> {
+    let invalid = |what: &str| Error::Persist(alloc::format!("invalid arena: {what}"));
+    let nodes = &archived.nodes;
+    for (i, node) in nodes.iter().enumerate() {
+        match node {
+            ArchivedArenaNode::Leaf { value } => {
+                if value.to_native() as usize > archived.dictionary.len() {
+                    return Err(invalid("leaf value outside the dictionary"));
+                }
+            }
+            ArchivedArenaNode::Branch {
+                level,
+                lower,
+                upper,
+            } => {
+                if *level >= LEAF_LEVEL {
+                    return Err(invalid("branch level out of range"));
+                }
+                for child in [lower.to_native(), upper.to_native()] {
+                    match nodes.get(child as usize) {
+                        Some(_) if child as usize >= i => {
+                            return Err(invalid("child node after its parent"));
+                        }
+                        Some(ArchivedArenaNode::Branch { level: deeper, .. })
+                            if *deeper <= *level =>
+                        {
+                            return Err(invalid("child level not below its parent"));
+                        }
+                        Some(_) => {}
+                        None => return Err(invalid("child node out of range")),
+                    }
+                }
+            }
+        }
+    }
+    for root in [
+        archived.lower_root.to_native(),
+        archived.upper_root.to_native(),
+    ] {
+        if root as usize >= nodes.len() {
+            return Err(invalid("root node out of range"));
+        }
     }
+    Ok(())
+}
+
+/// 検証済みの archived アリーナから作業木を組み直す。
+///
+/// [`read_arena`] と、ZeroCopy リーダから全体を読み出す経路（`Source::read_all`）で共有する。
+pub(crate) fn rebuild_core<V: SafeValue>(
+    archived: &ArchivedMapArena,
+    unpack: impl Fn(usize, &ArchivedDictionary) -> V,
+) -> Result<FlexTreeCore<V>, Error> {
+    // 所有型 `MapArena` へ復元してから木を組むと、ノード配列と値辞書を
+    // まるごと複製することになる。archived 表現を直接読んで組めばその一段が省ける。
+    let mut core = FlexTreeCore::<V>::new();
+    let empty = core.empty_leaf.clone();
+    core.lower_root = rebuild_node(
+        archived.lower_root.to_native(),
+        &archived.nodes,
+        &archived.dictionary,
+        &unpack,
+        &empty,
+    );
+    core.upper_root = rebuild_node(
+        archived.upper_root.to_native(),
+        &archived.nodes,
+        &archived.dictionary,
+        &unpack,
+        &empty,
+    );
+    core.shard = archived
+        .shard
+        .as_ref()
+        .map(rkyv::deserialize::<FlexId, rkyv::rancor::Error>)
+        .transpose()
+        .map_err(|e| Error::Persist(alloc::format!("deserialize shard: {e}")))?;
+
+    Ok(core)
 }
 
 /// 作業木の 1 ノードを後行順でアリーナへ書き出し、そのインデックスを返す。
-fn build_node(
-    node: &SharedNode<Node<Vec<u8>>>,
+fn build_node<V: SafeValue>(
+    node: &SharedNode<Node<V>>,
     nodes: &mut Vec<ArenaNode>,
     dictionary: &mut Vec<Vec<u8>>,
-    value_to_idx: &mut BTreeMap<Vec<u8>, u32>,
+    pack: &mut impl FnMut(&V, &mut Vec<Vec<u8>>) -> u32,
     empty_idx: &mut Option<u32>,
 ) -> u32 {
     match &**node {
@@ -238,19 +355,9 @@ fn build_node(
             }
         }
         Node::Leaf { value: Some(v) } => {
-            let dict_idx = match value_to_idx.get(v) {
-                Some(idx) => *idx,
-                None => {
-                    let idx = dictionary.len() as u32;
-                    dictionary.push(v.clone());
-                    value_to_idx.insert(v.clone(), idx);
-                    idx
-                }
-            };
+            let value = pack(v, dictionary);
             let i = nodes.len() as u32;
-            nodes.push(ArenaNode::Leaf {
-                value: dict_idx + 1,
-            });
+            nodes.push(ArenaNode::Leaf { value });
             i
         }
         Node::Branch {
@@ -259,8 +366,8 @@ fn build_node(
             upper_child,
             ..
         } => {
-            let lower = build_node(lower_child, nodes, dictionary, value_to_idx, empty_idx);
-            let upper = build_node(upper_child, nodes, dictionary, value_to_idx, empty_idx);
+            let lower = build_node(lower_child, nodes, dictionary, pack, empty_idx);
+            let upper = build_node(upper_child, nodes, dictionary, pack, empty_idx);
             let i = nodes.len() as u32;
             nodes.push(ArenaNode::Branch {
                 level: *level,
@@ -276,16 +383,17 @@ fn build_node(
 ///
 /// 所有型 `MapArena` を経由しないので、ノード配列と値辞書の複製が丸ごと省ける。
 /// 保存していない導出値（`leaf_count` / `max_zoom` / `split_mask`）はここで畳み直す。
-fn rebuild_node(
+fn rebuild_node<V: SafeValue>(
     idx: u32,
     nodes: &rkyv::vec::ArchivedVec<ArchivedArenaNode>,
-    dictionary: &rkyv::vec::ArchivedVec<rkyv::vec::ArchivedVec<u8>>,
-    empty: &SharedNode<Node<Vec<u8>>>,
-) -> SharedNode<Node<Vec<u8>>> {
+    dictionary: &ArchivedDictionary,
+    unpack: &impl Fn(usize, &ArchivedDictionary) -> V,
+    empty: &SharedNode<Node<V>>,
+) -> SharedNode<Node<V>> {
     match &nodes[idx as usize] {
         ArchivedArenaNode::Leaf { value } if value.to_native() == EMPTY_LEAF => empty.clone(),
         ArchivedArenaNode::Leaf { value } => SharedNode::new(Node::Leaf {
-            value: Some(dictionary[(value.to_native() - 1) as usize].to_vec()),
+            value: Some(unpack((value.to_native() - 1) as usize, dictionary)),
         }),
         ArchivedArenaNode::Branch {
             level,
@@ -293,11 +401,11 @@ fn rebuild_node(
             upper,
         } => {
             let level = *level;
-            let lower_child = rebuild_node(lower.to_native(), nodes, dictionary, empty);
-            let upper_child = rebuild_node(upper.to_native(), nodes, dictionary, empty);
+            let lower_child = rebuild_node(lower.to_native(), nodes, dictionary, unpack, empty);
+            let upper_child = rebuild_node(upper.to_native(), nodes, dictionary, unpack, empty);
             let leaf_count = (lower_child.leaf_count() + upper_child.leaf_count()) as u32;
-            let max_zoom = Node::<Vec<u8>>::fold_max_zoom(level, &lower_child, &upper_child);
-            let split_mask = Node::<Vec<u8>>::fold_split_mask(level, &lower_child, &upper_child);
+            let max_zoom = Node::<V>::fold_max_zoom(level, &lower_child, &upper_child);
+            let split_mask = Node::<V>::fold_split_mask(level, &lower_child, &upper_child);
             SharedNode::new(Node::Branch {
                 level,
                 leaf_count,
@@ -1,5 +1,5 @@
 use crate::spatial_id::collection::flex_tree::core::FlexTreeCore;
-use crate::{AllowedIntervals, FlexId, RangeId, SingleId, SpatialId};
+use crate::{AllowedIntervals, FlexId, RangeId, SingleId, SpatialId, SpatialPredicates};
 use alloc::vec::Vec;
 
 #[cfg(feature = "persist")]
@@ -53,6 +53,16 @@ where
         }
     }
 
+    /// 内部 [`FlexTreeCore`] からマップを組む（クエリ実行の出口変換用）。
+    pub(crate) fn from_core(inner: FlexTreeCore<V>) -> Self {
+        Self { inner }
+    }
+
+    /// 所有権ごと内部 [`FlexTreeCore`] を取り出す（クエリ実行の入口変換用）。
+    pub(crate) fn into_core(self) -> FlexTreeCore<V> {
+        self.inner
+    }
+
     /// シャード領域 `region` に閉じた空の[SpatialIdMap]を作成する。
     ///
     /// 以降は `region` の内側だけを保持する。`region` の外側への挿入は無視される。
@@ -96,6 +106,14 @@ where
         self.inner.remove(target.clone())
     }
 
+    /// 特定の範囲（RangeId）と交差するすべての領域と、その値への参照を切り取らずに返します。
+    pub fn get_range<'a>(
+        &'a self,
+        target: &'a crate::RangeId,
+    ) -> impl Iterator<Item = (FlexId, &'a V)> + 'a {
+        self.inner.range_overlap_ref(target)
+    }
+
     /// [`get`](Self::get) と異なり切り取りを行わず、target と重なった
     /// [`FlexId`]と値への参照をそのまま返します。
     pub fn get_overlapping<'a, S>(
@@ -114,6 +132,29 @@ where
         self.inner.remove_overlapping(target.clone())
     }
 
+    /// 図形と交わる[`FlexId`]と値への参照を、切り取らずにそのまま返します。
+    ///
+    /// 図形を空間IDへ変換せず、木を降りながら図形と交わらない部分木を捨てます。
+    /// 空間 3 軸のズームレベルが `max_z` 以上の部分木は、その領域が図形と交われば中を判定せずに全て返します。
+    pub fn get_overlapping_shape<P: SpatialPredicates>(
+        &self,
+        shape: &P,
+        max_z: u8,
+    ) -> impl Iterator<Item = (FlexId, &V)> + '_ {
+        self.inner.shape_overlap_ref(shape, max_z).into_iter()
+    }
+
+    /// 図形と交わる[`FlexId`]と値を、切り取らずにそのまま取り除いて返します。
+    ///
+    /// `max_z` の扱いは [`get_overlapping_shape`](Self::get_overlapping_shape) と同じです。
+    pub fn remove_overlapping_shape<P: SpatialPredicates>(
+        &mut self,
+        shape: &P,
+        max_z: u8,
+    ) -> Vec<(FlexId, V)> {
+        self.inner.shape_overlap_remove(shape, max_z)
+    }
+
     /// 指定した単体の空間 IDと面で接している[`FlexId`]と値への参照を重複なく返します。
     /// 入力された空間ID自身と重なる要素は除外します。
     pub fn neighbors_share_face<'a, S: SpatialId>(
@@ -1,4 +1,5 @@
 pub mod golden;
 pub mod par;
 pub mod rkyv;
+pub mod source;
 pub mod upsert;
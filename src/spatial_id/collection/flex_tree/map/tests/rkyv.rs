@@ -151,4 +151,65 @@ mod persist_tests {
             assert_eq!(got, expected, "base={base} count={count} で不一致");
         }
     }
+
+    /// `Source::read_range_ids` が、重なる bounds を渡しても bounds ごとの `get_range` の和と一致すること。
+    #[test]
+    fn archived_read_range_ids_matches_get_range() {
+        use crate::{CancellationToken, Source};
+
+        let mut map = SpatialIdMap::<Vec<u8>>::new();
+        for x in 400..408u32 {
+            map.insert(SingleId::new(20, 0, x, 400).unwrap(), alloc::vec![x as u8]);
+        }
+        map.insert(SingleId::new(18, -1, 100, 100).unwrap(), b"lower".to_vec());
+
+        let bytes = map.to_bytes().unwrap();
+        let archived = unsafe { ArchivedSpatialIdMap::access(&bytes).unwrap() };
+
+        let bounds = [
+            RangeId::new(20, [-1, 0], [400, 404], [400, 403]).unwrap(),
+            RangeId::new(20, [0, 0], [403, 407], [400, 400]).unwrap(),
+        ];
+        let mut got: Vec<_> = archived
+            .read_range_ids(&bounds, &CancellationToken::new())
+            .unwrap()
+            .into_iter()
+            .collect();
+        got.sort();
+
+        let mut expected: Vec<_> = bounds
+            .iter()
+            .flat_map(|b| {
+                archived
+                    .get_range(b)
+                    .into_iter()
+                    .map(|(id, v)| (id, v.to_vec()))
+            })
+            .collect();
+        expected.sort();
+        expected.dedup();
+
+        assert_eq!(got, expected);
+        // x=400..=407 の 8 Segmentと、下半球の粗いSegment 1 つ
+        assert_eq!(got.len(), 9);
+    }
+
+    /// 取り消し済みトークンでは読み出さずに `Cancelled` を返すこと。
+    #[test]
+    fn archived_read_range_ids_respects_cancellation() {
+        use crate::{CancellationToken, Error, Source};
+
+        let mut map = SpatialIdMap::<Vec<u8>>::new();
+        map.insert(SingleId::new(20, 0, 0, 0).unwrap(), b"a".to_vec());
+        let bytes = map.to_bytes().unwrap();
+        let archived = unsafe { ArchivedSpatialIdMap::access(&bytes).unwrap() };
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let bounds = [RangeId::new(20, [0, 0], [0, 0], [0, 0]).unwrap()];
+        assert!(matches!(
+            archived.read_range_ids(&bounds, &token),
+            Err(Error::Cancelled)
+        ));
+    }
 }
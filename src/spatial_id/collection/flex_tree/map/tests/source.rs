@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod source_tests {
+    use crate::{CancellationToken, RangeId, SingleId, Source, SpatialIdMap, SpatialIdSet};
+    use alloc::vec::Vec;
+
+    /// 葉の途中を切る bounds でも、Map は Set と同じく葉を切り取らずに読み出すこと。
+    #[test]
+    fn read_range_ids_does_not_clip_like_set() {
+        let coarse = SingleId::new(18, 0, 100, 100).unwrap();
+        let fine = SingleId::new(20, 0, 408, 400).unwrap();
+
+        let mut map = SpatialIdMap::new();
+        map.insert(coarse.clone(), 1u8);
+        map.insert(fine.clone(), 2u8);
+        let mut set = SpatialIdSet::new();
+        set.insert(coarse);
+        set.insert(fine);
+
+        // 粗いSegmentの一角だけにかかる bounds。
+        let bounds = [RangeId::new(20, [0, 0], [400, 401], [400, 400]).unwrap()];
+        let token = CancellationToken::new();
+
+        let mut from_map: Vec<_> = map
+            .read_range_ids(&bounds, &token)
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        from_map.sort();
+        let mut from_set: Vec<_> = set
+            .read_range_ids(&bounds, &token)
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        from_set.sort();
+
+        assert_eq!(from_map, from_set);
+        assert_eq!(from_map.len(), 1);
+    }
+}
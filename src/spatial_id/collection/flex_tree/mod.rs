@@ -1,8 +1,14 @@
 pub(crate) mod coalesce;
+pub mod components;
 pub(crate) mod core;
+pub(crate) mod export;
 #[cfg(feature = "json")]
 pub mod json;
 pub mod map;
+pub mod outline;
+pub mod path;
+pub mod ray;
 pub mod set;
 pub mod table;
 pub mod traits;
+pub mod wire;
@@ -0,0 +1,324 @@
+//! コレクションが覆う空間の境界を、図形として取り出す。
+//!
+//! 入力の [`RangeId`] を指定したズームレベルのボクセルに揃え、埋まったボクセルと空のボクセルの
+//! 境目を辿る。
+//!
+//! - 層ごとの外形: F を固定した層ごとに、辺で接するボクセルを 1 つの [`Polygon`] にまとめる。
+//!   囲まれた空のボクセルは穴になる。一直線に並ぶ頂点は省く。
+//! - 閉じた表面: 埋まったボクセルと空のボクセルが接する面を、向きと平面ごとに同じ方法で
+//!   [`Polygon`] にまとめ、[`Solid`] にする。隣り合う面が辺を共有するように、頂点はボクセルの
+//!   角ごとに残す。
+//!
+//! どちらも頂点で接するだけのボクセルは別の図形として扱う。
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+
+use crate::spatial_id::helpers;
+use crate::spatial_id::zoom_level::ZoomLevel;
+use crate::{Coordinate, Error, MultiPolygon, Polygon, RangeId, Solid};
+
+/// 同一点とみなす距離（メートル）。最大ズームレベルのボクセルの辺よりも十分短い。
+const EPSILON: f64 = 1e-6;
+
+/// 平面上の格子点、または格子の向き。
+type Point = (i64, i64);
+
+/// 外周と穴の周からなる、平面上の多角形。
+type Region = (Vec<Point>, Vec<Vec<Point>>);
+
+/// F を固定した 1 つの層の外形。
+#[derive(Debug, Clone)]
+pub struct LayerOutline {
+    /// 層のズームレベル。
+    pub z: u8,
+    /// 層の F インデックス。
+    pub f: i32,
+    /// 層の下端と上端の高度（メートル）。
+    pub altitude: [f64; 2],
+    /// 層の外形。各 [`Polygon`] は層の下端の高度に置かれ、上から見て外周は反時計回り、穴は時計回り。
+    pub polygons: MultiPolygon,
+}
+
+/// `ranges` をズームレベル `z` のボクセル `(f, x, y)` に揃える。
+///
+/// `z` より細かい [`RangeId`] は、それを含む `z` のボクセル全体として扱う。
+fn voxels(
+    z: u8,
+    ranges: impl IntoIterator<Item = RangeId>,
+) -> Result<BTreeSet<(i32, u32, u32)>, Error> {
+    let z = ZoomLevel::new(z)?.get();
+    let mut voxels = BTreeSet::new();
+    for range in ranges {
+        let range = if range.z() <= z {
+            range.spatial_children_at_zoom(z)?
+        } else {
+            range.spatial_parent_at_zoom(z)?
+        };
+        let ([f0, f1], [x0, x1], [y0, y1]) = (range.f(), range.x(), range.y());
+        for f in f0..=f1 {
+            for x in x0..=x1 {
+                for y in y0..=y1 {
+                    voxels.insert((f, x, y));
+                }
+            }
+        }
+    }
+    Ok(voxels)
+}
+
+/// ズームレベル `z` で `ranges` が覆う空間の、F の層ごとの外形を F の昇順に返す。
+pub(crate) fn layer_outlines(
+    z: u8,
+    ranges: impl IntoIterator<Item = RangeId>,
+) -> Result<Vec<LayerOutline>, Error> {
+    let mut layers: BTreeMap<i32, BTreeSet<Point>> = BTreeMap::new();
+    for (f, x, y) in voxels(z, ranges)? {
+        layers.entry(f).or_default().insert((x as i64, y as i64));
+    }
+
+    Ok(layers
+        .into_iter()
+        .map(|(f, cells)| {
+            let altitude = helpers::altitude(f as f64, z);
+            let to_coordinate = |&(x, y): &Point| lattice_coordinate(z, f as i64, x, y);
+            // (X, Y) 平面の反時計回りは、上から見ると時計回りになる
+            let ring = |points: &Vec<Point>| points.iter().rev().map(to_coordinate).collect();
+            let polygons = regions(&cells, true)
+                .iter()
+                .map(|(exterior, holes)| {
+                    Polygon::with_holes(ring(exterior), holes.iter().map(ring).collect(), EPSILON)
+                })
+                .collect();
+            LayerOutline {
+                z,
+                f,
+                altitude: [altitude, helpers::altitude(f as f64 + 1.0, z)],
+                polygons: MultiPolygon::new(polygons),
+            }
+        })
+        .collect())
+}
+
+/// ズームレベル `z` で `ranges` が覆う空間の表面を、閉じた [`Solid`] として返す。
+///
+/// 各面は外向きを表として、外から見て反時計回りに頂点を並べる。`ranges` が空なら `None` を返す。
+pub(crate) fn boundary_solid(
+    z: u8,
+    ranges: impl IntoIterator<Item = RangeId>,
+) -> Result<Option<Solid>, Error> {
+    let voxels = voxels(z, ranges)?;
+    if voxels.is_empty() {
+        return Ok(None);
+    }
+
+    // 面を (法線の軸, 平面の位置, 外向きか) ごとに集める。
+    // 軸 a の面は、残りの 2 軸を (a + 1, a + 2) の順に並べた平面上のマスとして持つ。
+    let mut faces: BTreeMap<(usize, i64, bool), BTreeSet<Point>> = BTreeMap::new();
+    let filled = |p: [i64; 3]| voxels.contains(&(p[0] as i32, p[1] as u32, p[2] as u32));
+    for &(f, x, y) in &voxels {
+        let p = [f as i64, x as i64, y as i64];
+        for axis in 0..3 {
+            let cell = (p[(axis + 1) % 3], p[(axis + 2) % 3]);
+            for positive in [false, true] {
+                let mut neighbor = p;
+                neighbor[axis] += if positive { 1 } else { -1 };
+                if !filled(neighbor) {
+                    let plane = if positive { p[axis] + 1 } else { p[axis] };
+                    faces
+                        .entry((axis, plane, positive))
+                        .or_default()
+                        .insert(cell);
+                }
+            }
+        }
+    }
+
+    let mut polygons = Vec::new();
+    for ((axis, plane, positive), cells) in faces {
+        let to_coordinate = |&(u, v): &Point| {
+            let mut p = [0; 3];
+            p[axis] = plane;
+            p[(axis + 1) % 3] = u;
+            p[(axis + 2) % 3] = v;
+            lattice_coordinate(z, p[0], p[1], p[2])
+        };
+        // (F, X, Y) は左手系なので、平面上の反時計回りの法線は軸の負の向きになる。
+        // 外向きが軸の正の向きの面は、周を逆に辿る。
+        let ring = |points: &Vec<Point>| -> Vec<Coordinate> {
+            if positive {
+                points.iter().rev().map(to_coordinate).collect()
+            } else {
+                points.iter().map(to_coordinate).collect()
+            }
+        };
+        for (exterior, holes) in regions(&cells, false) {
+            polygons.push(Polygon::with_holes(
+                ring(&exterior),
+                holes.iter().map(ring).collect(),
+                EPSILON,
+            ));
+        }
+    }
+    Solid::new(polygons, EPSILON).map(Some)
+}
+
+/// ズームレベル `z` の格子点 `(f, x, y)` の座標。
+fn lattice_coordinate(z: u8, f: i64, x: i64, y: i64) -> Coordinate {
+    let mut coordinate = Coordinate::default();
+    coordinate
+        .set_latitude(helpers::vertex_latitude(y as f64, z))
+        .expect("latitude must be within valid range");
+    coordinate
+        .set_longitude(helpers::longitude(x as f64, z))
+        .expect("longitude must be within valid range");
+    coordinate
+        .set_altitude(helpers::altitude(f as f64, z))
+        .expect("altitude must be within valid range");
+    coordinate
+}
+
+/// 平面上のマスの集まりを、辺で接するマスごとの多角形に分ける。
+///
+/// 外周は反時計回り、穴は時計回り。`simplify` が `true` なら一直線に並ぶ頂点を省く。
+fn regions(cells: &BTreeSet<Point>, simplify: bool) -> Vec<Region> {
+    let mut exteriors = Vec::new();
+    let mut holes = Vec::new();
+    for ring in rings(cells) {
+        let area = signed_area(&ring);
+        // 穴の内側の点として、周の最初の辺の左にあるマスの中心を 2 倍した座標を取る
+        let probe = {
+            let (p, q) = (ring[0], ring[1]);
+            let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+            (2 * p.0 + dx - dy, 2 * p.1 + dy + dx)
+        };
+        let ring = if simplify {
+            without_collinear(ring)
+        } else {
+            ring
+        };
+        if area > 0 {
+            exteriors.push((ring, area));
+        } else {
+            holes.push((ring, probe));
+        }
+    }
+
+    let mut regions: Vec<Region> = exteriors
+        .iter()
+        .map(|(ring, _)| (ring.clone(), Vec::new()))
+        .collect();
+    for (hole, probe) in holes {
+        // 穴の周に接するマスを含む外周のうち、最も小さいものが穴を持つ
+        let owner = exteriors
+            .iter()
+            .enumerate()
+            .filter(|(_, (ring, _))| contains_doubled(ring, probe))
+            .min_by_key(|(_, (_, area))| *area)
+            .map(|(i, _)| i);
+        if let Some(i) = owner {
+            regions[i].1.push(hole);
+        }
+    }
+    regions
+}
+
+/// マスの境界を、マスを左に見て辿った周の列にする。頂点はマスの角ごとに並ぶ。
+///
+/// 2 つのマスが頂点だけで接する点では、左に曲がって同じマスの周を辿り続ける。
+fn rings(cells: &BTreeSet<Point>) -> Vec<Vec<Point>> {
+    // 始点ごとの、境界の辺の向き
+    let mut edges: BTreeMap<Point, Vec<Point>> = BTreeMap::new();
+    for &(u, v) in cells {
+        let sides = [
+            ((u, v - 1), (u, v), (1, 0)),
+            ((u + 1, v), (u + 1, v), (0, 1)),
+            ((u, v + 1), (u + 1, v + 1), (-1, 0)),
+            ((u - 1, v), (u, v + 1), (0, -1)),
+        ];
+        for (neighbor, start, direction) in sides {
+            if !cells.contains(&neighbor) {
+                edges.entry(start).or_default().push(direction);
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    while let Some(mut entry) = edges.first_entry() {
+        let start = *entry.key();
+        let first = entry.get_mut().pop().expect("empty entries are removed");
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+
+        let mut ring = vec![start];
+        let mut at = (start.0 + first.0, start.1 + first.1);
+        let mut direction = first;
+        loop {
+            let left = (-direction.1, direction.0);
+            if at == start {
+                // 始点を頂点だけで接する別の周が通る場合は、まだ閉じない
+                let others_left = edges.get(&at).is_some_and(|d| d.contains(&left));
+                if first == left || !others_left {
+                    break;
+                }
+            }
+            let candidates = edges
+                .get_mut(&at)
+                .expect("boundary edges form closed rings");
+            let i = candidates
+                .iter()
+                .position(|d| *d == left)
+                .unwrap_or(candidates.len() - 1);
+            direction = candidates.swap_remove(i);
+            if candidates.is_empty() {
+                edges.remove(&at);
+            }
+            ring.push(at);
+            at = (at.0 + direction.0, at.1 + direction.1);
+        }
+        out.push(ring);
+    }
+    out
+}
+
+/// 一直線に並ぶ頂点を省く。
+fn without_collinear(ring: Vec<Point>) -> Vec<Point> {
+    let n = ring.len();
+    (0..n)
+        .filter(|&i| {
+            let (prev, at, next) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+            (at.0 - prev.0) * (next.1 - at.1) != (at.1 - prev.1) * (next.0 - at.0)
+        })
+        .map(|i| ring[i])
+        .collect()
+}
+
+/// 周の符号付き面積の 2 倍。反時計回りなら正。
+fn signed_area(ring: &[Point]) -> i64 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % n]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum()
+}
+
+/// 座標を 2 倍した点 `probe` が周 `ring` の内側にあるか。`probe` は周の上にないこと。
+fn contains_doubled(ring: &[Point], probe: Point) -> bool {
+    let n = ring.len();
+    let mut inside = false;
+    for i in 0..n {
+        let (a, b) = (ring[i], ring[(i + 1) % n]);
+        let (a, b) = ((2 * a.0, 2 * a.1), (2 * b.0, 2 * b.1));
+        if (a.1 > probe.1) != (b.1 > probe.1) {
+            // 辺が probe を通る水平線と交わる位置が probe より右か
+            let cross = (b.0 - a.0) * (probe.1 - a.1) - (probe.0 - a.0) * (b.1 - a.1);
+            if (cross > 0) == (b.1 > a.1) {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
@@ -0,0 +1,364 @@
+//! ボクセルの空き空間を通る最短経路を探す（A*）。
+//!
+//! 探索は 2 段で行う。
+//!
+//! - 粗い探索: 障害物とコストを指定したズームレベル以下の木にまとめ、その葉（空いた場所ほど大きい）を
+//!   節点として A* で辿り、目的地点の葉までの葉の列を求める。葉に入る位置は、直前の位置に最も近い葉の
+//!   中のボクセルとする。空いた空間は大きな葉 1 つで済むので、細かいボクセルを埋め尽くさない。
+//! - 細かい探索: 指定したズームレベルのボクセルを [`SingleId`] の `neighbors_share_*` で辿る A* で
+//!   最短経路を求める。ボクセルが通れるかは、それを含む葉で引く。まず粗い探索で辿った葉と、それに
+//!   接する通れる葉（回廊）の中だけで経路を求める。そのコストが直線距離に等しければそれ以上短い経路は
+//!   ないのでそのまま返し、そうでなければそのコストを上限に、見積もりが上限を超えるボクセルを
+//!   開かずに全域を探す。
+//!
+//! 距離はボクセルの中心どうしの直線距離（メートル）で測る。時間は見ない。
+
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use hashbrown::{HashMap, HashSet};
+
+use super::core::{FlexTreeCore, SafeValue};
+use crate::{
+    CancellationToken, Connectivity, Coordinate, Ecef, Error, FlexId, RangeId, SingleId, SpatialId,
+    Tube,
+};
+
+/// 見つかった経路。
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathPlan {
+    /// 出発地点から目的地点までに通るボクセル。両端を含む。
+    pub cells: Vec<SingleId>,
+    /// [`cells`](Self::cells) の中心を順に結んだ管。半径は通るボクセルの最も短い辺の半分。
+    pub tube: Tube,
+    /// 経路のコスト。隣のボクセルへ進むごとに、中心間の距離（メートル）に進んだ先のボクセルの重みを掛けて足す。
+    pub cost: f64,
+}
+
+/// 優先度付きキューの要素。`priority` の小さいものから取り出す。
+struct Open<T> {
+    priority: f64,
+    item: T,
+}
+
+impl<T> PartialEq for Open<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority.total_cmp(&other.priority) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Open<T> {}
+
+impl<T> PartialOrd for Open<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Open<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.total_cmp(&self.priority)
+    }
+}
+
+/// ボクセルの中心の ECEF 座標。
+fn center(cell: &SingleId) -> Ecef {
+    cell.spatial_center().into()
+}
+
+/// ズームレベル `z` 以下に揃えた、1 メートル進むごとの重みの木。
+///
+/// 全域を重み 1 とし、`core` の空間には `weight` の値（1 未満は 1）を重ねる。通れない空間は無限大。
+/// `z` より細かい空間は、それを含む `z` のボクセル全体に広げ、重なった重みは大きい方を取る。
+fn weights<V, W>(core: &FlexTreeCore<V>, z: u8, weight: W) -> Result<FlexTreeCore<f64>, Error>
+where
+    V: SafeValue,
+    W: Fn(&V) -> Option<f64>,
+{
+    let mut weights = FlexTreeCore::new();
+    weights.insert(FlexId::UPPER_MAX, 1.0);
+    weights.insert(FlexId::LOWER_MAX, 1.0);
+    let heavier = |a: &f64, b: &f64| a.max(*b);
+    for (id, value) in core.iter_ref() {
+        let w = match weight(value) {
+            Some(w) if !w.is_nan() => w.max(1.0),
+            _ => f64::INFINITY,
+        };
+        let id = id.without_time().spatial_parent_at_zoom(z)?;
+        weights.insert_with(id, w, &heavier);
+    }
+    Ok(weights)
+}
+
+/// 葉 `leaf` を含むボクセルの範囲（ズームレベル `z`）。
+fn leaf_box(leaf: &FlexId, z: u8) -> ([i32; 2], [u32; 2], [u32; 2]) {
+    let range = RangeId::from(leaf);
+    let (f0, f1) = range.f_fine_range(z);
+    let (x0, x1) = range.x_fine_range(z);
+    let (y0, y1) = range.y_fine_range(z);
+    ([f0, f1], [x0, x1], [y0, y1])
+}
+
+/// 葉 `leaf` の中で `from` に最も近いボクセル。X は巡回を考えて近い方の端を取る。
+fn nearest_in(leaf: &FlexId, from: &SingleId) -> SingleId {
+    let z = from.z();
+    let (f, x, y) = leaf_box(leaf, z);
+    let width = 1i64 << z;
+    let x = if (x[0]..=x[1]).contains(&from.x()) {
+        from.x()
+    } else {
+        let gap = |to: u32| (to as i64 - from.x() as i64).rem_euclid(width);
+        // 東へ x[0] まで進むか、西へ x[1] まで戻るかの近い方。
+        if gap(x[0]) <= width - gap(x[1]) {
+            x[0]
+        } else {
+            x[1]
+        }
+    };
+    SingleId::new(z, from.f().clamp(f[0], f[1]), x, from.y().clamp(y[0], y[1]))
+        .expect("葉の範囲内のボクセル")
+}
+
+/// 葉 `leaf` と `connectivity` で接する、通れる葉と重み。
+fn neighbor_leaves(
+    weights: &FlexTreeCore<f64>,
+    leaf: &FlexId,
+    connectivity: Connectivity,
+) -> Vec<(FlexId, f64)> {
+    weights
+        .get_overlapping_ref(leaf.touch_shell())
+        .filter(|(other, w)| other != leaf && w.is_finite() && leaf.touches(other, connectivity))
+        .map(|(other, w)| (other, *w))
+        .collect()
+}
+
+/// ボクセル `cell` を含む葉と重み。
+fn leaf_of(weights: &FlexTreeCore<f64>, cell: &SingleId) -> (FlexId, f64) {
+    weights
+        .get_overlapping_ref(cell.clone())
+        .next()
+        .map(|(leaf, w)| (leaf, *w))
+        .expect("重みの木は全域を覆う")
+}
+
+/// `connectivity` で接するボクセル。
+fn neighbor_cells(cell: &SingleId, connectivity: Connectivity) -> Vec<SingleId> {
+    let mut out: Vec<SingleId> = cell.neighbors_share_face().collect();
+    if matches!(connectivity, Connectivity::Edge | Connectivity::Vertex) {
+        out.extend(cell.neighbors_share_edge());
+    }
+    if connectivity == Connectivity::Vertex {
+        out.extend(cell.neighbors_share_vertex());
+    }
+    out
+}
+
+/// 粗い探索。出発地点の葉から目的地点の葉へ通れる葉を辿り、細かい探索に使う回廊を返す。
+///
+/// 回廊は辿った葉と、それらに接する通れる葉の集合。届かなければ `None` を返す。
+/// 葉はズームレベル `z` にそろっているので、接する葉どうしは `z` のボクセルでもつながっている。
+/// そのため、回廊の中だけを探す細かい探索も必ず届く。
+fn corridor(
+    weights: &FlexTreeCore<f64>,
+    start: &SingleId,
+    goal: &SingleId,
+    connectivity: Connectivity,
+    token: &CancellationToken,
+) -> Result<Option<HashSet<FlexId>>, Error> {
+    let (start_leaf, _) = leaf_of(weights, start);
+    let (goal_leaf, _) = leaf_of(weights, goal);
+    let goal_center = center(goal);
+
+    // 葉ごとに、最良のコストとその時に入った位置、直前の葉を持つ。
+    let mut best: HashMap<FlexId, (f64, SingleId, Option<FlexId>)> = HashMap::new();
+    let mut open = BinaryHeap::new();
+    best.insert(start_leaf, (0.0, start.clone(), None));
+    open.push(Open {
+        priority: center(start).distance(&goal_center),
+        item: (start_leaf, 0.0),
+    });
+
+    let mut counter = 0u32;
+    while let Some(Open {
+        item: (leaf, g), ..
+    }) = open.pop()
+    {
+        token.check_amortized(&mut counter)?;
+        let (best_g, entry, _) = best[&leaf].clone();
+        if g > best_g {
+            continue;
+        }
+        if leaf == goal_leaf {
+            let mut corridor = HashSet::new();
+            let mut cursor = Some(leaf);
+            while let Some(on_path) = cursor {
+                corridor.insert(on_path);
+                corridor.extend(
+                    neighbor_leaves(weights, &on_path, connectivity)
+                        .into_iter()
+                        .map(|(next, _)| next),
+                );
+                cursor = best[&on_path].2;
+            }
+            return Ok(Some(corridor));
+        }
+
+        let from = center(&entry);
+        let (_, w_here) = leaf_of(weights, &entry);
+        for (next, w_next) in neighbor_leaves(weights, &leaf, connectivity) {
+            let to = nearest_in(&next, &entry);
+            let to_center = center(&to);
+            let next_g = g + from.distance(&to_center) * w_here.max(w_next);
+            if best
+                .get(&next)
+                .is_some_and(|(known, _, _)| *known <= next_g)
+            {
+                continue;
+            }
+            best.insert(next, (next_g, to, Some(leaf)));
+            open.push(Open {
+                priority: next_g + to_center.distance(&goal_center),
+                item: (next, next_g),
+            });
+        }
+    }
+    Ok(None)
+}
+
+/// 細かい探索。`start` から `goal` へ進むボクセルの列とコストを返す。
+///
+/// `corridor` を渡せば、その葉の中だけを通る。コストと目的地点までの直線距離の和が `bound` を
+/// 超えるボクセルは開かない。
+///
+/// 距離はボクセルの中心どうしの直線距離で、重みは 1 以上なので、目的地点までの直線距離を
+/// 見積もりに使えば最短経路が求まる。
+fn fine_route(
+    weights: &FlexTreeCore<f64>,
+    corridor: Option<&HashSet<FlexId>>,
+    bound: f64,
+    start: &SingleId,
+    goal: &SingleId,
+    connectivity: Connectivity,
+    token: &CancellationToken,
+) -> Result<Option<(Vec<SingleId>, f64)>, Error> {
+    let goal_center = center(goal);
+    let mut best: HashMap<SingleId, (f64, Option<SingleId>)> = HashMap::new();
+    let mut open = BinaryHeap::new();
+    best.insert(start.clone(), (0.0, None));
+    open.push(Open {
+        priority: center(start).distance(&goal_center),
+        item: (start.clone(), 0.0),
+    });
+
+    let mut counter = 0u32;
+    while let Some(Open {
+        item: (cell, g), ..
+    }) = open.pop()
+    {
+        token.check_amortized(&mut counter)?;
+        if g > best[&cell].0 {
+            continue;
+        }
+        if cell == *goal {
+            let mut cells = Vec::from([cell.clone()]);
+            let mut cursor = cell;
+            while let Some((_, Some(prev))) = best.get(&cursor) {
+                cells.push(prev.clone());
+                cursor = prev.clone();
+            }
+            cells.reverse();
+            return Ok(Some((cells, g)));
+        }
+
+        let here = center(&cell);
+        for next in neighbor_cells(&cell, connectivity) {
+            let (leaf, w) = leaf_of(weights, &next);
+            if !w.is_finite() || corridor.is_some_and(|c| !c.contains(&leaf)) {
+                continue;
+            }
+            let there = center(&next);
+            let next_g = g + here.distance(&there) * w;
+            let priority = next_g + there.distance(&goal_center);
+            if priority > bound || best.get(&next).is_some_and(|(known, _)| *known <= next_g) {
+                continue;
+            }
+            best.insert(next.clone(), (next_g, Some(cell.clone())));
+            open.push(Open {
+                priority,
+                item: (next, next_g),
+            });
+        }
+    }
+    Ok(None)
+}
+
+/// `core` の空間を `weight` で重み付けした空間で、`start` から `goal` までの経路を探す。
+///
+/// `weight` が `None`・NaN・無限大を返す空間は通れない。出発地点か目的地点が通れない場合や、
+/// 経路がない場合は `None` を返す。
+pub(crate) fn find_path<V, W>(
+    core: &FlexTreeCore<V>,
+    weight: W,
+    start: &Coordinate,
+    goal: &Coordinate,
+    z: u8,
+    connectivity: Connectivity,
+    token: &CancellationToken,
+) -> Result<Option<PathPlan>, Error>
+where
+    V: SafeValue,
+    W: Fn(&V) -> Option<f64>,
+{
+    if token.is_cancelled() {
+        return Err(Error::Cancelled);
+    }
+    let start = start.single_id(z)?;
+    let goal = goal.single_id(z)?;
+    let weights = weights(core, z, weight)?;
+    if !leaf_of(&weights, &start).1.is_finite() || !leaf_of(&weights, &goal).1.is_finite() {
+        return Ok(None);
+    }
+
+    let Some(corridor) = corridor(&weights, &start, &goal, connectivity, token)? else {
+        return Ok(None);
+    };
+    let search = |corridor, bound| {
+        fine_route(
+            &weights,
+            corridor,
+            bound,
+            &start,
+            &goal,
+            connectivity,
+            token,
+        )
+    };
+    let Some(mut route) = search(Some(&corridor), f64::INFINITY)? else {
+        return Ok(None);
+    };
+    // 浮動小数点の足し合わせ順の違いで、回廊の経路そのものを上限で落とさないよう少し緩める。
+    let bound = route.1 * (1.0 + 1e-9);
+    // 直線距離と同じなら、回廊の経路より短い経路はない。
+    let straight = center(&start).distance(&center(&goal));
+    if route.1 > straight * (1.0 + 1e-9)
+        && let Some(shorter) = search(None, bound)?
+    {
+        route = shorter;
+    }
+    let (cells, cost) = route;
+    let radius_m = cells
+        .iter()
+        .map(|c| {
+            c.length_f_meters()
+                .min(c.length_x_meters())
+                .min(c.length_y_meters())
+        })
+        .fold(f64::INFINITY, f64::min)
+        / 2.0;
+    let points = cells.iter().map(|c| c.spatial_center()).collect();
+    Ok(Some(PathPlan {
+        tube: Tube::new(points, radius_m)?,
+        cells,
+        cost,
+    }))
+}
@@ -0,0 +1,286 @@
+//! 線分に沿って障害物を探し（レイキャスト）、観測点から見える空間（可視域）を求める。
+//!
+//! 線分は [`Line`](crate::Line) と同じく ECEF 上の直線をいくつかの区間に分け、区間ごとに
+//! インデックス空間の直線として 3 次元の DDA で辿る。現在のボクセルを含み障害物と重ならない
+//! 最も粗い親ボクセルを求め、その出口まで 1 歩で進むので、空いた空間は大きな単位で飛ばせる。
+//! 時間は見ない。
+
+use alloc::vec::Vec;
+
+use super::core::{FlexTreeCore, SafeValue};
+use crate::geometry::constants::WGS84_A;
+use crate::geometry::shape::line::impls::waypoints;
+use crate::geometry::traits::CoverRangeIds;
+use crate::spatial_id::zoom_level::ZoomLevel;
+use crate::{
+    CancellationToken, Coordinate, Ecef, Error, FlexId, FractionalId, RangeId, SingleId, SpatialId,
+    Sphere,
+};
+
+/// 線分が最初に当たった障害物。
+#[derive(Debug, Clone, PartialEq)]
+pub struct RayHit {
+    /// 当たった空間ID（木に格納されている広さのまま）。
+    pub id: FlexId,
+    /// 当たった位置を含む、指定したズームレベルのボクセル。
+    pub cell: SingleId,
+    /// 始点から [`cell`](Self::cell) に入った位置までの距離（メートル）。始点が障害物の中なら 0。
+    pub distance_m: f64,
+}
+
+/// `cell` を含み、木のどの葉とも重ならない最も粗い親ボクセル。`cell` 自身が重なれば、重なった葉を返す。
+fn empty_block<'a, V: SafeValue>(
+    core: &'a FlexTreeCore<V>,
+    cell: &SingleId,
+) -> Result<SingleId, (FlexId, &'a V)> {
+    let overlapping = |id: &SingleId| core.range_overlap_ref(&RangeId::from(id.clone())).next();
+    if let Some(hit) = overlapping(cell) {
+        return Err(hit);
+    }
+    // 親が空なら子も空なので、空になる最も粗いズームレベルを二分探索する。
+    let parent = |z: u8| {
+        cell.spatial_parent_at_zoom(z)
+            .expect("自身より粗いズームレベル")
+    };
+    let (mut lo, mut hi) = (0, cell.z());
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if overlapping(&parent(mid)).is_none() {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    Ok(parent(hi))
+}
+
+/// 区間 `a` → `b` を辿り、最初に当たった葉と値、当たったボクセル、そこへ入った位置を返す。
+fn cast_segment<V: SafeValue>(
+    core: &FlexTreeCore<V>,
+    z: u8,
+    a: Coordinate,
+    b: Coordinate,
+) -> Result<Option<(FlexId, &V, SingleId, Ecef)>, Error> {
+    let zoom = ZoomLevel::new(z)?;
+    let width = 1i64 << z;
+    let (pa, pb) = (a.fractional_id(z)?, b.fractional_id(z)?);
+    let p0 = [pa.f(), pa.x(), pa.y()];
+    let mut p1 = [pb.f(), pb.x(), pb.y()];
+    // X は巡回するので、近い向きへ辿る。以降の X は巻き戻さない値で持つ。
+    if p1[1] - p0[1] > width as f64 / 2.0 {
+        p1[1] -= width as f64;
+    } else if p0[1] - p1[1] > width as f64 / 2.0 {
+        p1[1] += width as f64;
+    }
+    let d = [p1[0] - p0[0], p1[1] - p0[1], p1[2] - p0[2]];
+    let at = |t: f64| [p0[0] + d[0] * t, p0[1] + d[1] * t, p0[2] + d[2] * t];
+
+    let (f_min, f_max) = (zoom.f_min() as i64, zoom.f_max() as i64);
+    // 南端の緯度や高度の上端ちょうどの点は、範囲の最後のボクセルに入れる。
+    let mut cell = p0.map(|v| libm::floor(v) as i64);
+    cell[0] = cell[0].clamp(f_min, f_max);
+    cell[2] = cell[2].clamp(0, width - 1);
+    let mut t = 0.0;
+    loop {
+        if !(f_min..=f_max).contains(&cell[0]) || !(0..width).contains(&cell[2]) {
+            return Ok(None);
+        }
+        let id = SingleId::new(
+            z,
+            cell[0] as i32,
+            cell[1].rem_euclid(width) as u32,
+            cell[2] as u32,
+        )?;
+        let block = match empty_block(core, &id) {
+            Ok(block) => block,
+            Err((leaf, value)) => {
+                // 区間の始点で当たった場合は、丸め誤差を避けて始点そのものを返す。
+                let entry = if t == 0.0 {
+                    a.into()
+                } else {
+                    let p = at(t);
+                    let w = width as f64;
+                    FractionalId::new(
+                        z,
+                        p[0].clamp(f_min as f64, (f_max + 1) as f64),
+                        p[1] - libm::floor(p[1] / w) * w,
+                        p[2].clamp(0.0, width as f64),
+                    )?
+                    .into()
+                };
+                return Ok(Some((leaf, value, id, entry)));
+            }
+        };
+
+        // 空の親ボクセルの範囲 [lo, hi)。X は現在のボクセルと同じ巻き方にそろえる。
+        let scale = 1i64 << (z - block.z());
+        let lo = [
+            block.f() as i64 * scale,
+            cell[1] - (id.x() as i64 - block.x() as i64 * scale),
+            block.y() as i64 * scale,
+        ];
+        let hi = lo.map(|v| v + scale);
+        let (mut exit, mut axis) = (f64::INFINITY, 0);
+        for i in 0..3 {
+            let bound = if d[i] > 0.0 {
+                (hi[i] as f64 - p0[i]) / d[i]
+            } else if d[i] < 0.0 {
+                (lo[i] as f64 - p0[i]) / d[i]
+            } else {
+                continue;
+            };
+            if bound < exit {
+                exit = bound;
+                axis = i;
+            }
+        }
+        if exit >= 1.0 {
+            return Ok(None);
+        }
+
+        // 出口の面の向こうのボクセルへ進む。他の軸は丸め誤差で親ボクセルからはみ出さないようにする。
+        t = exit.max(t);
+        let p = at(t);
+        for i in 0..3 {
+            cell[i] = if i != axis {
+                (libm::floor(p[i]) as i64).clamp(lo[i], hi[i] - 1)
+            } else if d[i] > 0.0 {
+                hi[i]
+            } else {
+                lo[i] - 1
+            };
+        }
+    }
+}
+
+/// `from` から `to` への線分がズームレベル `z` で最初に当たる葉と、その値を返す。
+pub(crate) fn ray_cast<'a, V: SafeValue>(
+    core: &'a FlexTreeCore<V>,
+    from: &Coordinate,
+    to: &Coordinate,
+    z: u8,
+) -> Result<Option<(RayHit, &'a V)>, Error> {
+    let origin: Ecef = (*from).into();
+    let mut points = waypoints(z, *from, *to)?;
+    // 分けた点は ECEF から戻した座標なので、始点だけは渡された座標に戻しておく。
+    points[0] = *from;
+    for pair in points.windows(2) {
+        if let Some((id, value, cell, entry)) = cast_segment(core, z, pair[0], pair[1])? {
+            let hit = RayHit {
+                id,
+                cell,
+                distance_m: origin.distance(&entry),
+            };
+            return Ok(Some((hit, value)));
+        }
+    }
+    Ok(None)
+}
+
+/// 観測点のボクセル `eye` と `range` を囲む範囲を広げたもの。
+///
+/// 観測点から `range` の中へ引いた線分はこの中を通る。地球の丸みで、長さ `L` の線分は端点より
+/// 最大で約 `L² / 8R` 低い高度を通るので、F はその分のボクセル数に 1 を足して広げる。X・Y は
+/// 1 ボクセル広げる。
+fn hull(eye: &SingleId, range: &RangeId) -> Result<RangeId, Error> {
+    let zoom = ZoomLevel::new(range.z())?;
+    let (f, x, y) = (range.f(), range.x(), range.y());
+    let reach_m = Ecef::from(eye.spatial_center()).distance(&Ecef::from(range.spatial_center()))
+        + libm::hypot(
+            range.length_f_meters(),
+            libm::hypot(range.length_x_meters(), range.length_y_meters()),
+        ) / 2.0;
+    let dip_m = reach_m * reach_m / (8.0 * WGS84_A);
+    let pad = libm::ceil(dip_m / eye.length_f_meters()) as i64 + 1;
+    let f = [
+        (f[0].min(eye.f()) as i64 - pad).max(zoom.f_min() as i64) as i32,
+        (f[1].max(eye.f()) as i64 + pad).min(zoom.f_max() as i64) as i32,
+    ];
+    let xy = |[lo, hi]: [u32; 2], at: u32| {
+        [
+            lo.min(at).saturating_sub(1),
+            hi.max(at).saturating_add(1).min(zoom.xy_max()),
+        ]
+    };
+    RangeId::new(range.z(), f, xy(x, eye.x()), xy(y, eye.y()))
+}
+
+/// `range` を最も長い軸で 2 つに分ける。1 ボクセルなら `None`。
+fn split(range: &RangeId) -> Option<(RangeId, RangeId)> {
+    let (f, x, y) = (range.f(), range.x(), range.y());
+    let lengths = [
+        f[1] as i64 - f[0] as i64,
+        x[1] as i64 - x[0] as i64,
+        y[1] as i64 - y[0] as i64,
+    ];
+    let axis = (0..3).max_by_key(|&i| lengths[i])?;
+    if lengths[axis] <= 0 {
+        return None;
+    }
+    let (mut lower, mut upper) = (range.clone(), range.clone());
+    match axis {
+        0 => {
+            let mid = ((f[0] as i64 + f[1] as i64).div_euclid(2)) as i32;
+            lower.set_f([f[0], mid]).ok()?;
+            upper.set_f([mid + 1, f[1]]).ok()?;
+        }
+        1 => {
+            let mid = x[0] + (x[1] - x[0]) / 2;
+            lower.set_x([x[0], mid]).ok()?;
+            upper.set_x([mid + 1, x[1]]).ok()?;
+        }
+        _ => {
+            let mid = y[0] + (y[1] - y[0]) / 2;
+            lower.set_y([y[0], mid]).ok()?;
+            upper.set_y([mid + 1, y[1]]).ok()?;
+        }
+    }
+    Some((lower, upper))
+}
+
+/// `observer` を中心とする半径 `radius_m` の球に掛かるズームレベル `z` のボクセルのうち、
+/// 観測点から見えるものを集める。
+///
+/// ボクセルの中心へ引いた線分が、そのボクセルより手前で障害物に当たらなければ見えるとする。
+/// 障害物のボクセル自身も、手前が空いていれば見える。観測点とのあいだに障害物がない範囲は、
+/// 線分を引かずにまとめて見えるものとする。
+pub(crate) fn viewshed<V: SafeValue>(
+    core: &FlexTreeCore<V>,
+    observer: &Coordinate,
+    radius_m: f64,
+    z: u8,
+    token: &CancellationToken,
+) -> Result<FlexTreeCore<()>, Error> {
+    if token.is_cancelled() {
+        return Err(Error::Cancelled);
+    }
+    let eye = observer.single_id(z)?;
+    let mut stack: Vec<RangeId> = Sphere::new(*observer, radius_m)?
+        .cover_range_ids(z)?
+        .collect();
+    let mut visible = FlexTreeCore::new();
+
+    let mut counter = 0u32;
+    while let Some(range) = stack.pop() {
+        token.check_amortized(&mut counter)?;
+        if core
+            .range_overlap_ref(&hull(&eye, &range)?)
+            .next()
+            .is_none()
+        {
+            visible.insert(range, ());
+            continue;
+        }
+        if let Some((lower, upper)) = split(&range) {
+            stack.push(lower);
+            stack.push(upper);
+            continue;
+        }
+        let cell = SingleId::new(z, range.f()[0], range.x()[0], range.y()[0])?;
+        let hit = ray_cast(core, observer, &cell.spatial_center(), z)?;
+        if hit.is_none_or(|(hit, _)| hit.cell == cell) {
+            visible.insert(cell, ());
+        }
+    }
+    Ok(visible)
+}
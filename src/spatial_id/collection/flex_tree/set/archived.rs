@@ -0,0 +1,106 @@
+//! [`SpatialIdSet`](crate::SpatialIdSet) のバイト列（[`super::arena`]）を、作業木を再構築せず
+//! 直接読む ZeroCopy リーダ。
+//!
+//! 走査は [`ArchivedSpatialIdMap`] に委ね、値（辞書インデックス）を捨てて [`FlexId`] だけを返す。
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use hashbrown::HashSet;
+
+use crate::spatial_id::collection::flex_tree::map::archived::ArchivedSpatialIdMap;
+use crate::spatial_id::collection::query::cancellation::CancellationToken;
+use crate::spatial_id::collection::query::source::Source;
+use crate::spatial_id::collection::query::working::WorkingTree;
+use crate::{Error, FlexId, RangeId, SpatialId};
+
+/// [`SpatialIdSet::to_bytes`](crate::SpatialIdSet::to_bytes) が生成したバイト列に対する、
+/// 読み取り専用の ZeroCopy リーダ。
+///
+/// 書き込みが必要なら [`SpatialIdSet::from_bytes`](crate::SpatialIdSet::from_bytes) で
+/// 作業木へ復元すること。
+pub struct ArchivedSpatialIdSet<'a> {
+    inner: ArchivedSpatialIdMap<'a>,
+}
+
+impl<'a> ArchivedSpatialIdSet<'a> {
+    /// archived バイト列上にリーダを開く。
+    ///
+    /// 検証の範囲は [`ArchivedSpatialIdMap::access`] と同じで、形式バージョンと
+    /// レイアウトフラグだけを確かめる。
+    ///
+    /// # Safety
+    /// `bytes` は [`crate::SpatialIdSet::to_bytes`] などフラットアリーナ形式の書き込み口が
+    /// 生成した正当なバイト列でなければならない。
+    pub unsafe fn access(bytes: &'a [u8]) -> Result<Self, Error> {
+        Ok(Self {
+            inner: unsafe { ArchivedSpatialIdMap::access(bytes) }?,
+        })
+    }
+
+    /// このバイト列に書かれている形式バージョン。
+    pub fn format_version(&self) -> u16 {
+        self.inner.format_version()
+    }
+
+    /// 指定した空間IDと重なる空間IDを切り出して返す。
+    ///
+    /// インメモリの [`SpatialIdSet::get`](crate::SpatialIdSet::get) と同じ意味論。
+    pub fn get<S: SpatialId>(&self, target: &S) -> Vec<FlexId> {
+        let mut out = Vec::new();
+        for flex_id in target.clone() {
+            self.inner.get_indexed(&flex_id, |id, _| out.push(id));
+        }
+        out
+    }
+
+    /// 指定した空間IDと接触していたすべての空間IDを、切り取らずにそのまま返す。
+    ///
+    /// インメモリの [`SpatialIdSet::get_overlapping`](crate::SpatialIdSet::get_overlapping)
+    /// と同じ意味論で、`target` が複数のSegmentに分かれていても同じ葉は1度だけ返す。
+    pub fn get_overlapping<S: SpatialId>(&self, target: &S) -> Vec<FlexId> {
+        let mut seen = HashSet::new();
+        let mut out = Vec::new();
+        for flex_id in target.clone() {
+            self.inner.get_overlapping_indexed(&flex_id, |id, _| {
+                if seen.insert(id) {
+                    out.push(id);
+                }
+            });
+        }
+        out
+    }
+
+    /// `target`（範囲）と重なる空間IDを、切り取らずにそのまま返す。
+    pub fn get_range(&self, target: &RangeId) -> Vec<FlexId> {
+        let mut out = Vec::new();
+        self.inner.get_range_indexed(target, |id, _| out.push(id));
+        out
+    }
+
+    /// 保持している全ての [`FlexId`] を返す。コストは葉の数に比例する。
+    pub fn flex_ids(&self) -> Vec<FlexId> {
+        let mut out = Vec::new();
+        self.inner.for_each_indexed(|id, _| out.push(id));
+        out
+    }
+}
+
+/// バイト列を読みながらクエリの入力源になる。
+///
+/// 意味論は [`SpatialIdSet`](crate::SpatialIdSet) の [`Source`] 実装と同じで、
+/// [`read_range_ids`](Source::read_range_ids) は `bounds` と重なる部分木だけを降りる。
+impl<'a> Source for ArchivedSpatialIdSet<'a> {
+    type Value = ();
+
+    fn read_range_ids(
+        &self,
+        bounds: &[RangeId],
+        token: &CancellationToken,
+    ) -> Result<WorkingTree<()>, Error> {
+        self.inner.read_range_ids_with(bounds, token, |_| ())
+    }
+
+    fn read_all(self: Box<Self>, token: &CancellationToken) -> Result<WorkingTree<()>, Error> {
+        self.inner.read_all_with(token, |_, _| ())
+    }
+}
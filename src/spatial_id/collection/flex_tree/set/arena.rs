@@ -0,0 +1,57 @@
+//! [`SpatialIdSet`] のバイト列表現と、その write / read 側変換。
+//!
+//! 形式は [`SpatialIdMap`](crate::SpatialIdMap) と同じフラットアリーナ
+//! （[`map::arena`](crate::spatial_id::collection::flex_tree::map::arena)）をそのまま使う。
+//! 集合は値を持たないので、辞書は空のバイト列 1 件だけで、値付きの葉はすべてそれを指す。
+//!
+//! 形式が同じなので、[`SpatialIdMap::to_bytes`](crate::SpatialIdMap::to_bytes) が書いた
+//! バイト列も集合として読める（値は捨てられ、占有の有無だけが残る）。
+//! バイト列を作業木に戻さず直接読む口は [`super::archived`] を参照。
+
+use alloc::vec::Vec;
+
+use super::SpatialIdSet;
+use crate::Error;
+use crate::spatial_id::collection::flex_tree::map::arena::{read_arena, write_arena};
+
+impl SpatialIdSet {
+    /// この [`SpatialIdSet`] をフラットアリーナ形式のバイト列へ直列化する。
+    ///
+    /// 形式は [`SpatialIdMap::to_bytes`](crate::SpatialIdMap::to_bytes) と同じで、
+    /// 先頭の [`FORMAT_VERSION`](crate::FORMAT_VERSION) とレイアウトフラグも同様に埋め込む。
+    ///
+    /// # 動作例
+    ///
+    /// 直列化して復元する:
+    /// ```
+    /// # use kasane_logic::{SingleId, SpatialIdSet};
+    /// let mut set = SpatialIdSet::new();
+    /// set.insert(SingleId::new(20, 0, 10, 10).unwrap());
+    ///
+    /// let bytes = set.to_bytes().unwrap();
+    /// let restored = unsafe { SpatialIdSet::from_bytes(&bytes) }.unwrap();
+    /// assert_eq!(set, restored);
+    /// ```
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        // 占有を表す値は 1 種類だけなので、辞書も 1 件で足りる。
+        let dictionary = if self.inner.is_empty() {
+            Vec::new()
+        } else {
+            alloc::vec![Vec::new()]
+        };
+        write_arena(&self.inner, dictionary, |_, _| 1)
+    }
+
+    /// [`to_bytes`](Self::to_bytes) で直列化したバイト列から作業木を復元する。
+    ///
+    /// 形式バージョンやレイアウトフラグが異なる場合のエラーは
+    /// [`SpatialIdMap::from_bytes`](crate::SpatialIdMap::from_bytes) と同じ。
+    ///
+    /// # Safety
+    /// `bytes` は [`SpatialIdSet::to_bytes`] などフラットアリーナ形式の書き込み口が生成した
+    /// 正当なバイト列でなければならない。
+    pub unsafe fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let inner = unsafe { read_arena(bytes, |_, _| ()) }?;
+        Ok(Self { inner })
+    }
+}
@@ -0,0 +1,37 @@
+//! [`SpatialIdSet`] の空間を連結成分に分ける。
+
+use alloc::vec::Vec;
+
+use super::super::components::{self, ComponentStats};
+use crate::{Connectivity, SpatialIdSet, SpatialIdTable};
+
+impl SpatialIdSet {
+    /// 集合の空間を、`connectivity` のつながり方で接するまとまりごとに分ける。
+    ///
+    /// 成分の番号を値に持つ [`SpatialIdTable`] と、番号順に並べた成分ごとの統計を返す。
+    /// ズームレベルの違う空間IDが混ざっていても、細かいズームレベルに割らずに判定する。
+    /// 時間は見ず、時間を外した空間の形で判定する。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 離れた 2 つの障害物
+    /// ```
+    /// # use kasane_logic::{Connectivity, RangeId, SingleId, SpatialIdSet};
+    /// let mut set = SpatialIdSet::new();
+    /// set.insert(RangeId::new(20, [0, 0], [931000, 931002], [412000, 412000]).unwrap());
+    /// set.insert(SingleId::new(20, 0, 931005, 412000).unwrap());
+    ///
+    /// let (labels, stats) = set.connected_components(Connectivity::Face);
+    /// assert_eq!(stats.len(), 2);
+    /// assert_eq!(stats[0].cell_count, 3);
+    /// assert_eq!(stats[1].cell_count, 1);
+    /// assert_eq!(labels.get(&SingleId::new(20, 0, 931005, 412000).unwrap()).next().map(|(_, v)| *v), Some(1));
+    /// ```
+    pub fn connected_components(
+        &self,
+        connectivity: Connectivity,
+    ) -> (SpatialIdTable<u32>, Vec<ComponentStats>) {
+        let (labels, stats) = components::connected_components(&self.inner, connectivity);
+        (labels.iter().collect(), stats)
+    }
+}
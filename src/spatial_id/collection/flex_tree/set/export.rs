@@ -0,0 +1,39 @@
+//! [`SpatialIdSet`] の可視化用の書き出し。
+
+use alloc::string::String;
+
+use super::super::export;
+use crate::SpatialIdSet;
+
+impl SpatialIdSet {
+    /// [`range_ids`](Self::range_ids) を GeoJSON の `FeatureCollection` として書き出す。
+    ///
+    /// 各 `Feature` は水平方向の外形を `Polygon` で持ち、プロパティ `id`（文字列表現）・`z`・
+    /// `altitude_min`・`altitude_max`（メートル）を持つ。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 集合を GeoJSON にする
+    /// ```
+    /// # use kasane_logic::{RangeId, SpatialIdSet};
+    /// let mut set = SpatialIdSet::new();
+    /// set.insert(RangeId::new(20, [0, 1], [931000, 931001], [412000, 412000]).unwrap());
+    ///
+    /// let geojson = set.to_geojson();
+    /// assert!(geojson.contains(r#""type":"FeatureCollection""#));
+    /// assert!(geojson.contains(r#""id":"20/0:1/931000:931001/412000""#));
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn to_geojson(&self) -> String {
+        export::geojson(self.range_ids().map(|range_id| (range_id, None)))
+    }
+
+    /// [`range_ids`](Self::range_ids) を Wavefront OBJ として書き出す。1 つの `RangeId` が
+    /// 1 つの直方体（オブジェクト名は `RangeId` の文字列表現）になる。
+    ///
+    /// 座標は全 `RangeId` の中心の平均を原点とする局所座標（メートル）で、
+    /// X が東、Y が上、Z が南。原点の緯度経度はコメント行に書く。
+    pub fn to_obj(&self) -> String {
+        export::obj(self.range_ids().map(|range_id| (range_id, None)), &[])
+    }
+}
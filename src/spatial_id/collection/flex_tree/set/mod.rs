@@ -1,13 +1,25 @@
 use crate::spatial_id::collection::flex_tree::core::FlexTreeCore;
-use crate::{AllowedIntervals, FlexId, RangeId, SingleId, SpatialId};
+use crate::{AllowedIntervals, FlexId, RangeId, SingleId, SpatialId, SpatialPredicates};
 use alloc::vec::Vec;
+#[cfg(feature = "persist")]
+pub mod archived;
+#[cfg(feature = "persist")]
+pub mod arena;
+pub mod components;
 pub mod convert;
+pub mod export;
 pub mod impls;
 #[cfg(feature = "json")]
 pub mod json;
 pub mod ops;
+pub mod outline;
+pub mod path;
+pub mod ray;
 pub mod shard;
 pub mod tests;
+#[cfg(feature = "std")]
+pub mod text;
+pub mod wire;
 
 /// 空間IDの集合を表す型。
 ///
@@ -20,6 +32,11 @@ pub mod tests;
 /// - 空間ごとに値を持たせたい場合は [`SpatialIdMap`](crate::SpatialIdMap) を使用する。
 /// - 値から空間を引きたい、または値の管理（重複排除など）が必要な場合は
 ///   [`SpatialIdTable`](crate::SpatialIdTable) を使用する。
+///
+/// # 永続化
+/// `persist` feature 有効時は `to_bytes` / `from_bytes` で
+/// [`SpatialIdMap`](crate::SpatialIdMap) と同じフラットアリーナ形式のバイト列と相互変換でき、
+/// `ArchivedSpatialIdSet` でそのバイト列を復元せず直接読める。
 #[derive(Default, Clone, Debug)]
 pub struct SpatialIdSet {
     inner: FlexTreeCore<()>,
@@ -148,6 +165,36 @@ impl SpatialIdSet {
             .collect()
     }
 
+    /// 図形と交わるすべての空間IDを、切り取らずにそのまま返す。
+    ///
+    /// 図形を空間IDへ変換せず、木を降りながら図形と交わらない部分木を捨てる。
+    /// 空間 3 軸のズームレベルが `max_z` 以上の部分木は、その領域が図形と交われば中を判定せずに全て返す。
+    pub fn get_overlapping_shape<P: SpatialPredicates>(
+        &self,
+        shape: &P,
+        max_z: u8,
+    ) -> impl Iterator<Item = FlexId> + '_ {
+        self.inner
+            .shape_overlap_ref(shape, max_z)
+            .into_iter()
+            .map(|(flex_id, _value)| flex_id)
+    }
+
+    /// 図形と交わるすべての空間IDを削除する。削除した空間IDを返す。
+    ///
+    /// `max_z` の扱いは [`get_overlapping_shape`](Self::get_overlapping_shape) と同じ。
+    pub fn remove_overlapping_shape<P: SpatialPredicates>(
+        &mut self,
+        shape: &P,
+        max_z: u8,
+    ) -> Vec<FlexId> {
+        self.inner
+            .shape_overlap_remove(shape, max_z)
+            .into_iter()
+            .map(|(flex_id, _value)| flex_id)
+            .collect()
+    }
+
     /// 指定した単体の空間 IDと面で接している[`FlexId`] を重複なく返す。入力された空間ID自身と重なる空間IDは除外する。
     pub fn neighbors_share_face<S: SpatialId>(
         &self,
@@ -0,0 +1,56 @@
+//! [`SpatialIdSet`] が覆う空間の境界を図形として取り出す。
+
+use alloc::vec::Vec;
+
+use super::super::outline::{self, LayerOutline};
+use crate::{Error, Solid, SpatialIdSet};
+
+impl SpatialIdSet {
+    /// ズームレベル `z` のボクセルに揃えたときの、F の層ごとの外形を F の昇順に返す。
+    ///
+    /// 層の中で辺を共有するボクセルは 1 つの [`Polygon`](crate::Polygon) にまとまり、
+    /// 囲まれた空のボクセルは穴になる。頂点だけで接するボクセルは別の [`Polygon`](crate::Polygon) になる。
+    /// `z` より細かい空間IDは、それを含む `z` のボクセル全体として扱う。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 中央が空いた 3×3 のボクセル
+    /// ```
+    /// # use kasane_logic::{RangeId, SingleId, SpatialIdSet};
+    /// let mut set = SpatialIdSet::new();
+    /// set.insert(RangeId::new(20, [0, 0], [931000, 931002], [412000, 412002]).unwrap());
+    /// set.remove(&SingleId::new(20, 0, 931001, 412001).unwrap());
+    ///
+    /// let layers = set.layer_outlines(20).unwrap();
+    /// assert_eq!(layers.len(), 1);
+    /// let polygons = layers[0].polygons.polygons();
+    /// assert_eq!(polygons.len(), 1);
+    /// assert_eq!(polygons[0].vertices().len(), 4);
+    /// assert_eq!(polygons[0].holes().len(), 1);
+    /// ```
+    pub fn layer_outlines(&self, z: u8) -> Result<Vec<LayerOutline>, Error> {
+        outline::layer_outlines(z, self.range_ids())
+    }
+
+    /// ズームレベル `z` のボクセルに揃えたときの表面を、閉じた [`Solid`] として返す。集合が空なら `None` を返す。
+    ///
+    /// 埋まったボクセルと空のボクセルが接する面を、同じ平面・同じ向きのものどうし
+    /// 1 つの [`Polygon`](crate::Polygon) にまとめる。各面は外から見て反時計回りで、
+    /// 三角形の網が必要なら [`Solid::triangles`] で分割できる。隣り合う面が辺を共有するように、
+    /// 頂点はボクセルの角ごとに残す。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 直方体の表面
+    /// ```
+    /// # use kasane_logic::{ExpandPolygons, RangeId, SpatialIdSet};
+    /// let mut set = SpatialIdSet::new();
+    /// set.insert(RangeId::new(20, [0, 1], [931000, 931002], [412000, 412000]).unwrap());
+    ///
+    /// let solid = set.boundary_solid(20).unwrap().unwrap();
+    /// assert_eq!(solid.expand_polygons().count(), 6);
+    /// ```
+    pub fn boundary_solid(&self, z: u8) -> Result<Option<Solid>, Error> {
+        outline::boundary_solid(z, self.range_ids())
+    }
+}
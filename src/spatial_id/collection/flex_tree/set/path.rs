@@ -0,0 +1,49 @@
+//! [`SpatialIdSet`] を障害物として、空き空間を通る経路を探す。
+
+use super::super::path::{self, PathPlan};
+use crate::{CancellationToken, Connectivity, Coordinate, Error, SpatialIdSet};
+
+impl SpatialIdSet {
+    /// 集合の空間を障害物とし、`start` から `goal` までの最短経路をズームレベル `z` のボクセルで探す。
+    ///
+    /// 隣のボクセルへは `connectivity` のつながり方で進み、コストは中心間の距離（メートル）の和になる。
+    /// まず空いた空間を木の大きな葉のまま粗く辿って目的地点へ届くかを確かめ、届く場合は辿った葉の
+    /// 周りだけを `z` のボクセルで細かく辿る。その経路のコストを上限にして最短経路を求める。
+    /// X は東西の端で巡回する。時間は見ない。
+    ///
+    /// 出発地点か目的地点が障害物の中にある場合や、経路がない場合は `None` を返す。
+    /// `token` がキャンセルされれば [`Error::Cancelled`] を返す。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 壁を回り込む
+    /// ```
+    /// # use kasane_logic::{CancellationToken, Connectivity, Coordinate, RangeId, SpatialIdSet};
+    /// let start = Coordinate::new(35.68, 139.7700, 10.0).unwrap();
+    /// let goal = Coordinate::new(35.68, 139.7712, 10.0).unwrap();
+    /// let [s, g] = [start, goal].map(|c| c.single_id(20).unwrap());
+    ///
+    /// // 出発地点と目的地点の間に、高さ 2 ボクセル・幅 5 ボクセルの壁を置く
+    /// let wall_x = (s.x() + g.x()) / 2;
+    /// let mut walls = SpatialIdSet::new();
+    /// walls.insert(RangeId::new(20, [s.f() - 1, s.f() + 1], [wall_x, wall_x], [s.y() - 2, s.y() + 2]).unwrap());
+    ///
+    /// let plan = walls
+    ///     .find_path(&start, &goal, 20, Connectivity::Face, &CancellationToken::never())
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert_eq!(plan.cells.first(), Some(&s));
+    /// assert_eq!(plan.cells.last(), Some(&g));
+    /// assert!(plan.cells.iter().all(|c| walls.get(c).next().is_none()));
+    /// ```
+    pub fn find_path(
+        &self,
+        start: &Coordinate,
+        goal: &Coordinate,
+        z: u8,
+        connectivity: Connectivity,
+        token: &CancellationToken,
+    ) -> Result<Option<PathPlan>, Error> {
+        path::find_path(&self.inner, |_| None, start, goal, z, connectivity, token)
+    }
+}
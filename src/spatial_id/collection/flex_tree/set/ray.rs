@@ -0,0 +1,82 @@
+//! [`SpatialIdSet`] を障害物として、見通しを調べる。
+
+use super::super::ray::{self, RayHit};
+use crate::{CancellationToken, Coordinate, Error, SpatialIdSet};
+
+impl SpatialIdSet {
+    /// `from` から `to` への線分を、ズームレベル `z` のボクセル単位で辿り、最初に当たった空間IDを返す。
+    ///
+    /// 始点と終点のボクセルも調べる。集合の空いた場所は、木の粗い単位のまま飛ばして進む。
+    /// 時間は見ない。どこにも当たらなければ `None` を返す。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 壁に当たる
+    /// ```
+    /// # use kasane_logic::{Coordinate, RangeId, SpatialIdSet};
+    /// let from = Coordinate::new(35.68, 139.7700, 10.0).unwrap();
+    /// let to = Coordinate::new(35.68, 139.7712, 10.0).unwrap();
+    /// let [s, g] = [from, to].map(|c| c.single_id(20).unwrap());
+    ///
+    /// let wall_x = (s.x() + g.x()) / 2;
+    /// let mut walls = SpatialIdSet::new();
+    /// walls.insert(RangeId::new(20, [s.f() - 1, s.f() + 1], [wall_x, wall_x], [s.y() - 2, s.y() + 2]).unwrap());
+    ///
+    /// let hit = walls.ray_cast(&from, &to, 20).unwrap().unwrap();
+    /// assert_eq!(hit.cell.x(), wall_x);
+    /// assert!(hit.distance_m > 0.0 && hit.distance_m < from.distance(&to));
+    /// ```
+    pub fn ray_cast(
+        &self,
+        from: &Coordinate,
+        to: &Coordinate,
+        z: u8,
+    ) -> Result<Option<RayHit>, Error> {
+        Ok(ray::ray_cast(&self.inner, from, to, z)?.map(|(hit, _)| hit))
+    }
+
+    /// `from` から `to` への線分が、ズームレベル `z` でどの空間IDにも当たらないかを返す。
+    ///
+    /// [`ray_cast`](Self::ray_cast) が `None` を返すことと同じ。
+    pub fn line_of_sight(&self, from: &Coordinate, to: &Coordinate, z: u8) -> Result<bool, Error> {
+        Ok(self.ray_cast(from, to, z)?.is_none())
+    }
+
+    /// 集合の空間を障害物とし、`observer` から半径 `radius_m` 以内で見えるズームレベル `z` のボクセルを返す。
+    ///
+    /// 半径 `radius_m` の [`Sphere`](crate::Sphere) に掛かるボクセルのうち、中心へ引いた線分が
+    /// そのボクセルより手前で障害物に当たらないものを見えるとする。障害物の手前の面も見える側に入る。
+    /// 観測点とのあいだに障害物がない範囲は、線分を引かずにまとめて見えるものとする。時間は見ない。
+    ///
+    /// `token` がキャンセルされれば [`Error::Cancelled`] を返す。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 壁の裏は見えない
+    /// ```
+    /// # use kasane_logic::{CancellationToken, Coordinate, RangeId, SingleId, SpatialIdSet};
+    /// let observer = Coordinate::new(35.68, 139.7700, 10.0).unwrap();
+    /// let eye = observer.single_id(20).unwrap();
+    ///
+    /// let mut walls = SpatialIdSet::new();
+    /// walls.insert(RangeId::new(20, [eye.f() - 3, eye.f() + 3], [eye.x() + 2, eye.x() + 2], [eye.y() - 3, eye.y() + 3]).unwrap());
+    ///
+    /// let visible = walls
+    ///     .viewshed(&observer, 150.0, 20, &CancellationToken::never())
+    ///     .unwrap();
+    /// let cell = |dx: u32| SingleId::new(20, eye.f(), eye.x() + dx, eye.y()).unwrap();
+    /// assert!(visible.get(&cell(1)).next().is_some());
+    /// assert!(visible.get(&cell(2)).next().is_some());
+    /// assert!(visible.get(&cell(3)).next().is_none());
+    /// ```
+    pub fn viewshed(
+        &self,
+        observer: &Coordinate,
+        radius_m: f64,
+        z: u8,
+        token: &CancellationToken,
+    ) -> Result<SpatialIdSet, Error> {
+        let visible = ray::viewshed(&self.inner, observer, radius_m, z, token)?;
+        Ok(SpatialIdSet::from_core(visible))
+    }
+}
@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod tests {
+    use crate::{Connectivity, RangeId, SingleId, SpatialId, SpatialIdSet};
+
+    fn cell(f: i32, x: u32, y: u32) -> SingleId {
+        SingleId::new(20, f, 931000 + x, 412000 + y).unwrap()
+    }
+
+    fn count(set: &SpatialIdSet, connectivity: Connectivity) -> usize {
+        set.connected_components(connectivity).1.len()
+    }
+
+    /// 離れたまとまりには別の番号が振られ、統計は成分ごとに求まる
+    #[test]
+    fn separate_blocks_get_separate_labels() {
+        let mut set = SpatialIdSet::new();
+        set.insert(RangeId::new(20, [0, 1], [931000, 931002], [412000, 412000]).unwrap());
+        set.insert(cell(0, 10, 0));
+
+        let (labels, stats) = set.connected_components(Connectivity::Vertex);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].label, 0);
+        assert_eq!(stats[0].cell_count, 6);
+        assert_eq!(
+            stats[0].bounds,
+            RangeId::new(20, [0, 1], [931000, 931002], [412000, 412000]).unwrap()
+        );
+        assert_eq!(stats[1].cell_count, 1);
+        assert_eq!(stats[1].bounds, RangeId::from(cell(0, 10, 0)));
+
+        let label_of = |id: SingleId| labels.get(&id).next().map(|(_, v)| *v);
+        assert_eq!(label_of(cell(1, 2, 0)), Some(0));
+        assert_eq!(label_of(cell(0, 10, 0)), Some(1));
+        assert_eq!(label_of(cell(0, 5, 0)), None);
+    }
+
+    /// 辺だけ・頂点だけで接するボクセルは、つながり方によって別の成分になる
+    #[test]
+    fn connectivity_decides_diagonal_contacts() {
+        let mut edge = SpatialIdSet::new();
+        edge.insert(cell(0, 0, 0));
+        edge.insert(cell(0, 1, 1));
+        assert_eq!(count(&edge, Connectivity::Face), 2);
+        assert_eq!(count(&edge, Connectivity::Edge), 1);
+        assert_eq!(count(&edge, Connectivity::Vertex), 1);
+
+        let mut vertex = SpatialIdSet::new();
+        vertex.insert(cell(0, 0, 0));
+        vertex.insert(cell(1, 1, 1));
+        assert_eq!(count(&vertex, Connectivity::Face), 2);
+        assert_eq!(count(&vertex, Connectivity::Edge), 2);
+        assert_eq!(count(&vertex, Connectivity::Vertex), 1);
+    }
+
+    /// ズームレベルの違う空間IDも、割らずに接しているかを判定する
+    #[test]
+    fn mixed_zoom_levels_connect() {
+        let coarse = SingleId::new(18, 0, 232750, 103000).unwrap();
+        let (x, y): (u32, u32) = (232750 * 4 + 4, 103000 * 4 + 1);
+        let mut set = SpatialIdSet::new();
+        set.insert(coarse.clone());
+        set.insert(SingleId::new(20, 0, x, y).unwrap());
+        // 少し離れたボクセルは別の成分になる
+        set.insert(SingleId::new(20, 0, x + 1, y + 10).unwrap());
+
+        let (_, stats) = set.connected_components(Connectivity::Face);
+        assert_eq!(stats.len(), 2);
+        let joined = stats.iter().find(|s| s.cell_count == 65).unwrap();
+        assert_eq!(joined.bounds.z(), 20);
+        assert_eq!(joined.bounds.x(), [232750 * 4, x]);
+    }
+
+    /// X は巡回するので、両端のボクセルは接している
+    #[test]
+    fn x_wraps_around() {
+        let last = (1u32 << 20) - 1;
+        let mut set = SpatialIdSet::new();
+        set.insert(SingleId::new(20, 0, 0, 412000).unwrap());
+        set.insert(SingleId::new(20, 0, last, 412000).unwrap());
+        assert_eq!(count(&set, Connectivity::Face), 1);
+    }
+
+    /// 体積はボクセルの辺の長さの積の和になる
+    #[test]
+    fn volume_sums_cells() {
+        let ids = [cell(0, 0, 0), cell(0, 1, 0), cell(0, 2, 0)];
+        let mut set = SpatialIdSet::new();
+        for id in &ids {
+            set.insert(id.clone());
+        }
+        let (_, stats) = set.connected_components(Connectivity::Face);
+        let expected: f64 = ids
+            .iter()
+            .map(|id| id.length_f_meters() * id.length_x_meters() * id.length_y_meters())
+            .sum();
+        assert!(libm::fabs(stats[0].volume_m3 - expected) < expected * 1e-6);
+    }
+
+    /// 時間だけが違う空間IDは、同じ場所として 1 つの成分にまとまる
+    #[cfg(feature = "temporal_id")]
+    #[test]
+    fn time_is_ignored() {
+        use alloc::vec::Vec;
+
+        use crate::Interval;
+
+        let mut set = SpatialIdSet::new();
+        set.insert(cell(0, 0, 0).with_time(Interval::HOUR, 0).unwrap());
+        set.insert(cell(0, 0, 0).with_time(Interval::HOUR, 5).unwrap());
+        set.insert(cell(0, 1, 0).with_time(Interval::HOUR, 9).unwrap());
+
+        let (labels, stats) = set.connected_components(Connectivity::Face);
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].cell_count, 2);
+        let values: Vec<u32> = labels.iter().map(|(_, v)| *v).collect();
+        assert!(values.iter().all(|v| *v == 0));
+    }
+}
@@ -0,0 +1,56 @@
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    use crate::{RangeId, SpatialId, SpatialIdSet};
+
+    fn parse(set: &SpatialIdSet) -> serde_json::Value {
+        serde_json::from_str(&set.to_geojson()).unwrap()
+    }
+
+    /// `RangeId` 1 つにつき 1 つの `Feature` を持ち、外形と高度は `spatial_vertices` と一致する
+    #[test]
+    fn geojson_has_one_feature_per_range_id() {
+        let mut set = SpatialIdSet::new();
+        set.insert(RangeId::new(20, [0, 1], [931000, 931001], [412000, 412000]).unwrap());
+        set.insert(RangeId::new(18, [3, 3], [232800, 232800], [103000, 103001]).unwrap());
+
+        let geojson = parse(&set);
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().unwrap();
+        let range_ids: Vec<RangeId> = set.range_ids().collect();
+        assert_eq!(features.len(), range_ids.len());
+        assert_eq!(features.len(), 2);
+
+        for range_id in &range_ids {
+            let feature = features
+                .iter()
+                .find(|f| f["properties"]["id"] == range_id.to_string())
+                .unwrap();
+            assert_eq!(feature["type"], "Feature");
+            assert_eq!(feature["geometry"]["type"], "Polygon");
+
+            let vertices = range_id.spatial_vertices();
+            let properties = &feature["properties"];
+            assert_eq!(properties["z"], range_id.z());
+            assert_eq!(properties["altitude_min"], vertices[0].altitude());
+            assert_eq!(properties["altitude_max"], vertices[4].altitude());
+            assert!(properties.get("value").is_none());
+
+            let ring = feature["geometry"]["coordinates"][0].as_array().unwrap();
+            let expected: Vec<serde_json::Value> = [0, 2, 3, 1, 0]
+                .iter()
+                .map(|&i| serde_json::json!([vertices[i].longitude(), vertices[i].latitude()]))
+                .collect();
+            assert_eq!(ring, &expected);
+        }
+    }
+
+    #[test]
+    fn geojson_of_empty_set_has_no_features() {
+        let geojson = parse(&SpatialIdSet::new());
+        assert_eq!(geojson["type"], "FeatureCollection");
+        assert!(geojson["features"].as_array().unwrap().is_empty());
+    }
+}
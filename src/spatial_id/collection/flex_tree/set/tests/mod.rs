@@ -5,20 +5,27 @@ use alloc::string::String;
 #[cfg(test)]
 use alloc::vec::Vec;
 
+pub mod components;
 pub mod corner_cases;
 pub mod count;
 pub mod difference;
 pub mod equal;
+pub mod export;
 pub mod insert;
 pub mod intersection;
 pub mod merge_probe;
+pub mod outline;
+pub mod path;
+pub mod ray;
+pub mod rkyv;
+pub mod shape;
 pub mod sharded;
 pub mod union;
 
 #[cfg(test)]
 use crate::ZoomLevel;
 #[cfg(test)]
-use crate::{RangeId, SingleId, SpatialIdSet};
+use crate::{Coordinate, RangeId, SingleId, SpatialIdSet};
 #[cfg(test)]
 use hashbrown::HashSet;
 #[cfg(test)]
@@ -266,3 +273,25 @@ pub(crate) fn sorted_single_ids(set: &SpatialIdSet, target_z: u8) -> Vec<SingleI
     ids.sort();
     ids
 }
+
+/// 東京付近・高度 10 m のズームレベル 20 のボクセル。
+#[cfg(test)]
+pub(crate) fn origin() -> SingleId {
+    Coordinate::new(35.68, 139.77, 10.0)
+        .unwrap()
+        .single_id(20)
+        .unwrap()
+}
+
+/// [`origin`] から F・X・Y にそれぞれ `df`・`dx`・`dy` ずらしたボクセル。
+#[cfg(test)]
+pub(crate) fn at(df: i32, dx: i32, dy: i32) -> SingleId {
+    let o = origin();
+    SingleId::new(
+        20,
+        o.f() + df,
+        (o.x() as i32 + dx) as u32,
+        (o.y() as i32 + dy) as u32,
+    )
+    .unwrap()
+}
@@ -0,0 +1,178 @@
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        ExpandPolygons, ExpandTriangles, Polygon, RangeId, SingleId, SpatialId, SpatialIdSet,
+        Triangle, Vec3, Vec3Ecef,
+    };
+
+    fn range(f: [i32; 2], x: [u32; 2], y: [u32; 2]) -> RangeId {
+        RangeId::new(20, f, x, y).unwrap()
+    }
+
+    fn triangle_area(triangle: &Triangle) -> f64 {
+        let [a, b, c] = triangle.points.map(Vec3Ecef::from);
+        (b - a).cross(&(c - a)).norm() / 2.0
+    }
+
+    fn polygon_area(polygon: &Polygon) -> f64 {
+        polygon.expand_triangles().map(|t| triangle_area(&t)).sum()
+    }
+
+    /// 中央が空いた 3×3 は、穴を 1 つ持つ 1 つの多角形になる
+    #[test]
+    fn ring_of_cells_has_one_hole() {
+        let mut set = SpatialIdSet::new();
+        set.insert(range([0, 0], [931000, 931002], [412000, 412002]));
+        set.remove(&SingleId::new(20, 0, 931001, 412001).unwrap());
+
+        let layers = set.layer_outlines(20).unwrap();
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].f, 0);
+        let polygons = layers[0].polygons.polygons();
+        assert_eq!(polygons.len(), 1);
+        assert_eq!(polygons[0].vertices().len(), 4);
+        assert_eq!(polygons[0].holes().len(), 1);
+        assert_eq!(polygons[0].holes()[0].len(), 4);
+
+        // 面積は 8 ボクセル分
+        let cell = SingleId::new(20, 0, 931001, 412001).unwrap();
+        let cell_area = {
+            let mut set = SpatialIdSet::new();
+            set.insert(cell);
+            polygon_area(&set.layer_outlines(20).unwrap()[0].polygons.polygons()[0])
+        };
+        let area = polygon_area(&polygons[0]);
+        assert!(
+            libm::fabs(area / cell_area - 8.0) < 1e-3,
+            "{}",
+            area / cell_area
+        );
+    }
+
+    /// 頂点だけで接するボクセルは別の多角形になる
+    #[test]
+    fn diagonal_cells_are_separate_polygons() {
+        let mut set = SpatialIdSet::new();
+        set.insert(SingleId::new(20, 0, 931000, 412000).unwrap());
+        set.insert(SingleId::new(20, 0, 931001, 412001).unwrap());
+
+        let layers = set.layer_outlines(20).unwrap();
+        let polygons = layers[0].polygons.polygons();
+        assert_eq!(polygons.len(), 2);
+        assert!(polygons.iter().all(|polygon| polygon.vertices().len() == 4));
+    }
+
+    /// 穴の中の島は、穴を持つ多角形とは別の多角形になる
+    #[test]
+    fn island_inside_hole_is_separate_polygon() {
+        let mut set = SpatialIdSet::new();
+        set.insert(range([0, 0], [931000, 931004], [412000, 412004]));
+        set.remove(&range([0, 0], [931001, 931003], [412001, 412003]));
+        set.insert(SingleId::new(20, 0, 931002, 412002).unwrap());
+
+        let layers = set.layer_outlines(20).unwrap();
+        let mut polygons: Vec<(usize, usize)> = layers[0]
+            .polygons
+            .polygons()
+            .iter()
+            .map(|polygon| (polygon.vertices().len(), polygon.holes().len()))
+            .collect();
+        polygons.sort();
+        assert_eq!(polygons, [(4, 0), (4, 1)]);
+    }
+
+    /// 層ごとに外形が分かれ、高度の範囲を持つ
+    #[test]
+    fn layers_are_split_by_f() {
+        let mut set = SpatialIdSet::new();
+        set.insert(range([0, 0], [931000, 931003], [412000, 412000]));
+        set.insert(range([1, 1], [931000, 931000], [412000, 412003]));
+
+        let layers = set.layer_outlines(20).unwrap();
+        assert_eq!(layers.iter().map(|l| l.f).collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(layers[0].altitude[1], layers[1].altitude[0]);
+        for layer in &layers {
+            let polygon = &layer.polygons.polygons()[0];
+            assert_eq!(polygon.vertices().len(), 4);
+            assert!(
+                polygon
+                    .vertices()
+                    .iter()
+                    .all(|v| libm::fabs(v.altitude() - layer.altitude[0]) < 1e-6)
+            );
+        }
+    }
+
+    /// 粗い空間IDは細かいボクセルに、細かい空間IDはそれを含むボクセルに揃える
+    #[test]
+    fn ids_are_aligned_to_zoom() {
+        let mut set = SpatialIdSet::new();
+        set.insert(SingleId::new(19, 0, 465500, 206000).unwrap());
+        set.insert(SingleId::new(22, 0, 3724016, 1648000).unwrap());
+
+        let layers = set.layer_outlines(20).unwrap();
+        let polygons = layers[0].polygons.polygons();
+        // 2×2 の正方形と、離れた 1 ボクセル
+        assert_eq!(polygons.len(), 2);
+        assert!(set.layer_outlines(31).is_err());
+    }
+
+    /// 露出したボクセルの面の面積の合計。
+    fn exposed_face_area(set: &SpatialIdSet) -> f64 {
+        let cells: Vec<SingleId> = set.flat_single_ids().collect();
+        let contains =
+            |f: i32, x: u32, y: u32| cells.iter().any(|id| (id.f(), id.x(), id.y()) == (f, x, y));
+        let mut area = 0.0;
+        for id in &cells {
+            let (f, x, y) = (id.f(), id.x(), id.y());
+            // spatial_vertices は [高度][緯度][経度] の順
+            let v = id.spatial_vertices();
+            let faces = [
+                (contains(f - 1, x, y), [0, 1, 3, 2]),
+                (contains(f + 1, x, y), [4, 5, 7, 6]),
+                (contains(f, x - 1, y), [0, 2, 6, 4]),
+                (contains(f, x + 1, y), [1, 3, 7, 5]),
+                (contains(f, x, y - 1), [0, 1, 5, 4]),
+                (contains(f, x, y + 1), [2, 3, 7, 6]),
+            ];
+            for (covered, [a, b, c, d]) in faces {
+                if !covered {
+                    area += triangle_area(&Triangle::new([v[a], v[b], v[c]]));
+                    area += triangle_area(&Triangle::new([v[a], v[c], v[d]]));
+                }
+            }
+        }
+        area
+    }
+
+    /// 凹凸のある立体の表面は閉じていて、露出したボクセルの面と同じ面積を持つ
+    #[test]
+    fn boundary_solid_is_closed() {
+        let mut set = SpatialIdSet::new();
+        // L 字の柱の上に、一部だけ 1 段積む
+        set.insert(range([0, 1], [931000, 931002], [412000, 412000]));
+        set.insert(range([0, 1], [931000, 931000], [412001, 412002]));
+        set.insert(SingleId::new(20, 2, 931000, 412000).unwrap());
+
+        let solid = set.boundary_solid(20).unwrap().unwrap();
+        // 下面・上面（2 段）・最上段の上面と、側面
+        assert!(solid.expand_polygons().count() >= 10);
+
+        let area: f64 = solid.triangles().iter().map(triangle_area).sum();
+        let expected = exposed_face_area(&set);
+        assert!(
+            libm::fabs(area / expected - 1.0) < 1e-6,
+            "{area} {expected}"
+        );
+    }
+
+    /// 空の集合は表面を持たない
+    #[test]
+    fn empty_set_has_no_boundary() {
+        let set = SpatialIdSet::new();
+        assert!(set.boundary_solid(20).unwrap().is_none());
+        assert!(set.layer_outlines(20).unwrap().is_empty());
+    }
+}
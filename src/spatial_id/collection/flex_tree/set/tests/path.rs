@@ -0,0 +1,210 @@
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BinaryHeap;
+    use alloc::vec::Vec;
+    use core::cmp::Reverse;
+    use hashbrown::HashMap;
+
+    use super::super::{at, origin};
+    use crate::{
+        CancellationToken, Connectivity, Coordinate, Ecef, Error, RangeId, SingleId, SpatialId,
+        SpatialIdSet,
+    };
+
+    fn coordinate(cell: &SingleId) -> Coordinate {
+        cell.spatial_center()
+    }
+
+    fn plan(
+        set: &SpatialIdSet,
+        start: &SingleId,
+        goal: &SingleId,
+        connectivity: Connectivity,
+    ) -> Option<crate::PathPlan> {
+        set.find_path(
+            &coordinate(start),
+            &coordinate(goal),
+            20,
+            connectivity,
+            &CancellationToken::never(),
+        )
+        .unwrap()
+    }
+
+    fn distance(a: &SingleId, b: &SingleId) -> f64 {
+        Ecef::from(a.spatial_center()).distance(&Ecef::from(b.spatial_center()))
+    }
+
+    /// 出発地点から `margin` ボクセルの箱の中だけを、面の近傍で全て調べる最短経路のコスト。
+    fn reference_cost(set: &SpatialIdSet, start: &SingleId, goal: &SingleId, margin: i32) -> f64 {
+        let o = origin();
+        let inside = |c: &SingleId| {
+            (c.f() - o.f()).abs() <= margin
+                && (c.x() as i32 - o.x() as i32).abs() <= margin
+                && (c.y() as i32 - o.y() as i32).abs() <= margin
+        };
+        let mut best: HashMap<SingleId, f64> = HashMap::new();
+        let mut open = BinaryHeap::new();
+        best.insert(start.clone(), 0.0);
+        open.push(Reverse(((0.0f64).to_bits(), start.clone())));
+        while let Some(Reverse((bits, cell))) = open.pop() {
+            let g = f64::from_bits(bits);
+            if g > best[&cell] {
+                continue;
+            }
+            if cell == *goal {
+                return g;
+            }
+            for next in cell.neighbors_share_face() {
+                if !inside(&next) || set.get(&next).next().is_some() {
+                    continue;
+                }
+                let next_g = g + distance(&cell, &next);
+                if best.get(&next).is_some_and(|known| *known <= next_g) {
+                    continue;
+                }
+                best.insert(next.clone(), next_g);
+                open.push(Reverse((next_g.to_bits(), next)));
+            }
+        }
+        f64::INFINITY
+    }
+
+    /// 何もない空間では、まっすぐ進む
+    #[test]
+    fn straight_line_in_empty_space() {
+        let set = SpatialIdSet::new();
+        let (start, goal) = (at(0, 0, 0), at(0, 10, 0));
+        let plan = plan(&set, &start, &goal, Connectivity::Face).unwrap();
+
+        assert_eq!(plan.cells.len(), 11);
+        assert!(
+            plan.cells
+                .iter()
+                .all(|c| c.f() == start.f() && c.y() == start.y())
+        );
+        assert!(libm::fabs(plan.cost - distance(&start, &goal)) < 1e-6 * plan.cost);
+        assert_eq!(plan.tube.points.len(), 11);
+        assert!(plan.tube.radius_m > 0.0);
+    }
+
+    /// 壁を回り込む経路は、箱の中を全て調べた最短経路と同じコストになる
+    #[test]
+    fn detour_matches_exhaustive_search() {
+        let mut set = SpatialIdSet::new();
+        let o = origin();
+        // 出発地点と目的地点の間に、F・Y 方向へ広がる壁を置く
+        set.insert(
+            RangeId::new(
+                20,
+                [o.f() - 2, o.f() + 3],
+                [o.x() + 3, o.x() + 3],
+                [o.y() - 4, o.y() + 2],
+            )
+            .unwrap(),
+        );
+        let (start, goal) = (at(0, 0, 0), at(0, 6, 1));
+        let plan = plan(&set, &start, &goal, Connectivity::Face).unwrap();
+
+        assert!(plan.cells.iter().all(|c| set.get(c).next().is_none()));
+        // 隣り合うボクセルは面で接する
+        for pair in plan.cells.windows(2) {
+            assert!(pair[0].neighbors_share_face().any(|n| n == pair[1]));
+        }
+        let expected = reference_cost(&set, &start, &goal, 8);
+        assert!(
+            libm::fabs(plan.cost - expected) < 1e-6 * expected,
+            "{} vs {expected}",
+            plan.cost
+        );
+    }
+
+    /// つながり方が広いほど、斜めに進める
+    #[test]
+    fn connectivity_allows_diagonal_steps() {
+        let set = SpatialIdSet::new();
+        let (start, goal) = (at(0, 0, 0), at(1, 1, 1));
+        assert_eq!(
+            plan(&set, &start, &goal, Connectivity::Face)
+                .unwrap()
+                .cells
+                .len(),
+            4
+        );
+        assert_eq!(
+            plan(&set, &start, &goal, Connectivity::Edge)
+                .unwrap()
+                .cells
+                .len(),
+            3
+        );
+        assert_eq!(
+            plan(&set, &start, &goal, Connectivity::Vertex)
+                .unwrap()
+                .cells
+                .len(),
+            2
+        );
+    }
+
+    /// 目的地点が障害物の中や、閉じた殻の中にあれば経路はない
+    #[test]
+    fn unreachable_goal_returns_none() {
+        let o = origin();
+        let mut set = SpatialIdSet::new();
+        set.insert(
+            RangeId::new(
+                20,
+                [o.f() + 4, o.f() + 8],
+                [o.x() + 4, o.x() + 8],
+                [o.y() + 4, o.y() + 8],
+            )
+            .unwrap(),
+        );
+        assert!(plan(&set, &at(0, 0, 0), &at(6, 6, 6), Connectivity::Vertex).is_none());
+
+        set.remove(&at(6, 6, 6));
+        assert!(plan(&set, &at(0, 0, 0), &at(6, 6, 6), Connectivity::Vertex).is_none());
+        // 出発地点が殻の中でも同じ
+        assert!(plan(&set, &at(6, 6, 6), &at(0, 0, 0), Connectivity::Face).is_none());
+    }
+
+    /// 東西の端をまたいで進める
+    #[test]
+    fn route_crosses_the_antimeridian() {
+        let last = (1u32 << 20) - 1;
+        let (start, goal) = (
+            SingleId::new(20, 0, last, 400000).unwrap(),
+            SingleId::new(20, 0, 1, 400000).unwrap(),
+        );
+        let plan = plan(&SpatialIdSet::new(), &start, &goal, Connectivity::Face).unwrap();
+        assert_eq!(
+            plan.cells.iter().map(|c| c.x()).collect::<Vec<_>>(),
+            Vec::from([last, 0, 1])
+        );
+    }
+
+    /// キャンセルされたトークンを渡すとエラーになる
+    #[test]
+    fn cancelled_token_stops_search() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let result = SpatialIdSet::new().find_path(
+            &coordinate(&at(0, 0, 0)),
+            &coordinate(&at(0, 5, 0)),
+            20,
+            Connectivity::Face,
+            &token,
+        );
+        assert!(matches!(result, Err(Error::Cancelled)));
+    }
+
+    /// 出発地点と目的地点が同じボクセルなら、そのボクセルだけの経路になる
+    #[test]
+    fn same_cell_is_trivial() {
+        let set = SpatialIdSet::new();
+        let plan = plan(&set, &at(0, 0, 0), &at(0, 0, 0), Connectivity::Face).unwrap();
+        assert_eq!(plan.cells, Vec::from([at(0, 0, 0)]));
+        assert_eq!(plan.cost, 0.0);
+    }
+}
@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeSet;
+    use alloc::vec::Vec;
+
+    use super::super::at;
+    use crate::{
+        CancellationToken, Coordinate, CoverSingleIds, Ecef, Error, Line, RangeId, SingleId,
+        SpatialId, SpatialIdSet, Sphere,
+    };
+
+    /// `origin` の周りの箱に、線形合同法で決まった位置のボクセルを散らした集合。
+    fn scattered(count: usize, seed: u64) -> SpatialIdSet {
+        let mut state = seed;
+        let mut next = |span: i32| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) % (2 * span as u64 + 1)) as i32 - span
+        };
+        let mut set = SpatialIdSet::new();
+        for _ in 0..count {
+            let (df, dx, dy) = (next(4), next(12), next(12));
+            set.insert(at(df, dx, dy));
+        }
+        set
+    }
+
+    /// 何もない集合では、どの線分も当たらない
+    #[test]
+    fn empty_set_has_line_of_sight() {
+        let set = SpatialIdSet::new();
+        let (from, to) = (
+            at(0, -30, 5).spatial_center(),
+            at(3, 40, -7).spatial_center(),
+        );
+        assert_eq!(set.ray_cast(&from, &to, 20).unwrap(), None);
+        assert!(set.line_of_sight(&from, &to, 20).unwrap());
+    }
+
+    /// 最初に当たるボクセルは、Line が DDA で辿るボクセルのうち最初に集合に含まれるものと一致する
+    #[test]
+    fn first_hit_matches_line_cover() {
+        let from = Coordinate::new(35.6797, 139.7683, 3.3).unwrap();
+        let targets = [
+            Coordinate::new(35.6805, 139.7718, 40.7).unwrap(),
+            Coordinate::new(35.6791, 139.7721, -12.1).unwrap(),
+            Coordinate::new(35.6812, 139.7690, 21.9).unwrap(),
+            Coordinate::new(35.6786, 139.7679, 35.2).unwrap(),
+        ];
+        for seed in 1..=6 {
+            let set = scattered(120, seed);
+            for to in &targets {
+                let expected = Line::new([from, *to])
+                    .cover_single_ids(20)
+                    .unwrap()
+                    .find(|cell| set.get(cell).next().is_some());
+                let got = set.ray_cast(&from, to, 20).unwrap().map(|hit| hit.cell);
+                assert_eq!(got, expected, "seed {seed}, to {to:?}");
+            }
+        }
+    }
+
+    /// 空いた場所を粗い単位で飛ばしても、遠くにある細かい空間IDを見落とさない
+    #[test]
+    fn finds_fine_obstacle_far_away() {
+        let from = Coordinate::new(35.68, 139.77, 10.0).unwrap();
+        let to = Coordinate::new(35.68, 139.80, 10.0).unwrap();
+        // 線分の 2/3 の位置（約 1.8 km 先）を含むズームレベル 25 のボクセル
+        let (a, b) = (Ecef::from(from), Ecef::from(to));
+        let middle: Coordinate = Ecef::new(
+            a.x() + (b.x() - a.x()) * 2.0 / 3.0,
+            a.y() + (b.y() - a.y()) * 2.0 / 3.0,
+            a.z() + (b.z() - a.z()) * 2.0 / 3.0,
+        )
+        .try_into()
+        .unwrap();
+        let tiny = middle.single_id(25).unwrap();
+        let mut set = SpatialIdSet::new();
+        set.insert(tiny.clone());
+
+        let hit = set.ray_cast(&from, &to, 25).unwrap().unwrap();
+        assert_eq!(hit.cell, tiny);
+        let expected = from.distance(&middle);
+        assert!(
+            libm::fabs(hit.distance_m - expected) < 2.0,
+            "{}",
+            hit.distance_m
+        );
+        // 粗いズームレベルで調べても、それを含むボクセルで当たる
+        let coarse = set.ray_cast(&from, &to, 20).unwrap().unwrap();
+        assert_eq!(coarse.cell, tiny.spatial_parent_at_zoom(20).unwrap());
+    }
+
+    /// 距離は始点から当たったボクセルへ入った位置までで、始点が障害物の中なら 0
+    #[test]
+    fn distance_is_to_entry_point() {
+        let mut set = SpatialIdSet::new();
+        let wall = at(0, 5, 0);
+        set.insert(
+            RangeId::new(
+                20,
+                [wall.f() - 2, wall.f() + 2],
+                wall.x(),
+                [wall.y() - 2, wall.y() + 2],
+            )
+            .unwrap(),
+        );
+        let (from, to) = (at(0, 0, 0).spatial_center(), at(0, 10, 0).spatial_center());
+
+        let hit = set.ray_cast(&from, &to, 20).unwrap().unwrap();
+        assert_eq!(hit.cell, wall);
+        // 中心から中心へ 4.5 ボクセル分進んだところで壁に入る
+        let cell_m = Ecef::from(from).distance(&Ecef::from(at(0, 1, 0).spatial_center()));
+        assert!(libm::fabs(hit.distance_m - 4.5 * cell_m) < 0.05 * cell_m);
+
+        let inside = set
+            .ray_cast(&wall.spatial_center(), &to, 20)
+            .unwrap()
+            .unwrap();
+        assert_eq!(inside.cell, wall);
+        assert_eq!(inside.distance_m, 0.0);
+    }
+
+    /// 可視域は、球に掛かるボクセルの中心へ 1 本ずつ線分を引いた結果と一致する
+    #[test]
+    fn viewshed_matches_per_cell_rays() {
+        let observer = at(0, 0, 0).spatial_center();
+        let radius_m = 200.0;
+        for seed in [3, 8] {
+            let set = scattered(40, seed);
+            let visible: BTreeSet<SingleId> = set
+                .viewshed(&observer, radius_m, 20, &CancellationToken::never())
+                .unwrap()
+                .single_ids()
+                .flat_map(|id| id.spatial_children_at_zoom(20).unwrap().collect::<Vec<_>>())
+                .collect();
+
+            let expected: BTreeSet<SingleId> = Sphere::new(observer, radius_m)
+                .unwrap()
+                .cover_single_ids(20)
+                .unwrap()
+                .filter(
+                    |cell| match set.ray_cast(&observer, &cell.spatial_center(), 20).unwrap() {
+                        None => true,
+                        Some(hit) => hit.cell == *cell,
+                    },
+                )
+                .collect();
+            assert_eq!(visible, expected, "seed {seed}");
+            // 障害物の裏に隠れたボクセルがある
+            assert!(
+                visible.len()
+                    < Sphere::new(observer, radius_m)
+                        .unwrap()
+                        .cover_single_ids(20)
+                        .unwrap()
+                        .count()
+            );
+        }
+    }
+
+    /// キャンセル済みのトークンを渡すと、可視域を求めずにエラーを返す
+    #[test]
+    fn viewshed_respects_cancellation() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let result = scattered(10, 1).viewshed(&at(0, 0, 0).spatial_center(), 100.0, 20, &token);
+        assert!(matches!(result, Err(Error::Cancelled)));
+    }
+}
@@ -0,0 +1,132 @@
+#[cfg(all(test, feature = "persist"))]
+mod persist_tests {
+    use crate::{ArchivedSpatialIdSet, FlexId, RangeId, SingleId, SpatialIdMap, SpatialIdSet};
+    use alloc::vec::Vec;
+
+    fn sample() -> SpatialIdSet {
+        let mut set = SpatialIdSet::new();
+        set.insert(SingleId::new(5, 3, 4, 6).unwrap());
+        set.insert(SingleId::new(5, -2, 4, 6).unwrap());
+        set.insert(RangeId::new(5, [1, 4], [8, 9], [5, 6]).unwrap());
+        set
+    }
+
+    fn sorted(mut ids: Vec<FlexId>) -> Vec<FlexId> {
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn round_trip() {
+        let set = sample();
+        let bytes = set.to_bytes().unwrap();
+        let restored = unsafe { SpatialIdSet::from_bytes(&bytes) }.unwrap();
+        assert_eq!(set, restored);
+        assert_eq!(set.count(), restored.count());
+    }
+
+    #[test]
+    fn round_trip_empty() {
+        let bytes = SpatialIdSet::new().to_bytes().unwrap();
+        let restored = unsafe { SpatialIdSet::from_bytes(&bytes) }.unwrap();
+        assert!(restored.is_empty());
+    }
+
+    /// 形式が共通なので、マップのバイト列を集合として読むと占有だけが残る。
+    #[test]
+    fn map_bytes_read_as_set() {
+        let mut map = SpatialIdMap::<Vec<u8>>::new();
+        map.insert(SingleId::new(5, 3, 4, 6).unwrap(), b"a".to_vec());
+        map.insert(SingleId::new(5, 3, 4, 7).unwrap(), b"b".to_vec());
+
+        let bytes = map.to_bytes().unwrap();
+        let set = unsafe { SpatialIdSet::from_bytes(&bytes) }.unwrap();
+
+        let expected: Vec<FlexId> = map.iter().map(|(id, _)| id).collect();
+        assert_eq!(sorted(set.iter().collect()), sorted(expected));
+    }
+
+    /// ZeroCopy 側の `get` / `get_overlapping` / `get_range` がインメモリと一致すること。
+    ///
+    /// 上下ルートの選択と、粗いSegmentを覆う/覆われる両方の target を踏む。
+    #[test]
+    fn archived_queries_match_in_memory() {
+        let set = sample();
+        let bytes = set.to_bytes().unwrap();
+        let archived = unsafe { ArchivedSpatialIdSet::access(&bytes) }.unwrap();
+
+        let targets = [
+            SingleId::new(5, 3, 4, 6).unwrap(),
+            SingleId::new(5, -2, 4, 6).unwrap(),
+            SingleId::new(5, 2, 8, 5).unwrap(),
+            SingleId::new(5, 3, 4, 7).unwrap(),
+            SingleId::new(7, 9, 33, 21).unwrap(),
+            SingleId::new(0, 0, 0, 0).unwrap(),
+            SingleId::new(0, -1, 0, 0).unwrap(),
+        ];
+        for target in targets {
+            assert_eq!(
+                sorted(archived.get(&target)),
+                sorted(set.get(&target).collect()),
+                "get: target={target}"
+            );
+            assert_eq!(
+                sorted(archived.get_overlapping(&target)),
+                sorted(set.get_overlapping(&target).collect()),
+                "get_overlapping: target={target}"
+            );
+            let range: RangeId = target.clone().into();
+            assert_eq!(
+                sorted(archived.get_range(&range)),
+                sorted(set.get_range(&range).collect()),
+                "get_range: target={target}"
+            );
+        }
+
+        assert_eq!(sorted(archived.flex_ids()), sorted(set.iter().collect()));
+    }
+
+    /// 複数Segmentに分かれる target でも、同じ葉を重複して返さないこと。
+    #[test]
+    fn archived_get_overlapping_has_no_duplicates() {
+        let mut set = SpatialIdSet::new();
+        set.insert(SingleId::new(3, 1, 1, 1).unwrap());
+
+        let bytes = set.to_bytes().unwrap();
+        let archived = unsafe { ArchivedSpatialIdSet::access(&bytes) }.unwrap();
+
+        let target = RangeId::new(5, [4, 7], [4, 7], [4, 7]).unwrap();
+        assert_eq!(archived.get_overlapping(&target).len(), 1);
+    }
+
+    /// archived バイト列をそのまま入力源にしたクエリが、インメモリと同じ結果になること。
+    #[test]
+    fn archived_source_matches_in_memory_query() {
+        use crate::Source;
+        use alloc::boxed::Box;
+
+        let set = sample();
+        let bytes: &'static [u8] = Box::leak(set.to_bytes().unwrap().into_boxed_slice());
+        let archived = unsafe { ArchivedSpatialIdSet::access(bytes) }.unwrap();
+
+        let expected = set.clone().query().shift_x(6, 3).run_set().unwrap();
+        let got = archived.query().shift_x(6, 3).run_set().unwrap();
+        assert_eq!(got, expected);
+    }
+
+    /// `read_all` は木の形を保ったまま全体を読み出すこと。
+    #[test]
+    fn archived_read_all_matches_in_memory() {
+        use crate::{CancellationToken, Source};
+        use alloc::boxed::Box;
+
+        let set = sample();
+        let bytes = set.to_bytes().unwrap();
+        let archived = unsafe { ArchivedSpatialIdSet::access(&bytes) }.unwrap();
+
+        let working = Box::new(archived)
+            .read_all(&CancellationToken::new())
+            .unwrap();
+        assert_eq!(SpatialIdSet::from(working), set);
+    }
+}
@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::super::sorted_single_ids;
+
+    use crate::{Coordinate, FlexId, RangeId, SingleId, SpatialIdSet, SpatialPredicates, Sphere};
+
+    /// 高度 100 m を中心とする半径 100 m の球。
+    fn sphere() -> Sphere {
+        Sphere::new(Coordinate::new(35.681, 139.766, 100.0).unwrap(), 100.0).unwrap()
+    }
+
+    fn center_cell() -> SingleId {
+        Coordinate::new(35.681, 139.766, 100.0)
+            .unwrap()
+            .single_id(20)
+            .unwrap()
+    }
+
+    /// 球の周りを z=20 で不揃いに埋めた集合。
+    fn scattered_set() -> SpatialIdSet {
+        let c = center_cell();
+        let mut set = SpatialIdSet::new();
+        for dx in -8i64..=8 {
+            for dy in -8i64..=8 {
+                if (dx * 3 + dy * 5).rem_euclid(4) == 0 {
+                    continue;
+                }
+                let x = (c.x() as i64 + dx) as u32;
+                let y = (c.y() as i64 + dy) as u32;
+                set.insert(
+                    RangeId::new(20, [0, (dx + dy).rem_euclid(12) as i32], [x, x], [y, y]).unwrap(),
+                );
+            }
+        }
+        set
+    }
+
+    fn sorted(ids: impl IntoIterator<Item = FlexId>) -> Vec<FlexId> {
+        let mut ids: Vec<FlexId> = ids.into_iter().collect();
+        ids.sort();
+        ids
+    }
+
+    /// 枝刈りしても、全ての葉を 1 つずつ判定した結果と一致する
+    #[test]
+    fn get_overlapping_shape_matches_brute_force() {
+        let set = scattered_set();
+        let sphere = sphere();
+        let expected = sorted(set.iter().filter(|id| sphere.intersects(id)));
+        assert!(!expected.is_empty());
+        assert!(expected.len() < set.iter().count());
+        assert_eq!(sorted(set.get_overlapping_shape(&sphere, 30)), expected);
+    }
+
+    /// 粗い max_z では部分木を丸ごと返すので、結果は細かい max_z の結果を含む
+    #[test]
+    fn coarse_max_z_returns_superset() {
+        let set = scattered_set();
+        let sphere = sphere();
+        let exact = sorted(set.get_overlapping_shape(&sphere, 30));
+        let coarse = sorted(set.get_overlapping_shape(&sphere, 16));
+        assert!(exact.iter().all(|id| coarse.binary_search(id).is_ok()));
+        assert!(coarse.len() >= exact.len());
+    }
+
+    /// 遠く離れた粗い葉は返さず、球を含む粗い葉は返す
+    #[test]
+    fn coarse_leaves_are_judged_by_their_region() {
+        let sphere = sphere();
+        let c = center_cell();
+
+        let mut set = SpatialIdSet::new();
+        // 地球の反対側の z=3 のボクセル
+        set.insert(SingleId::new(3, 0, 1, 6).unwrap());
+        assert_eq!(set.get_overlapping_shape(&sphere, 30).count(), 0);
+
+        let around = SingleId::new(3, 0, c.x() >> 17, c.y() >> 17).unwrap();
+        set.insert(around.clone());
+        let found: Vec<FlexId> = set.get_overlapping_shape(&sphere, 30).collect();
+        assert_eq!(found, around.into_iter().collect::<Vec<FlexId>>());
+    }
+
+    /// 削除した葉は get_overlapping_shape の結果と一致し、残りは球と交わらない
+    #[test]
+    fn remove_overlapping_shape_removes_exactly_the_overlapping_leaves() {
+        let mut set = scattered_set();
+        let sphere = sphere();
+        let before = sorted_single_ids(&set, 20);
+        let expected = sorted(set.get_overlapping_shape(&sphere, 30));
+
+        let removed = sorted(set.remove_overlapping_shape(&sphere, 30));
+        assert_eq!(removed, expected);
+
+        let mut removed_set = SpatialIdSet::new();
+        for id in removed {
+            removed_set.insert(id);
+        }
+        let removed_cells = sorted_single_ids(&removed_set, 20);
+        let remaining: Vec<SingleId> = before
+            .into_iter()
+            .filter(|cell| removed_cells.binary_search(cell).is_err())
+            .collect();
+        assert_eq!(sorted_single_ids(&set, 20), remaining);
+        assert!(set.iter().all(|id| !sphere.intersects(&id)));
+    }
+}
@@ -0,0 +1,331 @@
+//! `sample/tran1.txt` のような、空間 ID の文字列表現を並べたテキストの読み書き。
+//!
+//! 各 ID は [`RangeId`] の [`FromStr`](core::str::FromStr) が受け付ける
+//! `"{z}/{f}/{x}/{y}"`（各次元は `lo:hi` の範囲でもよい、末尾に `_{i}/{t}` の時間を付けてもよい）
+//! 形式で、カンマ・改行・空白のいずれかで区切る。区切りが連続した箇所や末尾の区切りは無視する。
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::io::{BufRead, BufReader, Read, Write};
+
+use crate::{Error, RangeId, SpatialIdError, SpatialIdSet};
+
+/// 空間 ID を並べたテキストを `std::io::Read` から少しずつ読み、[`RangeId`] を 1 件ずつ返すリーダ。
+///
+/// 解釈できない ID に出会うと、その ID の文字列と先頭のバイト位置を持つ
+/// [`SpatialIdError::ParseSpatialIdFormat`] を返し、以降は何も返さない。`reader` からの読み込みに
+/// 失敗した場合も同じく、元の種類を保った [`Error::Io`] を返して止まる。
+///
+/// # 動作例
+///
+/// `Vec<RangeId>` へ読み込む:
+/// ```
+/// # use kasane_logic::{RangeId, RangeIdTextReader};
+/// let text = "24/-1/14898522/6608285,24/-1/14898522:14898523/6608286:6608287,\n";
+/// let ids = RangeIdTextReader::new(text.as_bytes())
+///     .collect::<Result<Vec<RangeId>, _>>()
+///     .unwrap();
+/// assert_eq!(ids.len(), 2);
+/// assert_eq!(ids[1].x(), [14898522, 14898523]);
+/// ```
+///
+/// 不正な ID の位置を知る:
+/// ```
+/// # use kasane_logic::{Error, RangeIdTextReader, SpatialIdError};
+/// let text = "3/0/1/1, 3/0/9/1";
+/// let err = RangeIdTextReader::new(text.as_bytes())
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap_err();
+/// assert_eq!(
+///     err,
+///     Error::SpatialId(SpatialIdError::ParseSpatialIdFormat {
+///         kind: "RangeId",
+///         input: "3/0/9/1".to_string(),
+///         offset: Some(9),
+///     })
+/// );
+/// ```
+pub struct RangeIdTextReader<R> {
+    reader: BufReader<R>,
+    /// 次に読むバイトの位置。
+    offset: usize,
+    token: Vec<u8>,
+    done: bool,
+}
+
+impl<R: Read> RangeIdTextReader<R> {
+    /// `reader` から読むリーダを作る。内部でバッファするので、`BufReader` で包む必要は無い。
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            offset: 0,
+            token: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// 次の区切りまでを `self.token` へ読み、ID の先頭位置を返す。入力の終端なら `None`。
+    fn next_token(&mut self) -> Result<Option<usize>, Error> {
+        self.token.clear();
+        let mut start = None;
+        loop {
+            let buf = self.reader.fill_buf()?;
+            if buf.is_empty() {
+                return Ok(start);
+            }
+
+            let mut used = 0;
+            let mut ended = false;
+            for &byte in buf {
+                used += 1;
+                if is_separator(byte) {
+                    if start.is_some() {
+                        ended = true;
+                        break;
+                    }
+                } else {
+                    start.get_or_insert(self.offset + used - 1);
+                    self.token.push(byte);
+                }
+            }
+            self.reader.consume(used);
+            self.offset += used;
+            if ended {
+                return Ok(start);
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for RangeIdTextReader<R> {
+    type Item = Result<RangeId, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = match self.next_token() {
+            Ok(None) => {
+                self.done = true;
+                return None;
+            }
+            Ok(Some(start)) => parse_token(&self.token, start),
+            Err(e) => Err(e),
+        };
+        if result.is_err() {
+            self.done = true;
+        }
+        Some(result)
+    }
+}
+
+fn is_separator(byte: u8) -> bool {
+    byte == b',' || byte.is_ascii_whitespace()
+}
+
+/// 1 件分の文字列を [`RangeId`] へ変換する。範囲外の値なども含め、失敗はすべて
+/// 位置付きの [`SpatialIdError::ParseSpatialIdFormat`] として返す。
+fn parse_token(token: &[u8], offset: usize) -> Result<RangeId, Error> {
+    core::str::from_utf8(token)
+        .ok()
+        .and_then(|text| text.parse::<RangeId>().ok())
+        .ok_or_else(|| {
+            SpatialIdError::ParseSpatialIdFormat {
+                kind: "RangeId",
+                input: String::from_utf8_lossy(token).into_owned(),
+                offset: Some(offset),
+            }
+            .into()
+        })
+}
+
+impl SpatialIdSet {
+    /// 空間 ID を並べたテキストを読み、集合を作る。形式は [`RangeIdTextReader`] を参照。
+    ///
+    /// # 動作例
+    ///
+    /// ```
+    /// # use kasane_logic::SpatialIdSet;
+    /// let set = SpatialIdSet::read_text("3/0/1/1\n3/0/4/5\n".as_bytes()).unwrap();
+    /// assert_eq!(set.count(), 2);
+    /// ```
+    pub fn read_text<R: Read>(reader: R) -> Result<Self, Error> {
+        let mut set = Self::new();
+        for range_id in RangeIdTextReader::new(reader) {
+            set.insert(range_id?);
+        }
+        Ok(set)
+    }
+
+    /// [`range_ids`](Self::range_ids) をカンマ区切りのテキストとして書き出す。
+    ///
+    /// 出力は [`read_text`](Self::read_text) でそのまま読み戻せる。1 件ずつ書くので、
+    /// ファイル等へ書くときは [`std::io::BufWriter`] で包むこと。書き込みに失敗した場合は
+    /// 元の種類を保った [`Error::Io`] を返す。
+    pub fn write_text<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        for (i, range_id) in self.range_ids().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            write!(writer, "{separator}{range_id}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RangeIdTextReader;
+    use crate::{Error, RangeId, SingleId, SpatialIdError, SpatialIdSet};
+    use alloc::vec::Vec;
+
+    fn read_all(text: &str) -> Result<Vec<RangeId>, Error> {
+        RangeIdTextReader::new(text.as_bytes()).collect()
+    }
+
+    fn parse_error_at(text: &str) -> (alloc::string::String, Option<usize>) {
+        match read_all(text) {
+            Err(Error::SpatialId(SpatialIdError::ParseSpatialIdFormat {
+                input, offset, ..
+            })) => (input, offset),
+            other => panic!("unexpected: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn accepts_mixed_separators() {
+        let ids = read_all(" 3/0/1/1,\n3/-2:1/2/3\t\r\n,, 3/0/4/5 ").unwrap();
+        assert_eq!(
+            ids,
+            [
+                RangeId::new(3, [0, 0], [1, 1], [1, 1]).unwrap(),
+                RangeId::new(3, [-2, 1], [2, 2], [3, 3]).unwrap(),
+                RangeId::new(3, [0, 0], [4, 4], [5, 5]).unwrap(),
+            ]
+        );
+        assert!(read_all("").unwrap().is_empty());
+        assert!(read_all(",\n ").unwrap().is_empty());
+    }
+
+    #[cfg(feature = "temporal_id")]
+    #[test]
+    fn accepts_time_suffix() {
+        use crate::SpatialId;
+        let ids = read_all("3/0/1/1_60/10:12,3/0/1/1").unwrap();
+        assert_eq!(ids[0].t(), [10, 12]);
+        assert!(!ids[0].is_whole_time());
+        assert!(ids[1].is_whole_time());
+    }
+
+    #[test]
+    fn reports_token_and_offset() {
+        assert_eq!(
+            parse_error_at("3/0/1/1,3/0/x/1"),
+            ("3/0/x/1".into(), Some(8))
+        );
+        // 範囲外の値も同じく位置付きで返す。
+        assert_eq!(
+            parse_error_at("3/0/1/1\n\n  3/0/8/1"),
+            ("3/0/8/1".into(), Some(11))
+        );
+        // UTF-8 として不正なバイトも扱える。
+        let mut bytes = b"3/0/1/1,3/\xff/1/1".to_vec();
+        bytes.push(b',');
+        let err = RangeIdTextReader::new(bytes.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::SpatialId(SpatialIdError::ParseSpatialIdFormat {
+                offset: Some(8),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn stops_after_first_error() {
+        let mut reader = RangeIdTextReader::new("3/0/1/1,bad,3/0/2/2".as_bytes());
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+
+    /// 内部バッファの境界をまたぐ ID も正しく読めること。
+    #[test]
+    fn tokens_spanning_buffer_boundaries() {
+        let ids: Vec<RangeId> = (0..2000u32)
+            .map(|x| RangeId::new(24, [-1, -1], [x, x + 1], [7, 7]).unwrap())
+            .collect();
+        let text = ids
+            .iter()
+            .map(|id| alloc::format!("{id}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        assert!(text.len() > 8 * 1024);
+        assert_eq!(read_all(&text).unwrap(), ids);
+    }
+
+    #[test]
+    fn write_then_read_round_trips() {
+        let mut set = SpatialIdSet::new();
+        set.insert(RangeId::new(10, [-5, 3], [1, 40], [2, 2]).unwrap());
+        set.insert(SingleId::new(24, -1, 14898522, 6608285).unwrap());
+
+        let mut text = Vec::new();
+        set.write_text(&mut text).unwrap();
+        assert!(!text.ends_with(b","));
+        assert_eq!(SpatialIdSet::read_text(text.as_slice()).unwrap(), set);
+
+        let mut empty = Vec::new();
+        SpatialIdSet::new().write_text(&mut empty).unwrap();
+        assert!(empty.is_empty());
+    }
+
+    /// 読み書きの失敗は、元の種類を保った [`Error::Io`] になること。
+    #[test]
+    fn io_failures_keep_their_kind() {
+        struct Broken;
+        impl std::io::Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::ConnectionReset.into())
+            }
+        }
+        impl std::io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::StorageFull.into())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        assert!(matches!(
+            SpatialIdSet::read_text(Broken),
+            Err(Error::Io {
+                kind: std::io::ErrorKind::ConnectionReset,
+                ..
+            })
+        ));
+        let mut set = SpatialIdSet::new();
+        set.insert(SingleId::new(3, 0, 1, 1).unwrap());
+        assert!(matches!(
+            set.write_text(Broken),
+            Err(Error::Io {
+                kind: std::io::ErrorKind::StorageFull,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn loads_sample_tran1() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/sample/tran1.txt");
+        let file = std::fs::File::open(path).unwrap();
+        let set = SpatialIdSet::read_text(file).unwrap();
+        let probe = SingleId::new(24, -1, 14898522, 6608285).unwrap();
+        assert!(set.get_overlapping(&probe).next().is_some());
+
+        let mut text = Vec::new();
+        set.write_text(&mut text).unwrap();
+        assert_eq!(SpatialIdSet::read_text(text.as_slice()).unwrap(), set);
+    }
+}
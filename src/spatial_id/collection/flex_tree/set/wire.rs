@@ -0,0 +1,126 @@
+//! [`SpatialIdSet`] のワイヤ形式（[`super::super::wire`]）への書き出しと復元。
+
+use alloc::vec::Vec;
+
+use super::super::wire::{decode, encode};
+use crate::{Error, SpatialIdSet};
+
+impl SpatialIdSet {
+    /// ワイヤ形式のバイト列へ書き出す。値の辞書は持たない。
+    ///
+    /// # 動作例
+    ///
+    /// 書き出して復元する:
+    /// ```
+    /// # use kasane_logic::{RangeId, SpatialIdSet};
+    /// let mut set = SpatialIdSet::new();
+    /// set.insert(RangeId::new(20, [0, 0], [1000, 1031], [2000, 2031]).unwrap());
+    ///
+    /// let bytes = set.to_wire();
+    /// assert_eq!(SpatialIdSet::from_wire(&bytes).unwrap(), set);
+    /// ```
+    pub fn to_wire(&self) -> Vec<u8> {
+        encode(self.iter().map(|id| (id, 0)), None)
+    }
+
+    /// [`to_wire`](Self::to_wire) で書き出したバイト列から集合を復元する。
+    ///
+    /// 値の辞書を持つバイト列（[`SpatialIdTable::to_wire`](crate::SpatialIdTable::to_wire)）も
+    /// 読め、その場合は値を捨てて占有だけを取り出す。
+    pub fn from_wire(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(decode(bytes)?
+            .records
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, RangeId, SingleId, SpatialIdSet};
+
+    fn dense() -> SpatialIdSet {
+        let mut set = SpatialIdSet::new();
+        for x in 0..40u32 {
+            for y in 0..25u32 {
+                set.insert(SingleId::new(22, 3, 1_000_003 + x, 2_000_001 + y * 3).unwrap());
+            }
+        }
+        set
+    }
+
+    #[test]
+    fn round_trips_mixed_zooms_and_both_hemispheres() {
+        let mut set = dense();
+        set.insert(RangeId::new(5, [-3, 2], [1, 4], [8, 9]).unwrap());
+        set.insert(SingleId::new(0, -1, 0, 0).unwrap());
+        let bytes = set.to_wire();
+        assert_eq!(SpatialIdSet::from_wire(&bytes).unwrap(), set);
+    }
+
+    #[test]
+    fn round_trips_empty() {
+        let bytes = SpatialIdSet::new().to_wire();
+        assert!(SpatialIdSet::from_wire(&bytes).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "temporal_id")]
+    #[test]
+    fn round_trips_time() {
+        let mut set = dense();
+        let base = SingleId::new(12, 0, 3638, 1614).unwrap();
+        for t in [0u64, 1, 5, 6] {
+            set.insert(base.clone().with_time(crate::Interval::HOUR, t).unwrap());
+        }
+        set.insert(base.with_time(1800, 809712).unwrap());
+        let bytes = set.to_wire();
+        assert_eq!(SpatialIdSet::from_wire(&bytes).unwrap(), set);
+    }
+
+    /// 密な集合では、JSON の 1/10 未満に収まること。
+    #[cfg(feature = "json")]
+    #[test]
+    fn is_an_order_of_magnitude_smaller_than_json() {
+        let set = dense();
+        let json = serde_json::to_string(&set).unwrap();
+        let wire = set.to_wire();
+        assert!(
+            wire.len() * 10 < json.len(),
+            "wire={} json={}",
+            wire.len(),
+            json.len()
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let bytes = dense().to_wire();
+        for len in [0, 3, 6, bytes.len() / 2, bytes.len() - 1] {
+            assert!(
+                matches!(
+                    SpatialIdSet::from_wire(&bytes[..len]),
+                    Err(Error::Persist(_))
+                ),
+                "len={len}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_other_version_and_trailing_bytes() {
+        let mut bytes = dense().to_wire();
+        bytes.push(0);
+        assert!(matches!(
+            SpatialIdSet::from_wire(&bytes),
+            Err(Error::Persist(_))
+        ));
+
+        bytes.pop();
+        bytes[4] = crate::WIRE_VERSION + 1;
+        assert!(matches!(
+            SpatialIdSet::from_wire(&bytes),
+            Err(Error::UnsupportedFormatVersion { .. })
+        ));
+    }
+}
@@ -0,0 +1,180 @@
+//! [`SpatialIdTable`](crate::SpatialIdTable) のバイト列（[`super::arena`]）を、作業木を
+//! 再構築せず直接読む ZeroCopy リーダ。
+//!
+//! 空間側の走査は [`ArchivedSpatialIdMap`] に委ねる。値側の問い合わせ
+//! （[`value_get`](ArchivedSpatialIdTable::value_get) /
+//! [`value_range`](ArchivedSpatialIdTable::value_range)）は、辞書が値の昇順に並んでいる
+//! ことを使って対象の辞書インデックスを二分探索で絞り、木を 1 回走査して集める。
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ops::{Bound, RangeBounds};
+use hashbrown::HashSet;
+
+use crate::spatial_id::collection::flex_tree::map::archived::ArchivedSpatialIdMap;
+use crate::spatial_id::collection::query::cancellation::CancellationToken;
+use crate::spatial_id::collection::query::source::Source;
+use crate::spatial_id::collection::query::working::WorkingTree;
+use crate::{Error, FlexId, RangeId, SpatialId};
+
+/// [`SpatialIdTable::to_bytes`](crate::SpatialIdTable::to_bytes) が生成したバイト列に対する、
+/// 読み取り専用の ZeroCopy リーダ。値は `&[u8]` のまま返す。
+///
+/// 書き込みが必要なら [`SpatialIdTable::from_bytes`](crate::SpatialIdTable::from_bytes) で
+/// テーブルへ復元すること。
+pub struct ArchivedSpatialIdTable<'a> {
+    inner: ArchivedSpatialIdMap<'a>,
+}
+
+impl<'a> ArchivedSpatialIdTable<'a> {
+    /// archived バイト列上にリーダを開く。
+    ///
+    /// 検証の範囲は [`ArchivedSpatialIdMap::access`] と同じで、形式バージョンと
+    /// レイアウトフラグだけを確かめる。
+    ///
+    /// # Safety
+    /// `bytes` は [`crate::SpatialIdTable::to_bytes`] が生成した正当なバイト列でなければならない。
+    /// 辞書が値の昇順に並んでいることを前提にするため、
+    /// [`SpatialIdMap::to_bytes`](crate::SpatialIdMap::to_bytes) のバイト列を渡すと
+    /// 値側の問い合わせが誤った結果を返す。
+    pub unsafe fn access(bytes: &'a [u8]) -> Result<Self, Error> {
+        Ok(Self {
+            inner: unsafe { ArchivedSpatialIdMap::access(bytes) }?,
+        })
+    }
+
+    /// このバイト列に書かれている形式バージョン。
+    pub fn format_version(&self) -> u16 {
+        self.inner.format_version()
+    }
+
+    /// 指定した空間IDと重なる領域を切り出し、値と共に返す。
+    ///
+    /// インメモリの [`SpatialIdTable::get`](crate::SpatialIdTable::get) と同じ意味論。
+    pub fn get<S: SpatialId>(&self, target: &S) -> Vec<(FlexId, &'a [u8])> {
+        let mut out = Vec::new();
+        for flex_id in target.clone() {
+            self.inner.get_indexed(&flex_id, |id, packed| {
+                out.push((id, self.inner.value_bytes(packed)));
+            });
+        }
+        out
+    }
+
+    /// 指定した空間IDと重なった [`FlexId`] と値を、切り取らずにそのまま返す。
+    ///
+    /// インメモリの [`SpatialIdTable::get_overlapping`](crate::SpatialIdTable::get_overlapping)
+    /// と同じ意味論で、`target` が複数のSegmentに分かれていても同じ葉は1度だけ返す。
+    pub fn get_overlapping<S: SpatialId>(&self, target: &S) -> Vec<(FlexId, &'a [u8])> {
+        let mut seen = HashSet::new();
+        let mut out = Vec::new();
+        for flex_id in target.clone() {
+            self.inner.get_overlapping_indexed(&flex_id, |id, packed| {
+                if seen.insert(id) {
+                    out.push((id, self.inner.value_bytes(packed)));
+                }
+            });
+        }
+        out
+    }
+
+    /// `target`（範囲）と重なる (FlexId, 値) を、切り取らずにそのまま返す。
+    pub fn get_range(&self, target: &RangeId) -> Vec<(FlexId, &'a [u8])> {
+        self.inner.get_range(target)
+    }
+
+    /// 特定の値に対応するすべての [`FlexId`] を返す。
+    ///
+    /// 値の特定は辞書上の二分探索だが、該当する葉を集めるために木を 1 回走査するので、
+    /// コストは葉の数に比例する。
+    pub fn value_get(&self, value: &[u8]) -> Vec<FlexId> {
+        let dictionary = self.inner.dictionary();
+        let Ok(idx) = dictionary.binary_search_by(|v| v.as_slice().cmp(value)) else {
+            return Vec::new();
+        };
+        let wanted = idx as u32 + 1;
+
+        let mut out = Vec::new();
+        self.inner.for_each_indexed(|id, packed| {
+            if packed == wanted {
+                out.push(id);
+            }
+        });
+        out
+    }
+
+    /// 範囲条件に一致する全ての値の [`FlexId`] と値を返す。
+    ///
+    /// [`value_get`](Self::value_get) と同じく、コストは葉の数に比例する。
+    ///
+    /// # 動作例
+    ///
+    /// 値の範囲で引く:
+    /// ```
+    /// # use kasane_logic::{ArchivedSpatialIdTable, SingleId, SpatialIdTable};
+    /// let mut table = SpatialIdTable::new();
+    /// table.insert(SingleId::new(20, 0, 1, 1).unwrap(), vec![1u8]);
+    /// table.insert(SingleId::new(20, 0, 2, 2).unwrap(), vec![5u8]);
+    /// table.insert(SingleId::new(20, 0, 3, 3).unwrap(), vec![9u8]);
+    ///
+    /// let bytes = table.to_bytes().unwrap();
+    /// let archived = unsafe { ArchivedSpatialIdTable::access(&bytes) }.unwrap();
+    /// let hits = archived.value_range([2u8].as_slice()..[9u8].as_slice());
+    /// assert_eq!(hits.len(), 1);
+    /// assert_eq!(hits[0].1, [5u8].as_slice());
+    /// ```
+    pub fn value_range<'r, R: RangeBounds<&'r [u8]>>(&self, range: R) -> Vec<(FlexId, &'a [u8])> {
+        let dictionary = self.inner.dictionary();
+        let start = match range.start_bound() {
+            Bound::Included(lo) => dictionary.partition_point(|v| v.as_slice() < *lo),
+            Bound::Excluded(lo) => dictionary.partition_point(|v| v.as_slice() <= *lo),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(hi) => dictionary.partition_point(|v| v.as_slice() <= *hi),
+            Bound::Excluded(hi) => dictionary.partition_point(|v| v.as_slice() < *hi),
+            Bound::Unbounded => dictionary.len(),
+        };
+        if start >= end {
+            return Vec::new();
+        }
+        // 辞書インデックスは 1 始まりなので、`[start, end)` は packed では `(start, end]`。
+        let (lo, hi) = (start as u32, end as u32);
+
+        let mut out = Vec::new();
+        self.inner.for_each_indexed(|id, packed| {
+            if lo < packed && packed <= hi {
+                out.push((id, self.inner.value_bytes(packed)));
+            }
+        });
+        out
+    }
+
+    /// テーブルに保持されている値を昇順に返す。
+    pub fn values(&self) -> impl Iterator<Item = &'a [u8]> + 'a {
+        self.inner.dictionary().iter().map(|v| v.as_slice())
+    }
+}
+
+/// バイト列を読みながらクエリの入力源になる。
+///
+/// 意味論は [`SpatialIdTable`](crate::SpatialIdTable) の [`Source`] 実装と同じで、
+/// [`read_range_ids`](Source::read_range_ids) は `bounds` と重なる部分木だけを降りる。
+impl<'a> Source for ArchivedSpatialIdTable<'a> {
+    type Value = Vec<u8>;
+
+    fn read_range_ids(
+        &self,
+        bounds: &[RangeId],
+        token: &CancellationToken,
+    ) -> Result<WorkingTree<Vec<u8>>, Error> {
+        self.inner.read_range_ids_with(bounds, token, |packed| {
+            self.inner.value_bytes(packed).to_vec()
+        })
+    }
+
+    fn read_all(self: Box<Self>, token: &CancellationToken) -> Result<WorkingTree<Vec<u8>>, Error> {
+        self.inner
+            .read_all_with(token, |idx, dictionary| dictionary[idx].to_vec())
+    }
+}
@@ -0,0 +1,91 @@
+//! [`SpatialIdTable`] のバイト列表現と、その write / read 側変換。
+//!
+//! 形式は [`SpatialIdMap`](crate::SpatialIdMap) と同じフラットアリーナ
+//! （[`map::arena`](crate::spatial_id::collection::flex_tree::map::arena)）をそのまま使う。
+//! テーブル固有の約束は 1 つだけで、**辞書を値の昇順に並べて書く**。これにより
+//! 辞書インデックスの大小が値の大小と一致し、ZeroCopy リーダ（[`super::archived`]）が
+//! `value_get` / `value_range` を辞書上の二分探索で答えられる。
+//!
+//! 木が持つ rank はそのまま書かず、辞書インデックス（1始まり）へ振り直す。上書き等で
+//! 木から消えた rank の値は辞書へ載せない。
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use super::SpatialIdTable;
+use crate::Error;
+use crate::spatial_id::collection::flex_tree::map::archived::ArchivedSpatialIdMap;
+use crate::spatial_id::collection::flex_tree::map::arena::{
+    access_checked, read_arena, rebuild_core, write_arena,
+};
+
+impl SpatialIdTable<Vec<u8>> {
+    /// この [`SpatialIdTable`] をフラットアリーナ形式のバイト列へ直列化する。
+    ///
+    /// 形式は [`SpatialIdMap::to_bytes`](crate::SpatialIdMap::to_bytes) と同じで、値の辞書は
+    /// 値の昇順に並ぶ。木を 2 回走査する（生きている rank の収集と書き出し）。
+    ///
+    /// # 動作例
+    ///
+    /// 直列化して復元する:
+    /// ```
+    /// # use kasane_logic::{SingleId, SpatialIdTable};
+    /// let mut table = SpatialIdTable::new();
+    /// table.insert(SingleId::new(20, 0, 10, 10).unwrap(), b"warehouse".to_vec());
+    ///
+    /// let bytes = table.to_bytes().unwrap();
+    /// let restored = unsafe { SpatialIdTable::<Vec<u8>>::from_bytes(&bytes) }.unwrap();
+    /// assert_eq!(table, restored);
+    /// ```
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let live: BTreeSet<usize> = self.inner.iter_ref().map(|(_, rank)| *rank).collect();
+
+        let mut entries: Vec<(&Vec<u8>, usize)> = self
+            .reverse_dictionary
+            .iter()
+            .filter(|(rank, _)| live.contains(rank))
+            .map(|(rank, value)| (value, *rank))
+            .collect();
+        entries.sort();
+
+        // 値が等しい rank（`map_values_in_place` で写った結果など）は同じ辞書エントリへ畳む。
+        let mut dictionary: Vec<Vec<u8>> = Vec::new();
+        let mut packed_by_rank = alloc::vec![0u32; self.current_rank + 1];
+        for (value, rank) in entries {
+            if dictionary.last() != Some(value) {
+                dictionary.push(value.clone());
+            }
+            packed_by_rank[rank] = dictionary.len() as u32;
+        }
+
+        write_arena(&self.inner, dictionary, |rank, _| packed_by_rank[*rank])
+    }
+
+    /// [`to_bytes`](Self::to_bytes) で直列化したバイト列からテーブルを復元する。
+    ///
+    /// 値インデックスは未構築の状態で返る（[`insert`](Self::insert) 直後と同じ）。
+    /// 形式バージョンやレイアウトフラグが異なる場合のエラーは
+    /// [`SpatialIdMap::from_bytes`](crate::SpatialIdMap::from_bytes) と同じ。
+    ///
+    /// # Safety
+    /// `bytes` は [`SpatialIdTable::to_bytes`] が生成した正当なバイト列でなければならない。
+    pub unsafe fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        // 辞書インデックス（1始まり）をそのまま rank として使う。
+        let ranks = unsafe { read_arena(bytes, |idx, _| idx + 1) }?;
+        let archived = unsafe { ArchivedSpatialIdMap::access(bytes) }?;
+        let values = archived.dictionary().iter().map(|v| v.to_vec()).collect();
+        Ok(Self::from_ranked_core(ranks, values))
+    }
+
+    /// [`from_bytes`](Self::from_bytes) の検査付き版。ディスク上のスナップショットのように
+    /// 信頼できないバイト列から復元するときに使う。
+    ///
+    /// レイアウトとノードの参照関係を検証してから組み直すので、壊れた・細工されたバイト列でも
+    /// 未定義動作にはならずエラーを返す。
+    pub(crate) fn from_bytes_checked(bytes: &[u8]) -> Result<Self, Error> {
+        let archived = access_checked(bytes)?;
+        let ranks = rebuild_core(archived, |idx, _| idx + 1)?;
+        let values = archived.dictionary.iter().map(|v| v.to_vec()).collect();
+        Ok(Self::from_ranked_core(ranks, values))
+    }
+}
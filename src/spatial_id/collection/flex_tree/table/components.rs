@@ -0,0 +1,35 @@
+//! [`SpatialIdTable`] の空間を連結成分に分ける。
+
+use alloc::vec::Vec;
+
+use super::super::components::{self, ComponentStats};
+use crate::spatial_id::collection::flex_tree::core::ptr::SafeValue;
+use crate::{Connectivity, SpatialIdTable};
+
+impl<V> SpatialIdTable<V>
+where
+    V: SafeValue + Ord,
+{
+    /// 値のある空間を、[`SpatialIdSet::connected_components`](crate::SpatialIdSet::connected_components)
+    /// と同じ方法で連結成分に分ける。値は見ない。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 値が違っても接していれば 1 つの成分になる
+    /// ```
+    /// # use kasane_logic::{Connectivity, SingleId, SpatialIdTable};
+    /// let mut table = SpatialIdTable::new();
+    /// table.insert(SingleId::new(20, 0, 931000, 412000).unwrap(), "wall");
+    /// table.insert(SingleId::new(20, 0, 931001, 412001).unwrap(), "tower");
+    ///
+    /// assert_eq!(table.connected_components(Connectivity::Face).1.len(), 2);
+    /// assert_eq!(table.connected_components(Connectivity::Edge).1.len(), 1);
+    /// ```
+    pub fn connected_components(
+        &self,
+        connectivity: Connectivity,
+    ) -> (SpatialIdTable<u32>, Vec<ComponentStats>) {
+        let (labels, stats) = components::connected_components(&self.inner, connectivity);
+        (labels.iter().collect(), stats)
+    }
+}
@@ -0,0 +1,83 @@
+//! [`SpatialIdTable`] の可視化用の書き出し。
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+use super::super::export;
+use crate::spatial_id::collection::flex_tree::core::ptr::SafeValue;
+use crate::{Error, SpatialIdTable};
+
+impl<V> SpatialIdTable<V>
+where
+    V: SafeValue + Ord,
+{
+    /// [`range_ids`](Self::range_ids) を GeoJSON の `FeatureCollection` として書き出す。
+    ///
+    /// 形式は [`SpatialIdSet::to_geojson`](crate::SpatialIdSet::to_geojson) と同じで、
+    /// 各 `Feature` は値を `value` プロパティに持つ。JSON にできない値があれば [`Error::Json`] を返す。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: テーブルを GeoJSON にする
+    /// ```
+    /// # use kasane_logic::{SingleId, SpatialIdTable};
+    /// let mut table = SpatialIdTable::new();
+    /// table.insert(SingleId::new(20, 0, 931000, 412000).unwrap(), "warehouse".to_string());
+    ///
+    /// let geojson = table.to_geojson().unwrap();
+    /// assert!(geojson.contains(r#""value":"warehouse""#));
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn to_geojson(&self) -> Result<String, Error>
+    where
+        V: serde::Serialize,
+    {
+        use alloc::string::ToString;
+
+        let items = self
+            .range_ids()
+            .map(|(range_id, value)| {
+                let value = serde_json::to_value(value).map_err(|e| Error::Json(e.to_string()))?;
+                Ok((range_id, Some(value)))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(export::geojson(items.into_iter()))
+    }
+
+    /// [`range_ids`](Self::range_ids) を Wavefront OBJ として書き出す。
+    ///
+    /// 形式は [`SpatialIdSet::to_obj`](crate::SpatialIdSet::to_obj) と同じで、各直方体は
+    /// 値ごとのグループ `value_{n}`（`n` は [`values`](Self::values) での順位）に入れる。
+    /// 各グループが表す値は、先頭のコメント行に `Debug` 形式で書く。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 値ごとにグループを分ける
+    /// ```
+    /// # use kasane_logic::{SingleId, SpatialIdTable};
+    /// let mut table = SpatialIdTable::new();
+    /// table.insert(SingleId::new(20, 0, 931000, 412000).unwrap(), 10u32);
+    /// table.insert(SingleId::new(20, 0, 931005, 412000).unwrap(), 20u32);
+    ///
+    /// let obj = table.to_obj();
+    /// assert!(obj.contains("# value_1 = 20"));
+    /// assert_eq!(obj.lines().filter(|l| l.starts_with("g ")).count(), 2);
+    /// ```
+    pub fn to_obj(&self) -> String
+    where
+        V: Debug,
+    {
+        let values: Vec<&V> = self.values().collect();
+        let descriptions: Vec<String> = values.iter().map(|value| format!("{value:?}")).collect();
+        export::obj(
+            self.range_ids().map(|(range_id, value)| {
+                let n = values
+                    .binary_search(&value)
+                    .expect("ツリー内の値は必ず values() にある");
+                (range_id, Some(n))
+            }),
+            &descriptions,
+        )
+    }
+}
@@ -0,0 +1,591 @@
+//! [`SpatialIdTable`] の JSON を、文書全体をメモリへ載せずに読み書きするストリーム版。
+//!
+//! 形式は `Serialize`/`Deserialize` 実装（[`super::json`]）と同じ v1.0 スキーマで、
+//! `f`/`x`/`y`/`t` の `[lo]`/`[lo,hi]` 省略や `ref` による値の辞書も同じ規則に従う。
+//! 違いは中間表現を持たないことで、書き出しは [`range_ids_in`](SpatialIdTable::range_ids_in)
+//! の列を 1 件ずつ `std::io::Write` へ流し、読み込みは `ids` の要素を 1 件ずつ復元して
+//! 一定件数ごとに呼び出し側へ渡す。
+//!
+//! 値の辞書（`value`）は、書き出しでは値の昇順に並べ、`ids` より前に置く。読み込みでは
+//! どちらが先でも受け付けるが、`ids` が先に来た場合は `value` が現れるまで `(RangeId, ref)`
+//! を溜めておくことになるので、ストリームの利点は失われる。
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use std::io::{Read, Write};
+
+use serde::Serialize;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor,
+};
+
+use super::super::json::{IdEntry, Meta, SCHEMA_URL};
+use crate::spatial_id::collection::flex_tree::core::ptr::SafeValue;
+use crate::{AllowedIntervals, Error, RangeId, SpatialIdTable};
+
+/// JSON を `std::io::Read` から少しずつ読み、`(RangeId, V)` をバッチで渡すリーダ。
+///
+/// `serde_json` は入力を 1 byte ずつ読むので、ファイル等を渡すときは
+/// [`std::io::BufReader`] で包むこと。
+///
+/// # 動作例
+///
+/// バッチごとに受け取る:
+/// ```
+/// # use kasane_logic::{JsonStreamReader, SingleId, SpatialIdTable};
+/// let mut table = SpatialIdTable::new();
+/// for x in 0..10u32 {
+///     table.insert(SingleId::new(20, 0, x, 0).unwrap(), x % 3);
+/// }
+/// let mut json = Vec::new();
+/// table.write_json(&mut json).unwrap();
+///
+/// let mut batches = 0;
+/// let total = JsonStreamReader::new(json.as_slice())
+///     .batch_size(4)
+///     .for_each_batch(|batch: Vec<(_, u32)>| {
+///         assert!(batch.len() <= 4);
+///         batches += 1;
+///         Ok(())
+///     })
+///     .unwrap();
+/// assert_eq!(total, 10);
+/// assert_eq!(batches, 3);
+/// ```
+pub struct JsonStreamReader<R> {
+    reader: R,
+    batch_size: usize,
+}
+
+impl<R: Read> JsonStreamReader<R> {
+    /// 1 バッチの既定の件数。
+    pub const DEFAULT_BATCH_SIZE: usize = 4096;
+
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            batch_size: Self::DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// 1 バッチの件数を変える。`0` は `1` として扱う。
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// 文書を読みながら、`batch_size` 件たまるごとに `on_batch` を呼ぶ。読んだ総件数を返す。
+    ///
+    /// `on_batch` が `Err` を返すと、そこで読み込みを止めてその `Err` を返す。JSON として
+    /// 不正な場合や、`ref` が辞書の範囲外の場合は [`Error::Json`] を、`reader` からの読み込みに
+    /// 失敗した場合は [`Error::Io`] を返す。
+    pub fn for_each_batch<V, F>(self, on_batch: F) -> Result<usize, Error>
+    where
+        V: DeserializeOwned + Clone,
+        F: FnMut(Vec<(RangeId, V)>) -> Result<(), Error>,
+    {
+        let mut sink = Sink {
+            batch: Vec::new(),
+            batch_size: self.batch_size,
+            on_batch,
+            values: None,
+            pending: Vec::new(),
+            total: 0,
+            error: None,
+        };
+
+        let mut deserializer = serde_json::Deserializer::from_reader(self.reader);
+        let result = EnvelopeSeed(&mut sink)
+            .deserialize(&mut deserializer)
+            .and_then(|()| deserializer.end());
+        if let Some(error) = sink.error.take() {
+            return Err(error);
+        }
+        result.map_err(json_error)?;
+
+        sink.flush()?;
+        Ok(sink.total)
+    }
+
+    /// 文書を読みながら、バッチごとに `table` へ挿入する。読んだ総件数を返す。
+    ///
+    /// 挿入は文書の順に [`SpatialIdTable::insert`] で行うので、重なる ID は後勝ちになる
+    /// （[`Deserialize`](serde::Deserialize) 実装と同じ）。
+    pub fn read_into<V>(self, table: &mut SpatialIdTable<V>) -> Result<usize, Error>
+    where
+        V: SafeValue + Ord + DeserializeOwned,
+    {
+        self.for_each_batch(|batch: Vec<(RangeId, V)>| {
+            for (range_id, value) in batch {
+                table.insert(range_id, value);
+            }
+            Ok(())
+        })
+    }
+}
+
+impl<V> SpatialIdTable<V>
+where
+    V: SafeValue + Ord,
+{
+    /// v1.0 スキーマの JSON を `reader` から少しずつ読み、テーブルを組み立てる。
+    ///
+    /// [`JsonStreamReader::read_into`] の簡易版。
+    pub fn read_json<R: Read>(reader: R) -> Result<Self, Error>
+    where
+        V: DeserializeOwned,
+    {
+        let mut table = Self::new();
+        JsonStreamReader::new(reader).read_into(&mut table)?;
+        Ok(table)
+    }
+
+    /// v1.0 スキーマの JSON を、文書全体を組み立てずに `writer` へ書き出す。
+    ///
+    /// 内容は [`Serialize`] 実装と同じ（時間方向の結合と `{i}` の暦単位への正規化も同じ）で、
+    /// `value` の並びだけが値の昇順になる。小さな書き込みを多数行うので、ファイル等へ
+    /// 書くときは [`std::io::BufWriter`] で包むこと。
+    pub fn write_json<W: Write>(&self, mut writer: W) -> Result<(), Error>
+    where
+        V: Serialize,
+    {
+        let values: Vec<&V> = self.values().collect();
+        let units = AllowedIntervals::default();
+
+        write_raw(&mut writer, b"{\"$schema\":")?;
+        write_value(&mut writer, SCHEMA_URL)?;
+        write_raw(&mut writer, b",\"meta\":")?;
+        write_value(&mut writer, &Meta::v1())?;
+        write_raw(
+            &mut writer,
+            b",\"option\":{},\"data\":[{\"name\":\"\",\"value\":",
+        )?;
+        write_value(&mut writer, &values)?;
+        write_raw(&mut writer, b",\"ids\":[")?;
+        for (i, (range_id, value)) in self.range_ids_in(&units).enumerate() {
+            if i > 0 {
+                write_raw(&mut writer, b",")?;
+            }
+            let idx = values
+                .binary_search(&value)
+                .expect("ツリー内の値は必ず values() にある");
+            write_value(
+                &mut writer,
+                &IdEntry {
+                    range_id,
+                    r#ref: Some(idx),
+                },
+            )?;
+        }
+        write_raw(&mut writer, b"]}]}")?;
+        Ok(writer.flush()?)
+    }
+}
+
+fn write_raw<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), Error> {
+    Ok(writer.write_all(bytes)?)
+}
+
+fn write_value<W: Write, T: Serialize + ?Sized>(writer: &mut W, value: &T) -> Result<(), Error> {
+    serde_json::to_writer(writer, value).map_err(json_error)
+}
+
+/// `serde_json` のエラーを、入出力の失敗なら [`Error::Io`]、それ以外は [`Error::Json`] にする。
+fn json_error(error: serde_json::Error) -> Error {
+    match error.io_error_kind() {
+        Some(kind) => Error::Io {
+            kind,
+            message: error.to_string(),
+        },
+        None => Error::Json(error.to_string()),
+    }
+}
+
+/// 読み込み中の状態。`ids` の要素を 1 件ずつ受け取り、バッチにして `on_batch` へ渡す。
+struct Sink<V, F> {
+    batch: Vec<(RangeId, V)>,
+    batch_size: usize,
+    on_batch: F,
+    /// `value` を読み終えていれば `Some`。
+    values: Option<Vec<V>>,
+    /// `value` より先に現れた `ids` の要素。
+    pending: Vec<(RangeId, usize)>,
+    total: usize,
+    /// `on_batch` が返したエラー。serde のエラーへ畳まずに呼び出し側へ返すため、ここへ退避する。
+    error: Option<Error>,
+}
+
+impl<V, F> Sink<V, F>
+where
+    V: Clone,
+    F: FnMut(Vec<(RangeId, V)>) -> Result<(), Error>,
+{
+    fn push(&mut self, entry: IdEntry) -> Result<(), String> {
+        let idx = entry
+            .r#ref
+            .ok_or_else(|| "id entry is missing \"ref\"".to_string())?;
+        let Some(values) = &self.values else {
+            self.pending.push((entry.range_id, idx));
+            return Ok(());
+        };
+        let value = resolve(values, idx)?;
+        self.batch.push((entry.range_id, value));
+        if self.batch.len() >= self.batch_size {
+            self.flush_for_serde()?;
+        }
+        Ok(())
+    }
+
+    /// `value` が現れた時点で、溜めておいた要素を解決する。
+    fn set_values(&mut self, values: Vec<V>) -> Result<(), String> {
+        self.values = Some(values);
+        for (range_id, idx) in core::mem::take(&mut self.pending) {
+            self.push(IdEntry {
+                range_id,
+                r#ref: Some(idx),
+            })?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        self.total += self.batch.len();
+        (self.on_batch)(core::mem::take(&mut self.batch))
+    }
+
+    fn flush_for_serde(&mut self) -> Result<(), String> {
+        self.flush().map_err(|e| {
+            self.error = Some(e);
+            "aborted by batch callback".to_string()
+        })
+    }
+}
+
+fn resolve<V: Clone>(values: &[V], idx: usize) -> Result<V, String> {
+    values
+        .get(idx)
+        .cloned()
+        .ok_or_else(|| format!("\"ref\" index {idx} out of range"))
+}
+
+/// 文書の最上位。`data` 以外のキーは読み飛ばす。
+struct EnvelopeSeed<'s, V, F>(&'s mut Sink<V, F>);
+
+impl<'de, V, F> DeserializeSeed<'de> for EnvelopeSeed<'_, V, F>
+where
+    V: DeserializeOwned + Clone,
+    F: FnMut(Vec<(RangeId, V)>) -> Result<(), Error>,
+{
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, V, F> Visitor<'de> for EnvelopeSeed<'_, V, F>
+where
+    V: DeserializeOwned + Clone,
+    F: FnMut(Vec<(RangeId, V)>) -> Result<(), Error>,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a v1.0 spatial id document")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut seen_data = false;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "data" {
+                map.next_value_seed(DataSeed(&mut *self.0))?;
+                seen_data = true;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        if !seen_data {
+            return Err(de::Error::missing_field("data"));
+        }
+        Ok(())
+    }
+}
+
+/// `data` 配列。要素はちょうど 1 つでなければならない（[`Deserialize`](serde::Deserialize)
+/// 実装と同じ規則）。
+struct DataSeed<'s, V, F>(&'s mut Sink<V, F>);
+
+impl<'de, V, F> DeserializeSeed<'de> for DataSeed<'_, V, F>
+where
+    V: DeserializeOwned + Clone,
+    F: FnMut(Vec<(RangeId, V)>) -> Result<(), Error>,
+{
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, V, F> Visitor<'de> for DataSeed<'_, V, F>
+where
+    V: DeserializeOwned + Clone,
+    F: FnMut(Vec<(RangeId, V)>) -> Result<(), Error>,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a \"data\" array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let mut found = 0usize;
+        if seq.next_element_seed(EntrySeed(&mut *self.0))?.is_some() {
+            found += 1;
+        }
+        while seq.next_element::<IgnoredAny>()?.is_some() {
+            found += 1;
+        }
+        if found != 1 {
+            return Err(de::Error::custom(format!(
+                "expected \"data\" to contain exactly 1 entries, found {found}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// `data[0]`。`value` と `ids` を読む。
+struct EntrySeed<'s, V, F>(&'s mut Sink<V, F>);
+
+impl<'de, V, F> DeserializeSeed<'de> for EntrySeed<'_, V, F>
+where
+    V: DeserializeOwned + Clone,
+    F: FnMut(Vec<(RangeId, V)>) -> Result<(), Error>,
+{
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, V, F> Visitor<'de> for EntrySeed<'_, V, F>
+where
+    V: DeserializeOwned + Clone,
+    F: FnMut(Vec<(RangeId, V)>) -> Result<(), Error>,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a data entry with \"value\" and \"ids\"")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let sink = self.0;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "value" => {
+                    let values: Vec<V> = map.next_value()?;
+                    sink.set_values(values).map_err(de::Error::custom)?;
+                }
+                "ids" => map.next_value_seed(IdsSeed(&mut *sink))?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        if sink.values.is_none() {
+            return Err(de::Error::missing_field("value"));
+        }
+        Ok(())
+    }
+}
+
+/// `data[0].ids`。要素を 1 件ずつ復元して [`Sink`] へ渡す。
+struct IdsSeed<'s, V, F>(&'s mut Sink<V, F>);
+
+impl<'de, V, F> DeserializeSeed<'de> for IdsSeed<'_, V, F>
+where
+    V: DeserializeOwned + Clone,
+    F: FnMut(Vec<(RangeId, V)>) -> Result<(), Error>,
+{
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, V, F> Visitor<'de> for IdsSeed<'_, V, F>
+where
+    V: DeserializeOwned + Clone,
+    F: FnMut(Vec<(RangeId, V)>) -> Result<(), Error>,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("an \"ids\" array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(entry) = seq.next_element::<IdEntry>()? {
+            self.0.push(entry).map_err(de::Error::custom)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, JsonStreamReader, RangeId, SingleId, SpatialIdTable};
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+
+    fn sample() -> SpatialIdTable<i32> {
+        let mut table = SpatialIdTable::new();
+        table.insert(SingleId::new(20, 0, 0, 0).unwrap(), 20);
+        table.insert(SingleId::new(20, 1, 0, 0).unwrap(), 10);
+        table.insert(SingleId::new(20, 2, 0, 0).unwrap(), 20);
+        table.insert(RangeId::new(18, [0, 3], [5, 7], [9, 9]).unwrap(), 30);
+        table
+    }
+
+    #[test]
+    fn streamed_output_is_readable_by_serde() {
+        let table = sample();
+        let mut json = Vec::new();
+        table.write_json(&mut json).unwrap();
+
+        let text = String::from_utf8(json).unwrap();
+        assert!(text.contains("\"value\":[10,20,30]"));
+        assert!(text.contains("\"ref\":2"));
+
+        let restored: SpatialIdTable<i32> = serde_json::from_str(&text).unwrap();
+        assert_eq!(restored, table);
+    }
+
+    #[test]
+    fn serde_output_is_readable_by_stream() {
+        let table = sample();
+        let json = serde_json::to_string(&table).unwrap();
+        let restored = SpatialIdTable::<i32>::read_json(json.as_bytes()).unwrap();
+        assert_eq!(restored, table);
+    }
+
+    #[test]
+    fn empty_table_round_trips() {
+        let table = SpatialIdTable::<i32>::new();
+        let mut json = Vec::new();
+        table.write_json(&mut json).unwrap();
+        let restored = SpatialIdTable::<i32>::read_json(json.as_slice()).unwrap();
+        assert!(restored.is_empty());
+    }
+
+    /// `ids` が `value` より先に来ても、`ref` を後から解決して読めること。
+    #[test]
+    fn ids_before_value_is_accepted() {
+        let json = r#"{"data":[{"ids":[{"z":20,"f":[0],"x":[1],"y":[0],"ref":1},{"z":20,"f":[0],"x":[2],"y":[0],"ref":0}],"name":"","value":["a","b"]}],"meta":{"version":"v1.0","description":""},"option":{},"$schema":"https://airbee-project.github.io/schemas/json/v1.0.json"}"#;
+        let table = SpatialIdTable::<String>::read_json(json.as_bytes()).unwrap();
+        let one = SingleId::new(20, 0, 1, 0).unwrap();
+        let two = SingleId::new(20, 0, 2, 0).unwrap();
+        assert_eq!(table.get(&one).next().unwrap().1, "b");
+        assert_eq!(table.get(&two).next().unwrap().1, "a");
+    }
+
+    #[test]
+    fn batches_respect_batch_size() {
+        let mut table = SpatialIdTable::new();
+        for x in 0..25u32 {
+            table.insert(SingleId::new(20, 0, x * 2, 0).unwrap(), x);
+        }
+        let mut json = Vec::new();
+        table.write_json(&mut json).unwrap();
+
+        let mut sizes = Vec::new();
+        let total = JsonStreamReader::new(json.as_slice())
+            .batch_size(10)
+            .for_each_batch(|batch: Vec<(RangeId, u32)>| {
+                sizes.push(batch.len());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(total, 25);
+        assert_eq!(sizes, [10, 10, 5]);
+    }
+
+    /// コールバックの `Err` は serde のエラーへ畳まれず、そのまま返ること。
+    #[test]
+    fn callback_error_is_returned_as_is() {
+        let mut json = Vec::new();
+        sample().write_json(&mut json).unwrap();
+
+        let result = JsonStreamReader::new(json.as_slice())
+            .batch_size(1)
+            .for_each_batch(|_: Vec<(RangeId, i32)>| Err(Error::Cancelled));
+        assert_eq!(result, Err(Error::Cancelled));
+    }
+
+    #[test]
+    fn rejects_out_of_range_ref() {
+        let json = r#"{"data":[{"name":"","value":[10],"ids":[{"z":20,"f":[0],"x":[0],"y":[0],"ref":3}]}]}"#;
+        let err = SpatialIdTable::<i32>::read_json(json.as_bytes()).unwrap_err();
+        assert!(matches!(&err, Error::Json(msg) if msg.contains("\"ref\" index 3 out of range")));
+    }
+
+    #[test]
+    fn rejects_invalid_data_count() {
+        let json = r#"{"data":[]}"#;
+        let err = SpatialIdTable::<i32>::read_json(json.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("exactly 1 entries, found 0"));
+    }
+
+    #[test]
+    fn rejects_trailing_garbage() {
+        let mut json = Vec::new();
+        sample().write_json(&mut json).unwrap();
+        json.extend_from_slice(b" {}");
+        assert!(matches!(
+            SpatialIdTable::<i32>::read_json(json.as_slice()),
+            Err(Error::Json(_))
+        ));
+    }
+
+    /// 読み書きの失敗は、元の種類を保った [`Error::Io`] になること。
+    #[test]
+    fn io_failures_keep_their_kind() {
+        struct Broken;
+        impl std::io::Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::ConnectionReset.into())
+            }
+        }
+        impl std::io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::StorageFull.into())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        assert!(matches!(
+            SpatialIdTable::<i32>::read_json(Broken),
+            Err(Error::Io {
+                kind: std::io::ErrorKind::ConnectionReset,
+                ..
+            })
+        ));
+        assert!(matches!(
+            sample().write_json(Broken),
+            Err(Error::Io {
+                kind: std::io::ErrorKind::StorageFull,
+                ..
+            })
+        ));
+    }
+}
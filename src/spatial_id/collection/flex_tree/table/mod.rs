@@ -3,14 +3,36 @@ use alloc::vec::Vec;
 
 use alloc::collections::{BTreeMap, BTreeSet};
 use core::ops::RangeBounds;
+#[cfg(feature = "persist")]
+pub mod archived;
+#[cfg(feature = "persist")]
+pub mod arena;
+pub mod components;
 pub mod convert;
+pub mod export;
 #[cfg(feature = "json")]
 pub mod json;
+#[cfg(all(feature = "json", feature = "std"))]
+pub mod json_stream;
+pub mod outline;
+pub mod path;
+pub mod ray;
+#[cfg(feature = "persist")]
+pub mod store;
 pub mod test;
+pub mod wire;
 
-use crate::{AllowedIntervals, FlexId, FlexIdValue, RangeId, SingleId, SpatialId, SpatialIdSet};
+use crate::{
+    AllowedIntervals, FlexId, FlexIdValue, RangeId, SingleId, SpatialId, SpatialIdSet,
+    SpatialPredicates,
+};
 
 /// 値(V)と空間(FlexId)を相互に高速検索・管理するためのテーブル構造。
+///
+/// `persist` feature 有効時、値が `Vec<u8>` のテーブルは `to_bytes` / `from_bytes` で
+/// フラットアリーナ形式のバイト列と相互変換でき、`ArchivedSpatialIdTable` でそのバイト列を
+/// 復元せず直接読める（値による検索も含む）。変更を WAL でディスクへ逐次残すには
+/// `SpatialIdTableStore` を使う。
 #[derive(Clone, Debug)]
 pub struct SpatialIdTable<V>
 where
@@ -207,6 +229,44 @@ where
     /// [`FlexId`]と値をそのままの返します。
     pub fn remove_overlapping<S: SpatialId>(&mut self, target: &S) -> Vec<(FlexId, V)> {
         let removed_items = self.inner.remove_overlapping(target.clone());
+        self.release_ranks(removed_items)
+    }
+
+    /// 図形と交わる[`FlexId`]と値への参照を、切り取らずにそのまま返します。
+    ///
+    /// 図形を空間IDへ変換せず、木を降りながら図形と交わらない部分木を捨てます。
+    /// 空間 3 軸のズームレベルが `max_z` 以上の部分木は、その領域が図形と交われば中を判定せずに全て返します。
+    pub fn get_overlapping_shape<P: SpatialPredicates>(
+        &self,
+        shape: &P,
+        max_z: u8,
+    ) -> impl Iterator<Item = (FlexId, &V)> + '_ {
+        self.inner
+            .shape_overlap_ref(shape, max_z)
+            .into_iter()
+            .map(|(flex_id, rank)| {
+                let value = self
+                    .reverse_dictionary
+                    .get(rank)
+                    .expect("Dictionary mismatch");
+                (flex_id, value)
+            })
+    }
+
+    /// 図形と交わる[`FlexId`]と値を、切り取らずにそのまま取り除いて返します。
+    ///
+    /// `max_z` の扱いは [`get_overlapping_shape`](Self::get_overlapping_shape) と同じです。
+    pub fn remove_overlapping_shape<P: SpatialPredicates>(
+        &mut self,
+        shape: &P,
+        max_z: u8,
+    ) -> Vec<(FlexId, V)> {
+        let removed_items = self.inner.shape_overlap_remove(shape, max_z);
+        self.release_ranks(removed_items)
+    }
+
+    /// 丸ごと取り除いた葉のランクを値へ戻し、使われなくなったランクを辞書から消す。
+    fn release_ranks(&mut self, removed_items: Vec<(FlexId, usize)>) -> Vec<(FlexId, V)> {
         let mut results = Vec::new();
 
         for (flex_id, rank) in removed_items {
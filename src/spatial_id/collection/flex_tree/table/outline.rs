@@ -0,0 +1,38 @@
+//! [`SpatialIdTable`] の値ごとの空間の境界を図形として取り出す。
+
+use alloc::vec::Vec;
+
+use super::super::outline::{self, LayerOutline};
+use crate::spatial_id::collection::flex_tree::core::ptr::SafeValue;
+use crate::{Error, RangeId, Solid, SpatialIdTable};
+
+impl<V> SpatialIdTable<V>
+where
+    V: SafeValue + Ord,
+{
+    /// 値が `value` の空間について、[`SpatialIdSet::layer_outlines`](crate::SpatialIdSet::layer_outlines)
+    /// と同じ外形を返す。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 値ごとに外形を取り出す
+    /// ```
+    /// # use kasane_logic::{RangeId, SpatialIdTable};
+    /// let mut table = SpatialIdTable::new();
+    /// table.insert(RangeId::new(20, [0, 0], [931000, 931003], [412000, 412000]).unwrap(), "road");
+    /// table.insert(RangeId::new(20, [0, 0], [931000, 931000], [412001, 412003]).unwrap(), "park");
+    ///
+    /// let road = table.layer_outlines_of(&"road", 20).unwrap();
+    /// assert_eq!(road.len(), 1);
+    /// assert_eq!(road[0].polygons.polygons().len(), 1);
+    /// ```
+    pub fn layer_outlines_of(&self, value: &V, z: u8) -> Result<Vec<LayerOutline>, Error> {
+        outline::layer_outlines(z, self.value_get(value).map(RangeId::from))
+    }
+
+    /// 値が `value` の空間について、[`SpatialIdSet::boundary_solid`](crate::SpatialIdSet::boundary_solid)
+    /// と同じ表面を返す。値を持つ空間がなければ `None` を返す。
+    pub fn boundary_solid_of(&self, value: &V, z: u8) -> Result<Option<Solid>, Error> {
+        outline::boundary_solid(z, self.value_get(value).map(RangeId::from))
+    }
+}
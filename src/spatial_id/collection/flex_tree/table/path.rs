@@ -0,0 +1,57 @@
+//! [`SpatialIdTable`] の値をコストとして、経路を探す。
+
+use super::super::path::{self, PathPlan};
+use crate::spatial_id::collection::flex_tree::core::ptr::SafeValue;
+use crate::{CancellationToken, Connectivity, Coordinate, Error, SpatialIdTable};
+
+impl<V> SpatialIdTable<V>
+where
+    V: SafeValue + Ord + Into<f64>,
+{
+    /// 値を 1 メートル進むごとのコストの倍率とし、`start` から `goal` までの最もコストの小さい経路を探す。
+    ///
+    /// 値のない空間の倍率は 1 で、1 未満の値も 1 とみなす。無限大や NaN の空間は通れない。
+    /// 隣のボクセルへ進むコストは、中心間の距離（メートル）に進んだ先の倍率を掛けたもの。
+    /// 探索の方法と戻り値は [`SpatialIdSet::find_path`](crate::SpatialIdSet::find_path) と同じ。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: コストの高い空間を避ける
+    /// ```
+    /// # use kasane_logic::{CancellationToken, Connectivity, Coordinate, RangeId, SpatialIdTable};
+    /// let start = Coordinate::new(35.68, 139.7700, 10.0).unwrap();
+    /// let goal = Coordinate::new(35.68, 139.7712, 10.0).unwrap();
+    /// let [s, g] = [start, goal].map(|c| c.single_id(20).unwrap());
+    ///
+    /// // 出発地点と目的地点を結ぶ列を、通れるが 10 倍のコストがかかる空間にする
+    /// let mut costs = SpatialIdTable::new();
+    /// costs.insert(RangeId::new(20, s.f(), [s.x() + 1, g.x() - 1], s.y()).unwrap(), 10_u32);
+    ///
+    /// let plan = costs
+    ///     .find_path(&start, &goal, 20, Connectivity::Face, &CancellationToken::never())
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert!(plan.cells.iter().all(|c| costs.get(c).next().is_none()));
+    /// ```
+    pub fn find_path(
+        &self,
+        start: &Coordinate,
+        goal: &Coordinate,
+        z: u8,
+        connectivity: Connectivity,
+        token: &CancellationToken,
+    ) -> Result<Option<PathPlan>, Error> {
+        path::find_path(
+            &self.inner,
+            |rank: &usize| {
+                let value = self.reverse_dictionary.get(rank)?;
+                Some(value.clone().into())
+            },
+            start,
+            goal,
+            z,
+            connectivity,
+            token,
+        )
+    }
+}
@@ -0,0 +1,67 @@
+//! [`SpatialIdTable`] を障害物として、見通しを調べる。
+
+use super::super::ray::{self, RayHit};
+use crate::spatial_id::collection::flex_tree::core::ptr::SafeValue;
+use crate::{CancellationToken, Coordinate, Error, SpatialIdSet, SpatialIdTable};
+
+impl<V> SpatialIdTable<V>
+where
+    V: SafeValue + Ord,
+{
+    /// `from` から `to` への線分が最初に当たった空間IDと、その値を返す。
+    ///
+    /// 値の種類によらず、値を持つ空間を障害物とする。辿り方は
+    /// [`SpatialIdSet::ray_cast`](crate::SpatialIdSet::ray_cast) と同じ。
+    ///
+    /// # 動作例
+    ///
+    /// タイトル: 当たった建物の値を得る
+    /// ```
+    /// # use kasane_logic::{Coordinate, RangeId, SpatialIdTable};
+    /// let from = Coordinate::new(35.68, 139.7700, 10.0).unwrap();
+    /// let to = Coordinate::new(35.68, 139.7712, 10.0).unwrap();
+    /// let [s, g] = [from, to].map(|c| c.single_id(20).unwrap());
+    ///
+    /// let mut buildings = SpatialIdTable::new();
+    /// buildings.insert(RangeId::new(20, s.f(), s.x() + 2, s.y()).unwrap(), "A");
+    /// buildings.insert(RangeId::new(20, s.f(), g.x() - 1, s.y()).unwrap(), "B");
+    ///
+    /// let (hit, name) = buildings.ray_cast(&from, &to, 20).unwrap().unwrap();
+    /// assert_eq!(*name, "A");
+    /// assert_eq!(hit.cell.x(), s.x() + 2);
+    /// ```
+    pub fn ray_cast(
+        &self,
+        from: &Coordinate,
+        to: &Coordinate,
+        z: u8,
+    ) -> Result<Option<(RayHit, &V)>, Error> {
+        let Some((hit, rank)) = ray::ray_cast(&self.inner, from, to, z)? else {
+            return Ok(None);
+        };
+        let value = self
+            .reverse_dictionary
+            .get(rank)
+            .expect("Dictionary mismatch");
+        Ok(Some((hit, value)))
+    }
+
+    /// `from` から `to` への線分が、ズームレベル `z` でどの空間IDにも当たらないかを返す。
+    pub fn line_of_sight(&self, from: &Coordinate, to: &Coordinate, z: u8) -> Result<bool, Error> {
+        Ok(ray::ray_cast(&self.inner, from, to, z)?.is_none())
+    }
+
+    /// 値を持つ空間を障害物とし、`observer` から半径 `radius_m` 以内で見えるズームレベル `z` のボクセルを返す。
+    ///
+    /// 見えるかどうかの決め方は [`SpatialIdSet::viewshed`](crate::SpatialIdSet::viewshed) と同じ。
+    pub fn viewshed(
+        &self,
+        observer: &Coordinate,
+        radius_m: f64,
+        z: u8,
+        token: &CancellationToken,
+    ) -> Result<SpatialIdSet, Error> {
+        let visible = ray::viewshed(&self.inner, observer, radius_m, z, token)?;
+        Ok(SpatialIdSet::from_core(visible))
+    }
+}
@@ -0,0 +1,492 @@
+//! [`SpatialIdTable`] を先行書き込みログ（WAL）とスナップショットでディスクへ永続化するストア。
+//!
+//! ストアは 1 つのディレクトリに 2 つのファイルを持つ。
+//!
+//! - `snapshot.bin` … ある時点のテーブル全体。本体は [`SpatialIdTable::to_bytes`] の
+//!   フラットアリーナ形式そのもので、先頭に世代番号とチェックサムを付ける。
+//! - `wal.log` … スナップショット以降の変更を 1 件ずつ追記したログ。
+//!
+//! 変更は必ず**ログへ書いてからメモリ上のテーブルへ適用する**。開くときは最新の
+//! スナップショットを読み、その上へログを先頭から再生する。ログの記録は対象を
+//! [`FlexId`] へ展開した形で持ち、実行時と再生時で同じ適用処理を通るので、
+//! 再生結果は実行時の状態と一致する。
+//!
+//! # 世代番号
+//!
+//! 圧縮（[`SpatialIdTableStore::compact`]）は「新しいスナップショットを書く」→「ログを空にする」
+//! の 2 段なので、その間でプロセスが落ちると、新しいスナップショットと古いログが残る。
+//! 古いログを再生すると変更が二重に当たるため、両ファイルに世代番号を書き、ログの世代が
+//! スナップショットより古ければ内容を捨てる。
+//!
+//! # 書きかけの記録
+//!
+//! 追記の途中で落ちると、ログの末尾に不完全な記録が残る。末尾の記録だけが壊れている場合は
+//! 書きかけとみなして切り捨てる。途中の記録が壊れている場合は [`Error::Persist`] で停止する。
+//!
+//! 記録の長さ欄には専用のチェックサムを付けてある。長さ欄が壊れて記録がファイル末尾を
+//! 越えて見える場合も書きかけとは区別でき、以降の正しい記録を黙って捨てることはない。
+//! 実行中に追記や同期が失敗した場合は、ログを追記前の長さへ戻してから `Err` を返す。
+
+use alloc::vec::Vec;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::SpatialIdTable;
+use crate::spatial_id::collection::flex_tree::map::arena::{
+    FORMAT_VERSION, LAYOUT_FLAGS, check_format,
+};
+use crate::{Error, FlexId, SpatialId};
+
+const SNAPSHOT_FILE: &str = "snapshot.bin";
+const WAL_FILE: &str = "wal.log";
+
+const SNAPSHOT_MAGIC: [u8; 4] = *b"KSNP";
+const WAL_MAGIC: [u8; 4] = *b"KWAL";
+
+/// スナップショットのヘッダ長。magic(4) + generation(8) + checksum(4)。
+const SNAPSHOT_HEADER_LEN: usize = 16;
+/// ログのヘッダ長。magic(4) + version(2) + flags(1) + generation(8)。
+const WAL_HEADER_LEN: usize = 15;
+/// ログ 1 記録の枠の長さ。payload_len(4) + len_checksum(4) + checksum(4)。
+const FRAME_HEADER_LEN: usize = 12;
+
+/// ログ 1 件分の変更。対象は [`FlexId`] へ展開済み。
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+enum WalRecord {
+    Insert { ids: Vec<FlexId>, value: Vec<u8> },
+    Upsert { ids: Vec<FlexId>, value: Vec<u8> },
+    Remove { ids: Vec<FlexId> },
+    RemoveOverlapping { ids: Vec<FlexId> },
+}
+
+/// [`SpatialIdTable`] を WAL とスナップショットで永続化するストア。
+///
+/// 値は [`SpatialIdTable::to_bytes`] と同じく `Vec<u8>` に限る。変更系メソッドは
+/// ログへの追記が成功した場合にだけテーブルへ適用する。ただし自動圧縮の失敗は
+/// 変更を適用した後で `Err` として返る（その変更はログに残っており、失われない）。
+///
+/// 既定では追記のたびに OS へ書き出すが `fsync` はしない。プロセスが落ちても
+/// 変更は失われないが、電源断まで守るには [`set_sync_on_write`](Self::set_sync_on_write) で
+/// 毎回同期するか、区切りで [`sync`](Self::sync) を呼ぶ。
+///
+/// # 動作例
+///
+/// 書き込んで開き直す:
+/// ```
+/// # use kasane_logic::{SingleId, SpatialIdTableStore};
+/// let dir = std::env::temp_dir().join(format!("kasane-store-doc-{}", std::process::id()));
+/// # let _ = std::fs::remove_dir_all(&dir);
+/// let id = SingleId::new(20, 0, 10, 10).unwrap();
+///
+/// let mut store = SpatialIdTableStore::open(&dir).unwrap();
+/// store.insert(id.clone(), b"warehouse".to_vec()).unwrap();
+/// drop(store);
+///
+/// let store = SpatialIdTableStore::open(&dir).unwrap();
+/// assert_eq!(store.table().get(&id).next().unwrap().1, &b"warehouse".to_vec());
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub struct SpatialIdTableStore {
+    dir: PathBuf,
+    table: SpatialIdTable<Vec<u8>>,
+    wal: File,
+    /// ログの正しい記録の終端（バイト数）。追記に失敗したらここまで切り戻す。
+    wal_len: u64,
+    /// 追記の失敗を切り戻せなかったか、圧縮でスナップショットだけが新しい世代になり、
+    /// ログが信用できない。以降の変更は拒否し、[`compact`](Self::compact) が成功すれば戻る。
+    poisoned: bool,
+    generation: u64,
+    wal_records: usize,
+    compact_threshold: Option<usize>,
+    sync_on_write: bool,
+}
+
+impl SpatialIdTableStore {
+    /// 自動圧縮の既定のしきい値（ログの記録数）。
+    pub const DEFAULT_COMPACT_THRESHOLD: usize = 10_000;
+
+    /// `dir` のストアを開く。ディレクトリやファイルが無ければ空のストアを作る。
+    ///
+    /// スナップショットを読み、その上へログを再生する。形式バージョンやレイアウトフラグが
+    /// このビルドと異なる場合は [`Error::UnsupportedFormatVersion`] /
+    /// [`Error::UnsupportedFormatLayout`]、入出力の失敗や破損は [`Error::Persist`] を返す。
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, Error> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(|e| io_error("create directory", &dir, e))?;
+
+        let (table, generation) = read_snapshot(&dir.join(SNAPSHOT_FILE))?;
+        let mut store = Self {
+            wal: open_wal(&dir, generation)?,
+            dir,
+            table,
+            wal_len: WAL_HEADER_LEN as u64,
+            poisoned: false,
+            generation,
+            wal_records: 0,
+            compact_threshold: Some(Self::DEFAULT_COMPACT_THRESHOLD),
+            sync_on_write: false,
+        };
+        store.replay()?;
+        Ok(store)
+    }
+
+    /// 現在のテーブル。
+    pub fn table(&self) -> &SpatialIdTable<Vec<u8>> {
+        &self.table
+    }
+
+    /// ストアを閉じ、テーブルを取り出す。
+    pub fn into_table(self) -> SpatialIdTable<Vec<u8>> {
+        self.table
+    }
+
+    /// 最後の圧縮以降にログへ溜まっている記録数。
+    pub fn wal_records(&self) -> usize {
+        self.wal_records
+    }
+
+    /// ログの記録数がこの値に達したら自動で [`compact`](Self::compact) する。
+    ///
+    /// `None` で自動圧縮を止める。既定は [`DEFAULT_COMPACT_THRESHOLD`](Self::DEFAULT_COMPACT_THRESHOLD)。
+    pub fn set_compact_threshold(&mut self, threshold: Option<usize>) {
+        self.compact_threshold = threshold;
+    }
+
+    /// `true` にすると、追記のたびにログを `fsync` する。
+    pub fn set_sync_on_write(&mut self, sync_on_write: bool) {
+        self.sync_on_write = sync_on_write;
+    }
+
+    /// [`SpatialIdTable::insert`] をログへ記録してから適用する。
+    pub fn insert<S: SpatialId>(&mut self, target: S, value: Vec<u8>) -> Result<(), Error> {
+        let record = WalRecord::Insert {
+            ids: target.into_iter().collect(),
+            value,
+        };
+        self.commit(record).map(drop)
+    }
+
+    /// [`SpatialIdTable::upsert`] をログへ記録してから適用する。
+    pub fn upsert<S: SpatialId>(&mut self, target: S, value: Vec<u8>) -> Result<(), Error> {
+        let record = WalRecord::Upsert {
+            ids: target.into_iter().collect(),
+            value,
+        };
+        self.commit(record).map(drop)
+    }
+
+    /// [`SpatialIdTable::remove`] をログへ記録してから適用し、削除された領域と値を返す。
+    pub fn remove<S: SpatialId>(&mut self, target: &S) -> Result<Vec<(FlexId, Vec<u8>)>, Error> {
+        let record = WalRecord::Remove {
+            ids: target.clone().into_iter().collect(),
+        };
+        self.commit(record)
+    }
+
+    /// [`SpatialIdTable::remove_overlapping`] をログへ記録してから適用し、
+    /// 削除された領域と値を返す。
+    pub fn remove_overlapping<S: SpatialId>(
+        &mut self,
+        target: &S,
+    ) -> Result<Vec<(FlexId, Vec<u8>)>, Error> {
+        let record = WalRecord::RemoveOverlapping {
+            ids: target.clone().into_iter().collect(),
+        };
+        self.commit(record)
+    }
+
+    /// ログを `fsync` し、ここまでの変更を電源断からも守る。
+    pub fn sync(&mut self) -> Result<(), Error> {
+        let path = self.dir.join(WAL_FILE);
+        self.wal.sync_data().map_err(|e| io_error("sync", &path, e))
+    }
+
+    /// 現在のテーブルをスナップショットへ書き出し、ログを空にする。
+    ///
+    /// スナップショットは一時ファイルへ書いてから置き換えるので、途中で落ちても
+    /// 以前のスナップショットとログがそのまま残る。
+    ///
+    /// スナップショットを置き換えた後でログの置き換えに失敗した場合、古い世代のログは
+    /// 次に開くときに捨てられるので、そこへ追記した変更は失われる。そのためストアは以降の
+    /// 変更を拒否する。もう一度 `compact` が成功すれば、また変更を受け付ける。
+    pub fn compact(&mut self) -> Result<(), Error> {
+        let generation = self.generation + 1;
+        let body = self.table.to_bytes()?;
+
+        let mut bytes = Vec::with_capacity(SNAPSHOT_HEADER_LEN + body.len());
+        bytes.extend_from_slice(&SNAPSHOT_MAGIC);
+        bytes.extend_from_slice(&generation.to_le_bytes());
+        bytes.extend_from_slice(&checksum(&body).to_le_bytes());
+        bytes.extend_from_slice(&body);
+        replace_file(&self.dir, SNAPSHOT_FILE, &bytes)?;
+
+        // ここで落ちても、ログの世代が古いので次に開くときに捨てられる。
+        let wal = replace_file(&self.dir, WAL_FILE, &wal_header(generation))
+            .and_then(|()| open_wal(&self.dir, generation));
+        self.wal = match wal {
+            Ok(wal) => wal,
+            Err(e) => {
+                self.poisoned = true;
+                return Err(e);
+            }
+        };
+        self.wal_len = WAL_HEADER_LEN as u64;
+        self.poisoned = false;
+        self.generation = generation;
+        self.wal_records = 0;
+        Ok(())
+    }
+
+    /// 記録をログへ追記し、テーブルへ適用する。必要なら自動圧縮する。
+    fn commit(&mut self, record: WalRecord) -> Result<Vec<(FlexId, Vec<u8>)>, Error> {
+        let path = self.dir.join(WAL_FILE);
+        if self.poisoned {
+            return Err(Error::Persist(format!(
+                "wal is in an unknown state after a failed write: {}",
+                path.display()
+            )));
+        }
+
+        let payload = rkyv::to_bytes::<rkyv::rancor::Error>(&record)
+            .map_err(|e| Error::Persist(format!("serialize wal record: {e}")))?;
+
+        let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+        let len = (payload.len() as u32).to_le_bytes();
+        frame.extend_from_slice(&len);
+        frame.extend_from_slice(&checksum(&len).to_le_bytes());
+        frame.extend_from_slice(&checksum(&payload).to_le_bytes());
+        frame.extend_from_slice(&payload);
+
+        let written = self
+            .wal
+            .write_all(&frame)
+            .map_err(|e| io_error("append", &path, e))
+            .and_then(|()| {
+                if self.sync_on_write {
+                    self.sync()
+                } else {
+                    Ok(())
+                }
+            });
+        if let Err(e) = written {
+            // 書きかけの枠を残すと、以降の記録が次回の再生で末尾ごと切り捨てられる。
+            // 同期に失敗した枠も、適用しない変更が再生で当たらないよう取り除く。
+            if self.wal.set_len(self.wal_len).is_err() {
+                self.poisoned = true;
+            }
+            return Err(e);
+        }
+        self.wal_len += frame.len() as u64;
+
+        let removed = apply(&mut self.table, record);
+        self.wal_records += 1;
+
+        if self
+            .compact_threshold
+            .is_some_and(|threshold| self.wal_records >= threshold)
+        {
+            self.compact()?;
+        }
+        Ok(removed)
+    }
+
+    /// ログを先頭から読み、テーブルへ再生する。末尾の書きかけの記録は切り捨てる。
+    ///
+    /// 切り捨てるのは、枠がファイル末尾で途切れている記録だけ。長さ欄のチェックサムが
+    /// 合わない記録や、ファイル末尾以外で本体のチェックサムが合わない記録は破損として扱う。
+    fn replay(&mut self) -> Result<(), Error> {
+        let path = self.dir.join(WAL_FILE);
+        let mut bytes = Vec::new();
+        File::open(&path)
+            .and_then(|mut f| f.read_to_end(&mut bytes))
+            .map_err(|e| io_error("read", &path, e))?;
+        let corrupted = |offset: usize| {
+            Error::Persist(format!(
+                "corrupted wal record at byte {offset} in {}",
+                path.display()
+            ))
+        };
+
+        let mut offset = WAL_HEADER_LEN;
+        while offset < bytes.len() {
+            // 枠の途中でファイルが終わっている。追記の途中で落ちたもの。
+            let Some(frame) = bytes.get(offset..offset + FRAME_HEADER_LEN) else {
+                break;
+            };
+            if checksum(&frame[0..4]) != u32::from_le_bytes(frame[4..8].try_into().unwrap()) {
+                return Err(corrupted(offset));
+            }
+            let len = u32::from_le_bytes(frame[0..4].try_into().unwrap()) as usize;
+            let sum = u32::from_le_bytes(frame[8..12].try_into().unwrap());
+            let start = offset + FRAME_HEADER_LEN;
+            // 長さ欄は正しいので、本体が足りないのは末尾の書きかけに限られる。
+            let Some(payload) = bytes.get(start..start + len) else {
+                break;
+            };
+            if checksum(payload) != sum {
+                if start + len == bytes.len() {
+                    break;
+                }
+                return Err(corrupted(offset));
+            }
+            let record = rkyv::from_bytes::<WalRecord, rkyv::rancor::Error>(payload)
+                .map_err(|e| Error::Persist(format!("decode wal record at byte {offset}: {e}")))?;
+            apply(&mut self.table, record);
+            self.wal_records += 1;
+            offset = start + len;
+        }
+
+        if offset < bytes.len() {
+            self.wal
+                .set_len(offset as u64)
+                .map_err(|e| io_error("truncate", &path, e))?;
+        }
+        self.wal_len = offset as u64;
+        Ok(())
+    }
+}
+
+/// 記録を 1 件テーブルへ適用する。実行時と再生時で共通。
+fn apply(table: &mut SpatialIdTable<Vec<u8>>, record: WalRecord) -> Vec<(FlexId, Vec<u8>)> {
+    match record {
+        WalRecord::Insert { ids, value } => {
+            for id in ids {
+                table.insert(id, value.clone());
+            }
+            Vec::new()
+        }
+        WalRecord::Upsert { ids, value } => {
+            for id in ids {
+                table.upsert(id, value.clone());
+            }
+            Vec::new()
+        }
+        WalRecord::Remove { ids } => ids.iter().flat_map(|id| table.remove(id)).collect(),
+        WalRecord::RemoveOverlapping { ids } => ids
+            .iter()
+            .flat_map(|id| table.remove_overlapping(id))
+            .collect(),
+    }
+}
+
+/// スナップショットを読み、テーブルと世代番号を返す。無ければ空のテーブルと世代 0。
+fn read_snapshot(path: &Path) -> Result<(SpatialIdTable<Vec<u8>>, u64), Error> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok((SpatialIdTable::new(), 0));
+        }
+        Err(e) => return Err(io_error("read", path, e)),
+    };
+
+    if bytes.len() < SNAPSHOT_HEADER_LEN || bytes[0..4] != SNAPSHOT_MAGIC {
+        return Err(Error::Persist(format!(
+            "not a snapshot file: {}",
+            path.display()
+        )));
+    }
+    let generation = u64::from_le_bytes(bytes[4..12].try_into().unwrap());
+    let sum = u32::from_le_bytes(bytes[12..16].try_into().unwrap());
+    let body = &bytes[SNAPSHOT_HEADER_LEN..];
+    if checksum(body) != sum {
+        return Err(Error::Persist(format!(
+            "corrupted snapshot: {}",
+            path.display()
+        )));
+    }
+
+    // チェックサムは破損の検出にしか使えないので、本体はレイアウトから検査して読む。
+    let table = SpatialIdTable::<Vec<u8>>::from_bytes_checked(body)?;
+    Ok((table, generation))
+}
+
+/// ログを追記用に開く。無い、または世代がスナップショットより古ければ空のログを作り直す。
+fn open_wal(dir: &Path, generation: u64) -> Result<File, Error> {
+    let path = dir.join(WAL_FILE);
+    let mut header = [0u8; WAL_HEADER_LEN];
+    let found = match File::open(&path) {
+        Ok(mut f) => {
+            let mut read = 0;
+            while read < WAL_HEADER_LEN {
+                match f.read(&mut header[read..]) {
+                    Ok(0) => break,
+                    Ok(n) => read += n,
+                    Err(e) => return Err(io_error("read", &path, e)),
+                }
+            }
+            // ヘッダすら書き切れていないログは、作成途中で落ちたものとして作り直す。
+            (read == WAL_HEADER_LEN).then_some(header)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(io_error("open", &path, e)),
+    };
+
+    let reset = match found {
+        None => true,
+        Some(header) => {
+            if header[0..4] != WAL_MAGIC {
+                return Err(Error::Persist(format!(
+                    "not a wal file: {}",
+                    path.display()
+                )));
+            }
+            check_format(u16::from_le_bytes([header[4], header[5]]), header[6])?;
+            let wal_generation = u64::from_le_bytes(header[7..15].try_into().unwrap());
+            if wal_generation > generation {
+                return Err(Error::Persist(format!(
+                    "wal generation {wal_generation} is newer than snapshot generation {generation}"
+                )));
+            }
+            wal_generation < generation
+        }
+    };
+    if reset {
+        replace_file(dir, WAL_FILE, &wal_header(generation))?;
+    }
+
+    OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .map_err(|e| io_error("open", &path, e))
+}
+
+fn wal_header(generation: u64) -> [u8; WAL_HEADER_LEN] {
+    let mut header = [0u8; WAL_HEADER_LEN];
+    header[0..4].copy_from_slice(&WAL_MAGIC);
+    header[4..6].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+    header[6] = LAYOUT_FLAGS;
+    header[7..15].copy_from_slice(&generation.to_le_bytes());
+    header
+}
+
+/// 一時ファイルへ書いて `fsync` してから `name` を置き換える。
+fn replace_file(dir: &Path, name: &str, bytes: &[u8]) -> Result<(), Error> {
+    let path = dir.join(name);
+    let tmp = dir.join(format!("{name}.tmp"));
+    File::create(&tmp)
+        .and_then(|mut f| {
+            f.write_all(bytes)?;
+            f.sync_all()
+        })
+        .map_err(|e| io_error("write", &tmp, e))?;
+    fs::rename(&tmp, &path).map_err(|e| io_error("rename", &path, e))?;
+
+    // 置き換え（ディレクトリエントリの更新）自体も永続化する。
+    #[cfg(unix)]
+    File::open(dir)
+        .and_then(|d| d.sync_all())
+        .map_err(|e| io_error("sync", dir, e))?;
+    Ok(())
+}
+
+/// 記録やスナップショットの破損検出に使う FNV-1a（32 bit）。
+pub(crate) fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, b| {
+        (hash ^ u32::from(*b)).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn io_error(action: &str, path: &Path, e: std::io::Error) -> Error {
+    Error::Persist(format!("{action} {}: {e}", path.display()))
+}
@@ -0,0 +1,54 @@
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use alloc::collections::BTreeMap;
+    use alloc::string::{String, ToString};
+
+    use crate::{Error, SingleId, SpatialId, SpatialIdTable};
+
+    /// 各 `Feature` は自分の `RangeId` の外形と、その領域の値を `value` に持つ
+    #[test]
+    fn geojson_carries_each_value() {
+        let mut table = SpatialIdTable::new();
+        table.insert(
+            SingleId::new(20, 0, 931000, 412000).unwrap(),
+            String::from("warehouse"),
+        );
+        table.insert(
+            SingleId::new(20, 2, 931005, 412000).unwrap(),
+            String::from("office"),
+        );
+
+        let geojson: serde_json::Value =
+            serde_json::from_str(&table.to_geojson().unwrap()).unwrap();
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 2);
+
+        for (range_id, value) in table.range_ids() {
+            let feature = features
+                .iter()
+                .find(|f| f["properties"]["id"] == range_id.to_string())
+                .unwrap();
+            assert_eq!(feature["properties"]["value"], value.as_str());
+            assert_eq!(feature["geometry"]["type"], "Polygon");
+
+            let vertices = range_id.spatial_vertices();
+            let ring = &feature["geometry"]["coordinates"][0];
+            assert_eq!(ring[0][0], vertices[0].longitude());
+            assert_eq!(ring[0][1], vertices[0].latitude());
+            assert_eq!(ring[2][0], vertices[3].longitude());
+            assert_eq!(ring[2][1], vertices[3].latitude());
+        }
+    }
+
+    /// JSON にできない値があれば、黙って省かずにエラーを返す
+    #[test]
+    fn geojson_rejects_values_that_cannot_be_serialized() {
+        // キーが文字列でないマップは JSON のオブジェクトにできない。
+        let mut value = BTreeMap::new();
+        value.insert((1u8, 2u8), 3u8);
+        let mut table = SpatialIdTable::new();
+        table.insert(SingleId::new(20, 0, 931000, 412000).unwrap(), value);
+
+        assert!(matches!(table.to_geojson(), Err(Error::Json(_))));
+    }
+}
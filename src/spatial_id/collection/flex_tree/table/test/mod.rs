@@ -1,10 +1,14 @@
 #![cfg_attr(test, allow(dead_code))]
 
 pub mod count;
+pub mod export;
 pub mod insert;
 pub mod par;
 pub mod query;
 pub mod remove;
+pub mod rkyv;
+pub mod shape;
+pub mod store;
 pub mod upsert;
 
 #[cfg(test)]
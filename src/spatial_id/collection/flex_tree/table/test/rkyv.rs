@@ -0,0 +1,181 @@
+#[cfg(all(test, feature = "persist"))]
+mod persist_tests {
+    use crate::{ArchivedSpatialIdTable, FlexId, RangeId, SingleId, SpatialIdTable};
+    use alloc::vec::Vec;
+
+    fn sample() -> SpatialIdTable<Vec<u8>> {
+        let mut table = SpatialIdTable::new();
+        table.insert(SingleId::new(5, 3, 4, 6).unwrap(), b"gamma".to_vec());
+        table.insert(SingleId::new(5, -2, 4, 6).unwrap(), b"alpha".to_vec());
+        table.insert(
+            RangeId::new(5, [1, 4], [8, 9], [5, 6]).unwrap(),
+            b"beta".to_vec(),
+        );
+        table.insert(SingleId::new(5, 3, 4, 7).unwrap(), b"alpha".to_vec());
+        table
+    }
+
+    fn sorted<T: Ord>(mut v: Vec<T>) -> Vec<T> {
+        v.sort();
+        v
+    }
+
+    fn owned(v: Vec<(FlexId, &[u8])>) -> Vec<(FlexId, Vec<u8>)> {
+        sorted(v.into_iter().map(|(id, b)| (id, b.to_vec())).collect())
+    }
+
+    #[test]
+    fn round_trip() {
+        let table = sample();
+        let bytes = table.to_bytes().unwrap();
+        let restored = unsafe { SpatialIdTable::<Vec<u8>>::from_bytes(&bytes) }.unwrap();
+        assert_eq!(table, restored);
+        assert_eq!(
+            table.values().collect::<Vec<_>>(),
+            restored.values().collect::<Vec<_>>()
+        );
+    }
+
+    /// 上書きで木から消えた値は、バイト列の辞書に残らないこと。
+    #[test]
+    fn overwritten_values_are_not_persisted() {
+        let mut table = SpatialIdTable::new();
+        let id = SingleId::new(5, 3, 4, 6).unwrap();
+        table.insert(id.clone(), b"old".to_vec());
+        table.insert(id, b"new".to_vec());
+
+        let bytes = table.to_bytes().unwrap();
+        let archived = unsafe { ArchivedSpatialIdTable::access(&bytes) }.unwrap();
+        assert_eq!(archived.values().collect::<Vec<_>>(), [b"new".as_slice()]);
+
+        let restored = unsafe { SpatialIdTable::<Vec<u8>>::from_bytes(&bytes) }.unwrap();
+        assert_eq!(restored.values().collect::<Vec<_>>(), [&b"new".to_vec()]);
+    }
+
+    /// ZeroCopy 側の空間の問い合わせがインメモリと一致すること。
+    #[test]
+    fn archived_spatial_queries_match_in_memory() {
+        let table = sample();
+        let bytes = table.to_bytes().unwrap();
+        let archived = unsafe { ArchivedSpatialIdTable::access(&bytes) }.unwrap();
+
+        let targets = [
+            SingleId::new(5, 3, 4, 6).unwrap(),
+            SingleId::new(5, -2, 4, 6).unwrap(),
+            SingleId::new(5, 2, 8, 5).unwrap(),
+            SingleId::new(6, 6, 8, 13).unwrap(),
+            SingleId::new(0, 0, 0, 0).unwrap(),
+            SingleId::new(0, -1, 0, 0).unwrap(),
+        ];
+        for target in targets {
+            let expected: Vec<(FlexId, Vec<u8>)> =
+                sorted(table.get(&target).map(|(id, v)| (id, v.clone())).collect());
+            assert_eq!(owned(archived.get(&target)), expected, "get: {target}");
+
+            let expected: Vec<(FlexId, Vec<u8>)> = sorted(
+                table
+                    .get_overlapping(&target)
+                    .map(|(id, v)| (id, v.clone()))
+                    .collect(),
+            );
+            assert_eq!(
+                owned(archived.get_overlapping(&target)),
+                expected,
+                "get_overlapping: {target}"
+            );
+        }
+    }
+
+    /// ZeroCopy 側の値の問い合わせがインメモリと一致すること。
+    #[test]
+    fn archived_value_queries_match_in_memory() {
+        let table = sample();
+        let bytes = table.to_bytes().unwrap();
+        let archived = unsafe { ArchivedSpatialIdTable::access(&bytes) }.unwrap();
+
+        for value in [b"alpha".as_slice(), b"beta", b"gamma", b"missing"] {
+            assert_eq!(
+                sorted(archived.value_get(value)),
+                sorted(table.value_get(&value.to_vec()).collect()),
+                "value_get: {value:?}"
+            );
+        }
+
+        let expected: Vec<(FlexId, Vec<u8>)> = sorted(
+            table
+                .value_range(b"b".to_vec()..b"h".to_vec())
+                .map(|(id, v)| (id, v.clone()))
+                .collect(),
+        );
+        assert_eq!(
+            owned(archived.value_range(b"b".as_slice()..b"h".as_slice())),
+            expected
+        );
+
+        let expected: Vec<(FlexId, Vec<u8>)> = sorted(
+            table
+                .value_range(..=b"beta".to_vec())
+                .map(|(id, v)| (id, v.clone()))
+                .collect(),
+        );
+        assert_eq!(owned(archived.value_range(..=b"beta".as_slice())), expected);
+
+        assert!(archived.value_range(b"x".as_slice()..).is_empty());
+        assert_eq!(
+            archived.values().collect::<Vec<_>>(),
+            [b"alpha".as_slice(), b"beta", b"gamma"]
+        );
+    }
+
+    /// archived バイト列を入力源にした平行移動と合成が、インメモリと同じ結果になること。
+    #[test]
+    fn archived_source_matches_in_memory_query() {
+        use crate::Source;
+        use crate::merge_policy::Overwrite;
+        use alloc::boxed::Box;
+
+        let table = sample();
+        let bytes: &'static [u8] = Box::leak(table.to_bytes().unwrap().into_boxed_slice());
+
+        let mut other = SpatialIdTable::new();
+        other.insert(SingleId::new(5, 4, 4, 6).unwrap(), b"delta".to_vec());
+        other.insert(SingleId::new(5, 6, 4, 6).unwrap(), b"epsilon".to_vec());
+
+        let expected = table
+            .clone()
+            .query()
+            .shift_x(5, 1)
+            .merge(other.clone().query(), Vec::new(), Overwrite)
+            .run()
+            .unwrap();
+        let archived = unsafe { ArchivedSpatialIdTable::access(bytes) }.unwrap();
+        let got = archived
+            .query()
+            .shift_x(5, 1)
+            .merge(other.query(), Vec::new(), Overwrite)
+            .run()
+            .unwrap();
+
+        assert_eq!(got, expected);
+    }
+
+    /// 上書きで木から消えた値が、`read_all` の結果に紛れ込まないこと。
+    #[test]
+    fn archived_read_all_matches_in_memory() {
+        use crate::{CancellationToken, Source};
+        use alloc::boxed::Box;
+
+        let mut table = sample();
+        table.insert(SingleId::new(5, 3, 4, 6).unwrap(), b"zeta".to_vec());
+        let bytes = table.to_bytes().unwrap();
+        let archived = unsafe { ArchivedSpatialIdTable::access(&bytes) }.unwrap();
+
+        let working = Box::new(archived)
+            .read_all(&CancellationToken::new())
+            .unwrap();
+        let got: Vec<(FlexId, Vec<u8>)> = sorted(working.into_iter().collect());
+        let expected: Vec<(FlexId, Vec<u8>)> =
+            sorted(table.iter().map(|(id, v)| (id, v.clone())).collect());
+        assert_eq!(got, expected);
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use crate::{Coordinate, RangeId, SpatialIdTable, SpatialPredicates, Sphere};
+    use alloc::vec::Vec;
+
+    /// 球の中心の列に 1、球をかすめない離れた列に 2 を持つテーブル。
+    fn table_and_sphere() -> (SpatialIdTable<i32>, Sphere) {
+        let center = Coordinate::new(35.681, 139.766, 100.0).unwrap();
+        let cell = center.single_id(20).unwrap();
+        let mut table = SpatialIdTable::new();
+        table.insert(
+            RangeId::new(20, [0, 7], [cell.x(), cell.x()], [cell.y(), cell.y()]).unwrap(),
+            1,
+        );
+        table.insert(
+            RangeId::new(
+                20,
+                [0, 7],
+                [cell.x() + 20, cell.x() + 20],
+                [cell.y(), cell.y()],
+            )
+            .unwrap(),
+            2,
+        );
+        (table, Sphere::new(center, 100.0).unwrap())
+    }
+
+    /// 球と交わる葉だけを、値付きで返す
+    #[test]
+    fn get_overlapping_shape_returns_values() {
+        let (table, sphere) = table_and_sphere();
+        let found: Vec<(crate::FlexId, i32)> = table
+            .get_overlapping_shape(&sphere, 30)
+            .map(|(id, v)| (id, *v))
+            .collect();
+        assert!(!found.is_empty());
+        assert!(found.iter().all(|(id, v)| *v == 1 && sphere.intersects(id)));
+    }
+
+    /// 使われなくなった値は索引からも消える
+    #[test]
+    fn remove_overlapping_shape_releases_unused_values() {
+        let (mut table, sphere) = table_and_sphere();
+        table.rebuild_index();
+        let ones = table.value_get(&1).count();
+
+        let removed = table.remove_overlapping_shape(&sphere, 30);
+        assert!(removed.iter().all(|(_, v)| *v == 1));
+
+        assert_eq!(table.value_get(&1).count(), ones - removed.len());
+        assert!(table.value_get(&2).next().is_some());
+        assert_eq!(table.get_overlapping_shape(&sphere, 30).count(), 0);
+    }
+}
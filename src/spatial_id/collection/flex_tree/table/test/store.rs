@@ -0,0 +1,326 @@
+#[cfg(all(test, feature = "persist"))]
+mod persist_tests {
+    use crate::{Error, FlexId, RangeId, SingleId, SpatialIdTable, SpatialIdTableStore};
+    use alloc::vec::Vec;
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    /// テストごとに固有の空ディレクトリを用意する。
+    fn fresh_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("kasane-store-test-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn entries(table: &SpatialIdTable<Vec<u8>>) -> Vec<(FlexId, Vec<u8>)> {
+        let mut v: Vec<_> = table.iter().map(|(id, v)| (id, v.clone())).collect();
+        v.sort();
+        v
+    }
+
+    /// ストアと同じ変更をインメモリのテーブルにも当て、両者を返す。
+    fn populate(store: &mut SpatialIdTableStore) -> SpatialIdTable<Vec<u8>> {
+        let mut expected = SpatialIdTable::new();
+
+        let range = RangeId::new(5, [1, 4], [8, 9], [5, 6]).unwrap();
+        store.insert(range.clone(), b"beta".to_vec()).unwrap();
+        expected.insert(range, b"beta".to_vec());
+
+        let single = SingleId::new(5, -2, 4, 6).unwrap();
+        store.insert(single.clone(), b"alpha".to_vec()).unwrap();
+        expected.insert(single, b"alpha".to_vec());
+
+        let wide = RangeId::new(5, [1, 2], [8, 10], [5, 5]).unwrap();
+        store.upsert(wide.clone(), b"gamma".to_vec()).unwrap();
+        expected.upsert(wide, b"gamma".to_vec());
+
+        let hole = SingleId::new(6, 2, 16, 10).unwrap();
+        let removed = store.remove(&hole).unwrap();
+        assert_eq!(removed, expected.remove(&hole));
+
+        let coarse = SingleId::new(4, -1, 2, 3).unwrap();
+        let removed = store.remove_overlapping(&coarse).unwrap();
+        assert_eq!(removed, expected.remove_overlapping(&coarse));
+
+        expected
+    }
+
+    #[test]
+    fn reopen_replays_wal() {
+        let dir = fresh_dir("replay");
+        let mut store = SpatialIdTableStore::open(&dir).unwrap();
+        let expected = populate(&mut store);
+        assert_eq!(entries(store.table()), entries(&expected));
+        assert_eq!(store.wal_records(), 5);
+        drop(store);
+
+        let store = SpatialIdTableStore::open(&dir).unwrap();
+        assert_eq!(entries(store.table()), entries(&expected));
+        assert_eq!(store.wal_records(), 5);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compact_then_reopen_reads_snapshot_and_later_wal() {
+        let dir = fresh_dir("compact");
+        let mut store = SpatialIdTableStore::open(&dir).unwrap();
+        let mut expected = populate(&mut store);
+        store.compact().unwrap();
+        assert_eq!(store.wal_records(), 0);
+
+        let later = SingleId::new(7, 3, 40, 41).unwrap();
+        store.insert(later.clone(), b"delta".to_vec()).unwrap();
+        expected.insert(later, b"delta".to_vec());
+        drop(store);
+
+        let store = SpatialIdTableStore::open(&dir).unwrap();
+        assert_eq!(entries(store.table()), entries(&expected));
+        assert_eq!(store.wal_records(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn automatic_compaction_at_threshold() {
+        let dir = fresh_dir("threshold");
+        let mut store = SpatialIdTableStore::open(&dir).unwrap();
+        store.set_compact_threshold(Some(3));
+        for x in 0..7u32 {
+            store
+                .insert(SingleId::new(20, 0, x, 0).unwrap(), alloc::vec![x as u8])
+                .unwrap();
+        }
+        assert_eq!(store.wal_records(), 1);
+        let expected = entries(store.table());
+        drop(store);
+
+        let store = SpatialIdTableStore::open(&dir).unwrap();
+        assert_eq!(entries(store.table()), expected);
+        assert_eq!(expected.len(), 7);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// 圧縮の途中（スナップショットを書いた直後）で落ちても、古いログが二重に当たらないこと。
+    #[test]
+    fn stale_wal_after_interrupted_compaction_is_discarded() {
+        let dir = fresh_dir("stale");
+        let mut store = SpatialIdTableStore::open(&dir).unwrap();
+        let id = SingleId::new(10, 0, 5, 5).unwrap();
+        store.upsert(id.clone(), b"first".to_vec()).unwrap();
+        store.remove(&id).unwrap();
+        store.upsert(id.clone(), b"second".to_vec()).unwrap();
+        let stale_wal = fs::read(dir.join("wal.log")).unwrap();
+
+        store.compact().unwrap();
+        drop(store);
+        // ログの置き換えだけが間に合わなかった状態を再現する。
+        fs::write(dir.join("wal.log"), &stale_wal).unwrap();
+
+        let store = SpatialIdTableStore::open(&dir).unwrap();
+        assert_eq!(store.wal_records(), 0);
+        let got: Vec<_> = store.table().get(&id).map(|(_, v)| v.clone()).collect();
+        assert_eq!(got, [b"second".to_vec()]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// 圧縮でスナップショットだけが置き換わりログの置き換えに失敗したら、以降の変更を拒否し、
+    /// 受け付けた変更が開き直しで失われないこと。
+    #[test]
+    fn failed_wal_swap_in_compaction_rejects_later_changes() {
+        let dir = fresh_dir("wal-swap");
+        let mut store = SpatialIdTableStore::open(&dir).unwrap();
+        let kept = SingleId::new(10, 0, 5, 5).unwrap();
+        store.insert(kept.clone(), b"kept".to_vec()).unwrap();
+
+        // ログの一時ファイルの位置にディレクトリを置き、ログの置き換えだけを失敗させる。
+        fs::create_dir(dir.join("wal.log.tmp")).unwrap();
+        assert!(matches!(store.compact(), Err(Error::Persist(_))));
+
+        let rejected = SingleId::new(10, 0, 6, 6).unwrap();
+        assert!(matches!(
+            store.insert(rejected.clone(), b"rejected".to_vec()),
+            Err(Error::Persist(_))
+        ));
+        assert_eq!(store.table().get(&rejected).count(), 0);
+
+        // 置き換えられるようになれば、もう一度の圧縮で元に戻る。
+        fs::remove_dir(dir.join("wal.log.tmp")).unwrap();
+        store.compact().unwrap();
+        let later = SingleId::new(10, 0, 7, 7).unwrap();
+        store.insert(later.clone(), b"later".to_vec()).unwrap();
+        let expected = entries(store.table());
+        drop(store);
+
+        let store = SpatialIdTableStore::open(&dir).unwrap();
+        assert_eq!(entries(store.table()), expected);
+        assert_eq!(store.table().get(&later).count(), 1);
+        assert_eq!(store.table().get(&rejected).count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// 末尾の書きかけの記録は切り捨て、それ以前の変更は残ること。
+    #[test]
+    fn torn_tail_record_is_truncated() {
+        let dir = fresh_dir("torn");
+        let mut store = SpatialIdTableStore::open(&dir).unwrap();
+        let id = SingleId::new(10, 0, 5, 5).unwrap();
+        store.insert(id.clone(), b"kept".to_vec()).unwrap();
+        drop(store);
+
+        let wal = dir.join("wal.log");
+        let intact_len = fs::metadata(&wal).unwrap().len();
+        let mut f = OpenOptions::new().append(true).open(&wal).unwrap();
+        f.write_all(&[200, 0, 0, 0, 1, 2, 3, 4, 9, 9]).unwrap();
+        drop(f);
+
+        let mut store = SpatialIdTableStore::open(&dir).unwrap();
+        assert_eq!(store.wal_records(), 1);
+        assert_eq!(fs::metadata(&wal).unwrap().len(), intact_len);
+
+        // 切り捨てた後も追記と再生が続けられる。
+        let other = SingleId::new(10, 0, 6, 6).unwrap();
+        store.insert(other.clone(), b"after".to_vec()).unwrap();
+        drop(store);
+        let store = SpatialIdTableStore::open(&dir).unwrap();
+        assert_eq!(store.table().count(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// 途中の記録が壊れていれば、黙って読み飛ばさず `Persist` で止まること。
+    #[test]
+    fn corrupted_middle_record_is_an_error() {
+        let dir = fresh_dir("corrupt");
+        let mut store = SpatialIdTableStore::open(&dir).unwrap();
+        store
+            .insert(SingleId::new(10, 0, 5, 5).unwrap(), b"a".to_vec())
+            .unwrap();
+        store
+            .insert(SingleId::new(10, 0, 6, 6).unwrap(), b"b".to_vec())
+            .unwrap();
+        drop(store);
+
+        let wal = dir.join("wal.log");
+        let mut bytes = fs::read(&wal).unwrap();
+        // ヘッダ(15) + 枠(12) の直後、最初の記録の本体を 1 byte 壊す。
+        bytes[15 + 12] ^= 0xff;
+        fs::write(&wal, &bytes).unwrap();
+
+        assert!(matches!(
+            SpatialIdTableStore::open(&dir),
+            Err(Error::Persist(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// 途中の記録の長さ欄が壊れて末尾を越えて見えても、書きかけとして切り捨てず
+    /// `Persist` で止まり、ログも切り詰めないこと。
+    #[test]
+    fn corrupted_middle_record_length_is_an_error() {
+        let dir = fresh_dir("corrupt-len");
+        let mut store = SpatialIdTableStore::open(&dir).unwrap();
+        for x in 0..3u32 {
+            store
+                .insert(SingleId::new(10, 0, x, x).unwrap(), alloc::vec![x as u8])
+                .unwrap();
+        }
+        drop(store);
+
+        let wal = dir.join("wal.log");
+        let mut bytes = fs::read(&wal).unwrap();
+        // 最初の記録の長さ欄の最上位 byte を壊し、ファイル末尾を越える長さにする。
+        bytes[15 + 3] ^= 0x7f;
+        fs::write(&wal, &bytes).unwrap();
+
+        assert!(matches!(
+            SpatialIdTableStore::open(&dir),
+            Err(Error::Persist(_))
+        ));
+        assert_eq!(fs::read(&wal).unwrap(), bytes);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn wal_with_other_format_version_is_rejected() {
+        let dir = fresh_dir("version");
+        drop(SpatialIdTableStore::open(&dir).unwrap());
+
+        let wal = dir.join("wal.log");
+        let mut bytes = fs::read(&wal).unwrap();
+        bytes[4..6].copy_from_slice(&(crate::FORMAT_VERSION + 1).to_le_bytes());
+        fs::write(&wal, &bytes).unwrap();
+
+        assert!(matches!(
+            SpatialIdTableStore::open(&dir),
+            Err(Error::UnsupportedFormatVersion { .. })
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn corrupted_snapshot_is_an_error() {
+        let dir = fresh_dir("snapshot");
+        let mut store = SpatialIdTableStore::open(&dir).unwrap();
+        store
+            .insert(SingleId::new(10, 0, 5, 5).unwrap(), b"a".to_vec())
+            .unwrap();
+        store.compact().unwrap();
+        drop(store);
+
+        let snapshot = dir.join("snapshot.bin");
+        let mut bytes = fs::read(&snapshot).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(&snapshot, &bytes).unwrap();
+
+        assert!(matches!(
+            SpatialIdTableStore::open(&dir),
+            Err(Error::Persist(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// チェックサムが合っていても、形の崩れた本体は検査で弾かれること。
+    #[test]
+    fn crafted_snapshot_with_valid_checksum_is_an_error() {
+        use crate::spatial_id::collection::flex_tree::map::arena::{
+            ArenaNode, FORMAT_VERSION, LAYOUT_FLAGS, MapArena,
+        };
+        use crate::spatial_id::collection::flex_tree::table::store::checksum;
+
+        // 自分自身を子に持つ枝（辿ると止まらない）と、でたらめなバイト列。
+        let cyclic = MapArena {
+            version: FORMAT_VERSION,
+            flags: LAYOUT_FLAGS,
+            lower_root: 0,
+            upper_root: 0,
+            shard: None,
+            nodes: alloc::vec![ArenaNode::Branch {
+                level: 0,
+                lower: 0,
+                upper: 0,
+            }],
+            dictionary: Vec::new(),
+        };
+        let cyclic = rkyv::to_bytes::<rkyv::rancor::Error>(&cyclic)
+            .unwrap()
+            .to_vec();
+        let garbage: Vec<u8> = (0..64u8).map(|b| b.wrapping_mul(37)).collect();
+
+        for (name, body) in [("cyclic", cyclic), ("garbage", garbage)] {
+            let dir = fresh_dir(&format!("crafted-{name}"));
+            drop(SpatialIdTableStore::open(&dir).unwrap());
+            let mut bytes = b"KSNP".to_vec();
+            bytes.extend_from_slice(&0u64.to_le_bytes());
+            bytes.extend_from_slice(&checksum(&body).to_le_bytes());
+            bytes.extend_from_slice(&body);
+            fs::write(dir.join("snapshot.bin"), &bytes).unwrap();
+
+            assert!(
+                matches!(SpatialIdTableStore::open(&dir), Err(Error::Persist(_))),
+                "{name}"
+            );
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! [`SpatialIdTable`] のワイヤ形式（[`super::super::wire`]）への書き出しと復元。
+//!
+//! 値の辞書は値の昇順に並べて書き、各 ID はその添字で値を参照する。
+
+use alloc::vec::Vec;
+
+use super::super::wire::{decode, encode};
+use crate::{Error, SpatialIdTable};
+
+impl SpatialIdTable<Vec<u8>> {
+    /// ワイヤ形式のバイト列へ書き出す。値は辞書にまとめ、各 ID は添字で参照する。
+    ///
+    /// # 動作例
+    ///
+    /// 書き出して復元する:
+    /// ```
+    /// # use kasane_logic::{SingleId, SpatialIdTable};
+    /// let mut table = SpatialIdTable::new();
+    /// table.insert(SingleId::new(20, 0, 10, 10).unwrap(), b"warehouse".to_vec());
+    /// table.insert(SingleId::new(20, 0, 11, 10).unwrap(), b"office".to_vec());
+    ///
+    /// let bytes = table.to_wire();
+    /// assert_eq!(SpatialIdTable::<Vec<u8>>::from_wire(&bytes).unwrap(), table);
+    /// ```
+    pub fn to_wire(&self) -> Vec<u8> {
+        let values: Vec<&Vec<u8>> = self.values().collect();
+        let dictionary: Vec<&[u8]> = values.iter().map(|v| v.as_slice()).collect();
+        encode(
+            self.iter().map(|(id, value)| {
+                let value_ref = values
+                    .binary_search(&value)
+                    .expect("ツリー内の値は必ず values() にある");
+                (id, value_ref)
+            }),
+            Some(&dictionary),
+        )
+    }
+
+    /// [`to_wire`](Self::to_wire) で書き出したバイト列からテーブルを復元する。
+    ///
+    /// 値の辞書を持たないバイト列（[`SpatialIdSet::to_wire`](crate::SpatialIdSet::to_wire)）は
+    /// [`Error::Persist`] で拒否する。
+    pub fn from_wire(bytes: &[u8]) -> Result<Self, Error> {
+        let decoded = decode(bytes)?;
+        let dictionary = decoded
+            .dictionary
+            .ok_or_else(|| Error::Persist("wire: stream has no value dictionary".into()))?;
+
+        let mut table = Self::new();
+        for (id, value_ref) in decoded.records {
+            table.insert(id, dictionary[value_ref].to_vec());
+        }
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, RangeId, SingleId, SpatialIdSet, SpatialIdTable};
+    use alloc::vec::Vec;
+
+    fn sample() -> SpatialIdTable<Vec<u8>> {
+        let mut table = SpatialIdTable::new();
+        for x in 0..30u32 {
+            let value = if x % 3 == 0 {
+                b"alpha".to_vec()
+            } else {
+                b"beta".to_vec()
+            };
+            table.insert(SingleId::new(20, 0, 500 + x, 700).unwrap(), value);
+        }
+        table.insert(
+            RangeId::new(5, [-3, -1], [1, 4], [8, 9]).unwrap(),
+            b"gamma".to_vec(),
+        );
+        table
+    }
+
+    #[test]
+    fn round_trips_values() {
+        let table = sample();
+        let bytes = table.to_wire();
+        assert_eq!(SpatialIdTable::<Vec<u8>>::from_wire(&bytes).unwrap(), table);
+    }
+
+    #[test]
+    fn set_reads_table_stream_without_values() {
+        let table = sample();
+        let set = SpatialIdSet::from_wire(&table.to_wire()).unwrap();
+        assert_eq!(set, table.flex_ids().collect::<SpatialIdSet>());
+    }
+
+    #[test]
+    fn rejects_set_stream() {
+        let set: SpatialIdSet = sample().flex_ids().collect();
+        assert!(matches!(
+            SpatialIdTable::<Vec<u8>>::from_wire(&set.to_wire()),
+            Err(Error::Persist(_))
+        ));
+    }
+
+    /// 辞書の範囲外を指す `value_ref` は、パニックせず `Persist` で拒否すること。
+    #[test]
+    fn rejects_out_of_range_value_ref() {
+        let mut table = SpatialIdTable::new();
+        table.insert(SingleId::new(3, 0, 1, 1).unwrap(), b"v".to_vec());
+        let mut bytes = table.to_wire();
+        // 末尾は [value_ref][終端 0]。値は 1 つなので value_ref は 0。
+        let value_ref = bytes.len() - 2;
+        assert_eq!(bytes[value_ref], 0);
+        bytes[value_ref] = 1;
+        assert!(matches!(
+            SpatialIdTable::<Vec<u8>>::from_wire(&bytes),
+            Err(Error::Persist(_))
+        ));
+    }
+}
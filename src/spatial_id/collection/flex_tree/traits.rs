@@ -6,7 +6,7 @@ use crate::spatial_id::collection::query::cancellation::CancellationToken;
 use crate::spatial_id::collection::query::execution::Query;
 use crate::spatial_id::collection::query::source::Source;
 use crate::spatial_id::collection::query::working::WorkingTree;
-use crate::{Error, FlexId, RangeId, SpatialIdSet, SpatialIdTable};
+use crate::{Error, FlexId, RangeId, SpatialIdMap, SpatialIdSet, SpatialIdTable};
 
 /// Table の出入口変換で、これ未満なら rayon を使わず逐次で組む閾値。
 /// 単発・小規模クエリで rayon 起動コスト（par_build / from_par_iter の par_sort 等）を避ける。
@@ -59,6 +59,48 @@ impl From<WorkingTree<()>> for SpatialIdSet {
     }
 }
 
+impl<V> Source for SpatialIdMap<V>
+where
+    V: SafeValue + 'static,
+{
+    type Value = V;
+
+    fn read_range_ids(
+        &self,
+        bounds: &[RangeId],
+        token: &CancellationToken,
+    ) -> Result<WorkingTree<V>, Error> {
+        let mut time_segments: Vec<(FlexId, V)> = Vec::new();
+        for b in bounds {
+            if token.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            for (id, value) in self.get_range(b) {
+                time_segments.push((id, value.clone()));
+            }
+        }
+        Ok(time_segments.into_iter().collect())
+    }
+
+    fn read_all(self: Box<Self>, token: &CancellationToken) -> Result<WorkingTree<V>, Error> {
+        if token.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        // 所有権ごと移し替えるだけ（クローンしない）。
+        Ok(WorkingTree::from_core(SpatialIdMap::into_core(*self)))
+    }
+}
+
+impl<V> From<WorkingTree<V>> for SpatialIdMap<V>
+where
+    V: SafeValue + 'static,
+{
+    /// 包み直すだけでコストはかからない。
+    fn from(working: WorkingTree<V>) -> Self {
+        SpatialIdMap::from_core(working.into_core())
+    }
+}
+
 impl<V> Source for SpatialIdTable<V>
 where
     V: FlexIdValue + 'static,
@@ -0,0 +1,374 @@
+//! コレクションの空間ID列を、サービス間でやり取りするためのコンパクトなバイナリ形式。
+//!
+//! JSON（[`super::json`]）は1件ごとにキー名と10進数を書くため、数百万件規模では大きすぎる。
+//! この形式は [`FlexId`] を「ズームレベルの組」ごとにまとめ、F/X/Y のビットを交互に並べた
+//! 経路（interleaved path）の昇順に並べて、前の ID との差分を可変長整数（LEB128）で書く。
+//! 空間的に近い ID ほど差分が小さくなるので、密な集合なら1件あたり数バイトに収まる。
+//!
+//! # 形式
+//!
+//! ```text
+//! stream     := magic "KSWR" | version u8 | flags u8 | [dictionary] | frame* | varint 0
+//! dictionary := varint count | (varint len | bytes)*            ; flags & VALUES のとき
+//! frame      := varint n (>0) | zf u8 | zx u8 | zy u8 | [tz u8] | record * n
+//! record     := varint path_delta | [varint t] | [varint value_ref]
+//! ```
+//!
+//! - `path_delta` はフレーム内で直前の record の経路との差（先頭は経路そのもの）。
+//! - `t` は `flags & TIME` のときだけ現れる。経路が直前と同じ（`path_delta == 0`）なら
+//!   直前の `t` との差、そうでなければ `t` そのもの。
+//! - `value_ref` は `flags & VALUES` のときだけ現れ、`dictionary` の添字を表す。
+//! - フレームは同じズームレベルの組を最大 `FRAME_RECORDS` 件ずつ束ね、差分はフレームごとに
+//!   リセットする。受け手はフレーム単位で読み進められる。
+
+use alloc::vec::Vec;
+
+use crate::spatial_id::zoom_level::ZoomLevel;
+use crate::{Error, FlexId};
+
+const WIRE_MAGIC: [u8; 4] = *b"KSWR";
+
+/// ワイヤ形式のバージョン。互換性の無い変更をしたら上げる。
+///
+/// 異なるバージョンのバイト列は [`Error::UnsupportedFormatVersion`] で拒否する。
+pub const WIRE_VERSION: u8 = 1;
+
+/// record が時間（`tz` と `t`）を持つ。
+const FLAG_TIME: u8 = 0b0000_0001;
+/// 値の辞書と `value_ref` を持つ。
+const FLAG_VALUES: u8 = 0b0000_0010;
+
+/// 1 フレームに束ねる record の上限。
+const FRAME_RECORDS: usize = 4096;
+
+/// フレームを分ける単位。`(zf, zx, zy, tz)`。
+type ZoomKey = (u8, u8, u8, u8);
+
+/// 復元した record 列と値の辞書。
+pub(crate) struct Decoded<'a> {
+    /// `(FlexId, value_ref)`。辞書が無い形式では `value_ref` は常に `0`。
+    pub(crate) records: Vec<(FlexId, usize)>,
+    /// 辞書が無い形式では `None`。
+    pub(crate) dictionary: Option<Vec<&'a [u8]>>,
+}
+
+/// `(FlexId, value_ref)` の列をワイヤ形式へ書き出す。
+///
+/// `dictionary` が `Some` なら値の辞書を書き、各 record に `value_ref` を付ける。
+pub(crate) fn encode(
+    records: impl Iterator<Item = (FlexId, usize)>,
+    dictionary: Option<&[&[u8]]>,
+) -> Vec<u8> {
+    let mut records: Vec<(ZoomKey, u128, u64, usize)> = records
+        .map(|(id, value_ref)| (zoom_key(&id), path_key(&id), id.t(), value_ref))
+        .collect();
+    records.sort_unstable();
+
+    let has_time = records.iter().any(|(zoom, ..)| zoom.3 != 0);
+    let mut flags = 0;
+    if has_time {
+        flags |= FLAG_TIME;
+    }
+    if dictionary.is_some() {
+        flags |= FLAG_VALUES;
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&WIRE_MAGIC);
+    out.push(WIRE_VERSION);
+    out.push(flags);
+
+    if let Some(dictionary) = dictionary {
+        write_varint(&mut out, dictionary.len() as u128);
+        for value in dictionary {
+            write_varint(&mut out, value.len() as u128);
+            out.extend_from_slice(value);
+        }
+    }
+
+    for group in records.chunk_by(|a, b| a.0 == b.0) {
+        for frame in group.chunks(FRAME_RECORDS) {
+            let (zf, zx, zy, tz) = frame[0].0;
+            write_varint(&mut out, frame.len() as u128);
+            out.extend_from_slice(&[zf, zx, zy]);
+            if has_time {
+                out.push(tz);
+            }
+
+            let mut prev_path = 0u128;
+            let mut prev_t = 0u64;
+            for (i, &(_, path, t, value_ref)) in frame.iter().enumerate() {
+                let delta = path - prev_path;
+                write_varint(&mut out, delta);
+                if has_time {
+                    if i > 0 && delta == 0 {
+                        write_varint(&mut out, u128::from(t - prev_t));
+                    } else {
+                        write_varint(&mut out, u128::from(t));
+                    }
+                }
+                if dictionary.is_some() {
+                    write_varint(&mut out, value_ref as u128);
+                }
+                prev_path = path;
+                prev_t = t;
+            }
+        }
+    }
+    write_varint(&mut out, 0);
+    out
+}
+
+/// ワイヤ形式のバイト列を復元する。
+///
+/// 途中で切れている、`value_ref` が辞書の範囲外、末尾に余分なバイトがある等の破損は
+/// [`Error::Persist`]、バージョン違いは [`Error::UnsupportedFormatVersion`] を返す。
+/// 復元した ID は [`FlexId::new`] と同じ検証を通る。
+pub(crate) fn decode(bytes: &[u8]) -> Result<Decoded<'_>, Error> {
+    let mut reader = Reader { bytes, pos: 0 };
+
+    if reader.take(WIRE_MAGIC.len())? != WIRE_MAGIC {
+        return Err(corrupted("not a wire stream"));
+    }
+    let version = reader.byte()?;
+    if version != WIRE_VERSION {
+        return Err(Error::UnsupportedFormatVersion {
+            expected: u16::from(WIRE_VERSION),
+            found: u16::from(version),
+        });
+    }
+    let flags = reader.byte()?;
+    if flags & !(FLAG_TIME | FLAG_VALUES) != 0 {
+        return Err(corrupted("unknown flags"));
+    }
+    let has_time = flags & FLAG_TIME != 0;
+    #[cfg(not(feature = "temporal_id"))]
+    if has_time {
+        return Err(Error::Unsupported(
+            "wire stream carries time fields, but `temporal_id` is disabled",
+        ));
+    }
+
+    let dictionary = if flags & FLAG_VALUES != 0 {
+        let count = reader.varint_usize()?;
+        let mut dictionary = Vec::new();
+        for _ in 0..count {
+            let len = reader.varint_usize()?;
+            dictionary.push(reader.take(len)?);
+        }
+        Some(dictionary)
+    } else {
+        None
+    };
+
+    let mut records = Vec::new();
+    loop {
+        let n = reader.varint_usize()?;
+        if n == 0 {
+            break;
+        }
+        let zoom = reader.take(3)?;
+        let (zf, zx, zy) = (zoom[0], zoom[1], zoom[2]);
+        let tz = if has_time { reader.byte()? } else { 0 };
+        let bits = axis_bits(zf, zx, zy)?;
+
+        let mut prev_path = 0u128;
+        let mut prev_t = 0u64;
+        for i in 0..n {
+            let delta = reader.varint()?;
+            let path = prev_path
+                .checked_add(delta)
+                .filter(|path| path >> bits.iter().map(|b| u32::from(*b)).sum::<u32>() == 0)
+                .ok_or_else(|| corrupted("path out of range"))?;
+
+            let [f_shifted, x, y] = split_path(path, bits);
+            let f = i64::from(f_shifted) + i64::from(ZoomLevel::new(zf)?.f_min());
+            let id = FlexId::new(zf, f as i32, zx, x, zy, y)?;
+
+            let t = if has_time {
+                let t = reader.varint()?;
+                let t = if i > 0 && delta == 0 {
+                    u128::from(prev_t) + t
+                } else {
+                    t
+                };
+                u64::try_from(t).map_err(|_| corrupted("time index out of range"))?
+            } else {
+                0
+            };
+            #[cfg(feature = "temporal_id")]
+            let id = if has_time { id.with_time(tz, t)? } else { id };
+            #[cfg(not(feature = "temporal_id"))]
+            let _ = tz;
+
+            let value_ref = match &dictionary {
+                Some(dictionary) => {
+                    let value_ref = reader.varint_usize()?;
+                    if value_ref >= dictionary.len() {
+                        return Err(corrupted("value_ref out of range"));
+                    }
+                    value_ref
+                }
+                None => 0,
+            };
+
+            records.push((id, value_ref));
+            prev_path = path;
+            prev_t = t;
+        }
+    }
+
+    if reader.pos != bytes.len() {
+        return Err(corrupted("trailing bytes after the end of stream"));
+    }
+    Ok(Decoded {
+        records,
+        dictionary,
+    })
+}
+
+fn zoom_key(id: &FlexId) -> ZoomKey {
+    (
+        id.f_zoomlevel(),
+        id.x_zoomlevel(),
+        id.y_zoomlevel(),
+        id.t_zoomlevel(),
+    )
+}
+
+/// 各軸のビット数 `[zf + 1, zx, zy]`。F は負の側を含むので1ビット多い。
+fn axis_bits(zf: u8, zx: u8, zy: u8) -> Result<[u8; 3], Error> {
+    for z in [zf, zx, zy] {
+        ZoomLevel::new(z)?;
+    }
+    Ok([zf + 1, zx, zy])
+}
+
+/// F/X/Y の各インデックスを、上位ビットから F→X→Y の順に交互に並べた経路。
+///
+/// 軸ごとにビット数が異なる場合は、その桁を持たない軸を飛ばす。最大 31 + 30 + 30 = 91 ビット。
+fn path_key(id: &FlexId) -> u128 {
+    let f_min = ZoomLevel::new(id.f_zoomlevel()).unwrap().f_min();
+    let f_shifted = (i64::from(id.f_index()) - i64::from(f_min)) as u32;
+    let values = [f_shifted, id.x_index(), id.y_index()];
+    let bits = [id.f_zoomlevel() + 1, id.x_zoomlevel(), id.y_zoomlevel()];
+
+    let mut path = 0u128;
+    for level in (0..bits[0].max(bits[1]).max(bits[2])).rev() {
+        for axis in 0..3 {
+            if level < bits[axis] {
+                path = (path << 1) | u128::from((values[axis] >> level) & 1);
+            }
+        }
+    }
+    path
+}
+
+/// [`path_key`] の逆変換。`[f_shifted, x, y]` を返す。
+fn split_path(path: u128, bits: [u8; 3]) -> [u32; 3] {
+    let mut pos: u32 = bits.iter().map(|b| u32::from(*b)).sum();
+    let mut values = [0u32; 3];
+    for level in (0..bits[0].max(bits[1]).max(bits[2])).rev() {
+        for axis in 0..3 {
+            if level < bits[axis] {
+                pos -= 1;
+                values[axis] |= (((path >> pos) & 1) as u32) << level;
+            }
+        }
+    }
+    values
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u128) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn corrupted(what: &str) -> Error {
+    Error::Persist(format!("wire: {what}"))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| corrupted("unexpected end of input"))?;
+        let out = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u128, Error> {
+        let mut value = 0u128;
+        let mut shift = 0u32;
+        loop {
+            let byte = self.byte()?;
+            if shift >= 128 || (shift == 126 && byte & 0x7c != 0) {
+                return Err(corrupted("varint overflow"));
+            }
+            value |= u128::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn varint_usize(&mut self) -> Result<usize, Error> {
+        usize::try_from(self.varint()?).map_err(|_| corrupted("length out of range"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Reader, path_key, split_path, write_varint};
+    use crate::FlexId;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn path_key_round_trips_with_mixed_zooms() {
+        for (zf, f, zx, x, zy, y) in [
+            (0u8, -1i32, 0u8, 0u32, 0u8, 0u32),
+            (5, -32, 3, 7, 10, 1023),
+            (30, (1 << 30) - 1, 30, (1 << 30) - 1, 30, 0),
+            (12, 3, 25, 1 << 24, 1, 1),
+        ] {
+            let id = FlexId::new(zf, f, zx, x, zy, y).unwrap();
+            let [f_shifted, xs, ys] = split_path(path_key(&id), [zf + 1, zx, zy]);
+            assert_eq!(i64::from(f_shifted) - (1i64 << zf), i64::from(f));
+            assert_eq!((xs, ys), (x, y));
+        }
+    }
+
+    #[test]
+    fn varint_round_trips_u128_extremes() {
+        for value in [0u128, 1, 127, 128, u64::MAX as u128, u128::MAX] {
+            let mut out = Vec::new();
+            write_varint(&mut out, value);
+            let mut reader = Reader {
+                bytes: &out,
+                pos: 0,
+            };
+            assert_eq!(reader.varint().unwrap(), value);
+            assert_eq!(reader.pos, out.len());
+        }
+    }
+}
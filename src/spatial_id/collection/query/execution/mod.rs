@@ -112,12 +112,29 @@ impl<V: SafeValue + 'static> Query<V> {
 impl<V: SafeValue + 'static> Query<V> {
     /// 検証・AST最適化を適用して実行し、[WorkingTree]のまま返す。
     pub fn run_working_tree(self) -> Result<WorkingTree<V>, Error> {
+        self.run_working_tree_cancellable(&CancellationToken::never())
+    }
+
+    /// [`run_working_tree`](Self::run_working_tree) と同じだが、`token` がキャンセルされると
+    /// 演算子の切れ目で気づき次第 [`Error::Cancelled`] を返す。
+    pub fn run_working_tree_cancellable(
+        self,
+        token: &CancellationToken,
+    ) -> Result<WorkingTree<V>, Error> {
         self.validate()?;
-        self.optimize().raw_run_working_tree()
+        self.optimize().raw_run_working_tree_cancellable(token)
     }
 
     /// 検証も最適化もせず [`Query`] を実行し、[WorkingTree]のまま返す。
     pub fn raw_run_working_tree(self) -> Result<WorkingTree<V>, Error> {
+        self.raw_run_working_tree_cancellable(&CancellationToken::never())
+    }
+
+    /// [`raw_run_working_tree`](Self::raw_run_working_tree) のキャンセル可能版。
+    pub fn raw_run_working_tree_cancellable(
+        self,
+        token: &CancellationToken,
+    ) -> Result<WorkingTree<V>, Error> {
         fn run_internal<V: SafeValue + 'static>(
             query: Query<V>,
             token: &CancellationToken,
@@ -144,7 +161,7 @@ impl<V: SafeValue + 'static> Query<V> {
                 Query::Error(e) => Err(e),
             }
         }
-        run_internal(self, &CancellationToken::never())
+        run_internal(self, token)
     }
 }
 
@@ -361,6 +361,81 @@ impl<V: SafeValue> UniformGrid<V> {
     }
 }
 
+impl<V: SafeValue> UniformGrid<V> {
+    /// 行ごとに形の変わる近傍への散布。
+    ///
+    /// `offsets(y)` は演算ズーム `op_z` の行 `y` にあるボクセルの近傍の移動量 `[dF, dX, dY]` を返す。
+    /// 全ての位置を近傍の数だけ書き出し、同じ位置を `P` で畳む。
+    ///
+    /// 近傍が F / Y の軸範囲からはみ出す場合は、falloff と同じ理由で
+    /// [`Applied::Unsupported`] を返して木経路へ譲る。
+    pub(crate) fn dilate<P, K>(
+        &mut self,
+        op_z: ZoomLevel,
+        offsets: K,
+        token: &CancellationToken,
+    ) -> Result<Applied, Error>
+    where
+        P: crate::spatial_id::collection::query::merge_policy::MergePolicy<V>,
+        K: Fn(u32) -> Vec<[i32; 3]>,
+    {
+        let shift = self.z.get() - op_z.get();
+        let stride = 1i64 << shift;
+        let span = 1i64 << self.z.get();
+
+        // 近傍は行ごとに 1 度だけ求める。
+        let mut kernels: hashbrown::HashMap<u32, Vec<[i32; 3]>> = hashbrown::HashMap::new();
+        let mut total: u64 = 0;
+        let mut ctr = 0u32;
+        for e in &self.entries {
+            token.check_amortized(&mut ctr)?;
+            let kernel = kernels
+                .entry(e.2 >> shift)
+                .or_insert_with_key(|&y| offsets(y));
+            total += kernel.len() as u64;
+        }
+        if total > MAX_BYTES / core::mem::size_of::<SingleEntry<V>>() as u64 {
+            return Ok(Applied::Unsupported);
+        }
+
+        let reach = |axis: usize| {
+            kernels
+                .values()
+                .flat_map(|kernel| kernel.iter().map(move |o| o[axis].unsigned_abs() as i64))
+                .max()
+                .unwrap_or(0)
+                * stride
+        };
+        let (reach_f, reach_y) = (reach(0), reach(2));
+        let overflows = |axis: GridAxis, reach: i64, lo: i64, hi: i64| {
+            self.axis_span(axis, token)
+                .map(|s| s.is_some_and(|s| s.start() - reach < lo || s.end() + reach > hi))
+        };
+        if overflows(GridAxis::F, reach_f, -span, span - 1)?
+            || overflows(GridAxis::Y, reach_y, 0, span - 1)?
+        {
+            return Ok(Applied::Unsupported);
+        }
+
+        let mut out: Vec<SingleEntry<V>> = Vec::with_capacity(total as usize);
+        for e in &self.entries {
+            token.check_amortized(&mut ctr)?;
+            for &[df, dx, dy] in &kernels[&(e.2 >> shift)] {
+                out.push((
+                    (e.0 as i64 + df as i64 * stride) as i32,
+                    (e.1 as i64 + dx as i64 * stride).rem_euclid(span) as u32,
+                    (e.2 as i64 + dy as i64 * stride) as u32,
+                    e.3.clone(),
+                ));
+            }
+        }
+        self.entries = out;
+        self.order = None;
+        self.sort_morton(&|a: &V, b: &V| P::resolve(a.clone(), b.clone()));
+        Ok(Applied::Done)
+    }
+}
+
 /// グリッド経路で実行しきれたか。
 pub(crate) enum Applied {
     Done,
@@ -6,6 +6,7 @@
 use crate::CancellationToken;
 use crate::spatial_id::collection::query::execution::run_unary_chain;
 use crate::spatial_id::collection::query::grid::try_run_grid;
+use crate::spatial_id::collection::query::ops::unary::dilate::Dilate;
 use crate::spatial_id::collection::query::ops::unary::falloff::{
     FalloffPattern, falloff_f::FalloffF, falloff_x::FalloffX, falloff_y::FalloffY,
 };
@@ -134,6 +135,23 @@ fn falloff_with_other_commutative_policies() {
     assert_same_via_grid(sample(), ops);
 }
 
+/// 近傍の形が行ごとに変わる dilate も、粗い葉を含めて木経路と一致する。
+#[test]
+fn dilate_matches_tree_path() {
+    // ズームレベル 8 のボクセルは水平約 120 km・鉛直約 131 km。
+    for (horizontal, vertical) in [(150_000.0, 150_000.0), (400_000.0, 140_000.0)] {
+        let ops: Ops = alloc::vec![Box::new(
+            Dilate::<Max>::new(8u8, horizontal, vertical).unwrap()
+        )];
+        assert_same_via_grid(sample(), ops);
+
+        let ops: Ops = alloc::vec![Box::new(
+            Dilate::<Sum>::new(8u8, horizontal, vertical).unwrap()
+        )];
+        assert_same_via_grid(sample(), ops);
+    }
+}
+
 /// 演算ズームが木より粗い falloff（stride > 1 の経路）。
 #[test]
 fn falloff_at_coarser_zoom_matches_tree_path() {
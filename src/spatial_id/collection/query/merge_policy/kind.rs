@@ -0,0 +1,115 @@
+//! 衝突解決の規則を名前で選ぶための列挙と、それを具体的な [`MergePolicy`](crate::MergePolicy)
+//! の型へ振り分けるマクロ。
+
+use core::fmt;
+use core::str::FromStr;
+
+use crate::Error;
+
+/// 衝突解決の規則を、型ではなく値として選ぶための名前。
+///
+/// クエリ言語やコマンドライン引数のように、規則を実行時の文字列から選ぶときに使う。
+/// 文字列からは [`FromStr`] で変換し、受け付ける名前は各規則の
+/// [`MergePolicy::NAME`](crate::MergePolicy::NAME)（`"Max"`・`"KeepExisting"` など）。
+/// [`Display`](fmt::Display) は同じ名前を書く。
+///
+/// # 動作例
+///
+/// タイトル: 名前から規則を選ぶ
+/// ```
+/// # use kasane_logic::Policy;
+/// let policy: Policy = "KeepExisting".parse().unwrap();
+/// assert_eq!(policy, Policy::KeepExisting);
+/// assert!("keep_existing".parse::<Policy>().is_err());
+/// assert_eq!(policy.to_string(), "KeepExisting");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// [`Max`](crate::merge_policy::Max)。
+    Max,
+    /// [`Min`](crate::merge_policy::Min)。
+    Min,
+    /// [`Sum`](crate::merge_policy::Sum)。
+    Sum,
+    /// [`Average`](crate::merge_policy::Average)。
+    Average,
+    /// [`Overwrite`](crate::merge_policy::Overwrite)。
+    Overwrite,
+    /// [`KeepExisting`](crate::merge_policy::KeepExisting)。
+    KeepExisting,
+    /// [`Difference`](crate::merge_policy::Difference)。
+    Difference,
+}
+
+impl FromStr for Policy {
+    type Err = Error;
+
+    /// 規則の名前を [`Policy`] にする。知らない名前は [`Error::InvalidQueryParameter`] を返す。
+    fn from_str(name: &str) -> Result<Self, Error> {
+        Ok(match name {
+            "Max" => Policy::Max,
+            "Min" => Policy::Min,
+            "Sum" => Policy::Sum,
+            "Average" => Policy::Average,
+            "Overwrite" => Policy::Overwrite,
+            "KeepExisting" => Policy::KeepExisting,
+            "Difference" => Policy::Difference,
+            _ => return Err(Error::InvalidQueryParameter("unknown merge policy".into())),
+        })
+    }
+}
+
+impl fmt::Display for Policy {
+    /// [`FromStr`] が受け付ける名前を書く。
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Policy::Max => "Max",
+            Policy::Min => "Min",
+            Policy::Sum => "Sum",
+            Policy::Average => "Average",
+            Policy::Overwrite => "Overwrite",
+            Policy::KeepExisting => "KeepExisting",
+            Policy::Difference => "Difference",
+        })
+    }
+}
+
+/// `policy` に対応する [`MergePolicy`](crate::MergePolicy) の値を `$p` に束縛して `$body` を評価する。
+///
+/// 呼び出し側で [`Policy`] をスコープに入れておく。
+macro_rules! with_policy {
+    ($policy:expr, $p:ident => $body:expr) => {
+        match $policy {
+            Policy::Max => {
+                let $p = $crate::merge_policy::Max;
+                $body
+            }
+            Policy::Min => {
+                let $p = $crate::merge_policy::Min;
+                $body
+            }
+            Policy::Sum => {
+                let $p = $crate::merge_policy::Sum;
+                $body
+            }
+            Policy::Average => {
+                let $p = $crate::merge_policy::Average;
+                $body
+            }
+            Policy::Overwrite => {
+                let $p = $crate::merge_policy::Overwrite;
+                $body
+            }
+            Policy::KeepExisting => {
+                let $p = $crate::merge_policy::KeepExisting;
+                $body
+            }
+            Policy::Difference => {
+                let $p = $crate::merge_policy::Difference;
+                $body
+            }
+        }
+    };
+}
+
+pub(crate) use with_policy;
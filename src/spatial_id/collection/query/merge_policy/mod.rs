@@ -1,6 +1,7 @@
 pub mod average;
 pub mod difference;
 pub mod keep_existing;
+pub(crate) mod kind;
 pub mod max;
 pub mod min;
 pub mod overwrite;
@@ -10,6 +11,7 @@ pub mod sum;
 pub use average::Average;
 pub use difference::Difference;
 pub use keep_existing::KeepExisting;
+pub use kind::Policy;
 pub use max::Max;
 pub use min::Min;
 pub use overwrite::Overwrite;
@@ -22,6 +22,9 @@ pub mod working;
 /// クエリの表示の実装
 pub mod fmt;
 
+/// 文字列のクエリ定義の解釈
+pub mod parse;
+
 #[doc(hidden)]
 pub mod grid;
 pub use execution::Query;
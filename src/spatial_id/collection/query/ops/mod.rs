@@ -4,5 +4,8 @@ pub mod unary;
 /// 値を変換する演算子
 pub mod map_values;
 
+/// 図形と交わる部分に絞り込む演算子
+pub mod within_shape;
+
 /// 二項演算
 pub mod binary;
@@ -0,0 +1,229 @@
+use crate::spatial_id::collection::flex_tree::core::SafeValue;
+use crate::spatial_id::collection::query::execution::group_commutative::types::CommutativityInfo;
+use crate::spatial_id::collection::query::working::WorkingTree;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use crate::{
+    Error, FlexId, GeometryError, RangeId, Side, ZoomLevel,
+    spatial_id::collection::query::{merge_policy::MergePolicy, traits::UnaryOperator},
+};
+
+use super::MetricBall;
+
+/// 各ボクセルを、メートル単位の球（楕円体）の構造要素で膨らませる演算子。
+///
+/// ズームレベル `z` のボクセルの中心間の距離が、水平方向 `horizontal_m`・鉛直方向
+/// `vertical_m` の楕円体に収まる近傍へ値を広げる。近傍同士が重なった位置の値は `P` で合成する。
+pub struct Dilate<P> {
+    pub z: ZoomLevel,
+    pub horizontal_m: f64,
+    pub vertical_m: f64,
+    _marker: PhantomData<fn() -> P>,
+}
+
+impl<P> Dilate<P> {
+    /// 半径が負・非有限なら [`GeometryError::RadiusNegative`] を返す。
+    pub fn new<T: Into<u8>>(z: T, horizontal_m: f64, vertical_m: f64) -> Result<Self, Error> {
+        let z = ZoomLevel::new(z.into())?;
+        for radius in [horizontal_m, vertical_m] {
+            if !radius.is_finite() || radius < 0.0 {
+                return Err(GeometryError::RadiusNegative { radius }.into());
+            }
+        }
+        Ok(Self {
+            z,
+            horizontal_m,
+            vertical_m,
+            _marker: PhantomData,
+        })
+    }
+
+    fn ball(&self) -> MetricBall {
+        MetricBall {
+            horizontal_m: self.horizontal_m,
+            vertical_m: self.vertical_m,
+        }
+    }
+}
+
+/// `id` を、近傍の形が行によらず同じになる Y 方向の区間に割って `(区間, 近傍)` を `out` に加える。
+///
+/// 近傍はボクセルが極に近いほど広がる（赤道をまたがない範囲では単調）ので、両端の行で
+/// 同じなら間の行でも同じになる。違えば Y 方向に二分して調べ直す。
+fn split_by_kernel(id: FlexId, z: u8, ball: &MetricBall, out: &mut Vec<(FlexId, Vec<[i32; 3]>)>) {
+    let y_zoom = id.y_zoomlevel();
+    if y_zoom >= z {
+        out.push((id, ball.offsets(z, id.y_index() >> (y_zoom - z))));
+        return;
+    }
+    let scale = z - y_zoom;
+    let first = id.y_index() << scale;
+    let last = first + ((1 << scale) - 1);
+    // ズームレベル 0 の行だけが赤道をまたぐ。
+    if y_zoom > 0 {
+        let kernel = ball.offsets(z, first);
+        if kernel == ball.offsets(z, last) {
+            out.push((id, kernel));
+            return;
+        }
+    }
+    for side in [Side::Lower, Side::Upper] {
+        if let Some(half) = id.split_y(side) {
+            split_by_kernel(half, z, ball, out);
+        }
+    }
+}
+
+/// `id` を `offsets` の各移動量だけ動かした [`FlexId`] を `out` に加える。
+///
+/// F・Y の範囲外へ出る移動は、`falloff_*` と同じく葉ごと捨てる。X は巡回する。
+pub(crate) fn push_moved<V: Clone>(
+    id: FlexId,
+    z: u8,
+    offsets: &[[i32; 3]],
+    value: &V,
+    out: &mut Vec<(FlexId, V)>,
+) {
+    for &[df, dx, dy] in offsets {
+        let Ok(moved_f) = id.shift_f(z, df) else {
+            continue;
+        };
+        for a in moved_f {
+            let Ok(moved_x) = a.shift_x(z, dx) else {
+                continue;
+            };
+            for b in moved_x {
+                if let Ok(moved_y) = b.shift_y(z, dy) {
+                    out.extend(moved_y.map(|c| (c, value.clone())));
+                }
+            }
+        }
+    }
+}
+
+impl<V: SafeValue + 'static, P> UnaryOperator<V> for Dilate<P>
+where
+    P: MergePolicy<V> + Send + Sync + 'static,
+{
+    fn validate(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn run(&self, target: &mut WorkingTree<V>) -> Result<(), Error> {
+        if self.horizontal_m == 0.0 && self.vertical_m == 0.0 {
+            return Ok(());
+        }
+        let z = self.z.get();
+        let ball = self.ball();
+
+        // 近傍が互いに重なるので merge_with で合成する。
+        let rebuilt = target.core().map_rebuild_with(
+            |id, value| {
+                let mut pieces = Vec::new();
+                split_by_kernel(id, z, &ball, &mut pieces);
+                let mut out = Vec::new();
+                for (piece, offsets) in pieces {
+                    push_moved(piece, z, &offsets, value, &mut out);
+                }
+                Ok(out)
+            },
+            |a: &V, b: &V| P::resolve(a.clone(), b.clone()),
+        )?;
+        *target = WorkingTree::from_core(rebuilt);
+        Ok(())
+    }
+
+    fn commutativity_info(&self) -> CommutativityInfo {
+        // 近傍の形が緯度で変わるので、Y 方向の移動とすら入れ替えられない。
+        CommutativityInfo::None
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+
+    fn expansion_ratio(&self) -> f64 {
+        let z = self.z.get();
+        let [rf, rxy, _] = self.ball().reach(z, (1 << z) >> 1);
+        ((2 * rf + 1) * (2 * rxy + 1) * (2 * rxy + 1)) as f64
+    }
+
+    fn inverse_bounds(&self, bounds: RangeId) -> Option<RangeId> {
+        let z = self.z.get();
+        let target_z = z.max(bounds.z());
+        let scale = 1i64 << (target_z - z);
+        let ball = self.ball();
+
+        // 入力は出力から reach だけ離れた行にあり、極に近い行ほど reach が伸びる。
+        // 広げた範囲の両端の行で測った reach が変わらなくなるまで繰り返す。
+        let (y_min, y_max) = bounds.y_fine_range(target_z);
+        let (y_min, y_max) = (
+            (y_min as i64) >> (target_z - z),
+            (y_max as i64) >> (target_z - z),
+        );
+        let last_row = (1i64 << z) - 1;
+        let mut reach = [0i32; 3];
+        loop {
+            let lo = (y_min - reach[2] as i64).max(0) as u32;
+            let hi = (y_max + reach[2] as i64).min(last_row) as u32;
+            let [a, b] = [ball.reach(z, lo), ball.reach(z, hi)];
+            let next = [a[0].max(b[0]), a[1].max(b[1]), a[2].max(b[2])];
+            if next == reach {
+                break;
+            }
+            reach = next;
+        }
+
+        let [rf, rx, ry] = reach.map(|r| r as i64 * scale);
+        bounds
+            .f_edges_shift(target_z, -rf, rf)
+            .unwrap()?
+            .x_edges_shift(target_z, -rx, rx)
+            .unwrap()?
+            .y_edges_shift(target_z, -ry, ry)
+            .unwrap()
+    }
+
+    fn fmt_op(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.horizontal_m == self.vertical_m {
+            write!(
+                f,
+                "dilate(z={}, r={}, {})",
+                self.z.get(),
+                self.horizontal_m,
+                P::NAME
+            )
+        } else {
+            write!(
+                f,
+                "dilate(z={}, h={}, v={}, {})",
+                self.z.get(),
+                self.horizontal_m,
+                self.vertical_m,
+                P::NAME
+            )
+        }
+    }
+
+    fn grid_zoom(&self) -> Option<ZoomLevel> {
+        if !P::IS_COMMUTATIVE {
+            return None;
+        }
+        Some(self.z)
+    }
+
+    #[allow(private_interfaces)]
+    fn apply_to_grid(
+        &self,
+        grid: &mut crate::spatial_id::collection::query::grid::UniformGrid<V>,
+        token: &crate::CancellationToken,
+    ) -> Result<crate::spatial_id::collection::query::grid::Applied, Error> {
+        if !P::IS_COMMUTATIVE {
+            return Ok(crate::spatial_id::collection::query::grid::Applied::Unsupported);
+        }
+        let z = self.z.get();
+        let ball = self.ball();
+        grid.dilate::<P, _>(self.z, |y| ball.offsets(z, y), token)
+    }
+}
@@ -0,0 +1,67 @@
+#[allow(clippy::module_inception)]
+pub mod dilate;
+pub mod query;
+
+#[cfg(test)]
+mod test;
+
+pub use dilate::Dilate;
+
+use alloc::vec::Vec;
+
+use crate::{SingleId, SpatialId};
+
+/// メートル単位の楕円体の構造要素。水平方向の半径と鉛直方向の半径を持つ。
+///
+/// ズームレベル `z` のボクセル同士の中心間の距離で、近傍に入るかを決める。
+/// 水平方向のボクセルの長さは緯度によって変わるので、近傍の形は行（Y）ごとに求める。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct MetricBall {
+    pub horizontal_m: f64,
+    pub vertical_m: f64,
+}
+
+impl MetricBall {
+    /// ズームレベル `z` の行 `y` における、水平方向と鉛直方向のボクセルの長さ（メートル）。
+    fn cell_lengths(z: u8, y: u32) -> (f64, f64) {
+        let cell = SingleId::new(z, 0, 0, y).expect("y はズームレベル z の範囲内");
+        (cell.length_x_meters(), cell.length_f_meters())
+    }
+
+    /// 行 `y` において、各軸へ届くボクセル数 `[F, X, Y]`。
+    pub(crate) fn reach(&self, z: u8, y: u32) -> [i32; 3] {
+        let (horizontal, vertical) = Self::cell_lengths(z, y);
+        let cells = |radius: f64, length: f64| libm::floor(radius / length) as i32;
+        let h = cells(self.horizontal_m, horizontal);
+        [cells(self.vertical_m, vertical), h, h]
+    }
+
+    /// 行 `y` のボクセルから見た、近傍に入るボクセルの移動量 `[dF, dX, dY]` の列。
+    pub(crate) fn offsets(&self, z: u8, y: u32) -> Vec<[i32; 3]> {
+        let (horizontal, vertical) = Self::cell_lengths(z, y);
+        let [rf, rxy, _] = self.reach(z, y);
+        // 半径 0 の軸は動かさない（0 で割らない）。
+        let ratio = |d: i32, length: f64, radius: f64| {
+            if d == 0 {
+                0.0
+            } else {
+                let r = d as f64 * length / radius;
+                r * r
+            }
+        };
+
+        let mut out = Vec::new();
+        for df in -rf..=rf {
+            let ef = ratio(df, vertical, self.vertical_m);
+            for dy in -rxy..=rxy {
+                let ey = ratio(dy, horizontal, self.horizontal_m);
+                for dx in -rxy..=rxy {
+                    if ef + ey + ratio(dx, horizontal, self.horizontal_m) <= 1.0 {
+                        out.push([df, dx, dy]);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,44 @@
+use super::Dilate;
+use crate::spatial_id::collection::flex_tree::core::SafeValue;
+use crate::spatial_id::collection::query::{execution::Query, merge_policy::MergePolicy};
+
+impl<V: SafeValue + 'static> Query<V> {
+    /// 各ボクセルを、半径 `radius_m` メートルの球で膨らませる（バッファを取る）。
+    ///
+    /// ズームレベル `z` のボクセルの中心間の距離が `radius_m` 以下の近傍へ値を広げ、
+    /// 重なった位置の値は `policy` で合成する。ボクセルの水平方向の長さは緯度によって変わるが、
+    /// 近傍はメートルで測るので、どの緯度・どの軸でも同じ広さになる。
+    ///
+    /// ```ignore
+    /// // 建物の周囲 300 m を安全域にする
+    /// let buffer = buildings.query().dilate(20, 300.0, Max);
+    /// ```
+    pub fn dilate<Z: Into<u8>, P>(self, z: Z, radius_m: f64, policy: P) -> Self
+    where
+        P: MergePolicy<V> + Send + Sync,
+    {
+        self.dilate_ellipsoid(z, radius_m, radius_m, policy)
+    }
+
+    /// 水平方向の半径 `horizontal_m` と鉛直方向の半径 `vertical_m` の楕円体で膨らませる。
+    ///
+    /// 近傍の決め方と重なりの扱いは [`dilate`](Self::dilate) と同じ。
+    pub fn dilate_ellipsoid<Z: Into<u8>, P>(
+        self,
+        z: Z,
+        horizontal_m: f64,
+        vertical_m: f64,
+        _policy: P,
+    ) -> Self
+    where
+        P: MergePolicy<V> + Send + Sync,
+    {
+        if matches!(self, Query::Error(_)) {
+            return self;
+        }
+        match Dilate::<P>::new(z, horizontal_m, vertical_m) {
+            Ok(op) => self.wrap_unary(op),
+            Err(e) => Query::Error(e),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::vec::Vec;
+
+use crate::spatial_id::collection::query::merge_policy::{Max, Sum};
+use crate::{Coordinate, Error, GeometryError, Query, RangeId, SingleId, Source, SpatialIdTable};
+
+/// 緯度 `lat`・高度 0 m の地点を含むズームレベル 20 のボクセル。
+fn cell_at(lat: f64) -> SingleId {
+    Coordinate::new(lat, 139.77, 0.0)
+        .unwrap()
+        .single_id(20)
+        .unwrap()
+}
+
+/// `center` から見た移動量 `(dF, dX, dY) -> 値` の対応。
+fn offsets(table: &SpatialIdTable<u32>, center: &SingleId) -> BTreeMap<(i64, i64, i64), u32> {
+    // 木は揃った兄弟をまとめるので、ズームレベル 20 まで割ってから数える
+    table
+        .iter()
+        .flat_map(|(id, v)| {
+            let cells = RangeId::from(id).spatial_children_at_zoom(20).unwrap();
+            cells
+                .single_ids()
+                .map(move |sid| (sid, *v))
+                .collect::<Vec<_>>()
+        })
+        .map(|(sid, v)| {
+            let d = (
+                sid.f() as i64 - center.f() as i64,
+                sid.x() as i64 - center.x() as i64,
+                sid.y() as i64 - center.y() as i64,
+            );
+            (d, v)
+        })
+        .collect()
+}
+
+fn dilated(center: &SingleId, radius_m: f64) -> BTreeMap<(i64, i64, i64), u32> {
+    let mut table = SpatialIdTable::new();
+    table.insert(center.clone(), 1_u32);
+    let out = table.query().dilate(20, radius_m, Max).raw_run().unwrap();
+    offsets(&out, center)
+}
+
+/// 近傍は立方体ではなく球になる
+#[test]
+fn sphere_is_round() {
+    // 東京付近のズームレベル 20 は、水平約 31 m・鉛直 32 m。半径 100 m は各軸 3 ボクセルまで届く。
+    let center = cell_at(35.68);
+    let ball = dilated(&center, 100.0);
+
+    for d in [
+        (3, 0, 0),
+        (-3, 0, 0),
+        (0, 3, 0),
+        (0, -3, 0),
+        (0, 0, 3),
+        (0, 0, -3),
+    ] {
+        assert!(ball.contains_key(&d), "{d:?}");
+    }
+    assert!(ball.contains_key(&(0, 2, 2)));
+    // 立方体の角や辺の中ほどは届かない
+    assert!(!ball.contains_key(&(3, 3, 3)));
+    assert!(!ball.contains_key(&(0, 3, 3)));
+    assert!(!ball.contains_key(&(0, 4, 0)));
+    assert!(ball.len() < 7 * 7 * 7);
+}
+
+/// 同じ半径でも、ボクセルが短い高緯度ほど多くのボクセルへ届く
+#[test]
+fn reach_follows_latitude() {
+    let row = |lat: f64| {
+        let center = cell_at(lat);
+        dilated(&center, 100.0)
+            .keys()
+            .filter(|(df, _, dy)| *df == 0 && *dy == 0)
+            .count()
+    };
+    // 赤道では約 38 m で ±2、北緯 60 度では約 19 m で ±5。
+    assert_eq!(row(0.1), 5);
+    assert_eq!(row(60.0), 11);
+}
+
+/// 鉛直と水平で別の半径を取れる
+#[test]
+fn ellipsoid_has_separate_vertical_radius() {
+    let center = cell_at(35.68);
+    let mut table = SpatialIdTable::new();
+    table.insert(center.clone(), 1_u32);
+    let out = table
+        .query()
+        .dilate_ellipsoid(20, 100.0, 0.0, Max)
+        .raw_run()
+        .unwrap();
+    let flat = offsets(&out, &center);
+    assert!(flat.keys().all(|(df, _, _)| *df == 0));
+    assert!(flat.contains_key(&(0, 3, 0)));
+}
+
+/// 近傍の重なりは MergePolicy で合成する
+#[test]
+fn overlaps_are_merged_by_policy() {
+    let center = cell_at(35.68);
+    let neighbor = SingleId::new(20, center.f(), center.x() + 2, center.y()).unwrap();
+    let mut table = SpatialIdTable::new();
+    table.insert(center.clone(), 1_u32);
+    table.insert(neighbor, 1_u32);
+
+    let out = table.query().dilate(20, 40.0, Sum).raw_run().unwrap();
+    let merged = offsets(&out, &center);
+    assert_eq!(merged.get(&(0, 1, 0)), Some(&2));
+    assert_eq!(merged.get(&(0, -1, 0)), Some(&1));
+    assert_eq!(merged.get(&(0, 3, 0)), Some(&1));
+}
+
+/// 負の半径はエラーになる
+#[test]
+fn rejects_negative_radius() {
+    let table = SpatialIdTable::<u32>::new();
+    let result = table.query().dilate(20, -1.0, Max).raw_run();
+    assert!(matches!(
+        result,
+        Err(Error::Geometry(GeometryError::RadiusNegative { .. }))
+    ));
+}
+
+/// `lazy_get` は入力の必要な範囲だけを読んで、全体を実行したのと同じ値を返す
+#[test]
+fn lazy_get_matches_run() {
+    let center = cell_at(35.68);
+    let mut table = SpatialIdTable::new();
+    table.insert(center.clone(), 5_u32);
+
+    let expected: Vec<(SingleId, u32)> = table
+        .clone()
+        .query()
+        .dilate(20, 100.0, Max)
+        .raw_run()
+        .unwrap()
+        .flat_single_ids()
+        .map(|(id, v)| (id, *v))
+        .collect();
+
+    let query = table.query().dilate(20, 100.0, Max);
+    for (id, value) in &expected {
+        let mut got = query.lazy_get(id.clone()).unwrap();
+        assert_eq!(got.next().map(|(_, v)| v), Some(*value));
+    }
+    let outside = SingleId::new(20, center.f(), center.x() + 4, center.y()).unwrap();
+    assert!(query.lazy_get(outside).unwrap().next().is_none());
+}
+
+/// 表示した文字列を読み戻すと同じクエリになる
+#[test]
+fn display_round_trips_through_parse() {
+    let built = SpatialIdTable::<u32>::new()
+        .query()
+        .dilate_ellipsoid(20, 300.0, 50.5, Max);
+    let text = format!("{built}");
+    assert!(text.contains("dilate(z=20, h=300, v=50.5, Max)"), "{text}");
+
+    let parsed: Query<u32> = Query::parse("a | dilate(20, 300, Max)", |_| {
+        Some(SpatialIdTable::<u32>::new().query())
+    })
+    .unwrap();
+    assert!(format!("{parsed}").contains("dilate(z=20, r=300, Max)"));
+}
@@ -84,7 +84,7 @@ where
             && s > e
         {
             return Err(Error::InvalidQueryParameter(
-                "value range lower bound is greater than upper bound",
+                "value range lower bound is greater than upper bound".into(),
             ));
         }
         Ok(())
@@ -0,0 +1,73 @@
+use crate::spatial_id::collection::query::working::WorkingTree;
+pub mod query;
+#[cfg(test)]
+mod test;
+
+use crate::spatial_id::collection::flex_tree::components::connected_components;
+use crate::spatial_id::collection::query::execution::group_commutative::types::CommutativityInfo;
+use crate::{
+    Connectivity, Error, RangeId,
+    spatial_id::collection::{flex_tree::core::SafeValue, query::traits::UnaryOperator},
+};
+
+use alloc::vec;
+
+/// 値を、その空間が属する連結成分の番号に置き換える単項演算子。
+///
+/// 成分の分け方と番号の振り方は [`SpatialIdSet::connected_components`](crate::SpatialIdSet::connected_components)
+/// と同じ。番号が値の型に収まらなければ [`Error::InvalidQueryParameter`] を返す。
+pub struct LabelComponents {
+    pub connectivity: Connectivity,
+}
+
+impl LabelComponents {
+    pub fn new(connectivity: Connectivity) -> Self {
+        Self { connectivity }
+    }
+}
+
+impl<V> UnaryOperator<V> for LabelComponents
+where
+    V: SafeValue + TryFrom<u32> + 'static,
+{
+    fn validate(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn run(&self, target: &mut WorkingTree<V>) -> Result<(), Error> {
+        let (labels, _) = connected_components(target.core(), self.connectivity);
+        // 成分は時間を外した空間で分けるので、時間を外して番号を引く。葉は 1 つの成分にしか触れない。
+        let rebuilt = target.core().map_rebuild(|id, _| {
+            let label = labels
+                .get_overlapping_ref(id.without_time())
+                .next()
+                .map(|(_, label)| *label)
+                .expect("全ての葉はいずれかの成分に属する");
+            let value = V::try_from(label).map_err(|_| {
+                Error::InvalidQueryParameter(
+                    "label_components: label does not fit in the value type".into(),
+                )
+            })?;
+            Ok(vec![(id, value)])
+        })?;
+        *target = WorkingTree::from_core(rebuilt);
+        Ok(())
+    }
+
+    fn commutativity_info(&self) -> CommutativityInfo {
+        CommutativityInfo::None
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+
+    fn inverse_bounds(&self, _bounds: RangeId) -> Option<RangeId> {
+        // 番号は全体のつながりで決まるので、どの出力領域にも入力の全域が要る。
+        Some(RangeId::new(0, [-1, 0], 0, 0).unwrap())
+    }
+
+    fn fmt_op(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "label_components({:?})", self.connectivity)
+    }
+}
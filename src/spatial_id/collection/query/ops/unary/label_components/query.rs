@@ -0,0 +1,22 @@
+use super::LabelComponents;
+use crate::Connectivity;
+use crate::spatial_id::collection::flex_tree::core::SafeValue;
+use crate::spatial_id::collection::query::execution::Query;
+
+impl<V: SafeValue + TryFrom<u32> + 'static> Query<V> {
+    /// 値を、その空間が属する連結成分の番号（0 から）に置き換える。
+    ///
+    /// `connectivity` のつながり方で接する空間を 1 つの成分にまとめる。成分の統計も要るなら、
+    /// 実行結果に [`SpatialIdTable::connected_components`](crate::SpatialIdTable::connected_components) を使う。
+    ///
+    /// ```ignore
+    /// // 離れた障害物ごとに番号を振る
+    /// let obstacles = buildings.query().dilate(20, 5.0, Max).label_components(Connectivity::Face);
+    /// ```
+    pub fn label_components(self, connectivity: Connectivity) -> Self {
+        if matches!(self, Query::Error(_)) {
+            return self;
+        }
+        self.wrap_unary(LabelComponents::new(connectivity))
+    }
+}
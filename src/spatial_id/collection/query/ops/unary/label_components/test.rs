@@ -0,0 +1,86 @@
+use alloc::format;
+use alloc::vec::Vec;
+
+use crate::{Connectivity, Error, Query, SingleId, Source, SpatialIdTable};
+
+fn cell(x: u32, y: u32) -> SingleId {
+    SingleId::new(20, 0, 931000 + x, 412000 + y).unwrap()
+}
+
+fn label_of(table: &SpatialIdTable<u32>, id: SingleId) -> Option<u32> {
+    table.get(&id).next().map(|(_, v)| *v)
+}
+
+/// 値は、その空間が属する成分の番号に置き換わる
+#[test]
+fn values_become_component_labels() {
+    let mut table = SpatialIdTable::new();
+    table.insert(cell(0, 0), 7_u32);
+    table.insert(cell(1, 0), 9_u32);
+    table.insert(cell(5, 5), 7_u32);
+
+    let out = table
+        .query()
+        .label_components(Connectivity::Face)
+        .raw_run()
+        .unwrap();
+    assert_eq!(label_of(&out, cell(0, 0)), Some(0));
+    assert_eq!(label_of(&out, cell(1, 0)), Some(0));
+    assert_eq!(label_of(&out, cell(5, 5)), Some(1));
+}
+
+/// 番号は集合・テーブルの `connected_components` と一致する
+#[test]
+fn matches_collection_api() {
+    let mut table = SpatialIdTable::new();
+    for (x, y) in [(0, 0), (1, 1), (3, 0), (3, 1), (8, 8)] {
+        table.insert(cell(x, y), 1_u32);
+    }
+    let (expected, _) = table.connected_components(Connectivity::Edge);
+    let out = table
+        .query()
+        .label_components(Connectivity::Edge)
+        .raw_run()
+        .unwrap();
+
+    let flat = |t: &SpatialIdTable<u32>| -> Vec<(SingleId, u32)> {
+        t.flat_single_ids().map(|(id, v)| (id, *v)).collect()
+    };
+    assert_eq!(flat(&out), flat(&expected));
+}
+
+/// `lazy_get` は入力全体を読んで、全体を実行したのと同じ番号を返す
+#[test]
+fn lazy_get_matches_run() {
+    let mut table = SpatialIdTable::new();
+    for x in [0, 1, 4, 9, 10] {
+        table.insert(cell(x, 0), 1_u32);
+    }
+    let query = table.query().label_components(Connectivity::Face);
+    for (x, label) in [(0, 0), (1, 0), (4, 1), (9, 2), (10, 2)] {
+        let got = query.lazy_get(cell(x, 0)).unwrap().next().map(|(_, v)| v);
+        assert_eq!(got, Some(label), "x = {x}");
+    }
+}
+
+/// 番号が値の型に収まらなければエラーになる
+#[test]
+fn rejects_labels_out_of_value_range() {
+    let mut table = SpatialIdTable::new();
+    for x in 0..300 {
+        table.insert(cell(2 * x, 0), 1_u8);
+    }
+    let result = table.query().label_components(Connectivity::Face).raw_run();
+    assert!(matches!(result, Err(Error::InvalidQueryParameter(_))));
+}
+
+/// 表示した文字列を読み戻すと同じクエリになる
+#[test]
+fn display_round_trips_through_parse() {
+    let parsed: Query<u32> = Query::parse("a | label_components(conn=Vertex)", |_| {
+        Some(SpatialIdTable::<u32>::new().query())
+    })
+    .unwrap();
+    let text = format!("{parsed}");
+    assert!(text.contains("label_components(Vertex)"), "{text}");
+}
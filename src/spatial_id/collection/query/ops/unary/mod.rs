@@ -1,5 +1,8 @@
+pub mod dilate;
 pub mod extrude;
 pub mod falloff;
 pub mod filter_values;
+pub mod label_components;
+pub mod morphology;
 pub mod shift;
 pub mod zoom_out;
@@ -0,0 +1,89 @@
+use crate::spatial_id::collection::flex_tree::core::SafeValue;
+use crate::spatial_id::collection::query::execution::group_commutative::types::CommutativityInfo;
+use crate::spatial_id::collection::query::working::WorkingTree;
+use core::marker::PhantomData;
+
+use crate::{
+    Error, RangeId, ZoomLevel,
+    spatial_id::collection::query::{merge_policy::MergePolicy, traits::UnaryOperator},
+};
+
+use super::{Connectivity, Kernel, dilate_core, erode_core};
+
+/// 膨張してから同じ構造要素で収縮する（クロージング）演算子。
+///
+/// 構造要素より小さい穴や隙間を埋め、それ以外の形はおおむね保つ。
+/// 膨張で重なった位置の値は `P` で合成する。
+pub struct Close<P> {
+    pub z: ZoomLevel,
+    pub radii: [u32; 3],
+    pub connectivity: Connectivity,
+    _marker: PhantomData<fn() -> P>,
+}
+
+impl<P> Close<P> {
+    pub fn new<T: Into<u8>>(
+        z: T,
+        radii: [u32; 3],
+        connectivity: Connectivity,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            z: ZoomLevel::new(z.into())?,
+            radii,
+            connectivity,
+            _marker: PhantomData,
+        })
+    }
+
+    fn kernel(&self) -> Kernel {
+        Kernel {
+            radii: self.radii,
+            connectivity: self.connectivity,
+        }
+    }
+}
+
+impl<V: SafeValue + 'static, P> UnaryOperator<V> for Close<P>
+where
+    P: MergePolicy<V> + Send + Sync + 'static,
+{
+    fn validate(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn run(&self, target: &mut WorkingTree<V>) -> Result<(), Error> {
+        if self.radii == [0, 0, 0] {
+            return Ok(());
+        }
+        let z = self.z.get();
+        let kernel = self.kernel();
+        let dilated = dilate_core::<V, P>(target.core(), z, &kernel)?;
+        let closed = erode_core(&dilated, z, &kernel)?;
+        *target = WorkingTree::from_core(closed);
+        Ok(())
+    }
+
+    fn commutativity_info(&self) -> CommutativityInfo {
+        CommutativityInfo::None
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+
+    fn inverse_bounds(&self, bounds: RangeId) -> Option<RangeId> {
+        // 膨張と収縮の分だけ、半径の 2 倍まで入力を読む。
+        self.kernel().widen(self.z.get(), bounds, 2)
+    }
+
+    fn fmt_op(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let [rf, rx, ry] = self.radii;
+        write!(
+            f,
+            "close(z={}, f={rf}, x={rx}, y={ry}, {:?}, {})",
+            self.z.get(),
+            self.connectivity,
+            P::NAME
+        )
+    }
+}
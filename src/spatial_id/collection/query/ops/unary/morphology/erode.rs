@@ -0,0 +1,76 @@
+use crate::spatial_id::collection::flex_tree::core::SafeValue;
+use crate::spatial_id::collection::query::execution::group_commutative::types::CommutativityInfo;
+use crate::spatial_id::collection::query::working::WorkingTree;
+
+use crate::{Error, RangeId, ZoomLevel, spatial_id::collection::query::traits::UnaryOperator};
+
+use super::{Connectivity, Kernel, erode_core};
+
+/// 構造要素が収まらないボクセルを削る（収縮する）演算子。
+///
+/// ズームレベル `z` で、構造要素の全ての移動先が入力に含まれるボクセルだけを元の値のまま残す。
+/// 構造要素は、つながり方 `connectivity` の近傍を軸ごとの半径 `radii = [F, X, Y]` まで広げたもの。
+pub struct Erode {
+    pub z: ZoomLevel,
+    pub radii: [u32; 3],
+    pub connectivity: Connectivity,
+}
+
+impl Erode {
+    pub fn new<T: Into<u8>>(
+        z: T,
+        radii: [u32; 3],
+        connectivity: Connectivity,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            z: ZoomLevel::new(z.into())?,
+            radii,
+            connectivity,
+        })
+    }
+
+    fn kernel(&self) -> Kernel {
+        Kernel {
+            radii: self.radii,
+            connectivity: self.connectivity,
+        }
+    }
+}
+
+impl<V: SafeValue + 'static> UnaryOperator<V> for Erode {
+    fn validate(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn run(&self, target: &mut WorkingTree<V>) -> Result<(), Error> {
+        if self.radii == [0, 0, 0] {
+            return Ok(());
+        }
+        let eroded = erode_core(target.core(), self.z.get(), &self.kernel())?;
+        *target = WorkingTree::from_core(eroded);
+        Ok(())
+    }
+
+    fn commutativity_info(&self) -> CommutativityInfo {
+        // 範囲の端で削れ方が変わるので、F・Y 方向の移動とも入れ替えられない。
+        CommutativityInfo::None
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+
+    fn inverse_bounds(&self, bounds: RangeId) -> Option<RangeId> {
+        self.kernel().widen(self.z.get(), bounds, 1)
+    }
+
+    fn fmt_op(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let [rf, rx, ry] = self.radii;
+        write!(
+            f,
+            "erode(z={}, f={rf}, x={rx}, y={ry}, {:?})",
+            self.z.get(),
+            self.connectivity
+        )
+    }
+}
@@ -0,0 +1,148 @@
+pub mod close;
+pub mod erode;
+pub mod open;
+pub mod query;
+
+#[cfg(test)]
+mod test;
+
+pub use close::Close;
+pub use erode::Erode;
+pub use open::Open;
+
+pub use crate::spatial_id::helpers::Connectivity;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::spatial_id::collection::flex_tree::core::{FlexTreeCore, SafeValue};
+use crate::spatial_id::collection::query::merge_policy::MergePolicy;
+use crate::spatial_id::collection::query::ops::unary::dilate::dilate::push_moved;
+use crate::{Error, RangeId};
+
+/// 軸ごとの半径 `[F, X, Y]`（ズームレベル `z` のインデックス単位）とつながり方で決まる構造要素。
+///
+/// 半径 `r` の構造要素は、`connectivity` の近傍を `r` 回繰り返して届く範囲になる。
+/// `Face` は八面体、`Vertex` は直方体になる。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Kernel {
+    pub radii: [u32; 3],
+    pub connectivity: Connectivity,
+}
+
+impl Kernel {
+    /// 構造要素に入る移動量 `[dF, dX, dY]` の列。
+    ///
+    /// 各軸の移動量を半径で割った値を `a` として、`Face` は `Σa ≤ 1`、`Edge` は `max a ≤ 1` かつ
+    /// `Σa ≤ 2`、`Vertex` は `max a ≤ 1` を満たすものを集める。半径 0 の軸は動かさない。
+    pub(crate) fn offsets(&self) -> Vec<[i32; 3]> {
+        let [rf, rx, ry] = self.radii.map(|r| r as i32);
+        // 比較を整数で行うため、半径の積を掛けた値で比べる（半径 0 の軸は 1 とみなす）。
+        let scale = |r: i32| r.max(1) as i64;
+        let whole = scale(rf) * scale(rx) * scale(ry);
+        let limit = match self.connectivity {
+            Connectivity::Face => whole,
+            Connectivity::Edge => 2 * whole,
+            Connectivity::Vertex => 3 * whole,
+        };
+
+        let mut out = Vec::new();
+        for df in -rf..=rf {
+            for dx in -rx..=rx {
+                for dy in -ry..=ry {
+                    let sum = df.unsigned_abs() as i64 * scale(rx) * scale(ry)
+                        + dx.unsigned_abs() as i64 * scale(rf) * scale(ry)
+                        + dy.unsigned_abs() as i64 * scale(rf) * scale(rx);
+                    if sum <= limit {
+                        out.push([df, dx, dy]);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// 収縮を順に行う移動量の組。
+    ///
+    /// 直方体（`Vertex`）は軸ごとの線分に分けられるので 3 回に分け、それ以外は 1 回で行う。
+    fn passes(&self) -> Vec<Vec<[i32; 3]>> {
+        match self.connectivity {
+            Connectivity::Vertex => (0..3)
+                .map(|axis| {
+                    let r = self.radii[axis] as i32;
+                    (-r..=r)
+                        .map(|d| {
+                            let mut offset = [0; 3];
+                            offset[axis] = d;
+                            offset
+                        })
+                        .collect()
+                })
+                .collect(),
+            _ => vec![self.offsets()],
+        }
+    }
+
+    /// ズームレベル `target_z` で、出力領域 `bounds` を各軸へ半径の `times` 倍だけ広げる。
+    pub(crate) fn widen(&self, z: u8, bounds: RangeId, times: i64) -> Option<RangeId> {
+        let target_z = z.max(bounds.z());
+        let scale = 1i64 << (target_z - z);
+        let [rf, rx, ry] = self.radii.map(|r| r as i64 * scale * times);
+        bounds
+            .f_edges_shift(target_z, -rf, rf)
+            .unwrap()?
+            .x_edges_shift(target_z, -rx, rx)
+            .unwrap()?
+            .y_edges_shift(target_z, -ry, ry)
+            .unwrap()
+    }
+}
+
+/// 構造要素の全ての移動先が木に含まれるボクセルだけを、元の値のまま残す。
+///
+/// F・Y の範囲外は空とみなすので、範囲の端に接するボクセルは削られる。
+pub(crate) fn erode_core<V: SafeValue>(
+    core: &FlexTreeCore<V>,
+    z: u8,
+    kernel: &Kernel,
+) -> Result<FlexTreeCore<V>, Error> {
+    let mut acc = core.clone();
+    for pass in kernel.passes() {
+        let base = acc.clone();
+        for &[df, dx, dy] in &pass {
+            if [df, dx, dy] == [0, 0, 0] {
+                continue;
+            }
+            // 移動先 c + o が base に含まれる c は、base を -o だけ動かした木に含まれる。
+            let shifted = base.map_rebuild(|id, value| {
+                let mut out = Vec::new();
+                push_moved(id, z, &[[-df, -dx, -dy]], value, &mut out);
+                Ok(out)
+            })?;
+            // 差を 2 回取って、acc の値を保ったまま shifted に含まれる部分だけを残す。
+            acc = acc.difference(&base.difference(&shifted));
+        }
+    }
+    Ok(acc)
+}
+
+/// 構造要素の移動先へ値を広げ、重なった位置を `P` で合成する。
+pub(crate) fn dilate_core<V, P>(
+    core: &FlexTreeCore<V>,
+    z: u8,
+    kernel: &Kernel,
+) -> Result<FlexTreeCore<V>, Error>
+where
+    V: SafeValue,
+    P: MergePolicy<V>,
+{
+    let offsets = kernel.offsets();
+    core.map_rebuild_with(
+        |id, value| {
+            let mut out = Vec::new();
+            push_moved(id, z, &offsets, value, &mut out);
+            Ok(out)
+        },
+        |a: &V, b: &V| P::resolve(a.clone(), b.clone()),
+    )
+}
@@ -0,0 +1,71 @@
+use super::{Close, Connectivity, Erode, Open};
+use crate::spatial_id::collection::flex_tree::core::SafeValue;
+use crate::spatial_id::collection::query::{execution::Query, merge_policy::MergePolicy};
+
+impl<V: SafeValue + 'static> Query<V> {
+    /// 構造要素が収まりきらない縁のボクセルを削る（収縮）。
+    ///
+    /// 構造要素は、ズームレベル `z` のボクセルを `connectivity` の近傍で軸ごとに
+    /// `radii = [F, X, Y]` ボクセルまで広げたもの。構造要素の全体が入力に含まれる
+    /// ボクセルだけを、元の値のまま残す。F・Y の範囲の外は空とみなす。
+    ///
+    /// ```ignore
+    /// // 水平方向に 2 ボクセル以上の余裕がある内側だけを残す
+    /// let core = area.query().erode(20, [0, 2, 2], Connectivity::Face);
+    /// ```
+    pub fn erode<Z: Into<u8>>(self, z: Z, radii: [u32; 3], connectivity: Connectivity) -> Self {
+        if matches!(self, Query::Error(_)) {
+            return self;
+        }
+        match Erode::new(z, radii, connectivity) {
+            Ok(op) => self.wrap_unary(op),
+            Err(e) => Query::Error(e),
+        }
+    }
+
+    /// 収縮してから同じ構造要素で膨張する（オープニング）。
+    ///
+    /// 構造要素より細い突起や孤立したボクセルが消える。膨張で重なった位置の値は `policy` で合成する。
+    /// 構造要素は [`erode`](Self::erode) と同じ。
+    pub fn open<Z: Into<u8>, P>(
+        self,
+        z: Z,
+        radii: [u32; 3],
+        connectivity: Connectivity,
+        _policy: P,
+    ) -> Self
+    where
+        P: MergePolicy<V> + Send + Sync,
+    {
+        if matches!(self, Query::Error(_)) {
+            return self;
+        }
+        match Open::<P>::new(z, radii, connectivity) {
+            Ok(op) => self.wrap_unary(op),
+            Err(e) => Query::Error(e),
+        }
+    }
+
+    /// 膨張してから同じ構造要素で収縮する（クロージング）。
+    ///
+    /// 構造要素より小さい穴や隙間が埋まる。膨張で重なった位置の値は `policy` で合成する。
+    /// 構造要素は [`erode`](Self::erode) と同じ。
+    pub fn close<Z: Into<u8>, P>(
+        self,
+        z: Z,
+        radii: [u32; 3],
+        connectivity: Connectivity,
+        _policy: P,
+    ) -> Self
+    where
+        P: MergePolicy<V> + Send + Sync,
+    {
+        if matches!(self, Query::Error(_)) {
+            return self;
+        }
+        match Close::<P>::new(z, radii, connectivity) {
+            Ok(op) => self.wrap_unary(op),
+            Err(e) => Query::Error(e),
+        }
+    }
+}
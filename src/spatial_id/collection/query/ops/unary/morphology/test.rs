@@ -0,0 +1,231 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::vec::Vec;
+
+use super::Connectivity;
+use crate::spatial_id::collection::query::merge_policy::Max;
+use crate::{Coordinate, Query, RangeId, SingleId, Source, SpatialIdTable};
+
+/// 東京付近・高度 0 m のズームレベル 20 のボクセル。
+fn center() -> SingleId {
+    Coordinate::new(35.68, 139.77, 0.0)
+        .unwrap()
+        .single_id(20)
+        .unwrap()
+}
+
+/// `center` から水平に `(dX, dY)` 動かした位置に値を置いた表。
+fn plate(cells: impl IntoIterator<Item = ((i32, i32), u32)>) -> SpatialIdTable<u32> {
+    let c = center();
+    let mut table = SpatialIdTable::new();
+    for ((dx, dy), v) in cells {
+        let (x, y) = ((c.x() as i32 + dx) as u32, (c.y() as i32 + dy) as u32);
+        let id = SingleId::new(20, c.f(), x, y).unwrap();
+        table.insert(id, v);
+    }
+    table
+}
+
+/// `-half..=half` の正方形。
+fn square(half: i32) -> Vec<(i32, i32)> {
+    (-half..=half)
+        .flat_map(|dx| (-half..=half).map(move |dy| (dx, dy)))
+        .collect()
+}
+
+/// `center` から見た移動量 `(dF, dX, dY) -> 値` の対応。
+fn offsets(table: &SpatialIdTable<u32>) -> BTreeMap<(i64, i64, i64), u32> {
+    let c = center();
+    // 木は揃った兄弟をまとめるので、ズームレベル 20 まで割ってから数える
+    table
+        .iter()
+        .flat_map(|(id, v)| {
+            let cells = RangeId::from(id).spatial_children_at_zoom(20).unwrap();
+            cells
+                .single_ids()
+                .map(move |sid| (sid, *v))
+                .collect::<Vec<_>>()
+        })
+        .map(|(sid, v)| {
+            let d = (
+                sid.f() as i64 - c.f() as i64,
+                sid.x() as i64 - c.x() as i64,
+                sid.y() as i64 - c.y() as i64,
+            );
+            (d, v)
+        })
+        .collect()
+}
+
+/// 収縮は縁を削り、残ったボクセルの値を変えない
+#[test]
+fn erode_strips_rim_and_keeps_values() {
+    let table = plate(
+        square(2)
+            .into_iter()
+            .map(|(dx, dy)| ((dx, dy), (10 + dx + dy) as u32)),
+    );
+    let out = table
+        .query()
+        .erode(20, [0, 1, 1], Connectivity::Face)
+        .raw_run()
+        .unwrap();
+    let got = offsets(&out);
+
+    let expected: BTreeMap<_, _> = square(1)
+        .into_iter()
+        .map(|(dx, dy)| ((0, dx as i64, dy as i64), (10 + dx + dy) as u32))
+        .collect();
+    assert_eq!(got, expected);
+}
+
+/// 鉛直方向の半径を取ると、厚さ 1 の板は全て削れる
+#[test]
+fn erode_per_axis_radius() {
+    let table = plate(square(2).into_iter().map(|d| (d, 1)));
+    let out = table
+        .query()
+        .erode(20, [1, 0, 0], Connectivity::Face)
+        .raw_run()
+        .unwrap();
+    assert!(out.iter().next().is_none());
+}
+
+/// 角を 1 つ欠いた 3×3×3 の立方体の中心は、面・辺の近傍なら残り、頂点の近傍なら削れる
+#[test]
+fn connectivity_changes_kernel() {
+    let c = center();
+    let survives = |connectivity| {
+        let mut table = SpatialIdTable::new();
+        for df in -1i32..=1 {
+            for (dx, dy) in square(1) {
+                if (df, dx, dy) == (1, 1, 1) {
+                    continue;
+                }
+                let f = c.f() + df;
+                let (x, y) = ((c.x() as i32 + dx) as u32, (c.y() as i32 + dy) as u32);
+                table.insert(SingleId::new(20, f, x, y).unwrap(), 1_u32);
+            }
+        }
+        let out = table
+            .query()
+            .erode(20, [1, 1, 1], connectivity)
+            .raw_run()
+            .unwrap();
+        offsets(&out).contains_key(&(0, 0, 0))
+    };
+    assert!(survives(Connectivity::Face));
+    assert!(survives(Connectivity::Edge));
+    assert!(!survives(Connectivity::Vertex));
+}
+
+/// 構造要素に入る移動量の数は 6・18・26 近傍に一致する
+#[test]
+fn unit_kernels_match_neighbor_counts() {
+    let count = |connectivity| {
+        super::Kernel {
+            radii: [1, 1, 1],
+            connectivity,
+        }
+        .offsets()
+        .len()
+            - 1
+    };
+    let id = center();
+    assert_eq!(count(Connectivity::Face), id.neighbors_share_face().count());
+    assert_eq!(
+        count(Connectivity::Edge),
+        id.neighbors_share_face().count() + id.neighbors_share_edge().count()
+    );
+    assert_eq!(
+        count(Connectivity::Vertex),
+        id.neighbors_share_face().count()
+            + id.neighbors_share_edge().count()
+            + id.neighbors_share_vertex().count()
+    );
+}
+
+/// オープニングは突起を取り除き、本体を残す
+#[test]
+fn open_removes_spike() {
+    let mut cells: Vec<_> = square(1).into_iter().map(|d| (d, 1)).collect();
+    cells.push(((2, 0), 1));
+    let out = plate(cells)
+        .query()
+        .open(20, [0, 1, 1], Connectivity::Vertex, Max)
+        .raw_run()
+        .unwrap();
+
+    let expected: BTreeMap<_, _> = square(1)
+        .into_iter()
+        .map(|(dx, dy)| ((0, dx as i64, dy as i64), 1))
+        .collect();
+    assert_eq!(offsets(&out), expected);
+}
+
+/// クロージングは小さな穴を埋め、外形を変えない
+#[test]
+fn close_fills_hole() {
+    let cells: Vec<_> = square(2)
+        .into_iter()
+        .filter(|d| *d != (0, 0))
+        .map(|d| (d, 1))
+        .collect();
+    let out = plate(cells)
+        .query()
+        .close(20, [0, 1, 1], Connectivity::Face, Max)
+        .raw_run()
+        .unwrap();
+
+    let expected: BTreeMap<_, _> = square(2)
+        .into_iter()
+        .map(|(dx, dy)| ((0, dx as i64, dy as i64), 1))
+        .collect();
+    assert_eq!(offsets(&out), expected);
+}
+
+/// `lazy_get` は入力の必要な範囲だけを読んで、全体を実行したのと同じ結果を返す
+#[test]
+fn lazy_get_matches_run() {
+    let mut cells: Vec<_> = square(2).into_iter().map(|d| (d, 3)).collect();
+    cells.push(((3, 0), 3));
+    let table = plate(cells);
+    let c = center();
+
+    let build = |kind: usize| {
+        let query = table.clone().query();
+        match kind {
+            0 => query.erode(20, [0, 1, 1], Connectivity::Edge),
+            1 => query.open(20, [0, 1, 1], Connectivity::Face, Max),
+            _ => query.close(20, [0, 2, 2], Connectivity::Vertex, Max),
+        }
+    };
+
+    for kind in 0..3 {
+        let expected = offsets(&build(kind).raw_run().unwrap());
+        let query = build(kind);
+        for dx in -4i32..=4 {
+            for dy in -4i32..=4 {
+                let (x, y) = ((c.x() as i32 + dx) as u32, (c.y() as i32 + dy) as u32);
+                let id = SingleId::new(20, c.f(), x, y).unwrap();
+                let got = query.lazy_get(id).unwrap().next().map(|(_, v)| v);
+                let want = expected.get(&(0, dx as i64, dy as i64)).copied();
+                assert_eq!(got, want, "{query} at ({dx}, {dy})");
+            }
+        }
+    }
+}
+
+/// 表示した文字列を読み戻すと同じクエリになる
+#[test]
+fn display_round_trips_through_parse() {
+    let source = |_: &str| Some(SpatialIdTable::<u32>::new().query());
+    for text in [
+        "erode(z=20, f=0, x=2, y=2, Face)",
+        "open(z=20, f=1, x=1, y=1, Vertex, Max)",
+        "close(z=18, f=1, x=3, y=3, Edge, Max)",
+    ] {
+        let parsed: Query<u32> = Query::parse(&format!("a | {text}"), source).unwrap();
+        assert!(format!("{parsed}").contains(text), "{parsed}");
+    }
+}
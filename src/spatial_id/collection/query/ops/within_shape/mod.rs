@@ -0,0 +1,123 @@
+use crate::spatial_id::collection::query::working::WorkingTree;
+#[cfg(test)]
+mod test;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::spatial_id::collection::flex_tree::core::SafeValue;
+use crate::spatial_id::collection::flex_tree::core::ptr::MaybeSendSync;
+use crate::spatial_id::collection::flex_tree::core::shape::clip_to_shape;
+use crate::spatial_id::collection::query::cancellation::CancellationToken;
+use crate::spatial_id::collection::query::{execution::Query, source::Source};
+use crate::spatial_id::zoom_level::ZoomLevel;
+use crate::{Error, FlexId, RangeId, SpatialIdSet, SpatialPredicates};
+
+/// クエリの結果を、図形と交わる部分だけに絞り込む [`Source`]。
+///
+/// 図形の境界にかかる葉は、空間 3 軸のズームレベルが `max_z` になるまで割り、図形と交わらない部分を捨てる。
+/// 図形に丸ごと含まれる葉と、`max_z` より細かく図形と交わる葉は、割らずにそのまま残す。
+pub struct WithinShape<V: SafeValue + 'static, P> {
+    inner: Query<V>,
+    shape: P,
+    max_z: u8,
+}
+
+impl<V, P> WithinShape<V, P>
+where
+    V: SafeValue + 'static,
+    P: SpatialPredicates,
+{
+    pub fn new(inner: Query<V>, shape: P, max_z: u8) -> Self {
+        Self {
+            inner,
+            shape,
+            max_z,
+        }
+    }
+}
+
+/// `working` の各葉を `shape` で切り取る。
+fn clip_working<V, P>(
+    working: WorkingTree<V>,
+    shape: &P,
+    max_z: u8,
+    token: &CancellationToken,
+) -> Result<WorkingTree<V>, Error>
+where
+    V: SafeValue + 'static,
+    P: SpatialPredicates,
+{
+    let mut clipped: Vec<(FlexId, V)> = Vec::new();
+    let mut pieces = Vec::new();
+    for (id, value) in working {
+        clip_to_shape(shape, id, max_z, &mut pieces, token)?;
+        clipped.extend(pieces.drain(..).map(|piece| (piece, value.clone())));
+    }
+    Ok(clipped.into_iter().collect())
+}
+
+impl<V, P> Source for WithinShape<V, P>
+where
+    V: SafeValue + 'static,
+    P: SpatialPredicates + MaybeSendSync + 'static,
+{
+    type Value = V;
+
+    fn read_range_ids(
+        &self,
+        bounds: &[RangeId],
+        token: &CancellationToken,
+    ) -> Result<WorkingTree<V>, Error> {
+        let working = self.inner.run_within(bounds.to_vec(), token)?;
+        clip_working(working, &self.shape, self.max_z, token)
+    }
+
+    fn read_all(self: Box<Self>, token: &CancellationToken) -> Result<WorkingTree<V>, Error> {
+        let this = *self;
+        let working = this.inner.run_working_tree_cancellable(token)?;
+        clip_working(working, &this.shape, this.max_z, token)
+    }
+}
+
+impl<V: SafeValue + 'static> Query<V> {
+    /// 結果を図形 `shape` と交わる部分に絞り込む。
+    ///
+    /// 図形の境界にかかる葉は、空間 3 軸がズームレベル `max_z` になるまで割って、図形と交わる部分だけを残す。
+    /// 図形に丸ごと含まれる葉は割らない。
+    ///
+    /// ```ignore
+    /// // 球の内側にある値だけを集計する
+    /// let q = table.query().within_shape(sphere, 20);
+    /// ```
+    pub fn within_shape<P>(self, shape: P, max_z: u8) -> Query<V>
+    where
+        P: SpatialPredicates + MaybeSendSync + 'static,
+    {
+        if let Err(e) = ZoomLevel::new(max_z) {
+            return Query::Error(e);
+        }
+        Query::Source(Box::new(WithinShape::new(self, shape, max_z)))
+    }
+
+    /// [`run_within`](Self::run_within) の出力領域を、[`RangeId`] の列の代わりに図形で指定する。
+    ///
+    /// 出力領域は図形と交わる部分に切り取られる。図形の内側は粗い空間 ID のまま 1 つの領域にまとめ、
+    /// 境界だけをズームレベル `max_z` まで割る。
+    pub fn run_within_shape<P: SpatialPredicates>(
+        &self,
+        shape: &P,
+        max_z: u8,
+        token: &CancellationToken,
+    ) -> Result<WorkingTree<V>, Error> {
+        let max_z = ZoomLevel::new(max_z)?.get();
+        let mut cells = Vec::new();
+        for root in [FlexId::LOWER_MAX, FlexId::UPPER_MAX] {
+            clip_to_shape(shape, root, max_z, &mut cells, token)?;
+        }
+        // 隣り合うセルを畳んでから渡し、出力領域の数を境界の細かさに比例する程度に抑える。
+        let bounds = SpatialIdSet::from_iter(cells).range_ids().collect();
+        let working = self.run_within(bounds, token)?;
+        clip_working(working, shape, max_z, token)
+    }
+}
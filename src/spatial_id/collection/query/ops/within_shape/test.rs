@@ -0,0 +1,129 @@
+use alloc::vec::Vec;
+
+use crate::{
+    CancellationToken, Coordinate, Error, FlexId, RangeId, SingleId, Source, SpatialIdError,
+    SpatialIdTable, SpatialPredicates, Sphere,
+};
+
+/// 高度 100 m を中心とする半径 100 m の球。
+fn sphere() -> Sphere {
+    Sphere::new(Coordinate::new(35.681, 139.766, 100.0).unwrap(), 100.0).unwrap()
+}
+
+/// 球を囲む z=20 の範囲に 1、遠く離れた範囲に 2 を持つテーブル。
+fn table() -> SpatialIdTable<i32> {
+    let c = Coordinate::new(35.681, 139.766, 100.0)
+        .unwrap()
+        .single_id(20)
+        .unwrap();
+    let mut table = SpatialIdTable::new();
+    table.insert(
+        RangeId::new(20, [0, 15], [c.x() - 8, c.x() + 8], [c.y() - 8, c.y() + 8]).unwrap(),
+        1,
+    );
+    table.insert(
+        RangeId::new(20, [0, 15], [c.x() + 100, c.x() + 108], [c.y(), c.y() + 8]).unwrap(),
+        2,
+    );
+    table
+}
+
+/// z=20 のボクセルへ分解して並べる。
+fn sorted_cells(rows: impl IntoIterator<Item = (FlexId, i32)>) -> Vec<(SingleId, i32)> {
+    let mut cells: Vec<(SingleId, i32)> = rows
+        .into_iter()
+        .flat_map(|(id, v)| {
+            RangeId::from(id)
+                .spatial_children_at_zoom(20)
+                .unwrap()
+                .single_ids()
+                .map(move |s| (s, v))
+        })
+        .collect();
+    cells.sort();
+    cells
+}
+
+/// 球と交わる z=20 のボクセルだけが残る
+#[test]
+fn within_shape_keeps_cells_intersecting_the_shape() {
+    let sphere = sphere();
+    let table = table();
+    let expected: Vec<(SingleId, i32)> = sorted_cells(table.iter().map(|(id, v)| (id, *v)))
+        .into_iter()
+        .filter(|(cell, _)| sphere.intersects(cell))
+        .collect();
+    assert!(!expected.is_empty());
+    assert!(expected.iter().all(|(_, v)| *v == 1));
+
+    let out = table.query().within_shape(sphere, 20).raw_run().unwrap();
+    let actual = sorted_cells(out.iter().map(|(id, v)| (id, *v)));
+    assert_eq!(actual, expected);
+}
+
+/// run_within_shape は、within_shape を全体で評価した結果と一致する
+#[test]
+fn run_within_shape_matches_within_shape() {
+    let sphere = sphere();
+    let whole = table()
+        .query()
+        .within_shape(self::sphere(), 20)
+        .raw_run_working_tree()
+        .unwrap();
+    let lazy = table()
+        .query()
+        .run_within_shape(&sphere, 20, &CancellationToken::new())
+        .unwrap();
+    assert_eq!(sorted_cells(lazy), sorted_cells(whole));
+}
+
+/// ズームレベルの範囲外は実行時にエラーになる
+#[test]
+fn rejects_invalid_max_z() {
+    let result = table().query().within_shape(sphere(), 31).raw_run();
+    assert!(matches!(
+        result,
+        Err(Error::SpatialId(SpatialIdError::ZOutOfRange { z: 31 }))
+    ));
+    let result = table()
+        .query()
+        .run_within_shape(&sphere(), 31, &CancellationToken::new());
+    assert!(result.is_err());
+}
+
+/// 図形に丸ごと含まれる粗い葉は、max_z まで割らずにそのまま残る
+#[test]
+fn leaves_inside_the_shape_are_not_split() {
+    let center = Coordinate::new(35.681, 139.766, 0.0).unwrap();
+    let sphere = || Sphere::new(center, 15_000.0).unwrap();
+    let coarse = center.single_id(13).unwrap();
+    let mut table = SpatialIdTable::new();
+    table.insert(coarse.clone(), 5);
+    assert!(sphere().contains(&coarse));
+
+    let out = table
+        .clone()
+        .query()
+        .within_shape(sphere(), 16)
+        .raw_run()
+        .unwrap();
+    let expected = [(RangeId::from(coarse), 5)];
+    let rows: Vec<(RangeId, i32)> = out.iter().map(|(id, v)| (id.into(), *v)).collect();
+    assert_eq!(rows, expected);
+
+    let lazy = table
+        .query()
+        .run_within_shape(&sphere(), 16, &CancellationToken::new())
+        .unwrap();
+    let rows: Vec<(RangeId, i32)> = lazy.into_iter().map(|(id, v)| (id.into(), v)).collect();
+    assert_eq!(rows, expected);
+}
+
+/// 図形で出力領域を組む途中でもキャンセルできる
+#[test]
+fn run_within_shape_checks_cancellation() {
+    let token = CancellationToken::new();
+    token.cancel();
+    let result = table().query().run_within_shape(&sphere(), 20, &token);
+    assert!(matches!(result, Err(Error::Cancelled)));
+}
@@ -0,0 +1,709 @@
+//! 文字列で書いたクエリ定義を [`Query`] に組み立てる。
+//!
+//! クエリを Rust のコードではなく設定として保存・管理できるようにするための小さな言語。
+//! 書式は次の通り（`[...]` は省略可、`{...}` は 0 回以上の繰り返し）。
+//!
+//! ```text
+//! query    = term { "|" unary }
+//! term     = SOURCE
+//!          | "(" query ")"
+//!          | "merge" "(" POLICY "," query "," query [ "," VALUE ] ")"
+//!          | "intersection" "(" query "," query ")"
+//!          | "difference" "(" query "," query ")"
+//! unary    = "shift_f" "(" z "," f ")"       | "shift_x" "(" z "," x ")"   | "shift_y" "(" z "," y ")"
+//!          | "extrude_f" "(" z "," f "," POLICY ")"   (x, y も同様)
+//!          | "falloff_f" "(" z "," r "," [ DIR "," ] PATTERN "," POLICY ")"   (x, y も同様)
+//!          | "dilate" "(" z "," r "," POLICY ")" | "dilate" "(" z "," h "," v "," POLICY ")"
+//!          | "erode" "(" z "," f "," x "," y "," CONN ")"
+//!          | "open" "(" z "," f "," x "," y "," CONN "," POLICY ")"   (close も同様)
+//!          | "label_components" "(" CONN ")"
+//!          | "zoom_out" "(" z "," POLICY ")"
+//!          | "filter_eq" "(" VALUE ")"
+//!          | "filter_in" "(" range ")" | "filter_not_in" "(" range ")"
+//! range    = [ VALUE ] ( ".." | "..=" ) [ VALUE ]
+//! POLICY   = Max | Min | Sum | Average | Overwrite | KeepExisting | Difference
+//! DIR      = Both | Upper | Lower
+//! PATTERN  = Linear | QuadraticIn | QuadraticOut
+//! CONN     = Face | Edge | Vertex
+//! ```
+//!
+//! - `SOURCE` は英字か `_` で始まる名前で、[`Query::parse`] に渡した関数で解決する。
+//! - `merge` の `VALUE` を省くと `V::default()` を使う。
+//! - `extrude_*` の範囲は `extrude_f(25, 0, 5, Max)` とも `extrude_f(25, [0, 5], Max)` とも書ける。
+//! - 引数には [`Display`](core::fmt::Display) と同じ `z=25` や `r=2` のような名前を付けてもよい。
+//!   付けた場合は位置と名前が合っているかを確かめる。
+
+use alloc::format;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt::{self, Debug};
+use core::ops::{Bound, Div, Mul, Sub};
+use core::str::FromStr;
+
+use crate::Error;
+use crate::spatial_id::collection::flex_tree::core::SafeValue;
+use crate::spatial_id::collection::query::execution::Query;
+use crate::spatial_id::collection::query::merge_policy::kind::{Policy, with_policy};
+use crate::spatial_id::collection::query::merge_policy::saturating_add::Add;
+use crate::spatial_id::collection::query::ops::unary::falloff::FalloffPattern;
+use crate::spatial_id::collection::query::ops::unary::morphology::Connectivity;
+use crate::spatial_id::helpers::Side;
+
+#[cfg(test)]
+mod test;
+
+impl<V> Query<V>
+where
+    V: SafeValue
+        + Ord
+        + Default
+        + FromStr
+        + Add
+        + Sub<Output = V>
+        + Mul<Output = V>
+        + Div<Output = V>
+        + From<u16>
+        + TryFrom<u32>
+        + Send
+        + Sync
+        + 'static,
+    <V as TryFrom<u32>>::Error: Debug,
+{
+    /// 文字列のクエリ定義から [`Query`] を組み立てる。
+    ///
+    /// 定義に現れた名前は `sources` で [`Query`] に解決する。同じ名前が 2 回現れれば 2 回呼ぶ。
+    /// 書式の誤り・未知の名前・演算子のパラメータの誤りは [`Error::InvalidQueryParameter`] になり、
+    /// メッセージに誤りのある字句とその位置（バイト単位）を含む。
+    /// 書式は[`parse`](crate::spatial_id::collection::query::parse) モジュールを参照。
+    ///
+    /// タイトル: 2 つのテーブルを加工して重ね合わせる
+    /// ```
+    /// use kasane_logic::{Query, SingleId, Source, SpatialIdTable};
+    ///
+    /// let mut a = SpatialIdTable::new();
+    /// a.insert(SingleId::new(20, 0, 10, 10).unwrap(), 3u32);
+    /// let mut b = SpatialIdTable::new();
+    /// b.insert(SingleId::new(20, 0, 11, 10).unwrap(), 4u32);
+    ///
+    /// let query = Query::parse("merge(Sum, a | shift_x(20, 1), b)", |name| match name {
+    ///     "a" => Some(a.clone().query()),
+    ///     "b" => Some(b.clone().query()),
+    ///     _ => None,
+    /// })
+    /// .unwrap();
+    ///
+    /// let result = query.run().unwrap();
+    /// let values: Vec<u32> = result.iter().map(|(_, v)| *v).collect();
+    /// assert_eq!(values, [7]);
+    /// ```
+    pub fn parse<F>(text: &str, sources: F) -> Result<Self, Error>
+    where
+        F: FnMut(&str) -> Option<Query<V>>,
+    {
+        let mut parser = Parser {
+            text,
+            tokens: tokenize(text)?,
+            pos: 0,
+            sources,
+            _marker: core::marker::PhantomData,
+        };
+        let query = parser.query()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(parser
+                .span()
+                .error("unexpected input after the end of the query"));
+        }
+        Ok(query)
+    }
+}
+
+/// 入力の中の字句（または引数）の位置と、その文字列。
+#[derive(Debug, Clone, Copy)]
+struct Span<'a> {
+    offset: usize,
+    text: &'a str,
+}
+
+impl Span<'_> {
+    /// この位置で `message` の誤りがあったことを表す [`Error::InvalidQueryParameter`]。
+    fn error(self, message: impl fmt::Display) -> Error {
+        let Span { offset, text } = self;
+        Error::InvalidQueryParameter(
+            if text.is_empty() {
+                format!("{message} at byte {offset} (end of input)")
+            } else {
+                format!("{message} at byte {offset} near `{text}`")
+            }
+            .into(),
+        )
+    }
+}
+
+/// 字句。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Ident(&'a str),
+    Number(&'a str),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Pipe,
+    Eq,
+    /// `..`
+    DotDot,
+    /// `..=`
+    DotDotEq,
+}
+
+/// 字句に分け、それぞれの位置を添える。
+fn tokenize(text: &str) -> Result<Vec<(Token<'_>, Span<'_>)>, Error> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let span = |end: usize| Span {
+            offset: start,
+            text: &text[start..end],
+        };
+        let token = match bytes[i] {
+            b if b.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'(' => Token::LParen,
+            b')' => Token::RParen,
+            b'[' => Token::LBracket,
+            b']' => Token::RBracket,
+            b',' => Token::Comma,
+            b'|' => Token::Pipe,
+            b'=' => Token::Eq,
+            b'.' if bytes.get(i + 1) == Some(&b'.') => {
+                i += 2;
+                if bytes.get(i) == Some(&b'=') {
+                    i += 1;
+                    tokens.push((Token::DotDotEq, span(i)));
+                } else {
+                    tokens.push((Token::DotDot, span(i)));
+                }
+                continue;
+            }
+            b if b.is_ascii_alphabetic() || b == b'_' => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                tokens.push((Token::Ident(&text[start..i]), span(i)));
+                continue;
+            }
+            b if b.is_ascii_digit() || b == b'-' || b == b'+' => {
+                i += 1;
+                let digits = |i: &mut usize| {
+                    let from = *i;
+                    while *i < bytes.len() && bytes[*i].is_ascii_digit() {
+                        *i += 1;
+                    }
+                    *i > from
+                };
+                let has_digits = b.is_ascii_digit() | digits(&mut i);
+                if !has_digits {
+                    return Err(span(i).error("expected digits after the sign"));
+                }
+                // `1..5` の `..` は範囲なので、小数点として扱うのは直後が数字のときだけ。
+                if bytes.get(i) == Some(&b'.') && bytes.get(i + 1).is_some_and(u8::is_ascii_digit) {
+                    i += 1;
+                    digits(&mut i);
+                }
+                tokens.push((Token::Number(&text[start..i]), span(i)));
+                continue;
+            }
+            _ => {
+                let len = text[start..].chars().next().map_or(1, char::len_utf8);
+                return Err(span(start + len).error("unexpected character"));
+            }
+        };
+        tokens.push((token, span(i + 1)));
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+/// 演算子の引数 1 つ。
+#[derive(Debug, Clone, Copy)]
+enum Arg<'a> {
+    Ident(&'a str),
+    Number(&'a str),
+    /// `[start, end]`
+    Pair(&'a str, &'a str),
+    /// `start..end` / `start..=end`
+    Range(Option<&'a str>, Option<&'a str>, bool),
+}
+
+/// 名前付きの場合は `label` を持つ引数。`span` は名前を除いた引数の位置。
+#[derive(Debug, Clone, Copy)]
+struct Labeled<'a> {
+    label: Option<&'a str>,
+    arg: Arg<'a>,
+    span: Span<'a>,
+}
+
+/// 引数を解釈する。誤りは引数の位置を付けて返す。
+impl<'a> Labeled<'a> {
+    fn ident(self) -> Result<&'a str, Error> {
+        match self.arg {
+            Arg::Ident(ident) => Ok(ident),
+            _ => Err(self.span.error("expected a name")),
+        }
+    }
+
+    fn number<T: FromStr>(self) -> Result<T, Error> {
+        match self.arg {
+            Arg::Number(text) => parse_number(text).map_err(|m| self.span.error(m)),
+            _ => Err(self.span.error("expected a number")),
+        }
+    }
+
+    fn policy(self) -> Result<Policy, Error> {
+        self.ident()?
+            .parse()
+            .map_err(|_| self.span.error("unknown merge policy"))
+    }
+
+    fn direction(self) -> Result<Option<Side>, Error> {
+        match self.ident()? {
+            "Both" => Ok(None),
+            "Upper" => Ok(Some(Side::Upper)),
+            "Lower" => Ok(Some(Side::Lower)),
+            _ => Err(self.span.error("unknown falloff direction")),
+        }
+    }
+
+    fn pattern(self) -> Result<FalloffPattern, Error> {
+        match self.ident()? {
+            "Linear" => Ok(FalloffPattern::Linear),
+            "QuadraticIn" => Ok(FalloffPattern::QuadraticIn),
+            "QuadraticOut" => Ok(FalloffPattern::QuadraticOut),
+            _ => Err(self.span.error("unknown falloff pattern")),
+        }
+    }
+
+    fn connectivity(self) -> Result<Connectivity, Error> {
+        match self.ident()? {
+            "Face" => Ok(Connectivity::Face),
+            "Edge" => Ok(Connectivity::Edge),
+            "Vertex" => Ok(Connectivity::Vertex),
+            _ => Err(self.span.error("unknown connectivity")),
+        }
+    }
+
+    fn range<V: FromStr>(self) -> Result<(Bound<V>, Bound<V>), Error> {
+        self.arg.range().map_err(|m| self.span.error(m))
+    }
+}
+
+impl Arg<'_> {
+    fn range<V: FromStr>(self) -> Result<(Bound<V>, Bound<V>), &'static str> {
+        let Arg::Range(start, end, inclusive) = self else {
+            return Err("expected a range");
+        };
+        let start = match start {
+            Some(text) => Bound::Included(parse_number(text)?),
+            None => Bound::Unbounded,
+        };
+        let end = match (end, inclusive) {
+            (Some(text), true) => Bound::Included(parse_number(text)?),
+            (Some(text), false) => Bound::Excluded(parse_number(text)?),
+            (None, false) => Bound::Unbounded,
+            (None, true) => return Err("`..=` needs an end"),
+        };
+        Ok((start, end))
+    }
+}
+
+fn parse_number<T: FromStr>(text: &str) -> Result<T, &'static str> {
+    text.parse()
+        .map_err(|_| "number out of range for its parameter")
+}
+
+/// 演算子 `op` の引数の数と名前を確かめて取り出す。`labels[i]` は `i` 番目の引数に付けてよい名前。
+fn take<'a, const N: usize>(
+    op: Span<'a>,
+    args: &[Labeled<'a>],
+    labels: [&str; N],
+) -> Result<[Labeled<'a>; N], Error> {
+    let Ok(out) = <[Labeled<'a>; N]>::try_from(args) else {
+        return Err(op.error("wrong number of arguments"));
+    };
+    for (arg, label) in out.iter().zip(labels) {
+        if arg.label.is_some_and(|l| l != label) {
+            return Err(arg.span.error("argument name does not match its position"));
+        }
+    }
+    Ok(out)
+}
+
+/// 構築結果が [`Query::Error`] なら、演算子 `op` の位置を付けたエラーとして取り出す。
+///
+/// ズームレベルの範囲外などで演算子の構築が失敗した場合も、定義の誤りとして
+/// 位置を付けた [`Error::InvalidQueryParameter`] にそろえる。
+fn built<V: SafeValue + 'static>(op: Span<'_>, query: Query<V>) -> Result<Query<V>, Error> {
+    match query {
+        Query::Error(Error::InvalidQueryParameter(message)) => Err(op.error(message)),
+        Query::Error(_) => Err(op.error("operator parameter out of range")),
+        query => Ok(query),
+    }
+}
+
+/// 再帰下降の構文解析器。
+struct Parser<'a, V, F> {
+    text: &'a str,
+    tokens: Vec<(Token<'a>, Span<'a>)>,
+    pos: usize,
+    sources: F,
+    _marker: core::marker::PhantomData<fn() -> V>,
+}
+
+impl<'a, V, F> Parser<'a, V, F>
+where
+    V: SafeValue
+        + Ord
+        + Default
+        + FromStr
+        + Add
+        + Sub<Output = V>
+        + Mul<Output = V>
+        + Div<Output = V>
+        + From<u16>
+        + TryFrom<u32>
+        + Send
+        + Sync
+        + 'static,
+    <V as TryFrom<u32>>::Error: Debug,
+    F: FnMut(&str) -> Option<Query<V>>,
+{
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).map(|(token, _)| *token)
+    }
+
+    /// 次の字句の位置。入力の終わりでは、入力の長さと空の文字列。
+    fn span(&self) -> Span<'a> {
+        self.tokens.get(self.pos).map_or(
+            Span {
+                offset: self.text.len(),
+                text: "",
+            },
+            |(_, span)| *span,
+        )
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: Token<'a>) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: Token<'a>, what: &'static str) -> Result<(), Error> {
+        let span = self.span();
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(span.error(what))
+        }
+    }
+
+    /// `query = term { "|" unary }`
+    fn query(&mut self) -> Result<Query<V>, Error> {
+        let mut query = self.term()?;
+        while self.eat(Token::Pipe) {
+            let op = self.span();
+            let Some(Token::Ident(_)) = self.next() else {
+                return Err(op.error("expected an operator after `|`"));
+            };
+            let args = self.args()?;
+            query = built(op, self.unary(query, op, &args)?)?;
+        }
+        Ok(query)
+    }
+
+    fn term(&mut self) -> Result<Query<V>, Error> {
+        let span = self.span();
+        match self.next() {
+            Some(Token::LParen) => {
+                let query = self.query()?;
+                self.expect(Token::RParen, "expected `)`")?;
+                Ok(query)
+            }
+            Some(Token::Ident(_)) if self.peek() == Some(Token::LParen) => {
+                self.pos += 1;
+                self.binary(span)
+            }
+            Some(Token::Ident(name)) => {
+                (self.sources)(name).ok_or_else(|| span.error("unknown source"))
+            }
+            _ => Err(span.error("expected a source or a binary operator")),
+        }
+    }
+
+    /// `(` の直後から二項演算子 `op` の引数を読む。
+    fn binary(&mut self, op: Span<'a>) -> Result<Query<V>, Error> {
+        let query = match op.text {
+            "merge" => {
+                let span = self.span();
+                let policy: Policy = match self.next() {
+                    Some(Token::Ident(policy)) => policy
+                        .parse()
+                        .map_err(|_| span.error("unknown merge policy"))?,
+                    _ => return Err(span.error("merge needs a policy first")),
+                };
+                self.expect(Token::Comma, "expected `,`")?;
+                let lhs = self.query()?;
+                self.expect(Token::Comma, "expected `,`")?;
+                let rhs = self.query()?;
+                let default = if self.eat(Token::Comma) {
+                    let span = self.span();
+                    match self.next() {
+                        Some(Token::Number(text)) => {
+                            parse_number(text).map_err(|m| span.error(m))?
+                        }
+                        _ => return Err(span.error("expected a default value")),
+                    }
+                } else {
+                    V::default()
+                };
+                with_policy!(policy, p => lhs.merge(rhs, default, p))
+            }
+            "intersection" | "difference" => {
+                let lhs = self.query()?;
+                self.expect(Token::Comma, "expected `,`")?;
+                let rhs = self.query()?;
+                if op.text == "intersection" {
+                    lhs.intersection(rhs)
+                } else {
+                    lhs.difference(rhs)
+                }
+            }
+            _ => return Err(op.error("unknown binary operator")),
+        };
+        self.expect(Token::RParen, "expected `)`")?;
+        built(op, query)
+    }
+
+    /// `"(" [ arg { "," arg } ] ")"`
+    fn args(&mut self) -> Result<Vec<Labeled<'a>>, Error> {
+        self.expect(Token::LParen, "expected `(`")?;
+        let mut args = Vec::new();
+        if self.eat(Token::RParen) {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.arg()?);
+            if self.eat(Token::RParen) {
+                return Ok(args);
+            }
+            self.expect(Token::Comma, "expected `,` or `)`")?;
+        }
+    }
+
+    fn arg(&mut self) -> Result<Labeled<'a>, Error> {
+        let label = match (self.peek(), self.tokens.get(self.pos + 1).map(|(t, _)| *t)) {
+            (Some(Token::Ident(label)), Some(Token::Eq)) => {
+                self.pos += 2;
+                Some(label)
+            }
+            _ => None,
+        };
+        let first = self.span();
+        let arg = match self.next() {
+            Some(Token::Ident(ident)) => Arg::Ident(ident),
+            Some(Token::LBracket) => {
+                let (Some(Token::Number(start)), Some(Token::Comma), Some(Token::Number(end))) =
+                    (self.next(), self.next(), self.next())
+                else {
+                    return Err(first.error("expected `[start, end]`"));
+                };
+                self.expect(Token::RBracket, "expected `]`")?;
+                Arg::Pair(start, end)
+            }
+            Some(Token::Number(start)) => match self.peek() {
+                Some(Token::DotDot | Token::DotDotEq) => self.range(Some(start)),
+                _ => Arg::Number(start),
+            },
+            Some(Token::DotDot | Token::DotDotEq) => {
+                self.pos -= 1;
+                self.range(None)
+            }
+            _ => return Err(first.error("expected an argument")),
+        };
+        let (_, last) = self.tokens[self.pos - 1];
+        let span = Span {
+            offset: first.offset,
+            text: &self.text[first.offset..last.offset + last.text.len()],
+        };
+        Ok(Labeled { label, arg, span })
+    }
+
+    /// `..` / `..=` の位置から範囲の残りを読む。
+    fn range(&mut self, start: Option<&'a str>) -> Arg<'a> {
+        let inclusive = self.next() == Some(Token::DotDotEq);
+        let end = match self.peek() {
+            Some(Token::Number(end)) => {
+                self.pos += 1;
+                Some(end)
+            }
+            _ => None,
+        };
+        Arg::Range(start, end, inclusive)
+    }
+
+    fn unary(
+        &mut self,
+        query: Query<V>,
+        op: Span<'a>,
+        args: &[Labeled<'a>],
+    ) -> Result<Query<V>, Error> {
+        let name = op.text;
+        Ok(match name {
+            "shift_f" => {
+                let [z, f] = take(op, args, ["z", "f"])?;
+                query.shift_f(z.number::<u8>()?, f.number()?)
+            }
+            "shift_x" => {
+                let [z, x] = take(op, args, ["z", "x"])?;
+                query.shift_x(z.number::<u8>()?, x.number()?)
+            }
+            "shift_y" => {
+                let [z, y] = take(op, args, ["z", "y"])?;
+                query.shift_y(z.number::<u8>()?, y.number()?)
+            }
+            "extrude_f" => {
+                let (z, start, end, policy) = extrude_args(op, args, "f")?;
+                with_policy!(policy, p => query.extrude_f(z, start, end, p))
+            }
+            "extrude_x" => {
+                let (z, start, end, policy) = extrude_args(op, args, "x")?;
+                with_policy!(policy, p => query.extrude_x(z, start, end, p))
+            }
+            "extrude_y" => {
+                let (z, start, end, policy) = extrude_args(op, args, "y")?;
+                with_policy!(policy, p => query.extrude_y(z, start, end, p))
+            }
+            "falloff_f" | "falloff_x" | "falloff_y" => {
+                let (z, radius, direction, pattern, policy) = match args.len() {
+                    4 => {
+                        let [z, r, pat, policy] = take(op, args, ["z", "r", "pat", "policy"])?;
+                        (z, r, None, pat.pattern()?, policy.policy()?)
+                    }
+                    _ => {
+                        let [z, r, dir, pat, policy] =
+                            take(op, args, ["z", "r", "dir", "pat", "policy"])?;
+                        (z, r, dir.direction()?, pat.pattern()?, policy.policy()?)
+                    }
+                };
+                let (z, radius) = (z.number::<u8>()?, radius.number()?);
+                with_policy!(policy, p => match name {
+                    "falloff_f" => query.falloff_f(z, radius, direction, pattern, p),
+                    "falloff_x" => query.falloff_x(z, radius, direction, pattern, p),
+                    _ => query.falloff_y(z, radius, direction, pattern, p),
+                })
+            }
+            "dilate" => match args.len() {
+                3 => {
+                    let [z, r, policy] = take(op, args, ["z", "r", "policy"])?;
+                    let (z, radius) = (z.number::<u8>()?, r.number::<f64>()?);
+                    with_policy!(policy.policy()?, p => query.dilate(z, radius, p))
+                }
+                _ => {
+                    let [z, h, v, policy] = take(op, args, ["z", "h", "v", "policy"])?;
+                    let (z, h, v) = (z.number::<u8>()?, h.number::<f64>()?, v.number::<f64>()?);
+                    with_policy!(policy.policy()?, p => query.dilate_ellipsoid(z, h, v, p))
+                }
+            },
+            "erode" => {
+                let [z, f, x, y, conn] = take(op, args, ["z", "f", "x", "y", "conn"])?;
+                let radii = [f.number()?, x.number()?, y.number()?];
+                query.erode(z.number::<u8>()?, radii, conn.connectivity()?)
+            }
+            "open" | "close" => {
+                let [z, f, x, y, conn, policy] =
+                    take(op, args, ["z", "f", "x", "y", "conn", "policy"])?;
+                let (z, radii) = (z.number::<u8>()?, [f.number()?, x.number()?, y.number()?]);
+                let conn = conn.connectivity()?;
+                with_policy!(policy.policy()?, p => match name {
+                    "open" => query.open(z, radii, conn, p),
+                    _ => query.close(z, radii, conn, p),
+                })
+            }
+            "label_components" => {
+                let [conn] = take(op, args, ["conn"])?;
+                query.label_components(conn.connectivity()?)
+            }
+            "zoom_out" => {
+                let [z, policy] = take(op, args, ["z", "policy"])?;
+                let z = z.number::<u8>()?;
+                with_policy!(policy.policy()?, p => query.zoom_out(z, p))
+            }
+            "filter_eq" => {
+                let [value] = take(op, args, ["v"])?;
+                query.filter_eq(value.number()?)
+            }
+            "filter_in" => {
+                let [range] = take(op, args, ["range"])?;
+                query.filter_in(range.range::<V>()?)
+            }
+            "filter_not_in" => {
+                let [range] = take(op, args, ["range"])?;
+                query.filter_not_in(range.range::<V>()?)
+            }
+            _ => return Err(op.error("unknown operator")),
+        })
+    }
+}
+
+/// `extrude_*` の `(z, start, end, policy)` と `(z, [start, end], policy)` を揃える。
+fn extrude_args<'a, T: FromStr>(
+    op: Span<'a>,
+    args: &[Labeled<'a>],
+    axis: &str,
+) -> Result<(u8, T, T, Policy), Error> {
+    let (z, range, start, end, policy) = match args.len() {
+        3 => match take(op, args, ["z", axis, "policy"])? {
+            [
+                z,
+                range @ Labeled {
+                    arg: Arg::Pair(start, end),
+                    ..
+                },
+                policy,
+            ] => (z, range, start, end, policy),
+            [_, range, _] => return Err(range.span.error("expected `[start, end]`")),
+        },
+        _ => {
+            let [z, start, end, policy] = take(op, args, ["z", "start", "end", "policy"])?;
+            let (Arg::Number(start_text), Arg::Number(end_text)) = (start.arg, end.arg) else {
+                let bad = if matches!(start.arg, Arg::Number(_)) {
+                    end
+                } else {
+                    start
+                };
+                return Err(bad.span.error("expected a number"));
+            };
+            // 範囲外の数は、その数の位置で報告する。
+            let start = parse_number(start_text).map_err(|m| start.span.error(m))?;
+            let end = parse_number(end_text).map_err(|m| end.span.error(m))?;
+            return Ok((z.number()?, start, end, policy.policy()?));
+        }
+    };
+    let start = parse_number(start).map_err(|m| range.span.error(m))?;
+    let end = parse_number(end).map_err(|m| range.span.error(m))?;
+    Ok((z.number()?, start, end, policy.policy()?))
+}
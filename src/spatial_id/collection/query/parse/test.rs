@@ -0,0 +1,186 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::merge_policy::{Max, Sum};
+use crate::spatial_id::collection::query::ops::unary::falloff::FalloffPattern;
+use crate::{Error, FlexId, Query, Side, SingleId, Source, SpatialIdTable};
+
+fn table() -> SpatialIdTable<u32> {
+    let mut table = SpatialIdTable::new();
+    table.insert(SingleId::new(20, 0, 10, 10).unwrap(), 3);
+    table.insert(SingleId::new(20, 0, 20, 10).unwrap(), 8);
+    table
+}
+
+fn other() -> SpatialIdTable<u32> {
+    let mut table = SpatialIdTable::new();
+    table.insert(SingleId::new(20, 0, 11, 10).unwrap(), 4);
+    table
+}
+
+fn parse(text: &str) -> Result<Query<u32>, Error> {
+    Query::parse(text, |name| match name {
+        "a" => Some(table().query()),
+        "b" => Some(other().query()),
+        _ => None,
+    })
+}
+
+fn rows(table: SpatialIdTable<u32>) -> Vec<(FlexId, u32)> {
+    table.iter().map(|(id, v)| (id, *v)).collect()
+}
+
+/// 文字列から組んだクエリは、メソッドで組んだものと同じ表示・同じ結果になる。
+#[test]
+fn matches_builder() {
+    let parsed = parse(
+        "merge(Max, a | shift_x(20, 3) | extrude_f(20, 0, 2, Max), \
+         b | falloff_f(20, 2, Linear, Max))",
+    )
+    .unwrap();
+    let built = table()
+        .query()
+        .shift_x(20, 3)
+        .extrude_f(20, 0, 2, Max)
+        .merge(
+            other()
+                .query()
+                .falloff_f(20, 2, None, FalloffPattern::Linear, Max),
+            0,
+            Max,
+        );
+
+    assert_eq!(format!("{parsed}"), format!("{built}"));
+    assert_eq!(rows(parsed.run().unwrap()), rows(built.run().unwrap()));
+}
+
+/// `Display` と同じ名前付きの引数も受け付ける。
+#[test]
+fn accepts_labels_from_display() {
+    let labeled = parse(
+        "a | extrude_f(z=20, f=[0, 5], Max) \
+           | falloff_y(z=20, r=3, dir=Upper, pat=QuadraticOut, Max) | shift_f(z=20, f=-1)",
+    )
+    .unwrap();
+    let built = table()
+        .query()
+        .extrude_f(20, 0, 5, Max)
+        .falloff_y(20, 3, Some(Side::Upper), FalloffPattern::QuadraticOut, Max)
+        .shift_f(20, -1);
+    assert_eq!(format!("{labeled}"), format!("{built}"));
+
+    assert!(matches!(
+        parse("a | shift_x(x=3, z=20)"),
+        Err(Error::InvalidQueryParameter(_))
+    ));
+}
+
+#[test]
+fn filters_take_values_and_ranges() {
+    let cases: [(&str, &[u32]); 6] = [
+        ("a | filter_eq(8)", &[8]),
+        ("a | filter_in(3..8)", &[3]),
+        ("a | filter_in(3..=8)", &[3, 8]),
+        ("a | filter_in(4..)", &[8]),
+        ("a | filter_in(..=3)", &[3]),
+        ("a | filter_not_in(..8)", &[8]),
+    ];
+    for (text, expected) in cases {
+        let values: Vec<u32> = rows(parse(text).unwrap().run().unwrap())
+            .into_iter()
+            .map(|(_, v)| v)
+            .collect();
+        assert_eq!(values, expected, "{text}");
+    }
+}
+
+#[test]
+fn binary_operators_and_grouping() {
+    let merged = parse("merge(Sum, (a | shift_x(20, 1)), b, 0)").unwrap();
+    let built = table()
+        .query()
+        .shift_x(20, 1)
+        .merge(other().query(), 0, Sum);
+    assert_eq!(rows(merged.run().unwrap()), rows(built.run().unwrap()));
+
+    let intersected = rows(
+        parse("intersection(a | shift_x(20, 1), b)")
+            .unwrap()
+            .run()
+            .unwrap(),
+    );
+    assert_eq!(intersected.len(), 1);
+
+    let subtracted = rows(parse("difference(a, a)").unwrap().run().unwrap());
+    assert!(subtracted.is_empty());
+}
+
+/// 同じ名前が何度現れても、そのたびに解決する。
+#[test]
+fn resolves_each_occurrence() {
+    let mut seen = Vec::new();
+    Query::parse("merge(Max, a, a | zoom_out(18, Max))", |name| {
+        seen.push(String::from(name));
+        Some(table().query())
+    })
+    .unwrap();
+    assert_eq!(seen, ["a", "a"]);
+}
+
+#[test]
+fn rejects_malformed_queries() {
+    for text in [
+        "",
+        "c",
+        "a |",
+        "a | rotate(1)",
+        "a | shift_x(20)",
+        "a | shift_x(20, 1, 2)",
+        "a | shift_x(20, 1.5)",
+        "a | shift_x(20, 1",
+        "a | zoom_out(18, Median)",
+        "a | falloff_x(20, 2, Sideways, Linear, Max)",
+        "a | extrude_x(20, [5], Max)",
+        "a | extrude_x(20, -1, 5, Max)",
+        "a | filter_in(3)",
+        "a | filter_in(3..=)",
+        "merge(a, b)",
+        "merge(Max, a)",
+        "union(a, b)",
+        "a b",
+        "a # comment",
+        // パラメータの範囲外は演算子側の検証で弾かれる。
+        "a | shift_x(99, 1)",
+    ] {
+        assert!(
+            matches!(parse(text), Err(Error::InvalidQueryParameter(_))),
+            "{text:?}"
+        );
+    }
+}
+
+/// 誤りのメッセージは、その字句の位置（バイト単位）と文字列を含む。
+#[test]
+fn errors_point_at_the_offending_token() {
+    let message = |text: &str| match parse(text) {
+        Err(Error::InvalidQueryParameter(message)) => message.into_owned(),
+        other => panic!("{text:?}: {:?}", other.map(|_| ())),
+    };
+    assert_eq!(
+        message("a | shift_x(20, 1) | zoom_out(18, Median)"),
+        "unknown merge policy at byte 34 near `Median`"
+    );
+    assert_eq!(
+        message("merge(Max, a, c)"),
+        "unknown source at byte 14 near `c`"
+    );
+    assert_eq!(
+        message("a | shift_x(99, 1)"),
+        "operator parameter out of range at byte 4 near `shift_x`"
+    );
+    assert_eq!(
+        message("a | shift_x(20, 1"),
+        "expected `,` or `)` at byte 17 (end of input)"
+    );
+}
@@ -271,6 +271,7 @@ fn parse_error(input: &str) -> Error {
     SpatialIdError::ParseSpatialIdFormat {
         kind: "FlexId",
         input: input.to_string(),
+        offset: None,
     }
     .into()
 }
@@ -7,7 +7,7 @@ pub mod impls;
 pub mod ops;
 
 use crate::{
-    Error, Side, SpatialIdError,
+    Connectivity, Error, Side, SpatialIdError,
     spatial_id::{
         range_id::convert::{split_f, split_xy},
         time::span,
@@ -628,6 +628,40 @@ impl FlexId {
     /// assert!(!a.shares_face(&a)); // 重なり（自身）は面共有ではない
     /// ```
     pub fn shares_face(&self, other: &FlexId) -> bool {
+        self.adjacent_axes(other) == Some(1)
+    }
+
+    /// 自身と接しうる空間を全て含む範囲。自身を最も細かい軸のズームレベルで各軸 1 つずつ広げる。
+    ///
+    /// X は巡回するので、東西の端では反対側の端まで回り込む。F・Y は端で止める。時間は見ない。
+    pub(crate) fn touch_shell(&self) -> crate::RangeId {
+        let range = crate::RangeId::from(self);
+        let z = range.z();
+        range
+            .f_edges_shift(z, -1, 1)
+            .ok()
+            .flatten()
+            .and_then(|r| r.x_edges_shift(z, -1, 1).ok().flatten())
+            .and_then(|r| r.y_edges_shift(z, -1, 1).ok().flatten())
+            .unwrap_or(range)
+    }
+
+    /// `connectivity` のつながり方で `other` と接しているか。空間が重なる場合も接しているとみなす。
+    ///
+    /// 時間は見ない。
+    pub(crate) fn touches(&self, other: &FlexId, connectivity: Connectivity) -> bool {
+        let limit = match connectivity {
+            Connectivity::Face => 1,
+            Connectivity::Edge => 2,
+            Connectivity::Vertex => 3,
+        };
+        self.adjacent_axes(other).is_some_and(|n| n <= limit)
+    }
+
+    /// 空間の 3 軸のうち、`other` と端で接している（重ならずに隣り合う）軸の数。
+    ///
+    /// 離れている軸が 1 つでもあれば `None`、全ての軸で重なれば `Some(0)` を返す。
+    fn adjacent_axes(&self, other: &FlexId) -> Option<usize> {
         #[derive(PartialEq)]
         enum Rel {
             Overlap,
@@ -678,8 +712,10 @@ impl FlexId {
         );
 
         let rels = [rf, rx, ry];
-        rels.iter().filter(|r| **r == Rel::Adjacent).count() == 1
-            && rels.iter().filter(|r| **r == Rel::Overlap).count() == 2
+        if rels.contains(&Rel::Separated) {
+            return None;
+        }
+        Some(rels.iter().filter(|r| **r == Rel::Adjacent).count())
     }
 
     /// この [`FlexId`] を、指定した各軸のズームレベルで区切られたシャード単位で分割し、親と「シャード内に含まれる対象の分割部分」のペアを列挙する。
@@ -39,6 +39,14 @@ pub fn latitude(y: f64, z: u8) -> f64 {
     lat_rad.to_degrees()
 }
 
+/// [`latitude`] を [`Coordinate`](crate::Coordinate) が受け付ける範囲（-85.0511 〜 85.0511）に丸めた値。
+///
+/// Web Mercator の上下端（y = 0 と y = 2^z）の緯度は約 85.05113 度で、この範囲をわずかに超える。
+/// 頂点の座標を作るときはこちらを使う。
+pub(crate) fn vertex_latitude(y: f64, z: u8) -> f64 {
+    latitude(y, z).clamp(-85.0511, 85.0511)
+}
+
 /// 高度 (altitude) を返す（実数 f 対応）
 ///
 /// f: 高度方向 index（連続値）  
@@ -60,6 +68,21 @@ pub enum Side {
     Upper = 1,
 }
 
+/// ボクセル同士のつながり方。
+///
+/// [`SingleId`](crate::SingleId) の `neighbors_share_*` と同じく、共有する境界の次元で区別する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// 面を共有する 6 近傍（[`SingleId::neighbors_share_face`](crate::SingleId::neighbors_share_face)）。
+    Face,
+    /// 面か辺を共有する 18 近傍（[`neighbors_share_face`](crate::SingleId::neighbors_share_face) と
+    /// [`neighbors_share_edge`](crate::SingleId::neighbors_share_edge)）。
+    Edge,
+    /// 面・辺・頂点のいずれかを共有する 26 近傍（上の 2 つと
+    /// [`neighbors_share_vertex`](crate::SingleId::neighbors_share_vertex)）。
+    Vertex,
+}
+
 ///次元の区間表記の文字列を圧縮するための関数
 pub fn format_dimension<T: PartialEq + fmt::Display>(dimension: [T; 2]) -> String {
     if dimension[0] == dimension[1] {
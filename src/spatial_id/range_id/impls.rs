@@ -328,6 +328,7 @@ fn parse_error(input: &str) -> Error {
     SpatialIdError::ParseSpatialIdFormat {
         kind: "RangeId",
         input: input.to_string(),
+        offset: None,
     }
     .into()
 }
@@ -351,6 +351,7 @@ fn parse_error(input: &str) -> Error {
     SpatialIdError::ParseSpatialIdFormat {
         kind: "SingleId",
         input: input.to_string(),
+        offset: None,
     }
     .into()
 }